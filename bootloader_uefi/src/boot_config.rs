@@ -0,0 +1,56 @@
+use r_efi::efi;
+use r_efi::protocols::file;
+
+use crate::uefi;
+
+const BOOT_CONFIG_PATH: &str = "boot.cfg";
+const MAX_BOOT_CONFIG_SIZE: usize = 4096;
+
+/// The optional boot.cfg next to the kernel on the boot volume. Each line is a key=value
+/// pair and lines starting with # are comments. The only key so far is cmdline, which is
+/// passed to the kernel, for example:
+///
+/// cmdline=loglevel=debug log=kernel::pci=trace
+pub struct BootConfig {
+    buffer: [u8; MAX_BOOT_CONFIG_SIZE],
+    len: usize,
+}
+
+impl BootConfig {
+    pub const fn empty() -> BootConfig {
+        return BootConfig { buffer: [0; MAX_BOOT_CONFIG_SIZE], len: 0 };
+    }
+
+    /// Reads boot.cfg from the volume the bootloader was loaded from. A missing file gives
+    /// an empty config. Anything past MAX_BOOT_CONFIG_SIZE is ignored.
+    pub fn load(h: efi::Handle, system_table: uefi::SystemTableWrapper) -> Result<BootConfig, efi::Status> {
+        let file_volume = system_table.boot_services().open_volume(h)?;
+        let config_file = match file_volume.open(BOOT_CONFIG_PATH, file::MODE_READ, file::READ_ONLY) {
+            Ok(config_file) => config_file,
+            Err(efi::Status::NOT_FOUND) => { return Ok(BootConfig::empty()); },
+            Err(s) => { return Err(s); }
+        };
+
+        let mut config = BootConfig::empty();
+        let mut size = MAX_BOOT_CONFIG_SIZE;
+        config_file.read(&mut size, config.buffer.as_mut_ptr() as *mut core::ffi::c_void)?;
+        config.len = size;
+        return Ok(config);
+    }
+
+    /// The value of the first line setting *key*
+    pub fn get(&self, key: &str) -> Option<&str> {
+        let text = core::str::from_utf8(&self.buffer[..self.len]).ok()?;
+        return text.lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(|line| line.split_once('='))
+            .find(|(line_key, _)| line_key.trim() == key)
+            .map(|(_, value)| value.trim());
+    }
+
+    /// The kernel command line, empty if none was set
+    pub fn command_line(&self) -> &str {
+        return self.get("cmdline").unwrap_or("");
+    }
+}
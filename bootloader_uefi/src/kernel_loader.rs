@@ -0,0 +1,362 @@
+use core::fmt;
+
+use logging::{debug, info, warn};
+use r_efi::efi;
+use x86_64_hardware::memory::{PAGE_SIZE, PhysicalAddress, VirtualAddress};
+
+use crate::loaded_asset_list::LoadedAssetList;
+use crate::uefi;
+
+const KERNEL_PATH: &str = "kernel.elf";
+/// Setting the file position to this moves it to the end of the file
+const END_OF_FILE_POSITION: u64 = u64::MAX;
+/// The most PT_LOAD segments the kernel may have. They are checked against each other
+/// before anything is loaded and there is no allocator to hold them.
+const MAX_LOAD_SEGMENTS: usize = 16;
+/// Symbol and string tables larger than this together are assumed to be corrupt
+const MAX_SYMBOLS_SIZE: u64 = 64 * 1024 * 1024;
+/// The kernel is linked into the top 2GiB so a span from its lowest to highest segment
+/// larger than this is assumed to be corrupt
+const MAX_IMAGE_SIZE: u64 = 1024 * 1024 * 1024;
+
+/// Why the kernel could not be loaded
+#[derive(Debug)]
+pub enum LoadError {
+    /// A firmware call failed, such as opening or reading the kernel file
+    Firmware(efi::Status),
+    /// The file is not a well formed 64 bit ELF file
+    Elf(elf::ElfError),
+    UnsupportedType(elf::ElfType),
+    UnsupportedMachine(elf::ElfMachine),
+    UnsupportedVersion(elf::ElfVersion),
+    TooManySegments,
+    NoLoadableSegments,
+    /// p_filesz is larger than p_memsz
+    FileSizeExceedsMemorySize { segment: u16 },
+    /// p_align is not a power of two, or p_vaddr and p_offset are not congruent modulo it
+    MisalignedSegment { segment: u16, align: u64 },
+    /// Part of the segment lies outside the canonical address ranges
+    NonCanonicalSegment { segment: u16, start: u64, size: u64 },
+    OverlappingSegments { first: u16, second: u16 },
+    /// A writable and an executable segment share a page, which would have to be mapped both
+    WritableExecutablePage { first: u16, second: u16 },
+    EntryPointOutsideSegments(u64),
+    /// The segments are spread over more than MAX_IMAGE_SIZE bytes
+    ImageTooLarge(u64),
+}
+
+impl LoadError {
+    /// The status to hand back to the firmware
+    pub fn status(&self) -> efi::Status {
+        match self {
+            LoadError::Firmware(status) => *status,
+            _ => efi::Status::LOAD_ERROR,
+        }
+    }
+}
+
+impl From<efi::Status> for LoadError {
+    fn from(status: efi::Status) -> LoadError {
+        return LoadError::Firmware(status);
+    }
+}
+
+impl From<elf::ElfError> for LoadError {
+    fn from(error: elf::ElfError) -> LoadError {
+        return LoadError::Elf(error);
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Firmware(status) => write!(f, "firmware error {:#x}", status.as_usize()),
+            LoadError::Elf(error) => write!(f, "{}", error),
+            LoadError::UnsupportedType(elf_type) => write!(f, "unsupported type {:?}", elf_type),
+            LoadError::UnsupportedMachine(machine) => write!(f, "unsupported machine {:?}", machine),
+            LoadError::UnsupportedVersion(version) => write!(f, "unsupported ELF version {:?}", version),
+            LoadError::TooManySegments => write!(f, "more than {} loadable segments", MAX_LOAD_SEGMENTS),
+            LoadError::NoLoadableSegments => write!(f, "no loadable segments"),
+            LoadError::FileSizeExceedsMemorySize { segment } => write!(f, "segment {} has more file data than memory", segment),
+            LoadError::MisalignedSegment { segment, align } => write!(f, "segment {} is not aligned to {:#x}", segment, align),
+            LoadError::NonCanonicalSegment { segment, start, size } => write!(f, "segment {} at {:#x} size {:#x} is not canonical", segment, start, size),
+            LoadError::OverlappingSegments { first, second } => write!(f, "segments {} and {} overlap", first, second),
+            LoadError::WritableExecutablePage { first, second } => write!(f, "segments {} and {} share a page but one is writable and the other executable", first, second),
+            LoadError::EntryPointOutsideSegments(entry) => write!(f, "entry point {:#x} is not in a loaded segment", entry),
+            LoadError::ImageTooLarge(size) => write!(f, "segments span {:#x} bytes", size),
+        }
+    }
+}
+
+/// The kernel's .symtab section and the string table holding its names, read into one
+/// LOADER_DATA allocation with the strings straight after the symbols
+#[derive(Clone, Copy)]
+pub struct LoadedSymbols {
+    pub physical_address: PhysicalAddress,
+    pub num_pages: usize,
+    pub symbol_table_size: u64,
+    pub string_table_size: u64,
+}
+
+/// The kernel image, ready to be mapped
+pub struct LoadedKernel {
+    pub assets: LoadedAssetList,
+    pub entry_point: VirtualAddress,
+    /// None if the kernel is stripped or its symbols could not be read
+    pub symbols: Option<LoadedSymbols>,
+}
+
+/// The kernel file read into LOADER_DATA pages
+struct KernelFileBuffer {
+    buffer: *mut u8,
+    num_pages: usize,
+    size: usize,
+}
+
+impl KernelFileBuffer {
+    fn data(&self) -> &[u8] {
+        //This is safe as the buffer is only freed by consuming self and size is at most its size
+        return unsafe { core::slice::from_raw_parts(self.buffer as *const u8, self.size) };
+    }
+
+    /// Gives the pages back to the firmware. Nothing frees LOADER_DATA once the kernel is
+    /// running, so the file would otherwise be kept out of the kernel's free memory forever.
+    fn free(self, system_table: uefi::SystemTableWrapper) {
+        if let Err(status) = system_table.boot_services().free_pages(self.buffer, self.num_pages) {
+            warn!("Cannot free the kernel file. Status {:#x}", status.as_usize());
+        }
+    }
+}
+
+/// A PT_LOAD segment that has passed validation
+#[derive(Clone, Copy, Default)]
+struct LoadSegment<'a> {
+    index: u16,
+    /// The segment's contents in the file, p_filesz bytes long
+    data: &'a [u8],
+    virtual_address: u64,
+    memory_size: u64,
+    writable: bool,
+    executable: bool,
+}
+
+impl<'a> LoadSegment<'a> {
+    /// One past the last byte of the segment in memory
+    fn end(&self) -> u64 {
+        return self.virtual_address + self.memory_size;
+    }
+
+    /// True if the two segments have bytes in the same page
+    fn shares_page(&self, other: &LoadSegment) -> bool {
+        let first_page = |segment: &LoadSegment| segment.virtual_address / PAGE_SIZE;
+        let last_page = |segment: &LoadSegment| (segment.end() - 1) / PAGE_SIZE;
+        return first_page(self) <= last_page(other) && first_page(other) <= last_page(self);
+    }
+}
+
+/// Loads the kernel from the boot volume. The file is read into memory and every segment
+/// is checked before anything is copied, then the whole image is copied into one zeroed
+/// allocation so .bss starts out zeroed and segments that share a page share its memory.
+/// The symbol table is copied out too if there is one, but the kernel can boot without it.
+/// The file is freed afterwards as everything the kernel needs has been copied out of it.
+pub fn load_kernel(h: efi::Handle, system_table: uefi::SystemTableWrapper) -> Result<LoadedKernel, LoadError> {
+    let file_buffer = read_kernel_file(h, system_table)?;
+    let result = load_kernel_file(file_buffer.data(), system_table);
+    file_buffer.free(system_table);
+    return result;
+}
+
+fn load_kernel_file(file_data: &[u8], system_table: uefi::SystemTableWrapper) -> Result<LoadedKernel, LoadError> {
+    let kernel_file = elf::ElfFile::parse(file_data)?;
+    validate_elf(&kernel_file.header().common)?;
+    info!("Kernel header verified successfully!");
+
+    let mut segments = [LoadSegment::default(); MAX_LOAD_SEGMENTS];
+    let mut num_segments = 0;
+    for (header_index, phdr) in kernel_file.program_headers().enumerate() {
+        if phdr.p_type() != elf::ElfPhysicalType::ElfPhysicalTypeLoad || phdr.p_memsz == 0 {
+            continue;
+        }
+
+        let segment = validate_segment(&kernel_file, header_index as u16, &phdr)?;
+        if let Some(other) = segments[..num_segments].iter().find(|other| segment.virtual_address < other.end() && other.virtual_address < segment.end()) {
+            return Err(LoadError::OverlappingSegments { first: other.index, second: segment.index });
+        }
+        //Shared pages are mapped with the permissions of both segments, which would undo W^X
+        let conflicts = |other: &&LoadSegment| (segment.writable && other.executable) || (segment.executable && other.writable);
+        if let Some(other) = segments[..num_segments].iter().filter(conflicts).find(|other| segment.shares_page(other)) {
+            return Err(LoadError::WritableExecutablePage { first: other.index, second: segment.index });
+        }
+        if segment.writable && segment.executable {
+            warn!("Kernel segment {} is both writable and executable", segment.index);
+        }
+        if num_segments == MAX_LOAD_SEGMENTS {
+            return Err(LoadError::TooManySegments);
+        }
+        segments[num_segments] = segment;
+        num_segments += 1;
+    }
+
+    let segments = &segments[..num_segments];
+    if segments.is_empty() {
+        return Err(LoadError::NoLoadableSegments);
+    }
+    let entry_point = kernel_file.header().e_entry;
+    if !segments.iter().any(|segment| entry_point >= segment.virtual_address && entry_point < segment.end()) {
+        return Err(LoadError::EntryPointOutsideSegments(entry_point));
+    }
+
+    let image_start = segments.iter().map(|segment| segment.virtual_address).min().unwrap_or(0) & !(PAGE_SIZE - 1);
+    let image_end = segments.iter().map(|segment| segment.end()).max().unwrap_or(0);
+    if image_end - image_start > MAX_IMAGE_SIZE {
+        return Err(LoadError::ImageTooLarge(image_end - image_start));
+    }
+    let image_pages = ((image_end - image_start + PAGE_SIZE - 1) / PAGE_SIZE) as usize;
+    let image_memory = system_table.boot_services().allocate_pages::<u8>(r_efi::system::LOADER_DATA, image_pages)?;
+    //Zero everything first. This covers .bss and the gaps between segments.
+    unsafe { core::ptr::write_bytes(image_memory, 0, image_pages * PAGE_SIZE as usize); }
+
+    let mut kernel_asset_list = LoadedAssetList::new(segments.len(), system_table)?;
+    for segment in segments {
+        let segment_offset = segment.virtual_address - image_start;
+        //This is safe as the image was sized to hold every segment
+        unsafe { core::ptr::copy_nonoverlapping(segment.data.as_ptr(), image_memory.add(segment_offset as usize), segment.data.len()); }
+
+        //Assets are whole pages so a page shared with the previous segment is listed twice.
+        //Segments sharing a page were checked never to make it both writable and executable.
+        let first_page = segment_offset & !(PAGE_SIZE - 1);
+        let num_pages = ((segment.end() - image_start - first_page + PAGE_SIZE - 1) / PAGE_SIZE) as usize;
+        let physical_address = PhysicalAddress::new(image_memory as u64 + first_page);
+        //The list was sized for every segment
+        if kernel_asset_list.add_asset(physical_address, num_pages, VirtualAddress::new(image_start + first_page), segment.writable, segment.executable).is_none() {
+            return Err(LoadError::TooManySegments);
+        }
+        //Present pages are always readable so only W and X are shown
+        debug!("Loaded segment {} at {:#x}, {:#x} bytes from the file and {:#x} in memory, {}{}",
+            segment.index, segment.virtual_address, segment.data.len(), segment.memory_size,
+            if segment.writable { 'W' } else { '-' }, if segment.executable { 'X' } else { '-' });
+    }
+
+    let symbols = match load_symbols(&kernel_file, system_table) {
+        Ok(symbols) => symbols,
+        Err(error) => {
+            warn!("Cannot load the kernel's symbol table: {}", error);
+            None
+        }
+    };
+
+    return Ok(LoadedKernel {
+        assets: kernel_asset_list,
+        entry_point: VirtualAddress::new(entry_point),
+        symbols: symbols,
+    });
+}
+
+/// Reads the whole kernel file into LOADER_DATA pages, which the caller must free
+fn read_kernel_file(h: efi::Handle, system_table: uefi::SystemTableWrapper) -> Result<KernelFileBuffer, efi::Status> {
+    let file_volume = system_table.boot_services().open_volume(h)?;
+    let kernel_file = file_volume.open(KERNEL_PATH, r_efi::protocols::file::MODE_READ, r_efi::protocols::file::READ_ONLY)?;
+    debug!("Opened kernel file");
+
+    kernel_file.set_position(END_OF_FILE_POSITION)?;
+    let file_size = kernel_file.get_position()? as usize;
+    kernel_file.set_position(0)?;
+
+    let num_pages = (file_size + PAGE_SIZE as usize - 1) / PAGE_SIZE as usize;
+    let buffer = system_table.boot_services().allocate_pages::<u8>(r_efi::system::LOADER_DATA, num_pages.max(1))?;
+    //A short read leaves a shorter file, which the ELF parser reports as truncated
+    let mut read_size = file_size;
+    let file_buffer = KernelFileBuffer { buffer: buffer, num_pages: num_pages.max(1), size: 0 };
+    if let Err(status) = kernel_file.read(&mut read_size, buffer as *mut core::ffi::c_void) {
+        file_buffer.free(system_table);
+        return Err(status);
+    }
+    debug!("Read {} bytes of the kernel file", read_size);
+
+    return Ok(KernelFileBuffer { size: read_size.min(file_size), ..file_buffer });
+}
+
+/// Copies the symbol table and its string table out of the kernel file. Returns None if
+/// the kernel has been stripped.
+fn load_symbols(kernel_file: &elf::ElfFile, system_table: uefi::SystemTableWrapper) -> Result<Option<LoadedSymbols>, LoadError> {
+    let (symbol_table, string_table) = match kernel_file.symbol_table()? {
+        Some(tables) => tables,
+        None => {
+            debug!("The kernel has no symbol table");
+            return Ok(None);
+        }
+    };
+
+    let (symbol_data, string_data) = (symbol_table.as_bytes(), string_table.as_bytes());
+    let total_size = (symbol_data.len() + string_data.len()) as u64;
+    if total_size == 0 || total_size > MAX_SYMBOLS_SIZE {
+        warn!("Ignoring a {:#x} byte kernel symbol table", total_size);
+        return Ok(None);
+    }
+
+    let num_pages = ((total_size + PAGE_SIZE - 1) / PAGE_SIZE) as usize;
+    let memory = system_table.boot_services().allocate_pages::<u8>(r_efi::system::LOADER_DATA, num_pages)?;
+    //This is safe as the allocation holds both tables
+    unsafe {
+        core::ptr::copy_nonoverlapping(symbol_data.as_ptr(), memory, symbol_data.len());
+        core::ptr::copy_nonoverlapping(string_data.as_ptr(), memory.add(symbol_data.len()), string_data.len());
+    }
+
+    debug!("Loaded {} kernel symbols", symbol_table.len());
+    return Ok(Some(LoadedSymbols {
+        physical_address: PhysicalAddress::new(memory as u64),
+        num_pages: num_pages,
+        symbol_table_size: symbol_data.len() as u64,
+        string_table_size: string_data.len() as u64,
+    }));
+}
+
+/// Checks the kernel is an x86_64 executable. ElfFile has already checked it is a 64 bit
+/// little endian ELF file.
+fn validate_elf(header: &elf::ElfHeaderCommon) -> Result<(), LoadError> {
+    if header.e_type() != elf::ElfType::ElfTypeExec {
+        return Err(LoadError::UnsupportedType(header.e_type()));
+    }
+
+    if header.e_machine() != elf::ElfMachine::ElfMachineX8664 {
+        return Err(LoadError::UnsupportedMachine(header.e_machine()));
+    }
+
+    if header.e_version() != elf::ElfVersion::ElfVersionCurrent {
+        return Err(LoadError::UnsupportedVersion(header.e_version()));
+    }
+
+    return Ok(());
+}
+
+/// Checks a PT_LOAD header. The segment must fit its file data, keep p_vaddr and p_offset
+/// congruent modulo p_align as the ELF specification requires, and lie within one half of
+/// the canonical address space. Its file data must be inside the file.
+fn validate_segment<'a>(kernel_file: &elf::ElfFile<'a>, index: u16, phdr: &elf::ElfPhysicalHeader64) -> Result<LoadSegment<'a>, LoadError> {
+    if phdr.p_filesz > phdr.p_memsz {
+        return Err(LoadError::FileSizeExceedsMemorySize { segment: index });
+    }
+
+    //0 and 1 both mean no alignment is required
+    if phdr.p_align > 1 && (!phdr.p_align.is_power_of_two() || phdr.p_vaddr % phdr.p_align != phdr.p_offset % phdr.p_align) {
+        return Err(LoadError::MisalignedSegment { segment: index, align: phdr.p_align });
+    }
+
+    //Segments running up to the very top of memory are rejected too as their end wraps
+    let canonical = match phdr.p_vaddr.checked_add(phdr.p_memsz).map(|end| end - 1) {
+        Some(last_byte) => VirtualAddress::is_canonical(phdr.p_vaddr) && VirtualAddress::is_canonical(last_byte) && (phdr.p_vaddr >> 47) == (last_byte >> 47),
+        None => false,
+    };
+    if !canonical {
+        return Err(LoadError::NonCanonicalSegment { segment: index, start: phdr.p_vaddr, size: phdr.p_memsz });
+    }
+
+    return Ok(LoadSegment {
+        index: index,
+        data: kernel_file.segment_data(phdr)?,
+        virtual_address: phdr.p_vaddr,
+        memory_size: phdr.p_memsz,
+        writable: phdr.writable(),
+        executable: phdr.executable(),
+    });
+}
@@ -7,7 +7,9 @@ use crate::uefi;
 pub struct LoadedAsset {
     pub physical_address: PhysicalAddress,
     pub num_pages: usize,
-    pub virtual_address: VirtualAddress,    
+    pub virtual_address: VirtualAddress,
+    pub writable: bool,
+    pub executable: bool,
 }
 
 pub struct LoadedAssetList {
@@ -38,9 +40,9 @@ impl LoadedAssetList {
         return (PAGE_SIZE as usize * self.num_pages) / core::mem::size_of::<LoadedAsset>();
     }
 
-    /// Adds an asset to this list. Returns the index of the added item if successful.
-    /// If the list is full it returns None
-    pub fn add_asset(&mut self, physical_address: PhysicalAddress, num_pages: usize, virtual_address: VirtualAddress) -> Option<usize> {
+    /// Adds an asset to this list, to be mapped with the given access. Returns the index of
+    /// the added item if successful. If the list is full it returns None
+    pub fn add_asset(&mut self, physical_address: PhysicalAddress, num_pages: usize, virtual_address: VirtualAddress, writable: bool, executable: bool) -> Option<usize> {
         if self.max_items() == self.num_items {
             return None;
         }
@@ -54,6 +56,8 @@ impl LoadedAssetList {
                 physical_address: physical_address,
                 num_pages: num_pages,
                 virtual_address: virtual_address,
+                writable: writable,
+                executable: executable,
             };
         }
         self.num_items += 1;
@@ -0,0 +1,39 @@
+use core::fmt::Write;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use logging::{LineBuffer, Logger, Record};
+use x86_64_hardware::com1_println;
+use x86_64_hardware::cpu;
+
+const MAX_LOG_LINE_LEN: usize = 256;
+
+/// The TSC frequency from CPUID or 0 if the processor does not report it. The bootloader
+/// does not calibrate the TSC so timestamps fall back to raw cycle counts.
+static TSC_FREQUENCY: AtomicU64 = AtomicU64::new(0);
+
+/// Sends log messages to COM1, timestamped from the TSC
+struct BootLogger;
+
+static BOOT_LOGGER: BootLogger = BootLogger;
+
+impl Logger for BootLogger {
+    fn log(&self, record: &Record) {
+        let mut line = LineBuffer::<MAX_LOG_LINE_LEN>::new();
+        let tsc = cpu::read_tsc();
+        let tsc_frequency = TSC_FREQUENCY.load(Ordering::Relaxed);
+        let _ = if tsc_frequency != 0 {
+            let microseconds = (tsc as u128 * 1_000_000 / tsc_frequency as u128) as u64;
+            write!(line, "[{:5}.{:06}] ", microseconds / 1_000_000, microseconds % 1_000_000)
+        } else {
+            write!(line, "[tsc {}] ", tsc)
+        };
+        let _ = write!(line, "{:<5} {}: {}", record.level, record.module, record.args);
+
+        com1_println!("{}", line.as_str());
+    }
+}
+
+pub fn init_logging() {
+    TSC_FREQUENCY.store(cpu::tsc_frequency_from_cpuid().unwrap_or(0), Ordering::Relaxed);
+    logging::set_logger(&BOOT_LOGGER);
+}
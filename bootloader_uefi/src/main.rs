@@ -2,16 +2,18 @@
 #![no_std]
 
 use core::fmt::Write;
-use bootinfo::{BootInfo, MemInfo};
-use loaded_asset_list::LoadedAssetList;
+use boot_config::BootConfig;
+use bootinfo::{BootInfo, KernelSymbols, MemInfo};
+use logging::{debug, error, info, warn, Filter};
 use r_efi::efi;
-use elf;
 use x86_64_hardware::memory::{PAGE_SIZE, VirtualAddress, PhysicalAddress, MAX_VIRTUAL_ADDRESS};
 use x86_64_hardware::memory:: paging::{PageTableManager, PageFrameAllocator, MAX_MEM_SIZE, MEM_1G};
-use x86_64_hardware::com1_println;
 mod uefi;
 mod unicode;
+mod boot_config;
+mod kernel_loader;
 mod loaded_asset_list;
+mod logger;
 
 #[panic_handler]
 fn panic_handler(_info: &core::panic::PanicInfo) -> ! {
@@ -38,48 +40,82 @@ pub extern "C" fn efi_main(h: efi::Handle, st: *mut efi::SystemTable) -> efi::St
 }
 
 fn main(h: efi::Handle, system_table: uefi::SystemTableWrapper) -> Result<(),efi::Status> {
+    logger::init_logging();
+    let boot_config = BootConfig::load(h, system_table)?;
+    match Filter::from_command_line(boot_config.command_line()) {
+        Ok(filter) => { logging::set_filter(filter); },
+        Err(error) => { warn!("Ignoring invalid log options on the command line: {:?}", error); }
+    }
+
     let bootinfo_num_pages = (core::mem::size_of::<BootInfo>() + PAGE_SIZE as usize - 1) / PAGE_SIZE as usize;
     let mut bootinfo = system_table.boot_services().allocate_pages::<BootInfo>(r_efi::system::LOADER_DATA, bootinfo_num_pages)?;
     unsafe { (*bootinfo) = BootInfo::default(); }
+    if unsafe { (*bootinfo).set_command_line(boot_config.command_line()) } {
+        info!("Kernel command line: {}", boot_config.command_line());
+    } else {
+        warn!("Kernel command line is too long. Ignoring it");
+    }
 
-    unsafe { (*bootinfo).framebuffer = initialise_gop(system_table)?; }
+    let (framebuffer, video_mode) = initialise_gop(system_table, boot_config.get("resolution"))?;
+    unsafe {
+        (*bootinfo).framebuffer = framebuffer;
+        (*bootinfo).video_mode = video_mode;
+    }
 
-    let (kernel_asset_list, entry_point) = load_kernel(h, system_table)?;
+    let kernel = match kernel_loader::load_kernel(h, system_table) {
+        Ok(kernel) => kernel,
+        Err(error) => {
+            error!("Cannot load the kernel: {}", error);
+            return Err(error.status());
+        }
+    };
 
     let configuration_table = system_table.get_configuration_table();
+    match configuration_table.get_rsdp_physical_address() {
+        Some(rsdp_physical_address) => unsafe { (*bootinfo).rsdp_physical_address = rsdp_physical_address; },
+        None => { warn!("No ACPI RSDP found"); },
+    }
 
     let mut mem_info = system_table.boot_services().get_memory_map()?;
 
     system_table.boot_services().exit_boot_services(h, mem_info.map_key)?;
 
-    unsafe {
-        (*bootinfo).framebuffer.clear_framebuffer(0, 0);
-    }
-
     let mut allocator = mem_info.map.init_frame_allocator();
     let max_physical_address = mem_info.map.max_physical_address();
     //We're done with the mem_map so free the pages
     mem_info.map.free_pages(&mut allocator);
 
     let mut kernel_base_address = VirtualAddress::new(MAX_VIRTUAL_ADDRESS);
-    for asset in kernel_asset_list.iter() {
+    for asset in kernel.assets.iter() {
         allocator.lock_pages(asset.physical_address, asset.num_pages);
         if asset.virtual_address < kernel_base_address {
             kernel_base_address = asset.virtual_address;
         }
     }
+    if let Some(symbols) = kernel.symbols {
+        allocator.lock_pages(symbols.physical_address, symbols.num_pages);
+    }
 
     let firmware_page_table_manager = PageTableManager::new_from_cr3(0);
     let (mut page_table_manager, offset) = match init_page_table_manager(&mut allocator, max_physical_address, kernel_base_address) {
         Some(ptm) => ptm,
         None => {
-            com1_println!("Memsize too large");
+            error!("Memsize too large");
             return Err(efi::Status::ABORTED);
         }
     };
 
     unsafe { (*bootinfo).page_table_memory_offset = offset; }
 
+    //The execute disable bit is reserved until EFER.NXE is set, so turn it on before any
+    //page is mapped with it. The APs copy EFER from this CPU when they start.
+    let no_execute_enabled = x86_64_hardware::cpu::has_no_execute();
+    if no_execute_enabled {
+        unsafe { x86_64_hardware::cpu::enable_no_execute(); }
+    } else {
+        warn!("The CPU does not support no execute pages");
+    }
+
     //activate the new page table before turning on offset mapping
     unsafe { 
         page_table_manager.activate_page_table();
@@ -88,9 +124,10 @@ fn main(h: efi::Handle, system_table: uefi::SystemTableWrapper) -> Result<(),efi
 
     firmware_page_table_manager.release_tables(&mut allocator);
 
-    //Map the kernel into the new page table
-    for asset in kernel_asset_list.iter() {
-        page_table_manager.map_memory_pages(asset.virtual_address, asset.physical_address, asset.num_pages as u64, &mut allocator);
+    //Map the kernel into the new page table with each segment's permissions
+    for asset in kernel.assets.iter() {
+        page_table_manager.map_memory_pages_with_access(asset.virtual_address, asset.physical_address, asset.num_pages as u64,
+            asset.writable, asset.executable, no_execute_enabled, &mut allocator);
         let max_address = asset.virtual_address.increment_page_4kb(asset.num_pages as u64);
         if max_address > unsafe {(*bootinfo).next_available_kernel_page} {
             unsafe { (*bootinfo).next_available_kernel_page = max_address; }
@@ -103,6 +140,22 @@ fn main(h: efi::Handle, system_table: uefi::SystemTableWrapper) -> Result<(),efi
     page_table_manager.map_memory_pages(bootinfo_virtual_address, bootinfo_physical_address, bootinfo_num_pages as u64, &mut allocator);
     unsafe { (*bootinfo).next_available_kernel_page = bootinfo_virtual_address.increment_page_4kb(bootinfo_num_pages as u64); }
 
+    //Map the symbol table read only after the bootinfo so the kernel can symbolize backtraces
+    if let Some(symbols) = kernel.symbols {
+        let symbols_virtual_address = unsafe { (*bootinfo).next_available_kernel_page };
+        page_table_manager.map_memory_pages_with_access(symbols_virtual_address, symbols.physical_address, symbols.num_pages as u64,
+            false, false, no_execute_enabled, &mut allocator);
+        unsafe {
+            (*bootinfo).kernel_symbols = KernelSymbols {
+                symbol_table: symbols_virtual_address,
+                symbol_table_size: symbols.symbol_table_size,
+                string_table: VirtualAddress::new(symbols_virtual_address.as_u64() + symbols.symbol_table_size),
+                string_table_size: symbols.string_table_size,
+            };
+            (*bootinfo).next_available_kernel_page = symbols_virtual_address.increment_page_4kb(symbols.num_pages as u64);
+        }
+    }
+
     unsafe { page_table_manager.activate_page_table(); }
 
     //Update boot info pointer to point to the kernel mapped address
@@ -116,52 +169,20 @@ fn main(h: efi::Handle, system_table: uefi::SystemTableWrapper) -> Result<(),efi
     unsafe { (*bootinfo).next_available_kernel_page = bitmap_buffer_virtual_addr.increment_page_4kb(num_bitmap_pages as u64); }
 
     unsafe { page_table_manager.activate_page_table(); }
+    //From here a stray write to the kernel's code or read only data faults rather than
+    //corrupting it. Everything the bootloader still writes to is mapped writable.
+    unsafe { x86_64_hardware::cpu::enable_write_protect(); }
+
     //Pass new kernel space bitmap location to kernel
     let output_bitmap = unsafe { bitmap::Bitmap::new(allocator.page_bitmap().size(), bitmap_buffer_virtual_addr.get_mut_ptr::<u8>()) };
     unsafe {  (*bootinfo).meminfo = MemInfo::new(output_bitmap, allocator.get_free_ram(), allocator.get_reserved_ram(), allocator.get_used_ram(), max_physical_address); }
 
-    let kernel_start: unsafe extern "sysv64" fn(*mut BootInfo) = unsafe { core::mem::transmute(entry_point.get_mut_ptr::<core::ffi::c_void>()) };
+    let kernel_start: unsafe extern "sysv64" fn(*mut BootInfo) = unsafe { core::mem::transmute(kernel.entry_point.get_mut_ptr::<core::ffi::c_void>()) };
     unsafe { (kernel_start)(bootinfo) };
 
     return Ok(());
 }
 
-fn load_kernel(h: efi::Handle, system_table: uefi::SystemTableWrapper) -> Result<(LoadedAssetList, VirtualAddress), efi::Status> {
-    let file_volume = system_table.boot_services().open_volume(h)?;
-    let kernel_file = file_volume.open("kernel.elf", r_efi::protocols::file::MODE_READ, r_efi::protocols::file::READ_ONLY)?;
-    com1_println!("Opened kernel file");
-
-    let elf_common: elf::ElfHeaderCommon  = kernel_file.read_struct::<elf::ElfHeaderCommon>()?;
-
-    validate_elf(&elf_common)?;
-    com1_println!("Kernel header verified successfully!");
-
-    kernel_file.set_position(0)?;
-
-    let elf_64: elf::ElfHeader64 = kernel_file.read_struct::<elf::ElfHeader64>()?;
-
-    let mut kernel_asset_list = crate::loaded_asset_list::LoadedAssetList::new(elf_64.e_phnum as usize, system_table)?;
-    for header_index in 0..elf_64.e_phnum {
-        kernel_file.set_position(elf_64.e_phoff + (u64::from(header_index) * u64::from(elf_64.e_phentsize)))?;
-        let phdr: elf::ElfPhysicalHeader64 = kernel_file.read_struct::<elf::ElfPhysicalHeader64>()?;
-
-        match phdr.p_type() {
-            elf::ElfPhysicalType::ElfPhysicalTypeLoad => {
-                let pages: usize = ((phdr.p_memsz as usize) + PAGE_SIZE as usize - 1)  / PAGE_SIZE as usize;
-                let kernel_mem = system_table.boot_services().allocate_pages::<core::ffi::c_void>(r_efi::system::LOADER_DATA, pages)?;
-
-                kernel_file.set_position(phdr.p_offset)?;
-                let mut psize = phdr.p_filesz as usize;
-                kernel_file.read(&mut psize, kernel_mem)?;
-                kernel_asset_list.add_asset(PhysicalAddress::new(kernel_mem as u64), pages, VirtualAddress::new(phdr.p_vaddr));
-            },
-            _ => {}
-        }
-    }
-
-    return Ok((kernel_asset_list, VirtualAddress::new(elf_64.e_entry)));
-}
-
 fn init_page_table_manager(mut allocator: &mut PageFrameAllocator, max_physical_address: PhysicalAddress, kernel_base_address: VirtualAddress) -> Option<(PageTableManager, u64)> {
     if max_physical_address.as_u64() > MAX_MEM_SIZE {
         return None;
@@ -182,49 +203,88 @@ fn init_page_table_manager(mut allocator: &mut PageFrameAllocator, max_physical_
     return Some((page_table_manager, offset));
 }
 
-fn validate_elf(header: &elf::ElfHeaderCommon) -> Result<(),efi::Status> {
-    if !header.valid_magic() {
-        com1_println!("Invalid magic");
-        return Err(efi::Status::LOAD_ERROR);
-    }
+fn initialise_gop(system_table: uefi::SystemTableWrapper, resolution: Option<&str>) -> Result<(bootinfo::FrameBuffer, u32), efi::Status>{
+    let boot_services = system_table.boot_services();
+    let gop = match boot_services.get_graphics_output_protocol() {
+        Ok(gop) => gop,
+        Err(s) => { 
+            error!("Cannot load GOP. Status {:#x}", s.as_usize());
+            return Err(s);
+        }
+    };
+    info!("GOP loaded");
+
+    let preferred_resolution = match resolution.map(parse_resolution) {
+        Some(None) => {
+            warn!("Ignoring invalid resolution {:?}. Expected WIDTHxHEIGHT", resolution.unwrap_or(""));
+            None
+        },
+        Some(preferred_resolution) => preferred_resolution,
+        None => None,
+    };
 
-    if header.class() != elf::ElfClass::ElfClass64 {
-        com1_println!("Invalid class {:?}", header.class());
-        return Err(efi::Status::LOAD_ERROR);
+    let current_mode = gop.current_mode();
+    match choose_video_mode(&gop, &boot_services, preferred_resolution) {
+        Some(mode) => {
+            if let Some((width, height)) = preferred_resolution {
+                if (width, height) != (mode.width, mode.height) {
+                    warn!("No supported mode is {}x{}. Using the highest resolution instead", width, height);
+                }
+            }
+            if mode.number != current_mode {
+                if let Err(s) = gop.set_mode(mode.number) {
+                    warn!("Cannot set GOP mode {}. Status {:#x}. Keeping mode {}", mode.number, s.as_usize(), current_mode);
+                }
+            }
+        },
+        None => { warn!("GOP has no modes with a linear framebuffer"); }
     }
 
-    if header.data_encoding() != elf::ElfData::ElfData2Lsb {
-        com1_println!("Invalid data encoding {:?}", header.data_encoding());
-        return Err(efi::Status::LOAD_ERROR);
+    let framebuffer = gop.get_framebuffer();
+    let mode_number = gop.current_mode();
+    if framebuffer.pixel_format == bootinfo::PixelFormat::BltOnly {
+        //Blt is a boot service so the kernel would have no way to draw. Boot without a
+        //framebuffer rather than hand over one that cannot be written.
+        warn!("GOP mode has no linear framebuffer. Continuing without one");
+        return Ok((bootinfo::FrameBuffer::default(), bootinfo::NO_VIDEO_MODE));
     }
+    info!("Using GOP mode {}: {}x{} {:?}", mode_number, framebuffer.width, framebuffer.height, framebuffer.pixel_format);
 
-    if header.e_type() != elf::ElfType::ElfTypeExec {
-        com1_println!("Invalid type {:?}", header.e_type());
-        return Err(efi::Status::LOAD_ERROR);
-    }
+    return Ok((framebuffer, mode_number));
+}
 
-    if header.e_machine() != elf::ElfMachine::ElfMachineX8664 {
-        com1_println!("Invalid machine {:?}", header.e_machine());
-        return Err(efi::Status::LOAD_ERROR);
-    }
+/// Picks the mode matching *preferred_resolution*, or the supported mode with the most
+/// pixels if there is no preference or nothing matches
+fn choose_video_mode(gop: &uefi::GraphicsOutputProtocol, boot_services: &uefi::BootServices, preferred_resolution: Option<(u32, u32)>) -> Option<uefi::VideoMode> {
+    let mut preferred: Option<uefi::VideoMode> = None;
+    let mut largest: Option<uefi::VideoMode> = None;
+    for number in 0..gop.max_mode() {
+        let mode = match gop.query_mode(boot_services, number) {
+            Ok(mode) => mode,
+            Err(s) => {
+                debug!("Cannot query GOP mode {}. Status {:#x}", number, s.as_usize());
+                continue;
+            }
+        };
+        debug!("GOP mode {}: {}x{} {:?}", mode.number, mode.width, mode.height, mode.pixel_format);
+        if !mode.is_supported() {
+            continue;
+        }
 
-    if header.e_version() != elf::ElfVersion::ElfVersionCurrent {
-        com1_println!("Invalid elf version {:?}", header.e_version());
-        return Err(efi::Status::LOAD_ERROR);
+        if preferred.is_none() && preferred_resolution == Some((mode.width, mode.height)) {
+            preferred = Some(mode);
+        }
+        let pixels = mode.width as u64 * mode.height as u64;
+        if largest.map_or(true, |largest| pixels > largest.width as u64 * largest.height as u64) {
+            largest = Some(mode);
+        }
     }
-    
-    return Ok(());
-}
 
-fn initialise_gop(system_table: uefi::SystemTableWrapper) -> Result<bootinfo::FrameBuffer, efi::Status>{
-    let gop = match system_table.boot_services().get_graphics_output_protocol() {
-        Ok(gop) => gop,
-        Err(s) => { 
-            com1_println!("Cannot load GOP. Status{}", s.as_usize());
-            return Err(s);
-        }
-    };
-    com1_println!("GOP loaded");
+    return preferred.or(largest);
+}
 
-    return Ok(gop.get_framebuffer());
+/// Parses a resolution written as WIDTHxHEIGHT, for example 1024x768
+fn parse_resolution(resolution: &str) -> Option<(u32, u32)> {
+    let (width, height) = resolution.trim().split_once(|c| c == 'x' || c == 'X')?;
+    return Some((width.trim().parse().ok()?, height.trim().parse().ok()?));
 }
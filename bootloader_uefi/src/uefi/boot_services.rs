@@ -31,7 +31,7 @@ impl BootServices {
         }
     }
 
-    fn free_pages<T>(&self, mem: *mut T, num_pages: usize) -> Result<(), efi::Status> {
+    pub fn free_pages<T>(&self, mem: *mut T, num_pages: usize) -> Result<(), efi::Status> {
         return self.free_pages_raw(mem as u64, num_pages);
     }
 
@@ -47,6 +47,19 @@ impl BootServices {
         }
     }
 
+    /// Frees memory the firmware allocated from pool on our behalf
+    pub fn free_pool<T>(&self, mem: *mut T) -> Result<(), efi::Status> {
+        let s = unsafe {
+            ((*self.boot_services_ptr).free_pool)(mem as *mut core::ffi::c_void)
+        };
+
+        if s != efi::Status::SUCCESS {
+            return Err(s);
+        } else {
+            return Ok(());
+        }
+    }
+
     pub fn open_volume(&self, h: efi::Handle) -> Result<FileProtocol, efi::Status> {
         let loaded_image = self.get_loaded_image_protocol(h)?;
         let file_system = self.get_simple_file_system_protocol(loaded_image.device_handle())?;
@@ -39,6 +39,20 @@ impl ConfigurationTable {
         return None;
     }
 
+    /// Returns the physical address of the RSDP, preferring the ACPI 2.0 table when the
+    /// firmware provides both
+    pub fn get_rsdp_physical_address(&self) -> Option<u64> {
+        let mut output = None;
+        for entry in self.iter() {
+            match entry.get_type() {
+                TableType::AcpiV2_0 => { return Some(entry.vendor_table_address()); },
+                TableType::AcpiV1_0 => { output = Some(entry.vendor_table_address()); },
+                _ => {},
+            }
+        }
+        return output;
+    }
+
     pub fn get_entry(&self, index: usize) -> Option<ConfigurationTableEntry> {
         if index >= self.num_entries {
             return None;
@@ -101,6 +115,10 @@ impl ConfigurationTableEntry {
         }
     }
 
+    pub fn vendor_table_address(&self) -> u64 {
+        return self.vendor_table as u64;
+    }
+
     pub fn get_rsdp_v1(&self) -> Option<RsdpV1> {
         if self.get_type() == TableType::AcpiV1_0 {
             let rsdp_ptr = self.vendor_table as *mut RsdpV1;
@@ -62,11 +62,17 @@ impl FileProtocol {
         }
     }
 
-    pub fn read_struct<T: Default>(&self) -> Result<T, efi::Status> {
-        let mut size: usize = core::mem::size_of::<T>();
-        let output: T = Default::default();
-        self.read(&mut size, core::ptr::addr_of!(output) as *mut core::ffi::c_void)?;
-        return Ok(output);
+    pub fn get_position(&self) -> Result<u64, efi::Status> {
+        let mut pos: u64 = 0;
+        let s = unsafe {
+            ((*self.file_ptr).get_position)(self.file_ptr, &mut pos)
+        };
+
+        if s == efi::Status::SUCCESS {
+            return Ok(pos);
+        } else {
+            return Err(s);
+        }
     }
 
     pub fn set_position(&self, pos: u64) -> Result<(), efi::Status> {
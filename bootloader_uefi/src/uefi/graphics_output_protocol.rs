@@ -1,7 +1,26 @@
+use r_efi::efi;
 use r_efi::protocols::graphics_output;
-use bootinfo::FrameBuffer;
+use bootinfo::{FrameBuffer, PixelBitmask, PixelFormat};
 use x86_64_hardware::memory::PhysicalAddress;
 
+use crate::uefi::BootServices;
+
+/// One of the modes a GOP can be switched to
+#[derive(Clone, Copy)]
+pub struct VideoMode {
+    pub number: u32,
+    pub width: u32,
+    pub height: u32,
+    pub pixel_format: PixelFormat,
+}
+
+impl VideoMode {
+    /// True if the mode has a linear framebuffer the kernel can draw to
+    pub fn is_supported(&self) -> bool {
+        return self.pixel_format != PixelFormat::BltOnly;
+    }
+}
+
 pub struct GraphicsOutputProtocol {
     graphics_output_protocol_ptr: *mut graphics_output::Protocol,
 }
@@ -20,9 +39,61 @@ impl GraphicsOutputProtocol {
             self.mode().info().horizontal_resolution(),
             self.mode().info().vertical_resolution(),
             self.mode().info().pixels_per_scan_line(),
+            self.mode().info().pixel_format(),
         );
     }
 
+    /// The number of modes. Valid mode numbers run from 0 to one less than this.
+    pub fn max_mode(&self) -> u32 {
+        unsafe {
+            return (*self.mode().mode_ptr).max_mode;
+        }
+    }
+
+    pub fn current_mode(&self) -> u32 {
+        unsafe {
+            return (*self.mode().mode_ptr).mode;
+        }
+    }
+
+    /// Describes mode *number*. The firmware allocates the description from pool so it is
+    /// copied out and freed.
+    pub fn query_mode(&self, boot_services: &BootServices, number: u32) -> Result<VideoMode, efi::Status> {
+        let mut size_of_info: usize = 0;
+        let mut info_ptr: *mut graphics_output::ModeInformation = core::ptr::null_mut();
+        let s = unsafe {
+            ((*self.graphics_output_protocol_ptr).query_mode)(self.graphics_output_protocol_ptr, number, &mut size_of_info, &mut info_ptr)
+        };
+        if s != efi::Status::SUCCESS {
+            return Err(s);
+        }
+
+        let info = GopModeInfo::new(info_ptr);
+        let video_mode = VideoMode {
+            number: number,
+            width: info.horizontal_resolution(),
+            height: info.vertical_resolution(),
+            pixel_format: info.pixel_format(),
+        };
+        boot_services.free_pool(info_ptr)?;
+
+        return Ok(video_mode);
+    }
+
+    /// Switches to mode *number*. This clears the screen and moves the framebuffer so
+    /// get_framebuffer must be called again afterwards.
+    pub fn set_mode(&self, number: u32) -> Result<(), efi::Status> {
+        let s = unsafe {
+            ((*self.graphics_output_protocol_ptr).set_mode)(self.graphics_output_protocol_ptr, number)
+        };
+
+        if s != efi::Status::SUCCESS {
+            return Err(s);
+        } else {
+            return Ok(());
+        }
+    }
+
     fn mode(&self) -> GopMode {
         GopMode::new (
             unsafe {
@@ -92,4 +163,19 @@ impl GopModeInfo {
             return (*self.info_ptr).pixels_per_scan_line;
         }
     }
+
+    pub fn pixel_format(&self) -> PixelFormat {
+        let info = unsafe { &*self.info_ptr };
+        match info.pixel_format {
+            graphics_output::PIXEL_RED_GREEN_BLUE_RESERVED_8_BIT_PER_COLOR => PixelFormat::Rgb,
+            graphics_output::PIXEL_BLUE_GREEN_RED_RESERVED_8_BIT_PER_COLOR => PixelFormat::Bgr,
+            graphics_output::PIXEL_BIT_MASK => PixelFormat::Bitmask(PixelBitmask {
+                red: info.pixel_information.red_mask,
+                green: info.pixel_information.green_mask,
+                blue: info.pixel_information.blue_mask,
+                reserved: info.pixel_information.reserved_mask,
+            }),
+            _ => PixelFormat::BltOnly,
+        }
+    }
 }
@@ -0,0 +1,24 @@
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use acpi_system_tables::{RootTable, SignatureType, SystemDescriptionTable};
+
+use crate::memory::VIRTUAL_MEMORY_MANAGER;
+
+static RSDP_PHYSICAL_ADDRESS: AtomicU64 = AtomicU64::new(0);
+
+pub fn init(rsdp_physical_address: u64) {
+    RSDP_PHYSICAL_ADDRESS.store(rsdp_physical_address, Ordering::Relaxed);
+}
+
+/// Returns the first ACPI table with the given signature. The tables are read through
+/// the offset mapping of physical memory so this must not be called before the VMM
+/// is initialised.
+pub fn find_table(signature: SignatureType) -> Option<SystemDescriptionTable> {
+    let rsdp_physical_address = RSDP_PHYSICAL_ADDRESS.load(Ordering::Relaxed);
+    if rsdp_physical_address == 0 {
+        return None;
+    }
+
+    let root_table = unsafe { RootTable::from_rsdp(rsdp_physical_address, VIRTUAL_MEMORY_MANAGER.mapped_mem_offset()) }?;
+    return root_table.find_table(signature);
+}
@@ -0,0 +1,165 @@
+use acpi_system_tables::{InterruptPolarity, InterruptTriggerMode, MadtEntry, SignatureType};
+use logging::{debug, info, warn};
+use spin::{Mutex, Once};
+use x86_64_hardware::apic::*;
+use x86_64_hardware::memory::{PAGE_SIZE, PhysicalAddress, VirtualAddress};
+use x86_64_hardware::tables::{register_interrupt_handler, InterruptFrame};
+
+use crate::acpi;
+use crate::memory::VIRTUAL_MEMORY_MANAGER;
+
+const MAX_IO_APICS: usize = 8;
+const NUM_ISA_IRQS: usize = 16;
+const NO_IO_APIC: Option<IoApic> = None;
+
+pub const APIC_ERROR_VECTOR: u8 = 0xFE;
+pub const APIC_SPURIOUS_VECTOR: u8 = 0xFF;
+
+pub static LOCAL_APIC: Once<LocalApic> = Once::new();
+static IO_APICS: Mutex<[Option<IoApic>; MAX_IO_APICS]> = Mutex::new([NO_IO_APIC; MAX_IO_APICS]);
+static ISA_ROUTES: Mutex<[IsaRoute; NUM_ISA_IRQS]> = Mutex::new(IsaRoute::identity_table());
+
+/// Where an ISA IRQ arrives at the IO APICs. ISA IRQs are identity mapped to global system
+/// interrupts and are edge triggered active high unless the MADT overrides them.
+#[derive(Clone, Copy)]
+struct IsaRoute {
+    gsi: u32,
+    polarity: PinPolarity,
+    trigger_mode: TriggerMode,
+}
+
+impl IsaRoute {
+    const fn identity_table() -> [IsaRoute; NUM_ISA_IRQS] {
+        let mut table = [IsaRoute { gsi: 0, polarity: PinPolarity::ActiveHigh, trigger_mode: TriggerMode::Edge }; NUM_ISA_IRQS];
+        let mut irq = 0;
+        while irq < NUM_ISA_IRQS {
+            table[irq].gsi = irq as u32;
+            irq += 1;
+        }
+        return table;
+    }
+}
+
+/// Enables the local APIC of the bootstrap processor, preferring x2APIC mode. The PIC is
+/// left running so legacy IRQs keep working until the IO APIC takes over.
+pub fn init_local_apic() -> bool {
+    let mode = match supported_apic_mode() {
+        Some(mode) => mode,
+        None => {
+            warn!("No local APIC found");
+            return false;
+        }
+    };
+
+    let local_apic = LOCAL_APIC.call_once(|| match mode {
+        ApicMode::X2Apic => unsafe { LocalApic::new_x2apic() },
+        ApicMode::XApic => {
+            let virtual_address = VIRTUAL_MEMORY_MANAGER.map_mmio(apic_base_address(), XAPIC_MMIO_SIZE / PAGE_SIZE);
+            unsafe { LocalApic::new_xapic(virtual_address) }
+        },
+    });
+
+    register_interrupt_handler(APIC_SPURIOUS_VECTOR, spurious_interrupt_handler);
+    register_interrupt_handler(APIC_ERROR_VECTOR, error_interrupt_handler);
+    local_apic.enable(APIC_SPURIOUS_VECTOR);
+    local_apic.set_error_vector(APIC_ERROR_VECTOR);
+
+    info!("Local APIC {} enabled in {:?} mode, version {:#x}", local_apic.id(), local_apic.mode(), local_apic.version());
+    return true;
+}
+
+/// Enables the local APIC of an application processor using the mode chosen by the BSP
+pub fn enable_local_apic() {
+    let local_apic = LOCAL_APIC.get().unwrap();
+    local_apic.enable(APIC_SPURIOUS_VECTOR);
+    local_apic.set_error_vector(APIC_ERROR_VECTOR);
+}
+
+fn spurious_interrupt_handler(_frame: &mut InterruptFrame) {
+    //Spurious interrupts are not in service so they must not be acknowledged
+}
+
+fn error_interrupt_handler(_frame: &mut InterruptFrame) {
+    let local_apic = LOCAL_APIC.get().unwrap();
+    warn!("APIC error {:#x}", local_apic.error_status());
+    local_apic.end_of_interrupt();
+}
+
+/// Maps every IO APIC described by the MADT, masks all of their inputs and records the ISA
+/// interrupt source overrides. Returns false if there is no MADT or no IO APIC.
+pub fn init_io_apics() -> bool {
+    let madt = match acpi::find_table(SignatureType::APIC).and_then(|table| table.as_madt()) {
+        Some(madt) => madt,
+        None => {
+            warn!("No MADT found");
+            return false;
+        }
+    };
+
+    let mut io_apics = IO_APICS.lock();
+    let mut isa_routes = ISA_ROUTES.lock();
+    let mut num_io_apics = 0;
+
+    for entry in madt.iter() {
+        match entry {
+            MadtEntry::IoApic(io_apic_entry) => {
+                if num_io_apics == MAX_IO_APICS {
+                    warn!("Too many IO APICs. Ignoring IO APIC {}", io_apic_entry.io_apic_id());
+                    continue;
+                }
+
+                let physical_address = PhysicalAddress::new(io_apic_entry.address());
+                let page_offset = io_apic_entry.address() - physical_address.as_u64();
+                let num_pages = (page_offset + IO_APIC_MMIO_SIZE + PAGE_SIZE - 1) / PAGE_SIZE;
+                let virtual_address = VIRTUAL_MEMORY_MANAGER.map_mmio(physical_address, num_pages);
+                let register_address = VirtualAddress::new(virtual_address.as_u64() + page_offset);
+
+                let io_apic = unsafe { IoApic::new(register_address, io_apic_entry.global_system_interrupt_base()) };
+                io_apic.mask_all();
+                info!("IO APIC {} at {:#x} handles GSIs {} - {}", io_apic.id(), io_apic_entry.address(),
+                    io_apic.gsi_base(), io_apic.gsi_base() + io_apic.num_entries() - 1);
+                io_apics[num_io_apics] = Some(io_apic);
+                num_io_apics += 1;
+            },
+            MadtEntry::InterruptSourceOverride(source_override) => {
+                let irq = source_override.source() as usize;
+                if source_override.bus() != 0 || irq >= NUM_ISA_IRQS {
+                    continue;
+                }
+
+                isa_routes[irq].gsi = source_override.global_system_interrupt();
+                //Anything that conforms to the bus keeps the ISA defaults
+                if source_override.polarity() == InterruptPolarity::ActiveLow {
+                    isa_routes[irq].polarity = PinPolarity::ActiveLow;
+                }
+                if source_override.trigger_mode() == InterruptTriggerMode::Level {
+                    isa_routes[irq].trigger_mode = TriggerMode::Level;
+                }
+                debug!("ISA IRQ {} is routed to GSI {}", irq, source_override.global_system_interrupt());
+            },
+            _ => {},
+        }
+    }
+
+    return num_io_apics > 0;
+}
+
+/// The global system interrupt an ISA IRQ arrives on
+pub fn isa_irq_to_gsi(irq: u8) -> u32 {
+    return ISA_ROUTES.lock()[irq as usize % NUM_ISA_IRQS].gsi;
+}
+
+/// Routes a global system interrupt to *vector* on the processor with APIC ID *cpu*. GSIs
+/// that an ISA IRQ is routed to use its polarity and trigger mode, and any other GSI is
+/// treated as a level triggered, active low PCI interrupt. Returns false if no IO APIC
+/// handles *gsi*.
+pub fn route_irq(gsi: u32, vector: u8, cpu: u32) -> bool {
+    let (polarity, trigger_mode) = match ISA_ROUTES.lock().iter().find(|route| route.gsi == gsi) {
+        Some(route) => (route.polarity, route.trigger_mode),
+        None if gsi < NUM_ISA_IRQS as u32 => (PinPolarity::ActiveHigh, TriggerMode::Edge),
+        None => (PinPolarity::ActiveLow, TriggerMode::Level),
+    };
+
+    let entry = RedirectionEntry::new(vector, cpu as u8, polarity, trigger_mode);
+    return IO_APICS.lock().iter().flatten().any(|io_apic| io_apic.set_gsi_entry(gsi, entry));
+}
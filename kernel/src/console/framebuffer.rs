@@ -0,0 +1,359 @@
+use bootinfo::{FrameBuffer, PixelFormat};
+use graphics::PsfFont;
+use logging::{info, warn};
+use spin::Mutex;
+use x86_64_hardware::tables::without_interrupts;
+
+use crate::screen::screen_buffers;
+use super::{register_console, Console};
+
+/// The font built into the kernel, the public domain X11 "fixed" 8x13 font converted to PSF2
+pub static DEFAULT_FONT_DATA: &[u8] = include_bytes!("../../fonts/fixed-8x13.psfu");
+
+const TAB_WIDTH: usize = 8;
+const MAX_ESCAPE_PARAMETERS: usize = 8;
+
+/// The standard ANSI colours followed by their bright versions, as 0x00RRGGBB
+const ANSI_COLOURS: [u32; 16] = [
+    0x000000, 0xAA0000, 0x00AA00, 0xAA5500, 0x0000AA, 0xAA00AA, 0x00AAAA, 0xAAAAAA,
+    0x555555, 0xFF5555, 0x55FF55, 0xFFFF55, 0x5555FF, 0xFF55FF, 0x55FFFF, 0xFFFFFF,
+];
+const DEFAULT_FOREGROUND: u32 = ANSI_COLOURS[7];
+const DEFAULT_BACKGROUND: u32 = ANSI_COLOURS[0];
+
+/// Where the terminal is in an escape sequence
+#[derive(Clone, Copy, PartialEq)]
+enum EscapeState {
+    None,
+    /// After ESC
+    Escape,
+    /// After ESC [, collecting parameters
+    ControlSequence,
+}
+
+/// A text terminal drawn into a framebuffer. Text is drawn into a back buffer of 32 bit
+/// pixels in normal memory and the changed rows are copied to the framebuffer, so scrolling
+/// never reads the framebuffer, which is slow.
+struct FramebufferTerminal {
+    front_buffer: *mut u8,
+    back_buffer: *mut u32,
+    width: usize,
+    height: usize,
+    pixels_per_scan_line: usize,
+    pixel_format: PixelFormat,
+    bytes_per_pixel: usize,
+    font: PsfFont<'static>,
+    columns: usize,
+    rows: usize,
+    cursor_column: usize,
+    cursor_row: usize,
+    foreground: u32,
+    background: u32,
+    bold: bool,
+    escape_state: EscapeState,
+    escape_parameters: [u16; MAX_ESCAPE_PARAMETERS],
+    num_escape_parameters: usize,
+    /// The pixel rows changed since the last flush, as a half open range
+    dirty_start: usize,
+    dirty_end: usize,
+}
+
+//The buffers are only touched with the console lock held
+unsafe impl Send for FramebufferTerminal {}
+
+impl FramebufferTerminal {
+    /// ## Safety
+    ///
+    /// *front_buffer* must point at a mapped framebuffer of *pixels_per_scan_line* by
+    /// *height* pixels in *pixel_format* and *back_buffer* at *width* by *height* 32 bit
+    /// pixels of unused memory.
+    unsafe fn new(front_buffer: *mut u8, back_buffer: *mut u32, width: usize, height: usize, pixels_per_scan_line: usize, pixel_format: PixelFormat, font: PsfFont<'static>) -> FramebufferTerminal {
+        let mut terminal = FramebufferTerminal {
+            front_buffer: front_buffer,
+            back_buffer: back_buffer,
+            width: width,
+            height: height,
+            pixels_per_scan_line: pixels_per_scan_line,
+            pixel_format: pixel_format,
+            bytes_per_pixel: pixel_format.bytes_per_pixel(),
+            columns: width / font.width(),
+            rows: height / font.height(),
+            font: font,
+            cursor_column: 0,
+            cursor_row: 0,
+            foreground: pixel_format.pack_rgb(DEFAULT_FOREGROUND),
+            background: pixel_format.pack_rgb(DEFAULT_BACKGROUND),
+            bold: false,
+            escape_state: EscapeState::None,
+            escape_parameters: [0; MAX_ESCAPE_PARAMETERS],
+            num_escape_parameters: 0,
+            dirty_start: 0,
+            dirty_end: 0,
+        };
+
+        terminal.clear();
+        terminal.flush();
+        return terminal;
+    }
+
+    fn clear(&mut self) {
+        self.fill_pixel_rows(0, self.height, self.background);
+        self.cursor_column = 0;
+        self.cursor_row = 0;
+    }
+
+    fn write_str(&mut self, s: &str) {
+        for character in s.chars() {
+            self.write_char(character);
+        }
+        self.flush();
+    }
+
+    fn write_char(&mut self, character: char) {
+        match self.escape_state {
+            EscapeState::None => {},
+            EscapeState::Escape => {
+                if character == '[' {
+                    self.escape_state = EscapeState::ControlSequence;
+                    self.escape_parameters = [0; MAX_ESCAPE_PARAMETERS];
+                    self.num_escape_parameters = 0;
+                } else {
+                    self.escape_state = EscapeState::None;
+                }
+                return;
+            },
+            EscapeState::ControlSequence => {
+                self.control_sequence_char(character);
+                return;
+            }
+        }
+
+        match character {
+            '\x1b' => { self.escape_state = EscapeState::Escape; },
+            '\n' => { self.new_line(); },
+            '\r' => { self.cursor_column = 0; },
+            '\t' => {
+                let next_stop = (self.cursor_column / TAB_WIDTH + 1) * TAB_WIDTH;
+                while self.cursor_column < next_stop.min(self.columns) {
+                    self.write_char(' ');
+                }
+            },
+            '\x08' => {
+                if self.cursor_column > 0 {
+                    self.cursor_column -= 1;
+                }
+            },
+            _ => {
+                //Wrapping happens when the next character is printed so a line that exactly
+                //fills the screen is not followed by a blank line
+                if self.cursor_column == self.columns {
+                    self.new_line();
+                }
+                self.draw_glyph(character, self.cursor_column, self.cursor_row);
+                self.cursor_column += 1;
+            }
+        }
+    }
+
+    fn control_sequence_char(&mut self, character: char) {
+        match character {
+            '0'..='9' => {
+                if self.num_escape_parameters == 0 {
+                    self.num_escape_parameters = 1;
+                }
+                let index = self.num_escape_parameters - 1;
+                let digit = character as u16 - '0' as u16;
+                self.escape_parameters[index] = self.escape_parameters[index].saturating_mul(10).saturating_add(digit);
+            },
+            ';' => {
+                if self.num_escape_parameters == 0 {
+                    self.num_escape_parameters = 1;
+                }
+                if self.num_escape_parameters < MAX_ESCAPE_PARAMETERS {
+                    self.num_escape_parameters += 1;
+                }
+            },
+            'm' => {
+                self.select_graphic_rendition();
+                self.escape_state = EscapeState::None;
+            },
+            'J' => {
+                if self.escape_parameters[0] == 2 {
+                    self.clear();
+                }
+                self.escape_state = EscapeState::None;
+            },
+            'H' => {
+                self.cursor_row = (self.escape_parameters[0].max(1) as usize - 1).min(self.rows - 1);
+                self.cursor_column = (self.escape_parameters[1].max(1) as usize - 1).min(self.columns - 1);
+                self.escape_state = EscapeState::None;
+            },
+            //Anything else ends the sequence and is ignored
+            '\x40'..='\x7e' => { self.escape_state = EscapeState::None; },
+            _ => {}
+        }
+    }
+
+    /// Handles ESC [ ... m, which sets the colours
+    fn select_graphic_rendition(&mut self) {
+        if self.num_escape_parameters == 0 {
+            self.num_escape_parameters = 1;
+        }
+
+        for index in 0..self.num_escape_parameters {
+            let parameter = self.escape_parameters[index] as usize;
+            match parameter {
+                0 => {
+                    self.foreground = self.pixel_format.pack_rgb(DEFAULT_FOREGROUND);
+                    self.background = self.pixel_format.pack_rgb(DEFAULT_BACKGROUND);
+                    self.bold = false;
+                },
+                1 => { self.bold = true; },
+                22 => { self.bold = false; },
+                30..=37 => { self.foreground = self.pixel_format.pack_rgb(ANSI_COLOURS[parameter - 30 + if self.bold { 8 } else { 0 }]); },
+                39 => { self.foreground = self.pixel_format.pack_rgb(DEFAULT_FOREGROUND); },
+                40..=47 => { self.background = self.pixel_format.pack_rgb(ANSI_COLOURS[parameter - 40]); },
+                49 => { self.background = self.pixel_format.pack_rgb(DEFAULT_BACKGROUND); },
+                90..=97 => { self.foreground = self.pixel_format.pack_rgb(ANSI_COLOURS[parameter - 90 + 8]); },
+                100..=107 => { self.background = self.pixel_format.pack_rgb(ANSI_COLOURS[parameter - 100 + 8]); },
+                _ => {}
+            }
+        }
+    }
+
+    fn new_line(&mut self) {
+        self.cursor_column = 0;
+        if self.cursor_row + 1 < self.rows {
+            self.cursor_row += 1;
+        } else {
+            self.scroll_up();
+        }
+    }
+
+    /// Moves every text row up by one and clears the last row
+    fn scroll_up(&mut self) {
+        let row_pixels = self.font.height() * self.width;
+        let text_pixels = self.rows * row_pixels;
+        unsafe {
+            core::ptr::copy(self.back_buffer.add(row_pixels), self.back_buffer, text_pixels - row_pixels);
+        }
+
+        let last_row_start = (self.rows - 1) * self.font.height();
+        self.fill_pixel_rows(last_row_start, last_row_start + self.font.height(), self.background);
+        self.mark_dirty(0, self.rows * self.font.height());
+    }
+
+    fn draw_glyph(&mut self, character: char, column: usize, row: usize) {
+        let glyph = self.font.glyph(character);
+        let bytes_per_row = self.font.bytes_per_row();
+        let x = column * self.font.width();
+        let y = row * self.font.height();
+
+        for glyph_row in 0..self.font.height() {
+            let bits = &glyph[glyph_row * bytes_per_row..(glyph_row + 1) * bytes_per_row];
+            let line = unsafe { self.back_buffer.add((y + glyph_row) * self.width + x) };
+            for glyph_column in 0..self.font.width() {
+                let set = bits[glyph_column / 8] & (0x80 >> (glyph_column % 8)) != 0;
+                unsafe { *line.add(glyph_column) = if set { self.foreground } else { self.background }; }
+            }
+        }
+        self.mark_dirty(y, y + self.font.height());
+    }
+
+    fn fill_pixel_rows(&mut self, start: usize, end: usize, colour: u32) {
+        for pixel in start * self.width..end * self.width {
+            unsafe { *self.back_buffer.add(pixel) = colour; }
+        }
+        self.mark_dirty(start, end);
+    }
+
+    fn mark_dirty(&mut self, start: usize, end: usize) {
+        if self.dirty_start == self.dirty_end {
+            self.dirty_start = start;
+            self.dirty_end = end;
+        } else {
+            self.dirty_start = self.dirty_start.min(start);
+            self.dirty_end = self.dirty_end.max(end);
+        }
+    }
+
+    /// Copies the changed rows of the back buffer to the framebuffer. The back buffer holds
+    /// packed pixels so 32 bit framebuffers are a straight copy and narrower ones only need
+    /// the low bytes of each pixel.
+    fn flush(&mut self) {
+        for y in self.dirty_start..self.dirty_end {
+            let source = unsafe { self.back_buffer.add(y * self.width) };
+            let destination = unsafe { self.front_buffer.add(y * self.pixels_per_scan_line * self.bytes_per_pixel) };
+            if self.bytes_per_pixel == 4 {
+                unsafe { core::ptr::copy_nonoverlapping(source, destination as *mut u32, self.width); }
+            } else {
+                for x in 0..self.width {
+                    unsafe {
+                        let pixel = (*source.add(x)).to_le_bytes();
+                        core::ptr::copy_nonoverlapping(pixel.as_ptr(), destination.add(x * self.bytes_per_pixel), self.bytes_per_pixel);
+                    }
+                }
+            }
+        }
+        self.dirty_start = 0;
+        self.dirty_end = 0;
+    }
+}
+
+/// A console drawn on the GOP framebuffer
+pub struct FramebufferConsole {
+    terminal: Mutex<Option<FramebufferTerminal>>,
+}
+
+impl Console for FramebufferConsole {
+    fn name(&self) -> &str {
+        return "framebuffer";
+    }
+
+    fn write_str(&self, s: &str) {
+        without_interrupts(|| {
+            if let Some(terminal) = self.terminal.lock().as_mut() {
+                terminal.write_str(s);
+            }
+        });
+    }
+}
+
+pub static FRAMEBUFFER_CONSOLE: FramebufferConsole = FramebufferConsole { terminal: Mutex::new(None) };
+
+/// Takes over the framebuffer the bootloader set up, with the back buffer the boot splash
+/// used if there was one, and registers it as a console. The log so far is drawn as it is registered.
+pub fn init_framebuffer_console(framebuffer: &FrameBuffer) -> bool {
+    let width = framebuffer.width as usize;
+    let height = framebuffer.height as usize;
+    let pixels_per_scan_line = framebuffer.pixels_per_scan_line as usize;
+    if !framebuffer.is_linear() {
+        warn!("No linear framebuffer. The framebuffer console is not available");
+        return false;
+    }
+
+    let font = match PsfFont::parse(DEFAULT_FONT_DATA) {
+        Some(font) => font,
+        None => {
+            warn!("The built in console font is not a valid PSF font");
+            return false;
+        }
+    };
+
+    if width < font.width() || height < font.height() {
+        warn!("The framebuffer is too small for the console font");
+        return false;
+    }
+
+    let buffers = screen_buffers(framebuffer);
+    let terminal = unsafe { FramebufferTerminal::new(buffers.front_buffer, buffers.back_buffer, width, height, pixels_per_scan_line, framebuffer.pixel_format, font) };
+    let (columns, rows) = (terminal.columns, terminal.rows);
+    *FRAMEBUFFER_CONSOLE.terminal.lock() = Some(terminal);
+
+    if !register_console(&FRAMEBUFFER_CONSOLE) {
+        warn!("No space to register the framebuffer console");
+        return false;
+    }
+    info!("Framebuffer console {}x{} at {}x{} pixels", columns, rows, width, height);
+    return true;
+}
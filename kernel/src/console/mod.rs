@@ -0,0 +1,99 @@
+use core::fmt;
+
+use spin::Mutex;
+use x86_64_hardware::tables::without_interrupts;
+
+use crate::log::{replay_log, LOG_BUFFER};
+
+mod framebuffer;
+mod serial;
+
+pub use framebuffer::*;
+pub use serial::*;
+
+pub const MAX_CONSOLES: usize = 8;
+
+/// Something kernel output can be written to. Implementations do their own locking as
+/// output can come from any processor.
+pub trait Console: Sync {
+    fn name(&self) -> &str;
+
+    fn write_str(&self, s: &str);
+}
+
+/// Adapts a console to fmt::Write so formatted output is written without a buffer
+struct ConsoleWriter<'a>(&'a dyn Console);
+
+impl fmt::Write for ConsoleWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.write_str(s);
+        Ok(())
+    }
+}
+
+/// COM1 is registered from the start so early output is not lost
+static CONSOLES: Mutex<[Option<&'static dyn Console>; MAX_CONSOLES]> = Mutex::new(initial_consoles());
+
+const fn initial_consoles() -> [Option<&'static dyn Console>; MAX_CONSOLES] {
+    let mut consoles: [Option<&'static dyn Console>; MAX_CONSOLES] = [None; MAX_CONSOLES];
+    consoles[0] = Some(&SERIAL_CONSOLES[0]);
+    return consoles;
+}
+
+/// Adds a console that all kernel output is sent to. The log so far is replayed to it first.
+/// Returns false if it is already registered or there is no space left.
+pub fn register_console(console: &'static dyn Console) -> bool {
+    return without_interrupts(|| {
+        //The log buffer is held until the console is in the list so no message is missed
+        //or written twice
+        let log_buffer = LOG_BUFFER.lock();
+        let mut consoles = CONSOLES.lock();
+        if consoles.iter().flatten().any(|registered| core::ptr::addr_eq(*registered, console)) {
+            return false;
+        }
+
+        match consoles.iter_mut().find(|slot| slot.is_none()) {
+            Some(slot) => {
+                replay_log(&log_buffer, console);
+                *slot = Some(console);
+                return true;
+            },
+            None => { return false; }
+        }
+    });
+}
+
+/// Stops sending kernel output to a console. Returns false if it was not registered.
+pub fn unregister_console(console: &'static dyn Console) -> bool {
+    return without_interrupts(|| {
+        let mut consoles = CONSOLES.lock();
+        match consoles.iter_mut().find(|slot| slot.is_some_and(|registered| core::ptr::addr_eq(registered, console))) {
+            Some(slot) => {
+                *slot = None;
+                return true;
+            },
+            None => { return false; }
+        }
+    });
+}
+
+#[macro_export]
+macro_rules! print {
+    ($($arg:tt)*) => ($crate::console::_print(format_args!($($arg)*)));
+}
+
+#[macro_export]
+macro_rules! println {
+    () => ($crate::print!("\n"));
+    ($($arg:tt)*) => ($crate::print!("{}\n", format_args!($($arg)*)));
+}
+
+#[doc(hidden)]
+pub fn _print(args: fmt::Arguments) {
+    //Copy the list so the lock is not held while writing. Interrupts are off while it is
+    //held as an interrupt handler that prints would spin on it forever.
+    let consoles = without_interrupts(|| *CONSOLES.lock());
+    for console in consoles.iter().flatten() {
+        let _ = fmt::write(&mut ConsoleWriter(*console), args);
+    }
+}
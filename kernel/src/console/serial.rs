@@ -0,0 +1,203 @@
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+use acpi_system_tables::{AddressSpace, GenericAddress, SignatureType};
+use logging::{debug, info, warn};
+use spin::{Mutex, Once};
+use x86_64_hardware::devices::uart_16550::{baud_divisor, SerialBuffers, SerialInterruptHandler, SerialPort, COM1, COM2, COM3, COM4, DEFAULT_UART_CLOCK_HZ};
+use x86_64_hardware::memory::{PAGE_SIZE, PhysicalAddress, VirtualAddress};
+use x86_64_hardware::tables::{without_interrupts, InterruptFrame};
+
+use crate::acpi;
+use crate::irq;
+use crate::memory::VIRTUAL_MEMORY_MANAGER;
+use super::{register_console, unregister_console, Console};
+
+/// The size of a 16550 register block with 32 bit register spacing
+const UART_MMIO_SIZE: u64 = 8 * 4;
+const COM1_IRQ: u8 = 4;
+const NO_IRQ: u8 = 0xFF;
+/// SPCR interrupt type bit for a PC-AT compatible IRQ
+const SPCR_INTERRUPT_TYPE_PC_AT: u8 = 1 << 0;
+
+/// The ISA IRQ of the console UART, or NO_IRQ if it can only be polled
+static CONSOLE_IRQ: AtomicU8 = AtomicU8::new(COM1_IRQ);
+/// Set once a port described by the firmware has replaced the early COM1 port
+static FIRMWARE_CONSOLE: AtomicBool = AtomicBool::new(false);
+static CONSOLE_BUFFERS: SerialBuffers = SerialBuffers::new();
+static CONSOLE_INTERRUPT_HANDLER: Once<SerialInterruptHandler> = Once::new();
+
+/// A serial port used as a console
+pub struct SerialConsole {
+    name: &'static str,
+    port: &'static Mutex<SerialPort>,
+}
+
+impl Console for SerialConsole {
+    fn name(&self) -> &str {
+        return self.name;
+    }
+
+    fn write_str(&self, s: &str) {
+        use core::fmt::Write;
+        //An interrupt that prints while the port is locked would deadlock
+        without_interrupts(|| {
+            let _ = self.port.lock().write_str(s);
+        });
+    }
+}
+
+pub static SERIAL_CONSOLES: [SerialConsole; 4] = [
+    SerialConsole { name: "COM1", port: &COM1 },
+    SerialConsole { name: "COM2", port: &COM2 },
+    SerialConsole { name: "COM3", port: &COM3 },
+    SerialConsole { name: "COM4", port: &COM4 },
+];
+
+struct ConsoleDescription {
+    base_address: GenericAddress,
+    baud_rate: Option<u32>,
+    clock_hz: u32,
+    irq: Option<u8>,
+}
+
+/// Picks the serial console from the SPCR, then the DBG2, and replaces the early COM1 port
+/// with it. COM1 is kept when the firmware does not describe a usable 16550.
+pub fn init_serial_console() {
+    let description = match find_spcr_console().or_else(find_dbg2_console) {
+        Some(description) => description,
+        None => {
+            info!("No firmware serial console found. Using COM1");
+            return;
+        }
+    };
+
+    let base_address = description.base_address;
+    let port = match base_address.address_space() {
+        AddressSpace::SystemIo => unsafe { SerialPort::new(base_address.address() as u16) },
+        AddressSpace::SystemMemory => {
+            let physical_address = PhysicalAddress::new(base_address.address());
+            let page_offset = base_address.address() - physical_address.as_u64();
+            let num_pages = (page_offset + UART_MMIO_SIZE + PAGE_SIZE - 1) / PAGE_SIZE;
+            let virtual_address = VIRTUAL_MEMORY_MANAGER.map_mmio(physical_address, num_pages);
+            let register_address = VirtualAddress::new(virtual_address.as_u64() + page_offset);
+            unsafe { SerialPort::new_mmio(register_address, register_width(&base_address)) }
+        },
+        _ => {
+            warn!("Serial console is in an unsupported address space. Using COM1");
+            return;
+        }
+    };
+
+    match description.baud_rate {
+        Some(baud_rate) => port.initialise_with_divisor(baud_divisor(description.clock_hz, baud_rate)),
+        None => port.initialise_preserving_baud(),
+    }
+
+    debug!("Switching console to {:?} {:#x}", base_address.address_space(), base_address.address());
+    //All early output goes through COM1 so the firmware console takes over its slot
+    *COM1.lock() = port;
+    FIRMWARE_CONSOLE.store(true, Ordering::Relaxed);
+    CONSOLE_IRQ.store(description.irq.unwrap_or(NO_IRQ), Ordering::Relaxed);
+    info!("Console initialised from firmware tables");
+}
+
+/// Probes COM1-COM4 and registers each one that passes its self-test as a console. COM1 is
+/// registered from the start for early output and is removed again if nothing answers
+/// there, unless the firmware described the port in its slot. A port the firmware console
+/// has already moved into the COM1 slot is skipped so it is not written to twice.
+pub fn init_serial_ports() {
+    if !FIRMWARE_CONSOLE.load(Ordering::Relaxed) {
+        let port = COM1.lock();
+        let present = port.probe();
+        if present {
+            port.initialise();
+        }
+        drop(port);
+
+        if !present {
+            unregister_console(&SERIAL_CONSOLES[0]);
+            info!("No serial port found at COM1");
+        }
+    }
+
+    let console_base = COM1.lock().io_base();
+
+    for console in SERIAL_CONSOLES[1..].iter() {
+        let port = console.port.lock();
+        if port.io_base().is_some() && port.io_base() == console_base {
+            continue;
+        }
+        if !port.probe() {
+            continue;
+        }
+
+        port.initialise();
+        drop(port);
+        if register_console(console) {
+            info!("Found serial port {}", console.name());
+        }
+    }
+}
+
+/// Switches the console to interrupt driven IO. The console stays polled if its IRQ is not
+/// known. IRQ routing must be set up first.
+pub fn init_serial_interrupts() -> bool {
+    let console_irq = CONSOLE_IRQ.load(Ordering::Relaxed);
+    if console_irq == NO_IRQ {
+        warn!("Console IRQ unknown. The console stays polled");
+        return false;
+    }
+
+    without_interrupts(|| {
+        irq::enable_isa_irq(console_irq, serial_interrupt_handler);
+        let handler = COM1.lock().enable_interrupts(&CONSOLE_BUFFERS);
+        CONSOLE_INTERRUPT_HANDLER.call_once(|| handler);
+    });
+    info!("Console is interrupt driven on IRQ {}", console_irq);
+    return true;
+}
+
+fn serial_interrupt_handler(_frame: &mut InterruptFrame) {
+    if let Some(handler) = CONSOLE_INTERRUPT_HANDLER.get() {
+        handler.handle_interrupt();
+    }
+    irq::end_of_isa_irq(CONSOLE_IRQ.load(Ordering::Relaxed));
+}
+
+fn find_spcr_console() -> Option<ConsoleDescription> {
+    let spcr = acpi::find_table(SignatureType::SPCR)?.as_spcr()?;
+    if !spcr.interface_type().is_16550_compatible() || spcr.base_address().is_null() {
+        return None;
+    }
+
+    return Some(ConsoleDescription {
+        base_address: spcr.base_address(),
+        baud_rate: spcr.baud_rate(),
+        clock_hz: spcr.uart_clock_frequency(),
+        irq: if spcr.interrupt_type() & SPCR_INTERRUPT_TYPE_PC_AT != 0 { Some(spcr.irq()) } else { None },
+    });
+}
+
+fn find_dbg2_console() -> Option<ConsoleDescription> {
+    let dbg2 = acpi::find_table(SignatureType::DBG2)?.as_dbg2()?;
+    let device = dbg2.find_16550()?;
+    let base_address = device.base_address(0)?;
+    if base_address.is_null() {
+        return None;
+    }
+
+    //The DBG2 does not describe the baud rate so leave it as the firmware configured it
+    return Some(ConsoleDescription {
+        base_address: base_address,
+        baud_rate: None,
+        clock_hz: DEFAULT_UART_CLOCK_HZ,
+        irq: None,
+    });
+}
+
+fn register_width(base_address: &GenericAddress) -> u8 {
+    if base_address.access_size_bytes() == 4 || base_address.register_bit_width() == 32 {
+        return 4;
+    }
+    return 1;
+}
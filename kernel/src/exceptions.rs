@@ -0,0 +1,39 @@
+use logging::error;
+use x86_64_hardware::cpu::read_cr2;
+use x86_64_hardware::tables::{register_interrupt_handler, InterruptFrame, FIRST_EXTERNAL_VECTOR};
+
+use crate::{smp, symbols};
+
+const PAGE_FAULT_VECTOR: u64 = 14;
+
+const EXCEPTION_NAMES: [&str; FIRST_EXTERNAL_VECTOR as usize] = [
+    "divide error", "debug", "non-maskable interrupt", "breakpoint",
+    "overflow", "bound range exceeded", "invalid opcode", "device not available",
+    "double fault", "coprocessor segment overrun", "invalid TSS", "segment not present",
+    "stack segment fault", "general protection fault", "page fault", "reserved",
+    "x87 floating point error", "alignment check", "machine check", "SIMD floating point error",
+    "virtualisation exception", "control protection exception", "reserved", "reserved",
+    "reserved", "reserved", "reserved", "reserved",
+    "hypervisor injection exception", "VMM communication exception", "security exception", "reserved",
+];
+
+/// Reports CPU exceptions with the faulting instruction resolved to a kernel symbol. Nothing
+/// recovers from exceptions yet so the report ends in a panic.
+pub fn init() {
+    for vector in 0..FIRST_EXTERNAL_VECTOR {
+        register_interrupt_handler(vector, exception_handler);
+    }
+}
+
+fn exception_handler(frame: &mut InterruptFrame) {
+    error!("Exception {}, {}, error code {:#x}", frame.vector, EXCEPTION_NAMES[frame.vector as usize], frame.error_code);
+    if smp::per_cpu_ready() {
+        error!("  on CPU {} running thread {}", smp::current_cpu().cpu_index(), smp::current_thread());
+    }
+    symbols::log_address("  faulting instruction", frame.rip);
+    if frame.vector == PAGE_FAULT_VECTOR {
+        error!("  faulting address {:#018x}", read_cr2());
+    }
+    //The panic handler's backtrace runs from here into the callers of the faulting function
+    panic!("Unhandled exception {} at {:#x}", frame.vector, frame.rip);
+}
@@ -13,4 +13,6 @@ stack_top:
 # rdi - Pointer to the BootInfo struct. This is just passed onto kernel_main
 _start:
     movq $stack_top, %rsp
+    # A null frame pointer marks the end of the chain for backtraces
+    xorq %rbp, %rbp
     call kernel_main
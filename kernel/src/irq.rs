@@ -0,0 +1,51 @@
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use logging::{info, warn};
+use x86_64_hardware::devices::pic_8259::{PICS, PIC_MASTER_OFFSET};
+use x86_64_hardware::tables::{register_interrupt_handler, without_interrupts, InterruptHandler};
+
+use crate::apic::{self, LOCAL_APIC};
+
+static USING_IO_APIC: AtomicBool = AtomicBool::new(false);
+
+/// ISA IRQs keep the vectors the PIC was remapped to whichever controller delivers them
+pub fn isa_irq_vector(irq: u8) -> u8 {
+    return PIC_MASTER_OFFSET + irq;
+}
+
+/// Moves legacy IRQ delivery from the PIC to the IO APICs when the local APIC is running
+/// and the MADT describes at least one IO APIC. The PIC is masked once the switch is made.
+pub fn init_irq_routing() {
+    if LOCAL_APIC.get().is_some() && apic::init_io_apics() {
+        without_interrupts(|| PICS.lock().disable());
+        USING_IO_APIC.store(true, Ordering::Relaxed);
+        info!("Legacy IRQs routed through the IO APIC");
+    } else {
+        info!("Legacy IRQs routed through the PIC");
+    }
+}
+
+/// Installs *handler* for an ISA IRQ and unmasks it. The handler must call
+/// *end_of_isa_irq* once it has serviced the device.
+pub fn enable_isa_irq(irq: u8, handler: InterruptHandler) {
+    without_interrupts(|| {
+        register_interrupt_handler(isa_irq_vector(irq), handler);
+
+        if USING_IO_APIC.load(Ordering::Relaxed) {
+            let cpu = LOCAL_APIC.get().unwrap().id();
+            if !apic::route_irq(apic::isa_irq_to_gsi(irq), isa_irq_vector(irq), cpu) {
+                warn!("No IO APIC handles ISA IRQ {}", irq);
+            }
+        } else {
+            PICS.lock().unmask(irq);
+        }
+    });
+}
+
+pub fn end_of_isa_irq(irq: u8) {
+    if USING_IO_APIC.load(Ordering::Relaxed) {
+        LOCAL_APIC.get().unwrap().end_of_interrupt();
+    } else {
+        PICS.lock().end_of_interrupt(irq);
+    }
+}
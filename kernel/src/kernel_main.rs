@@ -1,13 +1,21 @@
+use logging::{debug, error, info};
 use x86_64_hardware::memory::VirtualAddress;
 use x86_64_hardware::memory::paging::PageTableManager;
-use x86_64_hardware::{com1_println, devices::uart_16550::COM1};
+use x86_64_hardware::{devices::pic_8259::PICS, devices::uart_16550::COM1};
 use x86_64_hardware::tables::*;
 
-use crate::memory::{TEMP_ALLOC, FRAME_ALLOCATOR, get_pmm_functions, VIRTUAL_MEMORY_MANAGER};
+use crate::{acpi, apic, console, exceptions, irq, log, pci, smp, splash, symbols, time};
+use crate::memory::{TEMP_ALLOC, FRAME_ALLOCATOR, get_pmm_functions, init_numa, init_page_attributes, VIRTUAL_MEMORY_MANAGER};
 
+/// The number of times kernel_main advances the boot splash progress bar, plus one for
+/// finishing
+const BOOT_STAGES: u32 = 6;
 
 #[panic_handler]
-fn panic_handler(_info: &core::panic::PanicInfo) -> ! {
+fn panic_handler(info: &core::panic::PanicInfo) -> ! {
+    disable_interrupts();
+    error!("Kernel panic: {}", info);
+    symbols::log_current_backtrace();
     loop {}
 }
 
@@ -16,13 +24,21 @@ pub extern "C" fn kernel_main(bootinfo: *mut bootinfo::BootInfo) {
     COM1.lock().initialise();
 
     if !unsafe { (*bootinfo).valid_magic() } {
-        com1_println!("Invalid BootInfo header!");
+        log::init_logging("");
+        error!("Invalid BootInfo header!");
         loop { }
     }
+    log::init_logging(unsafe { (*bootinfo).command_line() });
 
-    com1_println!("Starting kernel initialisation!");
-    init_default_gdt();
-    com1_println!("Loaded GDT!");
+    info!("Starting kernel initialisation!");
+    smp::init_bsp_per_cpu();
+    init_page_attributes();
+    debug!("Loaded GDT and per-CPU data!");
+    PICS.lock().initialise();
+    debug!("Remapped PIC!");
+    init_default_idt();
+    exceptions::init();
+    debug!("Loaded IDT!");
 
     let meminfo = unsafe { (*bootinfo).meminfo.move_out() };
 
@@ -35,18 +51,59 @@ pub extern "C" fn kernel_main(bootinfo: *mut bootinfo::BootInfo) {
     for index in 0..256usize {
         page_table_manager.unmap_p4_index(index, &FRAME_ALLOCATOR);
     }
-    com1_println!("After identity map cleared!");
+    debug!("After identity map cleared!");
 
     let kernel_heap_base = VirtualAddress::new(0xFFFF800000000000);
     VIRTUAL_MEMORY_MANAGER.init(mem_map_offset, page_table_manager.get_p4_address(), true, kernel_heap_base);
-    com1_println!("After VMM initialised!");
+    debug!("After VMM initialised!");
     VIRTUAL_MEMORY_MANAGER.alter_heap(0, 1);
 
     unsafe {
         let test_ptr = kernel_heap_base.get_mut_ptr::<u8>();
         *test_ptr = 5;
     }
-    com1_println!("After heap access!");
+    debug!("After heap access!");
+    symbols::init(unsafe { &(*bootinfo).kernel_symbols });
+    let framebuffer = unsafe { &(*bootinfo).framebuffer };
+    if unsafe { (*bootinfo).video_mode } != bootinfo::NO_VIDEO_MODE {
+        info!("Video mode {}: {}x{} {:?}", unsafe { (*bootinfo).video_mode }, framebuffer.width, framebuffer.height, framebuffer.pixel_format);
+    }
+
+    acpi::init(unsafe { (*bootinfo).rsdp_physical_address });
+    //The console takes over the screen once the splash is finished with it
+    if !splash::init(framebuffer, unsafe { (*bootinfo).command_line() }, BOOT_STAGES) {
+        console::init_framebuffer_console(framebuffer);
+    }
+
+    console::init_serial_console();
+    console::init_serial_ports();
+    splash::advance();
+    init_numa();
+    apic::init_local_apic();
+    irq::init_irq_routing();
+    splash::advance();
+    pci::init();
+    splash::advance();
+    time::init_hpet();
+    time::init_tsc();
+    time::init_pit(1000);
+    time::init_clock_source();
+    time::init_wall_clock();
+    splash::advance();
+    enable_interrupts();
+    info!("Interrupts enabled!");
+    console::init_serial_interrupts();
 
-    loop { }
+    splash::advance();
+    smp::start_application_processors();
+    if splash::finish() {
+        console::init_framebuffer_console(framebuffer);
+    }
+    if let Some(now) = time::wall_clock_now() {
+        info!("Kernel initialised at {}", now);
+    }
+
+    loop {
+        wait_for_interrupt();
+    }
 }
\ No newline at end of file
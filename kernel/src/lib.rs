@@ -1,5 +1,17 @@
 #![no_main]
 #![no_std]
 
+mod acpi;
+mod apic;
+mod console;
+mod exceptions;
+mod irq;
 mod kernel_main;
+mod log;
 mod memory;
+mod pci;
+mod screen;
+mod smp;
+mod splash;
+mod symbols;
+mod time;
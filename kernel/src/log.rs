@@ -0,0 +1,71 @@
+use core::fmt::Write;
+
+use logging::{Filter, LevelFilter, LineBuffer, LogBuffer, Logger, Record};
+use spin::Mutex;
+use x86_64_hardware::tables::without_interrupts;
+
+use crate::console::{self, Console};
+use crate::time;
+
+const LOG_BUFFER_SIZE: usize = 64 * 1024;
+const MAX_LOG_LINE_LEN: usize = 256;
+
+/// Every message logged since boot, or as much as fits. It is replayed to each console as it
+/// is registered so output from before a console existed is not lost. Lock it with
+/// interrupts disabled as interrupt handlers log too.
+pub static LOG_BUFFER: Mutex<LogBuffer<LOG_BUFFER_SIZE>> = Mutex::new(LogBuffer::new());
+
+struct KernelLogger;
+
+static KERNEL_LOGGER: KernelLogger = KernelLogger;
+
+impl Logger for KernelLogger {
+    fn log(&self, record: &Record) {
+        let nanoseconds = time::monotonic_nanoseconds();
+        let mut line = LineBuffer::<MAX_LOG_LINE_LEN>::new();
+        let _ = write!(line, "[{:5}.{:06}] {:<5} {}: {}",
+            nanoseconds / 1_000_000_000, nanoseconds % 1_000_000_000 / 1000, record.level, record.module, record.args);
+
+        //The buffer stays locked while the line is printed so a console registered in between
+        //gets it either from the replay or from here, never both
+        without_interrupts(|| {
+            let mut log_buffer = LOG_BUFFER.lock();
+            log_buffer.push_line(line.as_str().as_bytes());
+            console::_print(format_args!("{}\n", line.as_str()));
+        });
+    }
+}
+
+/// Installs the kernel logger and sets the filter from the loglevel= and log= options on
+/// the command line. Messages logged before this are dropped so it should run first.
+pub fn init_logging(command_line: &str) {
+    logging::set_logger(&KERNEL_LOGGER);
+    match Filter::from_command_line(command_line) {
+        Ok(filter) => { logging::set_filter(filter); },
+        Err(error) => {
+            logging::set_filter(Filter::new(LevelFilter::Info));
+            logging::warn!("Ignoring invalid log options on the command line: {:?}", error);
+        }
+    }
+}
+
+/// Writes the contents of the log buffer to *console*. The caller holds the buffer lock so
+/// nothing can be logged part way through.
+pub fn replay_log(log_buffer: &LogBuffer<LOG_BUFFER_SIZE>, console: &dyn Console) {
+    if log_buffer.dropped_lines() != 0 {
+        let mut line = LineBuffer::<64>::new();
+        let _ = write!(line, "({} older lines were dropped)\n", log_buffer.dropped_lines());
+        console.write_str(line.as_str());
+    }
+
+    let (first, second) = log_buffer.as_slices();
+    for part in [first, second] {
+        //A character split where the ring wraps comes out as a replacement character
+        for chunk in part.utf8_chunks() {
+            console.write_str(chunk.valid());
+            if !chunk.invalid().is_empty() {
+                console.write_str("\u{FFFD}");
+            }
+        }
+    }
+}
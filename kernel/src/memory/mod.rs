@@ -1,7 +1,9 @@
+mod numa;
 mod physical_frame_allocator;
 mod temp_allocator;
 mod virtual_memory_manager;
 
+pub use numa::*;
 pub use physical_frame_allocator::*;
 pub use temp_allocator::*;
 pub use virtual_memory_manager::*;
\ No newline at end of file
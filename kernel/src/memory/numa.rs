@@ -0,0 +1,208 @@
+use acpi_system_tables::{SignatureType, SratEntry, SLIT_LOCAL_DISTANCE};
+use logging::{debug, info, warn};
+use x86_64_hardware::memory::PhysicalAddress;
+
+use crate::acpi;
+use super::FRAME_ALLOCATOR;
+
+pub const MAX_NUMA_NODES: usize = 8;
+const MAX_MEMORY_RANGES: usize = 32;
+const MAX_APIC_IDS: usize = 256;
+const NO_NODE: u8 = 0xFF;
+const REMOTE_DISTANCE: u8 = 20;
+
+#[derive(Clone, Copy)]
+pub struct NodeMemoryRange {
+    pub start: u64,
+    pub end: u64,
+    pub node: u8,
+}
+
+impl NodeMemoryRange {
+    const fn empty() -> NodeMemoryRange {
+        return NodeMemoryRange { start: 0, end: 0, node: NO_NODE };
+    }
+
+    pub fn contains(&self, address: PhysicalAddress) -> bool {
+        return self.start <= address.as_u64() && address.as_u64() < self.end;
+    }
+}
+
+/// The NUMA layout of the machine as described by the SRAT and SLIT. Proximity domains are
+/// mapped onto dense node indexes so they can be used to index per-node data.
+#[derive(Clone, Copy)]
+pub struct NumaTopology {
+    num_nodes: usize,
+    proximity_domains: [u32; MAX_NUMA_NODES],
+    memory_ranges: [NodeMemoryRange; MAX_MEMORY_RANGES],
+    num_memory_ranges: usize,
+    apic_nodes: [u8; MAX_APIC_IDS],
+    distances: [[u8; MAX_NUMA_NODES]; MAX_NUMA_NODES],
+}
+
+impl NumaTopology {
+    /// A topology with all memory and processors in node 0. Used until ACPI has been parsed
+    /// and on machines without an SRAT.
+    pub const fn single_node() -> NumaTopology {
+        let mut distances = [[REMOTE_DISTANCE; MAX_NUMA_NODES]; MAX_NUMA_NODES];
+        let mut index = 0;
+        while index < MAX_NUMA_NODES {
+            distances[index][index] = SLIT_LOCAL_DISTANCE;
+            index += 1;
+        }
+
+        return NumaTopology {
+            num_nodes: 1,
+            proximity_domains: [0; MAX_NUMA_NODES],
+            memory_ranges: [NodeMemoryRange::empty(); MAX_MEMORY_RANGES],
+            num_memory_ranges: 0,
+            apic_nodes: [NO_NODE; MAX_APIC_IDS],
+            distances: distances,
+        };
+    }
+
+    /// Builds the topology from the SRAT and SLIT. Returns None if there is no SRAT or it
+    /// describes a single node.
+    pub fn from_acpi() -> Option<NumaTopology> {
+        let srat = acpi::find_table(SignatureType::SRAT)?.as_srat()?;
+        let mut topology = NumaTopology::single_node();
+        topology.num_nodes = 0;
+
+        for entry in srat.iter() {
+            match entry {
+                SratEntry::ProcessorAffinity(affinity) => {
+                    if !affinity.is_enabled() || affinity.apic_id() as usize >= MAX_APIC_IDS {
+                        continue;
+                    }
+                    if let Some(node) = topology.node_for_domain(affinity.proximity_domain()) {
+                        topology.apic_nodes[affinity.apic_id() as usize] = node as u8;
+                    }
+                },
+                SratEntry::MemoryAffinity(affinity) => {
+                    if !affinity.is_enabled() || affinity.length() == 0 || topology.num_memory_ranges == MAX_MEMORY_RANGES {
+                        continue;
+                    }
+                    let end = match affinity.base_address().checked_add(affinity.length()) {
+                        Some(end) => end,
+                        None => {
+                            warn!("Ignoring an SRAT memory range at {:#x} that wraps the address space", affinity.base_address());
+                            continue;
+                        }
+                    };
+                    if let Some(node) = topology.node_for_domain(affinity.proximity_domain()) {
+                        topology.memory_ranges[topology.num_memory_ranges] = NodeMemoryRange {
+                            start: affinity.base_address(),
+                            end: end,
+                            node: node as u8,
+                        };
+                        topology.num_memory_ranges += 1;
+                    }
+                },
+                SratEntry::Unknown(_) => {},
+            }
+        }
+
+        if topology.num_nodes <= 1 {
+            return None;
+        }
+
+        if let Some(slit) = acpi::find_table(SignatureType::SLIT).and_then(|table| table.as_slit()) {
+            for from in 0..topology.num_nodes {
+                for to in 0..topology.num_nodes {
+                    let from_domain = topology.proximity_domains[from] as u64;
+                    let to_domain = topology.proximity_domains[to] as u64;
+                    if let Some(distance) = slit.distance(from_domain, to_domain) {
+                        topology.distances[from][to] = distance;
+                    }
+                }
+            }
+        }
+
+        return Some(topology);
+    }
+
+    /// Finds the node index for a proximity domain, allocating a new one if this is the first
+    /// time the domain has been seen
+    fn node_for_domain(&mut self, proximity_domain: u32) -> Option<usize> {
+        if let Some(node) = self.proximity_domains[..self.num_nodes].iter().position(|domain| *domain == proximity_domain) {
+            return Some(node);
+        }
+
+        if self.num_nodes == MAX_NUMA_NODES {
+            return None;
+        }
+
+        self.proximity_domains[self.num_nodes] = proximity_domain;
+        self.num_nodes += 1;
+        return Some(self.num_nodes - 1);
+    }
+
+    pub fn num_nodes(&self) -> usize {
+        return self.num_nodes;
+    }
+
+    pub fn is_single_node(&self) -> bool {
+        return self.num_nodes <= 1;
+    }
+
+    pub fn proximity_domain(&self, node: usize) -> u32 {
+        return self.proximity_domains[node];
+    }
+
+    pub fn distance(&self, from: usize, to: usize) -> u8 {
+        return self.distances[from][to];
+    }
+
+    /// The node a processor belongs to. Processors not listed in the SRAT are placed in node 0
+    pub fn node_for_apic_id(&self, apic_id: u32) -> usize {
+        if apic_id as usize >= MAX_APIC_IDS || self.apic_nodes[apic_id as usize] == NO_NODE {
+            return 0;
+        }
+        return self.apic_nodes[apic_id as usize] as usize;
+    }
+
+    pub fn node_for_address(&self, address: PhysicalAddress) -> Option<usize> {
+        return self.memory_ranges().find(|range| range.contains(address)).map(|range| range.node as usize);
+    }
+
+    pub fn memory_ranges(&self) -> impl Iterator<Item = &NodeMemoryRange> {
+        return self.memory_ranges[..self.num_memory_ranges].iter();
+    }
+
+    /// All nodes ordered from nearest to furthest from *node*, starting with *node* itself.
+    /// Returns the ordering and the number of valid entries in it.
+    pub fn nodes_by_distance(&self, node: usize) -> ([usize; MAX_NUMA_NODES], usize) {
+        let mut order = [0usize; MAX_NUMA_NODES];
+        for (index, entry) in order.iter_mut().enumerate() {
+            *entry = index;
+        }
+
+        let count = core::cmp::max(self.num_nodes, 1);
+        order[..count].sort_unstable_by_key(|other| (*other != node, self.distances[node][*other]));
+        return (order, count);
+    }
+}
+
+/// Reads the NUMA topology from ACPI and switches the frame allocator to per-node pools.
+/// This must run before any other processor is started.
+pub fn init_numa() {
+    let topology = match NumaTopology::from_acpi() {
+        Some(topology) => topology,
+        None => {
+            warn!("No NUMA topology found. Using a single memory node");
+            return;
+        }
+    };
+
+    info!("Found {} NUMA nodes", topology.num_nodes());
+    for range in topology.memory_ranges() {
+        info!("Node {} (domain {}): {:#x} - {:#x}", range.node, topology.proximity_domain(range.node as usize), range.start, range.end);
+    }
+    for from in 0..topology.num_nodes() {
+        for to in 0..topology.num_nodes() {
+            debug!("Distance {} -> {}: {}", from, to, topology.distance(from, to));
+        }
+    }
+
+    unsafe { FRAME_ALLOCATOR.set_numa_topology(topology) };
+}
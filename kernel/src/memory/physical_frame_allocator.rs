@@ -2,39 +2,56 @@ use core::cell::UnsafeCell;
 
 use data_structures::ringbuffer::RingBuffer;
 use spin::Mutex;
+use x86_64_hardware::cpu::initial_apic_id;
 use x86_64_hardware::memory::{PhysicalAddress, paging::FrameAllocator};
+use x86_64_hardware::tables::without_interrupts;
+
+use crate::smp::{current_cpu, per_cpu_ready, FRAME_CACHE_SIZE};
+use super::numa::{NumaTopology, MAX_NUMA_NODES};
+
+const POOL_SIZE: usize = 512;
+const EMPTY_POOL: RingBuffer<PhysicalAddress, POOL_SIZE> = RingBuffer::new(PhysicalAddress::new(0));
 
 #[derive(Clone, Copy)]
 pub struct PhysicalMemoryManagerFunctions {
     pub bulk_alloc: fn(store: &mut[PhysicalAddress], count: usize) -> usize,
+    pub bulk_alloc_range: fn(store: &mut[PhysicalAddress], start: PhysicalAddress, end: PhysicalAddress) -> usize,
     pub free: fn(page: PhysicalAddress),
 }
 
 impl PhysicalMemoryManagerFunctions {
-    pub fn new(bulk_alloc: fn(store: &mut[PhysicalAddress], count: usize) -> usize, free: fn(page: PhysicalAddress)) -> PhysicalMemoryManagerFunctions {
-        return PhysicalMemoryManagerFunctions { bulk_alloc: bulk_alloc, free: free };
+    pub fn new(bulk_alloc: fn(store: &mut[PhysicalAddress], count: usize) -> usize,
+        bulk_alloc_range: fn(store: &mut[PhysicalAddress], start: PhysicalAddress, end: PhysicalAddress) -> usize,
+        free: fn(page: PhysicalAddress)) -> PhysicalMemoryManagerFunctions {
+        return PhysicalMemoryManagerFunctions { bulk_alloc: bulk_alloc, bulk_alloc_range: bulk_alloc_range, free: free };
     }
 }
 
+/// Caches free frames in one pool per NUMA node. Requests are served from the pool of the
+/// node the requesting processor belongs to, falling back to the nearest other node when it
+/// runs out of memory.
 pub struct PhysicalFrameAllocator {
-    buffer: RingBuffer<PhysicalAddress, 512>,
+    pools: [RingBuffer<PhysicalAddress, POOL_SIZE>; MAX_NUMA_NODES],
     mem_manager: UnsafeCell<Option<PhysicalMemoryManagerFunctions>>,
+    topology: UnsafeCell<NumaTopology>,
     fill_lock: Mutex<()>,
 }
 
 impl PhysicalFrameAllocator {
     pub fn new(mem_manager: PhysicalMemoryManagerFunctions) -> PhysicalFrameAllocator {
         PhysicalFrameAllocator { 
-            buffer: RingBuffer::new(PhysicalAddress::new(0)),
+            pools: [EMPTY_POOL; MAX_NUMA_NODES],
             mem_manager: UnsafeCell::new(Some(mem_manager)),
+            topology: UnsafeCell::new(NumaTopology::single_node()),
             fill_lock: Mutex::new(()),
         }
     }
     
     pub const fn new_uninit() -> PhysicalFrameAllocator {
         PhysicalFrameAllocator { 
-            buffer: RingBuffer::new(PhysicalAddress::new(0)),
+            pools: [EMPTY_POOL; MAX_NUMA_NODES],
             mem_manager: UnsafeCell::new(None),
+            topology: UnsafeCell::new(NumaTopology::single_node()),
             fill_lock: Mutex::new(()),
         }
     }
@@ -47,21 +64,105 @@ impl PhysicalFrameAllocator {
         return unsafe { (*self.mem_manager.get()).unwrap() };
     }
 
-    pub fn fill_buffer(&self) {
+    /// Switches the allocator to per-node pools. Frames cached before the topology was known
+    /// may belong to any node so they are handed back to the physical memory manager.
+    /// 
+    /// ## Safety
+    /// 
+    /// This must only be called while no other processor is using the allocator.
+    pub unsafe fn set_numa_topology(&self, topology: NumaTopology) {
         let _lock_guard = self.fill_lock.lock();
 
-        if self.buffer.is_empty() {
-            let mut required_count = self.buffer.max_items() as usize / 2;
+        for pool in self.pools.iter() {
+            while let Some(address) = pool.read() {
+                self.free(address);
+            }
+        }
+        if per_cpu_ready() {
+            while let Some(address) = current_cpu().frame_cache().pop() {
+                self.free(address);
+            }
+        }
+        *self.topology.get() = topology;
+    }
+
+    pub fn topology(&self) -> &NumaTopology {
+        return unsafe { &*self.topology.get() };
+    }
+
+    /// Refills the pool of the given node if it is empty. Returns false if the node has no
+    /// free memory left.
+    pub fn fill_pool(&self, node: usize) -> bool {
+        let _lock_guard = self.fill_lock.lock();
+        let pool = &self.pools[node];
+
+        if !pool.is_empty() {
+            return true;
+        }
+
+        let mut required_count = pool.max_items() as usize / 2;
+        let mut alloc_buffer: [PhysicalAddress;256] = [PhysicalAddress::new(0);256];
 
+        if self.topology().is_single_node() {
             while required_count > 0 {
-                let mut alloc_buffer: [PhysicalAddress;256] = [PhysicalAddress::new(0);256];
-                let alloced_count = self.bulk_alloc(&mut alloc_buffer, required_count);
+                let store_len = core::cmp::min(required_count, alloc_buffer.len());
+                let alloced_count = self.bulk_alloc(&mut alloc_buffer, store_len);
 
                 for index in 0..alloced_count {
-                    self.buffer.write(alloc_buffer[index]);
+                    pool.write(alloc_buffer[index]);
                 }
                 required_count -= alloced_count;
             }
+            return true;
+        }
+
+        for range in self.topology().memory_ranges().filter(|range| range.node as usize == node) {
+            while required_count > 0 {
+                let store_len = core::cmp::min(required_count, alloc_buffer.len());
+                let alloced_count = self.bulk_alloc_range(&mut alloc_buffer[..store_len],
+                    PhysicalAddress::new(range.start), PhysicalAddress::new(range.end));
+                if alloced_count == 0 {
+                    break;
+                }
+
+                for index in 0..alloced_count {
+                    pool.write(alloc_buffer[index]);
+                }
+                required_count -= alloced_count;
+            }
+        }
+
+        return !pool.is_empty();
+    }
+
+    /// Takes a frame from the shared pools, nearest node first
+    fn request_pool_page(&self) -> PhysicalAddress {
+        let topology = self.topology();
+        //Only the BSP runs before the per-CPU blocks are ready
+        let apic_id = if per_cpu_ready() { current_cpu().apic_id() } else { initial_apic_id() as u32 };
+        let local_node = topology.node_for_apic_id(apic_id);
+        let (nodes, num_nodes) = topology.nodes_by_distance(local_node);
+
+        loop {
+            for &node in nodes[..num_nodes].iter() {
+                if let Some(address) = self.pools[node].read() {
+                    return address;
+                }
+
+                if self.fill_pool(node) {
+                    if let Some(address) = self.pools[node].read() {
+                        return address;
+                    }
+                }
+            }
+        }
+    }
+
+    fn free_pool_page(&self, address: PhysicalAddress) {
+        let node = self.topology().node_for_address(address).unwrap_or(0);
+        match self.pools[node].write(address) {
+            Some(_) => {},
+            None => { self.free(address); }
         }
     }
 
@@ -69,28 +170,53 @@ impl PhysicalFrameAllocator {
         return (self.mem_manager().bulk_alloc)(store, count);
     }
 
+    fn bulk_alloc_range(&self, store: &mut [PhysicalAddress], start: PhysicalAddress, end: PhysicalAddress) -> usize {
+        return (self.mem_manager().bulk_alloc_range)(store, start, end);
+    }
+
     fn free(&self, page: PhysicalAddress) {
         return (self.mem_manager().free)(page);
     }
 }
 
+/// Requests are served from the per-CPU frame cache once it exists. The cache is refilled
+/// and drained half at a time so a CPU alternating between requests and frees stays off the
+/// shared pools.
 impl FrameAllocator for PhysicalFrameAllocator {
     fn request_page(&self) -> PhysicalAddress {
-        loop {
-            match self.buffer.read() {
-                Some(address) => { return address },
-                None => {
-                    self.fill_buffer();
-                }
-            }
+        if !per_cpu_ready() {
+            return self.request_pool_page();
         }
+
+        return without_interrupts(|| {
+            let cache = current_cpu().frame_cache();
+            if let Some(address) = cache.pop() {
+                return address;
+            }
+
+            for _ in 0..FRAME_CACHE_SIZE / 2 {
+                cache.push(self.request_pool_page());
+            }
+            return self.request_pool_page();
+        });
     }
 
     fn free_page(&self, address: PhysicalAddress) {
-        match self.buffer.write(address) {
-            Some(_) => {},
-            None => { self.free(address); }
+        if !per_cpu_ready() {
+            return self.free_pool_page(address);
         }
+
+        without_interrupts(|| {
+            let cache = current_cpu().frame_cache();
+            if cache.is_full() {
+                for _ in 0..FRAME_CACHE_SIZE / 2 {
+                    if let Some(cached) = cache.pop() {
+                        self.free_pool_page(cached);
+                    }
+                }
+            }
+            cache.push(address);
+        });
     }
 }
 
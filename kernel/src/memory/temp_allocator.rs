@@ -6,7 +6,7 @@ pub static TEMP_ALLOC: PageFrameAllocator = PageFrameAllocator::new_uninit();
 
 
 pub fn get_pmm_functions() -> PhysicalMemoryManagerFunctions {
-    return PhysicalMemoryManagerFunctions::new(bulk_alloc, free);
+    return PhysicalMemoryManagerFunctions::new(bulk_alloc, bulk_alloc_range, free);
 }
 
 pub fn bulk_alloc(store: &mut[PhysicalAddress], count: usize) -> usize {
@@ -16,6 +16,10 @@ pub fn bulk_alloc(store: &mut[PhysicalAddress], count: usize) -> usize {
     return count;
 }
 
+pub fn bulk_alloc_range(store: &mut[PhysicalAddress], start: PhysicalAddress, end: PhysicalAddress) -> usize {
+    return TEMP_ALLOC.request_pages_in_range(store, start, end);
+}
+
 pub fn free(address: PhysicalAddress) {
     TEMP_ALLOC.free_page(address);
 }
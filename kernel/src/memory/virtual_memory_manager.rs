@@ -1,20 +1,31 @@
 use core::{panic, cell::UnsafeCell};
 
 use spin::Mutex;
-use x86_64_hardware::memory::{PhysicalAddress, VirtualAddress, paging::{FrameAllocator, PageTableManager}};
+use x86_64_hardware::cpu;
+use x86_64_hardware::memory::{PhysicalAddress, VirtualAddress, PAGE_SIZE, paging::{FrameAllocator, PageTableManager}};
 
 use super::FRAME_ALLOCATOR;
 
+//Memory mapped IO beyond the physical memory window is mapped here, between the kernel heap
+//and the window, which starts at most MAX_MEM_SIZE below the kernel image
+const MMIO_BASE: u64 = 0xFFFF_C000_0000_0000;
+const MMIO_END: u64 = 0xFFFF_FF00_0000_0000;
+
+extern "C" {
+    static _KernelStart: u8;
+}
+
 struct MemorySpace {
     p4_addr: PhysicalAddress,
     is_wired: bool,
     heap_base: VirtualAddress,
     heap_end: VirtualAddress,
+    mmio_end: VirtualAddress,
 }
 
 impl MemorySpace {
     pub const fn new(p4_addr: PhysicalAddress, is_wired: bool, heap_base: VirtualAddress) -> MemorySpace {
-        MemorySpace { p4_addr: p4_addr, is_wired: is_wired, heap_base: heap_base, heap_end: heap_base }
+        MemorySpace { p4_addr: p4_addr, is_wired: is_wired, heap_base: heap_base, heap_end: heap_base, mmio_end: VirtualAddress::new(MMIO_BASE) }
     }
 
     pub fn reinit(&mut self, p4_addr: PhysicalAddress, is_wired: bool, heap_base: VirtualAddress) {
@@ -22,6 +33,7 @@ impl MemorySpace {
         self.is_wired = is_wired;
         self.heap_base = heap_base;
         self.heap_end = heap_base;
+        self.mmio_end = VirtualAddress::new(MMIO_BASE);
     }
 
     pub fn alter_heap(&mut self, mapped_offset: u64, page_increment: isize) -> VirtualAddress {
@@ -80,16 +92,101 @@ impl VirtualMemoryManager {
         }
     }
 
+    /// Maps *num_pages* new pages of memory at the end of the kernel heap and returns the
+    /// address of the first one
+    pub fn allocate_pages(&self, num_pages: u64) -> VirtualAddress {
+        let mut vmem0 = self.vmem0.lock();
+        let start = vmem0.heap_end;
+        vmem0.alter_heap(self.mapped_mem_offset(), num_pages as isize);
+        return start;
+    }
+
+    /// Maps a memory mapped IO region into the kernel memory space with caching disabled.
+    /// See *mmio_address* for where it is mapped.
+    pub fn map_mmio(&self, physical_addr: PhysicalAddress, num_pages: u64) -> VirtualAddress {
+        let mut vmem0 = self.vmem0.lock();
+        let virtual_addr = self.mmio_address(&mut vmem0, physical_addr, num_pages);
+        let page_table_manager = PageTableManager::new(vmem0.p4_addr, self.mapped_mem_offset());
+        page_table_manager.map_mmio_pages(virtual_addr, physical_addr, num_pages, &FRAME_ALLOCATOR);
+        return virtual_addr;
+    }
+
+    /// Maps a framebuffer with write combining, so writes are batched without being cached.
+    /// It is mapped uncached if the CPU has no PAT. See *mmio_address* for where it is mapped.
+    pub fn map_write_combining(&self, physical_addr: PhysicalAddress, num_pages: u64) -> VirtualAddress {
+        if !cpu::has_pat() {
+            return self.map_mmio(physical_addr, num_pages);
+        }
+
+        let mut vmem0 = self.vmem0.lock();
+        let virtual_addr = self.mmio_address(&mut vmem0, physical_addr, num_pages);
+        let page_table_manager = PageTableManager::new(vmem0.p4_addr, self.mapped_mem_offset());
+        page_table_manager.map_write_combining_pages(virtual_addr, physical_addr, num_pages, &FRAME_ALLOCATOR);
+        return virtual_addr;
+    }
+
+    /// Where to map *num_pages* of memory mapped IO at *physical_addr*. A region inside the
+    /// physical memory window is remapped in place so its memory never has two cache types.
+    /// Anything past the window, which would run into the kernel image, is given new pages
+    /// in the MMIO range instead.
+    fn mmio_address(&self, vmem0: &mut MemorySpace, physical_addr: PhysicalAddress, num_pages: u64) -> VirtualAddress {
+        let window_size = core::ptr::addr_of!(_KernelStart) as u64 - self.mapped_mem_offset();
+        let size = match num_pages.checked_mul(PAGE_SIZE) {
+            Some(size) => size,
+            None => panic!("Cannot map {} pages of memory mapped IO", num_pages),
+        };
+        if physical_addr.as_u64().checked_add(size).is_some_and(|end| end <= window_size) {
+            return physical_addr.get_virtual_address_at_offset(self.mapped_mem_offset());
+        }
+
+        let virtual_addr = vmem0.mmio_end;
+        match virtual_addr.as_u64().checked_add(size) {
+            Some(end) if end <= MMIO_END => vmem0.mmio_end = VirtualAddress::new(end),
+            _ => panic!("Out of address space to map {} pages of memory mapped IO at {:#x}", num_pages, physical_addr.as_u64()),
+        }
+        return virtual_addr;
+    }
+
+    /// The physical address of the kernel P4 table
+    pub fn kernel_p4_address(&self) -> PhysicalAddress {
+        return self.vmem0.lock().p4_addr;
+    }
+
+    /// Maps a single page at the virtual address equal to its physical address. This is only
+    /// for code that runs before paging is fully set up, such as the AP trampoline, and must
+    /// be undone with *unmap_identity_page* as the lower half belongs to user space.
+    pub fn map_identity_page(&self, physical_addr: PhysicalAddress) {
+        let vmem0 = self.vmem0.lock();
+        let page_table_manager = PageTableManager::new(vmem0.p4_addr, self.mapped_mem_offset());
+        page_table_manager.map_memory(VirtualAddress::new(physical_addr.as_u64()), physical_addr, &FRAME_ALLOCATOR);
+    }
+
+    /// Removes an identity mapping made by *map_identity_page*. Only the page's own entry is
+    /// cleared so other mappings sharing its page tables are left in place.
+    pub fn unmap_identity_page(&self, physical_addr: PhysicalAddress) {
+        let vmem0 = self.vmem0.lock();
+        let page_table_manager = PageTableManager::new(vmem0.p4_addr, self.mapped_mem_offset());
+        page_table_manager.unmap_page(VirtualAddress::new(physical_addr.as_u64()));
+    }
+
     fn set_mapped_mem_offset(&self, value: u64) {
         unsafe { *self.mapped_mem_offset.get() = value };
     }
 
-    fn mapped_mem_offset(&self) -> u64 {
+    pub fn mapped_mem_offset(&self) -> u64 {
         return unsafe { *self.mapped_mem_offset.get() };
     }
 }
 
 
+/// Sets up this CPU's page attribute table for *map_write_combining*. Every CPU must call
+/// this before touching a write combining mapping.
+pub fn init_page_attributes() {
+    if cpu::has_pat() {
+        unsafe { cpu::enable_write_combining(); }
+    }
+}
+
 unsafe impl Sync for VirtualMemoryManager {}
 
 pub static VIRTUAL_MEMORY_MANAGER: VirtualMemoryManager = VirtualMemoryManager::new_uninit();
@@ -0,0 +1,164 @@
+use acpi_system_tables::SignatureType;
+use logging::{debug, info, warn};
+use spin::Mutex;
+use x86_64_hardware::devices::pci::*;
+use x86_64_hardware::memory::{PAGE_SIZE, PhysicalAddress};
+
+use crate::acpi;
+use crate::memory::VIRTUAL_MEMORY_MANAGER;
+
+const MAX_ECAM_SEGMENTS: usize = 8;
+const NO_SEGMENT: Option<EcamConfigSpace> = None;
+
+static ECAM_SEGMENTS: Mutex<[Option<EcamConfigSpace>; MAX_ECAM_SEGMENTS]> = Mutex::new([NO_SEGMENT; MAX_ECAM_SEGMENTS]);
+static PORT_CONFIG_SPACE: PortConfigSpace = unsafe { PortConfigSpace::new() };
+
+/// Configuration space access for the whole system. Functions covered by an MCFG entry are
+/// reached through ECAM and everything else falls back to the legacy port mechanism.
+pub struct PciConfigSpace;
+
+pub static PCI_CONFIG_SPACE: PciConfigSpace = PciConfigSpace;
+pub static PCI_DEVICES: Mutex<PciDeviceList> = Mutex::new(PciDeviceList::new());
+
+impl PciConfigSpace {
+    fn ecam_index(segments: &[Option<EcamConfigSpace>], address: PciAddress) -> Option<usize> {
+        return segments.iter().position(|segment| match segment {
+            Some(ecam) => ecam.contains(address),
+            None => false,
+        });
+    }
+}
+
+impl ConfigSpace for PciConfigSpace {
+    fn config_space_size(&self) -> u16 {
+        if ECAM_SEGMENTS.lock().iter().any(|segment| segment.is_some()) {
+            return EXTENDED_CONFIG_SPACE_SIZE;
+        } else {
+            return LEGACY_CONFIG_SPACE_SIZE;
+        }
+    }
+
+    fn read_u8(&self, address: PciAddress, offset: u16) -> u8 {
+        let segments = ECAM_SEGMENTS.lock();
+        match Self::ecam_index(&*segments, address) {
+            Some(index) => segments[index].as_ref().unwrap().read_u8(address, offset),
+            None => PORT_CONFIG_SPACE.read_u8(address, offset),
+        }
+    }
+
+    fn read_u16(&self, address: PciAddress, offset: u16) -> u16 {
+        let segments = ECAM_SEGMENTS.lock();
+        match Self::ecam_index(&*segments, address) {
+            Some(index) => segments[index].as_ref().unwrap().read_u16(address, offset),
+            None => PORT_CONFIG_SPACE.read_u16(address, offset),
+        }
+    }
+
+    fn read_u32(&self, address: PciAddress, offset: u16) -> u32 {
+        let segments = ECAM_SEGMENTS.lock();
+        match Self::ecam_index(&*segments, address) {
+            Some(index) => segments[index].as_ref().unwrap().read_u32(address, offset),
+            None => PORT_CONFIG_SPACE.read_u32(address, offset),
+        }
+    }
+
+    fn write_u8(&self, address: PciAddress, offset: u16, value: u8) {
+        let segments = ECAM_SEGMENTS.lock();
+        match Self::ecam_index(&*segments, address) {
+            Some(index) => segments[index].as_ref().unwrap().write_u8(address, offset, value),
+            None => PORT_CONFIG_SPACE.write_u8(address, offset, value),
+        }
+    }
+
+    fn write_u16(&self, address: PciAddress, offset: u16, value: u16) {
+        let segments = ECAM_SEGMENTS.lock();
+        match Self::ecam_index(&*segments, address) {
+            Some(index) => segments[index].as_ref().unwrap().write_u16(address, offset, value),
+            None => PORT_CONFIG_SPACE.write_u16(address, offset, value),
+        }
+    }
+
+    fn write_u32(&self, address: PciAddress, offset: u16, value: u32) {
+        let segments = ECAM_SEGMENTS.lock();
+        match Self::ecam_index(&*segments, address) {
+            Some(index) => segments[index].as_ref().unwrap().write_u32(address, offset, value),
+            None => PORT_CONFIG_SPACE.write_u32(address, offset, value),
+        }
+    }
+}
+
+/// Maps the ECAM regions listed in the MCFG and enumerates every PCI segment into
+/// PCI_DEVICES. Without an MCFG only segment 0 is enumerated using port IO.
+pub fn init() {
+    let mut devices = PCI_DEVICES.lock();
+    devices.clear();
+
+    match acpi::find_table(SignatureType::MCFG).and_then(|table| table.as_mcfg()) {
+        Some(mcfg) => {
+            let mut segments = ECAM_SEGMENTS.lock();
+            for (index, entry) in mcfg.iter().enumerate() {
+                if index >= MAX_ECAM_SEGMENTS {
+                    warn!("Too many ECAM segments. Ignoring segment {}", entry.pci_segment_group());
+                    continue;
+                }
+
+                let region_size = EcamConfigSpace::region_size(entry.start_bus(), entry.end_bus());
+                let start_bus_address = PhysicalAddress::new(entry.base_address() + entry.start_bus() as u64 * ECAM_BUS_SIZE);
+                let virtual_address = VIRTUAL_MEMORY_MANAGER.map_mmio(start_bus_address, region_size / PAGE_SIZE);
+                segments[index] = Some(unsafe { EcamConfigSpace::new(virtual_address, entry.pci_segment_group(), entry.start_bus(), entry.end_bus()) });
+            }
+            drop(segments);
+
+            for entry in mcfg.iter() {
+                PciEnumerator::new(&PCI_CONFIG_SPACE, entry.pci_segment_group(), entry.start_bus(), entry.end_bus()).enumerate(&mut devices);
+            }
+        },
+        None => {
+            warn!("No MCFG found. Using legacy PCI configuration access");
+            PciEnumerator::new(&PCI_CONFIG_SPACE, 0, 0, 255).enumerate(&mut devices);
+        }
+    }
+
+    if devices.is_full() {
+        warn!("PCI device list full. Some devices were not enumerated");
+    }
+
+    print_devices(&devices);
+}
+
+fn print_devices(devices: &PciDeviceList) {
+    info!("Found {} PCI functions", devices.len());
+    for device in devices.iter() {
+        debug!("{} {:04x}:{:04x} class {:02x}:{:02x}:{:02x} rev {:02x} header {:?}",
+            device.address, device.vendor_id, device.device_id, device.class_code, device.subclass,
+            device.prog_if, device.revision_id, device.header_type);
+
+        for (index, bar) in device.bars.iter().enumerate() {
+            match *bar {
+                Bar::None => {},
+                Bar::Memory32 { address, size, prefetchable } => {
+                    debug!("    BAR{} mem32 {:#010x} size {:#x}{}", index, address, size, if prefetchable { " prefetchable" } else { "" });
+                },
+                Bar::Memory64 { address, size, prefetchable } => {
+                    debug!("    BAR{} mem64 {:#018x} size {:#x}{}", index, address, size, if prefetchable { " prefetchable" } else { "" });
+                },
+                Bar::Io { port, size } => {
+                    debug!("    BAR{} io {:#06x} size {:#x}", index, port, size);
+                },
+            }
+        }
+
+        if let (Some(secondary_bus), Some(subordinate_bus)) = (device.secondary_bus, device.subordinate_bus) {
+            debug!("    bridge to buses {:02x}-{:02x}", secondary_bus, subordinate_bus);
+        }
+        if let Some(offset) = device.msi_capability {
+            debug!("    MSI capability at {:#04x}", offset);
+        }
+        if let Some(offset) = device.msix_capability {
+            debug!("    MSI-X capability at {:#04x}", offset);
+        }
+        if let Some(offset) = device.pcie_capability {
+            debug!("    PCIe capability at {:#04x}", offset);
+        }
+    }
+}
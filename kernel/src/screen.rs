@@ -0,0 +1,36 @@
+use bootinfo::FrameBuffer;
+use spin::Once;
+use x86_64_hardware::memory::PAGE_SIZE;
+
+use crate::memory::VIRTUAL_MEMORY_MANAGER;
+
+/// The mapped framebuffer and a back buffer for it. They are set up once and handed from the
+/// boot splash to the framebuffer console, which only ever hold the screen one at a time.
+pub struct ScreenBuffers {
+    /// The framebuffer, mapped write combining
+    pub front_buffer: *mut u8,
+    /// Width by height 32 bit pixels of the framebuffer's pixel format
+    pub back_buffer: *mut u32,
+}
+
+//The buffers are only drawn to by whichever of the splash or console owns the screen
+unsafe impl Send for ScreenBuffers {}
+unsafe impl Sync for ScreenBuffers {}
+
+static SCREEN_BUFFERS: Once<ScreenBuffers> = Once::new();
+
+/// The buffers for *framebuffer*, which are mapped and allocated on the first call. The
+/// framebuffer must be linear and the same on every call.
+pub fn screen_buffers(framebuffer: &FrameBuffer) -> &'static ScreenBuffers {
+    return SCREEN_BUFFERS.call_once(|| {
+        let height = framebuffer.height as u64;
+        let front_pages = (framebuffer.pixels_per_scan_line as u64 * height * framebuffer.bytes_per_pixel() as u64).div_ceil(PAGE_SIZE);
+        let back_pages = (framebuffer.width as u64 * height * 4).div_ceil(PAGE_SIZE);
+        return unsafe {
+            ScreenBuffers {
+                front_buffer: VIRTUAL_MEMORY_MANAGER.map_write_combining(framebuffer.base_address, front_pages).get_mut_ptr::<u8>(),
+                back_buffer: VIRTUAL_MEMORY_MANAGER.allocate_pages(back_pages).get_mut_ptr::<u32>(),
+            }
+        };
+    });
+}
@@ -0,0 +1,219 @@
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use acpi_system_tables::{MadtEntry, SignatureType};
+use logging::{info, warn};
+use x86_64_hardware::apic::ApicMode;
+use x86_64_hardware::cpu;
+use x86_64_hardware::memory::{PAGE_SIZE, PhysicalAddress, VirtualAddress};
+use x86_64_hardware::tables::{enable_interrupts, load_default_idt, wait_for_interrupt};
+
+use crate::acpi;
+use crate::apic::{self, LOCAL_APIC};
+use crate::memory::{init_page_attributes, TEMP_ALLOC, VIRTUAL_MEMORY_MANAGER};
+use crate::time;
+
+mod per_cpu;
+
+pub use per_cpu::*;
+
+pub const MAX_CPUS: usize = 16;
+const AP_STACK_SIZE: usize = 4 * PAGE_SIZE as usize;
+const IA32_EFER_MSR: u32 = 0xC000_0080;
+const EFER_LONG_MODE_ACTIVE: u64 = 1 << 10;
+/// Startup IPIs can only start execution below 1MiB
+const TRAMPOLINE_LIMIT: u64 = 0x10_0000;
+const AP_STARTUP_TIMEOUT_MS: u64 = 100;
+
+/// The patchable data at the end of the trampoline. This must match the layout of
+/// ap_trampoline_data in trampoline.s
+#[repr(C, packed)]
+struct TrampolineData {
+    gdt_limit: u16,
+    gdt_base: u32,
+    protected_mode_entry: u32,
+    protected_mode_selector: u16,
+    long_mode_entry: u32,
+    long_mode_selector: u16,
+    cr3: u32,
+    efer: u32,
+    stack_top: u64,
+    cpu_index: u64,
+    acknowledged: u8,
+}
+
+extern "C" {
+    static ap_trampoline_start: u8;
+    static ap_trampoline_end: u8;
+    static ap_trampoline_data: u8;
+}
+
+#[repr(C, align(16))]
+struct ApStack([u8; AP_STACK_SIZE]);
+
+struct ApStacks(UnsafeCell<[ApStack; MAX_CPUS]>);
+
+//Each stack is only used by the AP it is handed to
+unsafe impl Sync for ApStacks {}
+
+const EMPTY_STACK: ApStack = ApStack([0; AP_STACK_SIZE]);
+static AP_STACKS: ApStacks = ApStacks(UnsafeCell::new([EMPTY_STACK; MAX_CPUS]));
+
+static ONLINE_CPUS: AtomicUsize = AtomicUsize::new(1);
+
+/// The number of processors that have finished starting, including the BSP
+pub fn online_cpus() -> usize {
+    return ONLINE_CPUS.load(Ordering::Acquire);
+}
+
+fn stack_top(cpu_index: usize) -> VirtualAddress {
+    let stack = unsafe { &(*AP_STACKS.0.get())[cpu_index] };
+    return VirtualAddress::new(stack.0.as_ptr() as u64 + AP_STACK_SIZE as u64);
+}
+
+/// Starts every enabled processor in the MADT using INIT-SIPI-SIPI. The APs are started one
+/// at a time and park in an idle loop. Interrupts must be enabled as the startup delays use
+/// the PIT tick.
+pub fn start_application_processors() {
+    let local_apic = match LOCAL_APIC.get() {
+        Some(local_apic) => local_apic,
+        None => { return; },
+    };
+    let madt = match acpi::find_table(SignatureType::APIC).and_then(|table| table.as_madt()) {
+        Some(madt) => madt,
+        None => { return; },
+    };
+
+    let bsp_apic_id = local_apic.id();
+    current_cpu().set_apic_id(bsp_apic_id);
+
+    let kernel_p4 = VIRTUAL_MEMORY_MANAGER.kernel_p4_address();
+    if kernel_p4.as_u64() > u32::MAX as u64 {
+        warn!("Kernel page tables are above 4GiB. Not starting APs");
+        return;
+    }
+
+    let mut trampoline_page = [PhysicalAddress::new(0)];
+    if TEMP_ALLOC.request_pages_in_range(&mut trampoline_page, PhysicalAddress::new(PAGE_SIZE), PhysicalAddress::new(TRAMPOLINE_LIMIT)) == 0 {
+        warn!("No free page below 1MiB for the AP trampoline");
+        return;
+    }
+    let trampoline_page = trampoline_page[0];
+    let trampoline = unsafe { install_trampoline(trampoline_page, kernel_p4) };
+    VIRTUAL_MEMORY_MANAGER.map_identity_page(trampoline_page);
+
+    let mut next_cpu_index = 1;
+    for entry in madt.iter() {
+        let processor = match entry {
+            MadtEntry::ProcessorLocalApic(processor) => processor,
+            _ => { continue; },
+        };
+
+        if !processor.is_enabled() || processor.apic_id() == bsp_apic_id {
+            continue;
+        }
+        if next_cpu_index == MAX_CPUS {
+            warn!("Too many CPUs. Not starting APIC ID {}", processor.apic_id());
+            continue;
+        }
+        if local_apic.mode() == ApicMode::XApic && processor.apic_id() > u8::MAX as u32 {
+            warn!("APIC ID {} cannot be reached in xAPIC mode", processor.apic_id());
+            continue;
+        }
+
+        if start_application_processor(trampoline, trampoline_page, next_cpu_index, processor.apic_id()) {
+            next_cpu_index += 1;
+        } else {
+            warn!("CPU with APIC ID {} did not start", processor.apic_id());
+        }
+    }
+
+    wait_for_online_cpus(next_cpu_index);
+    //The trampoline page stays reserved so it can be used again later
+    VIRTUAL_MEMORY_MANAGER.unmap_identity_page(trampoline_page);
+    info!("{} CPUs online", online_cpus());
+}
+
+/// Copies the trampoline into *page* and relocates it. Returns a pointer to its data area
+unsafe fn install_trampoline(page: PhysicalAddress, kernel_p4: PhysicalAddress) -> *mut TrampolineData {
+    let start = &ap_trampoline_start as *const u8;
+    let length = &ap_trampoline_end as *const u8 as usize - start as usize;
+    let data_offset = &ap_trampoline_data as *const u8 as usize - start as usize;
+
+    let destination = page.get_virtual_address_at_offset(VIRTUAL_MEMORY_MANAGER.mapped_mem_offset()).get_mut_ptr::<u8>();
+    core::ptr::copy_nonoverlapping(start, destination, length);
+
+    let base = page.as_u64() as u32;
+    let data = destination.add(data_offset) as *mut TrampolineData;
+    (*data).gdt_base += base;
+    (*data).protected_mode_entry += base;
+    (*data).long_mode_entry += base;
+    (*data).cr3 = kernel_p4.as_u64() as u32;
+    (*data).efer = (cpu::read_msr(IA32_EFER_MSR) & !EFER_LONG_MODE_ACTIVE) as u32;
+    return data;
+}
+
+/// Starts one AP and waits for it to take its stack and CPU index from the trampoline. An
+/// AP that does not answer in time is put back into INIT so it cannot read the slot once it
+/// holds the next AP's values.
+fn start_application_processor(trampoline: *mut TrampolineData, trampoline_page: PhysicalAddress, cpu_index: usize, apic_id: u32) -> bool {
+    unsafe {
+        per_cpu(cpu_index).prepare(cpu_index, apic_id);
+        (*trampoline).stack_top = stack_top(cpu_index).as_u64();
+        (*trampoline).cpu_index = cpu_index as u64;
+        (*trampoline).acknowledged = 0;
+    }
+
+    let local_apic = LOCAL_APIC.get().unwrap();
+    let start_page = (trampoline_page.as_u64() / PAGE_SIZE) as u8;
+
+    local_apic.send_init(apic_id);
+    time::sleep_ms(10);
+    local_apic.send_startup(apic_id, start_page);
+    time::busy_sleep_ms(1);
+    if !trampoline_acknowledged(trampoline) {
+        local_apic.send_startup(apic_id, start_page);
+    }
+
+    for _ in 0..AP_STARTUP_TIMEOUT_MS {
+        if trampoline_acknowledged(trampoline) {
+            return true;
+        }
+        time::busy_sleep_ms(1);
+    }
+
+    local_apic.send_init(apic_id);
+    return false;
+}
+
+fn trampoline_acknowledged(trampoline: *mut TrampolineData) -> bool {
+    return unsafe { core::ptr::addr_of!((*trampoline).acknowledged).read_volatile() } != 0;
+}
+
+/// Waits for every AP that took its slot to finish starting
+fn wait_for_online_cpus(count: usize) {
+    for _ in 0..AP_STARTUP_TIMEOUT_MS {
+        if online_cpus() >= count {
+            return;
+        }
+        time::busy_sleep_ms(1);
+    }
+}
+
+/// The Rust entry point for APs, called from ap_long_mode_entry on the stack the BSP set up
+#[no_mangle]
+extern "C" fn ap_main(cpu_index: usize) -> ! {
+    let cpu = per_cpu(cpu_index);
+    unsafe { cpu.activate(); }
+    init_page_attributes();
+    load_default_idt();
+    apic::enable_local_apic();
+
+    info!("CPU {} (APIC ID {}) online", cpu.cpu_index(), cpu.apic_id());
+    ONLINE_CPUS.fetch_add(1, Ordering::Release);
+
+    enable_interrupts();
+    loop {
+        wait_for_interrupt();
+    }
+}
@@ -0,0 +1,225 @@
+use core::cell::{Cell, UnsafeCell};
+use core::mem::offset_of;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use x86_64_hardware::cpu;
+use x86_64_hardware::memory::{PAGE_SIZE, PhysicalAddress, VirtualAddress};
+use x86_64_hardware::tables::{GdtWithTss, TaskStateSegment};
+
+use super::MAX_CPUS;
+
+pub const FRAME_CACHE_SIZE: usize = 32;
+pub const PER_CPU_SCRATCH_SLOTS: usize = 4;
+/// The thread ID reported before any thread has been scheduled on a CPU
+pub const IDLE_THREAD: usize = 0;
+
+//The fields reached relative to the GS base are at fixed offsets. Assembly entry points that
+//cannot use the stack, such as a syscall entry saving the user stack pointer, use the scratch
+//slots as %gs:PER_CPU_SCRATCH_OFFSET.
+const PER_CPU_SELF_OFFSET: usize = 0;
+pub const PER_CPU_SCRATCH_OFFSET: usize = 8;
+pub const PER_CPU_CURRENT_THREAD_OFFSET: usize = PER_CPU_SCRATCH_OFFSET + PER_CPU_SCRATCH_SLOTS * 8;
+const INTERRUPT_STACK_SIZE: usize = 4 * PAGE_SIZE as usize;
+
+static PER_CPU_READY: AtomicBool = AtomicBool::new(false);
+
+#[repr(C, align(16))]
+struct InterruptStack([u8; INTERRUPT_STACK_SIZE]);
+
+struct InterruptStacks(UnsafeCell<[InterruptStack; MAX_CPUS]>);
+
+//Each stack is only used by the CPU whose TSS points at it
+unsafe impl Sync for InterruptStacks {}
+
+const EMPTY_INTERRUPT_STACK: InterruptStack = InterruptStack([0; INTERRUPT_STACK_SIZE]);
+static INTERRUPT_STACKS: InterruptStacks = InterruptStacks(UnsafeCell::new([EMPTY_INTERRUPT_STACK; MAX_CPUS]));
+
+/// The top of the stack the processor switches to when an interrupt arrives in user mode
+fn interrupt_stack_top(cpu_index: usize) -> VirtualAddress {
+    let stack = unsafe { &(*INTERRUPT_STACKS.0.get())[cpu_index] };
+    return VirtualAddress::new(stack.0.as_ptr() as u64 + INTERRUPT_STACK_SIZE as u64);
+}
+
+/// A small stack of free frames owned by one CPU so most frame requests do not touch the
+/// shared pools. It must only be used by its own CPU with interrupts disabled.
+pub struct FrameCache {
+    frames: [Cell<PhysicalAddress>; FRAME_CACHE_SIZE],
+    count: Cell<usize>,
+}
+
+impl FrameCache {
+    const fn new() -> FrameCache {
+        const EMPTY_FRAME: Cell<PhysicalAddress> = Cell::new(PhysicalAddress::new(0));
+        return FrameCache { frames: [EMPTY_FRAME; FRAME_CACHE_SIZE], count: Cell::new(0) };
+    }
+
+    pub fn pop(&self) -> Option<PhysicalAddress> {
+        let count = self.count.get();
+        if count == 0 {
+            return None;
+        }
+
+        self.count.set(count - 1);
+        return Some(self.frames[count - 1].get());
+    }
+
+    /// Returns the frame back if the cache is full
+    pub fn push(&self, frame: PhysicalAddress) -> Option<PhysicalAddress> {
+        let count = self.count.get();
+        if count == FRAME_CACHE_SIZE {
+            return Some(frame);
+        }
+
+        self.frames[count].set(frame);
+        self.count.set(count + 1);
+        return None;
+    }
+
+    pub fn len(&self) -> usize {
+        return self.count.get();
+    }
+
+    pub fn is_full(&self) -> bool {
+        return self.len() == FRAME_CACHE_SIZE;
+    }
+}
+
+/// The data owned by a single CPU. The active GS base of each CPU points at its own block
+/// while it runs kernel code, with the first field pointing back at the block so it can be
+/// found with a single GS relative load.
+#[repr(C)]
+pub struct PerCpu {
+    self_pointer: Cell<*const PerCpu>,
+    scratch: [Cell<u64>; PER_CPU_SCRATCH_SLOTS],
+    current_thread: Cell<usize>,
+    cpu_index: Cell<usize>,
+    apic_id: Cell<u32>,
+    tss: UnsafeCell<TaskStateSegment>,
+    gdt: UnsafeCell<GdtWithTss>,
+    frame_cache: FrameCache,
+}
+
+//Each block is only modified by its own CPU once it is running
+unsafe impl Sync for PerCpu {}
+
+const _: () = assert!(offset_of!(PerCpu, self_pointer) == PER_CPU_SELF_OFFSET);
+const _: () = assert!(offset_of!(PerCpu, scratch) == PER_CPU_SCRATCH_OFFSET);
+const _: () = assert!(offset_of!(PerCpu, current_thread) == PER_CPU_CURRENT_THREAD_OFFSET);
+
+impl PerCpu {
+    const fn new() -> PerCpu {
+        const EMPTY_SCRATCH: Cell<u64> = Cell::new(0);
+        return PerCpu {
+            self_pointer: Cell::new(core::ptr::null()),
+            scratch: [EMPTY_SCRATCH; PER_CPU_SCRATCH_SLOTS],
+            current_thread: Cell::new(IDLE_THREAD),
+            cpu_index: Cell::new(0),
+            apic_id: Cell::new(0),
+            tss: UnsafeCell::new(TaskStateSegment::new()),
+            gdt: UnsafeCell::new(GdtWithTss::new()),
+            frame_cache: FrameCache::new(),
+        };
+    }
+
+    pub fn cpu_index(&self) -> usize {
+        return self.cpu_index.get();
+    }
+
+    pub fn apic_id(&self) -> u32 {
+        return self.apic_id.get();
+    }
+
+    /// Sets the thread *current_thread* reports for this CPU
+    pub fn set_current_thread(&self, thread_id: usize) {
+        self.current_thread.set(thread_id);
+    }
+
+    /// The BSP block is set up before the local APIC is enabled, using the 8 bit initial APIC
+    /// ID. This corrects it once the full ID can be read.
+    pub fn set_apic_id(&self, apic_id: u32) {
+        self.apic_id.set(apic_id);
+    }
+
+    /// The TSS of this CPU. The stack tables can be changed while it is loaded as the
+    /// processor reads them on each privilege change or IST interrupt.
+    ///
+    /// ## Safety
+    ///
+    /// Only the owning CPU may modify its TSS and there must be no other reference to it.
+    pub unsafe fn tss_mut(&self) -> &mut TaskStateSegment {
+        return &mut *self.tss.get();
+    }
+
+    pub fn frame_cache(&self) -> &FrameCache {
+        return &self.frame_cache;
+    }
+
+    /// Fills in the identity of the CPU before it is started.
+    ///
+    /// ## Safety
+    ///
+    /// This must be called before the CPU is activated and not while it is running.
+    pub unsafe fn prepare(&self, cpu_index: usize, apic_id: u32) {
+        self.cpu_index.set(cpu_index);
+        self.apic_id.set(apic_id);
+        self.set_current_thread(IDLE_THREAD);
+        for slot in self.scratch.iter() {
+            slot.set(0);
+        }
+    }
+
+    /// Loads the GDT and TSS of this block and points the GS base at it. The kernel GS base
+    /// is cleared, ready to be swapped for the user GS base on the first return to user mode.
+    /// Interrupts from user mode use this CPU's own interrupt stack.
+    ///
+    /// ## Safety
+    ///
+    /// This must be called once, on the CPU the block was prepared for.
+    pub unsafe fn activate(&'static self) {
+        self.self_pointer.set(self);
+        self.tss_mut().privilege_stack_table[0] = interrupt_stack_top(self.cpu_index());
+        let gdt: &'static mut GdtWithTss = &mut *self.gdt.get();
+        gdt.set_tss(&*self.tss.get());
+        gdt.load();
+
+        cpu::write_gs_base(self as *const PerCpu as u64);
+        cpu::write_kernel_gs_base(0);
+    }
+}
+
+const EMPTY_PER_CPU: PerCpu = PerCpu::new();
+static PER_CPU: [PerCpu; MAX_CPUS] = [EMPTY_PER_CPU; MAX_CPUS];
+
+/// The per-CPU block of a CPU. Only its own CPU may use it once it has been activated.
+pub fn per_cpu(cpu_index: usize) -> &'static PerCpu {
+    return &PER_CPU[cpu_index];
+}
+
+/// Sets up the per-CPU block of the BSP. This must happen before anything uses *current_cpu*.
+pub fn init_bsp_per_cpu() {
+    let bsp = per_cpu(0);
+    unsafe {
+        bsp.prepare(0, cpu::initial_apic_id() as u32);
+        bsp.activate();
+    }
+    PER_CPU_READY.store(true, Ordering::Release);
+}
+
+/// True once the BSP has a per-CPU block. APs activate theirs before running any other
+/// kernel code so this is true on every CPU after the BSP has set it.
+pub fn per_cpu_ready() -> bool {
+    return PER_CPU_READY.load(Ordering::Acquire);
+}
+
+/// The per-CPU block of the CPU running the code. This relies on the interrupt and syscall
+/// entry points swapping GS when coming from user mode, so the active GS base always points
+/// at the block while in the kernel.
+pub fn current_cpu() -> &'static PerCpu {
+    return unsafe { &*(cpu::read_gs_u64(PER_CPU_SELF_OFFSET) as *const PerCpu) };
+}
+
+/// The thread running on the current CPU, or IDLE_THREAD if the scheduler has not run one
+/// yet. It is read with a single GS relative load.
+pub fn current_thread() -> usize {
+    return unsafe { cpu::read_gs_u64(PER_CPU_CURRENT_THREAD_OFFSET) } as usize;
+}
@@ -0,0 +1,119 @@
+# Application processor startup trampoline
+#
+# This is copied to a page below 1MiB and each AP starts executing it in real mode after
+# a startup IPI, with CS set to the page number << 8 and IP 0. All the addresses it needs
+# are stored in ap_trampoline_data as offsets from ap_trampoline_start, and the BSP adds
+# the physical address of the page to them after copying. The page must be identity mapped
+# in the kernel page tables while APs are starting.
+#
+# The stack and CPU index are a single slot shared by every AP. An AP sets
+# trampoline_acknowledged once it has read them, and the BSP does not refill the slot
+# until then.
+
+    .section .text.ap_trampoline, "ax"
+    .global ap_trampoline_start
+    .global ap_trampoline_end
+    .global ap_trampoline_data
+
+    .code16
+ap_trampoline_start:
+    cli
+    cld
+    movw %cs, %ax
+    movw %ax, %ds
+
+    # Keep the linear address of the trampoline in ebx for the rest of the startup
+    xorl %ebx, %ebx
+    movw %cs, %bx
+    shll $4, %ebx
+
+    lgdtl trampoline_gdt_pointer - ap_trampoline_start
+
+    movl %cr0, %eax
+    orl $0x1, %eax
+    movl %eax, %cr0
+
+    ljmpl *(trampoline_protected_mode_pointer - ap_trampoline_start)
+
+    .code32
+trampoline_protected_mode:
+    movw $0x10, %ax
+    movw %ax, %ds
+    movw %ax, %es
+    movw %ax, %ss
+
+    # Enable PAE
+    movl %cr4, %eax
+    orl $(1 << 5), %eax
+    movl %eax, %cr4
+
+    movl (trampoline_cr3 - ap_trampoline_start)(%ebx), %eax
+    movl %eax, %cr3
+
+    # Use the BSP's EFER value which sets LME and any other features the kernel relies on
+    movl $0xC0000080, %ecx
+    movl (trampoline_efer - ap_trampoline_start)(%ebx), %eax
+    xorl %edx, %edx
+    wrmsr
+
+    # Enable paging and write protection
+    movl %cr0, %eax
+    orl $0x80010000, %eax
+    movl %eax, %cr0
+
+    ljmpl *(trampoline_long_mode_pointer - ap_trampoline_start)(%ebx)
+
+    .code64
+trampoline_long_mode:
+    movabsq $ap_long_mode_entry, %rax
+    jmpq *%rax
+
+    .align 8
+trampoline_gdt:
+    .quad 0x0000000000000000 # NULL descriptor
+    .quad 0x00CF9A000000FFFF # 32 bit code
+    .quad 0x00CF92000000FFFF # 32 bit data
+    .quad 0x00AF9A000000FFFF # 64 bit code
+trampoline_gdt_end:
+
+# The layout of this must match TrampolineData in smp/mod.rs
+    .align 8
+ap_trampoline_data:
+trampoline_gdt_pointer:
+    .word trampoline_gdt_end - trampoline_gdt - 1
+    .long trampoline_gdt - ap_trampoline_start
+trampoline_protected_mode_pointer:
+    .long trampoline_protected_mode - ap_trampoline_start
+    .word 0x08
+trampoline_long_mode_pointer:
+    .long trampoline_long_mode - ap_trampoline_start
+    .word 0x18
+trampoline_cr3:
+    .long 0
+trampoline_efer:
+    .long 0
+trampoline_stack_top:
+    .quad 0
+trampoline_cpu_index:
+    .quad 0
+trampoline_acknowledged:
+    .byte 0
+ap_trampoline_end:
+
+    .section .text
+    .global ap_long_mode_entry
+
+# Entered in long mode on the kernel page tables with ebx still holding the address of
+# the trampoline. Switches to the stack the BSP allocated, releases the shared slot and
+# calls ap_main.
+ap_long_mode_entry:
+    movl %ebx, %ebx
+    movq (trampoline_stack_top - ap_trampoline_start)(%rbx), %rsp
+    movq (trampoline_cpu_index - ap_trampoline_start)(%rbx), %rdi
+    movb $1, (trampoline_acknowledged - ap_trampoline_start)(%rbx)
+    xorl %ebp, %ebp
+    call ap_main
+ap_halt:
+    cli
+    hlt
+    jmp ap_halt
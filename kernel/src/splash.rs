@@ -0,0 +1,157 @@
+use acpi_system_tables::{Bgrt, SignatureType};
+use bootinfo::FrameBuffer;
+use graphics::{Bitmap, Canvas, Colour, DoubleBuffer, Image, Rect, Rotated};
+use logging::{debug, info, warn};
+use spin::Mutex;
+
+use crate::acpi;
+use crate::memory::VIRTUAL_MEMORY_MANAGER;
+use crate::screen::screen_buffers;
+
+const BACKGROUND: Colour = Colour::BLACK;
+const PROGRESS_OUTLINE: Colour = Colour::rgb(0x808080);
+const PROGRESS_FILL: Colour = Colour::rgb(0xD0D0D0);
+const PROGRESS_WIDTH: u32 = 240;
+const PROGRESS_HEIGHT: u32 = 8;
+
+/// The vendor logo and a progress bar, shown in place of the framebuffer console while the
+/// kernel boots
+struct Splash {
+    double_buffer: DoubleBuffer<'static>,
+    progress_bar: Rect,
+    num_stages: u32,
+    stage: u32,
+}
+
+//The splash is only touched through SPLASH so the raw pointers never cross threads unguarded
+unsafe impl Send for Splash {}
+
+impl Splash {
+    fn draw_progress(&mut self) {
+        let bar = self.progress_bar;
+        let filled = bar.width.saturating_sub(4) as u64 * self.stage.min(self.num_stages) as u64 / self.num_stages.max(1) as u64;
+        let canvas = self.double_buffer.canvas();
+        canvas.draw_rect(bar, PROGRESS_OUTLINE);
+        canvas.fill_rect(Rect::new(bar.x + 2, bar.y + 2, filled as u32, bar.height - 4), PROGRESS_FILL);
+        self.double_buffer.present();
+    }
+}
+
+static SPLASH: Mutex<Option<Splash>> = Mutex::new(None);
+
+/// Redraws the firmware logo from the BGRT with a progress bar of *num_stages* steps under
+/// it. Returns false, leaving the screen alone, if there is no logo, no linear framebuffer
+/// or the command line has "nosplash". ACPI must be initialised first.
+pub fn init(framebuffer: &FrameBuffer, command_line: &str, num_stages: u32) -> bool {
+    if command_line.split_ascii_whitespace().any(|option| option == "nosplash") || !framebuffer.is_linear() {
+        return false;
+    }
+
+    let bgrt = match acpi::find_table(SignatureType::BGRT).and_then(|table| table.as_bgrt()) {
+        Some(bgrt) => bgrt,
+        None => {
+            debug!("No BGRT so there is no boot logo");
+            return false;
+        }
+    };
+
+    let logo = match read_logo(&bgrt) {
+        Some(logo) => logo,
+        None => return false,
+    };
+    //The firmware drew the logo turned to suit the panel, so turn it the same way
+    let logo = match Rotated::new(&logo, bgrt.orientation_degrees()) {
+        Some(logo) => logo,
+        None => return false,
+    };
+
+    let buffers = screen_buffers(framebuffer);
+    let back_buffer = unsafe { core::slice::from_raw_parts_mut(buffers.back_buffer, framebuffer.width as usize * framebuffer.height as usize) };
+    let mut double_buffer = match unsafe { DoubleBuffer::from_framebuffer(framebuffer, buffers.front_buffer, back_buffer) } {
+        Some(double_buffer) => double_buffer,
+        None => return false,
+    };
+
+    let canvas = double_buffer.canvas();
+    canvas.clear(BACKGROUND);
+    let logo_position = logo_position(&bgrt, &logo, canvas);
+    canvas.blit(logo_position.x, logo_position.y, &logo);
+
+    //The bar sits halfway between the logo and the bottom of the screen
+    let logo_bottom = logo_position.bottom().max(0) as u32;
+    let progress_width = PROGRESS_WIDTH.min(canvas.width());
+    let progress_y = logo_bottom + (canvas.height().saturating_sub(logo_bottom + PROGRESS_HEIGHT)) / 2;
+    let progress_bar = Rect::new(((canvas.width() - progress_width) / 2) as i32, progress_y as i32, progress_width, PROGRESS_HEIGHT);
+    double_buffer.present_all();
+
+    let mut splash = Splash {
+        double_buffer: double_buffer,
+        progress_bar: progress_bar,
+        num_stages: num_stages,
+        stage: 0,
+    };
+    splash.draw_progress();
+    *SPLASH.lock() = Some(splash);
+
+    info!("Showing the {}x{} boot logo at {},{}", logo_position.width, logo_position.height, logo_position.x, logo_position.y);
+    return true;
+}
+
+/// Moves the progress bar on by one stage
+pub fn advance() {
+    if let Some(splash) = SPLASH.lock().as_mut() {
+        splash.stage += 1;
+        splash.draw_progress();
+    }
+}
+
+/// Fills the progress bar and lets go of the screen so the framebuffer console can take it
+/// over. Returns true if a splash was being shown.
+pub fn finish() -> bool {
+    let mut splash = SPLASH.lock();
+    if let Some(splash) = splash.as_mut() {
+        splash.stage = splash.num_stages;
+        splash.draw_progress();
+    }
+    return splash.take().is_some();
+}
+
+/// Reads the logo through the offset mapping. It is in boot services data, which the
+/// kernel is never handed as free memory.
+fn read_logo(bgrt: &Bgrt) -> Option<Image<'static>> {
+    let image_size = match unsafe { bgrt.image_size(VIRTUAL_MEMORY_MANAGER.mapped_mem_offset()) } {
+        Some(image_size) => image_size,
+        None => {
+            warn!("The BGRT logo is not a BMP file or is too large");
+            return None;
+        }
+    };
+
+    //The image need not start on a page boundary so the address is not masked to one
+    let image_address = bgrt.image_address() + VIRTUAL_MEMORY_MANAGER.mapped_mem_offset();
+    let image_data = unsafe { core::slice::from_raw_parts(image_address as *const u8, image_size as usize) };
+    match Image::parse_bmp(image_data) {
+        Ok(image) => return Some(image),
+        Err(error) => {
+            warn!("Cannot decode the boot logo: {:?}", error);
+            return None;
+        }
+    }
+}
+
+/// Where to draw the logo once it has been rotated. The BGRT offsets are for the mode the
+/// firmware drew in, so they are only used if they still centre the logo horizontally.
+/// Otherwise the logo is centred with its middle 38.2% of the way down the screen, where
+/// firmware conventionally puts it.
+fn logo_position<B: Bitmap>(bgrt: &Bgrt, logo: &B, canvas: &Canvas) -> Rect {
+    let (width, height) = (logo.width(), logo.height());
+    let (offset_x, offset_y) = (bgrt.image_offset_x(), bgrt.image_offset_y());
+    let centred = (offset_x as u64 * 2 + width as u64).abs_diff(canvas.width() as u64) <= 1;
+    if centred && offset_y as u64 + height as u64 <= canvas.height() as u64 {
+        return Rect::new(offset_x as i32, offset_y as i32, width, height);
+    }
+
+    let x = (canvas.width() as i64 - width as i64) / 2;
+    let y = canvas.height() as i64 * 382 / 1000 - height as i64 / 2;
+    return Rect::new(x as i32, y.max(0) as i32, width, height);
+}
@@ -0,0 +1,94 @@
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use bootinfo::KernelSymbols;
+use elf::{ElfStringTable, ElfSymbolTable};
+use logging::{debug, error};
+use spin::Once;
+
+/// The most frames a backtrace follows, in case the frame pointer chain is corrupt
+const MAX_BACKTRACE_DEPTH: usize = 32;
+/// Kernel stacks are all in the higher half so a frame pointer below this ends the chain
+const KERNEL_SPACE_START: u64 = 0xFFFF_8000_0000_0000;
+
+struct Symbols {
+    symbol_table: ElfSymbolTable<'static>,
+    string_table: ElfStringTable<'static>,
+}
+
+static SYMBOLS: Once<Symbols> = Once::new();
+/// Set while a backtrace is being walked so a fault part way through does not start another
+static IN_BACKTRACE: AtomicBool = AtomicBool::new(false);
+
+/// Keeps the symbol table the bootloader mapped. Without it addresses are logged on their own.
+pub fn init(kernel_symbols: &KernelSymbols) {
+    if !kernel_symbols.is_present() {
+        debug!("No kernel symbol table so backtraces are not symbolized");
+        return;
+    }
+
+    //The bootloader mapped the tables in kernel space, which is never unmapped
+    let symbols = unsafe {
+        Symbols {
+            symbol_table: ElfSymbolTable::new(kernel_symbols.symbol_table_bytes()),
+            string_table: ElfStringTable::new(kernel_symbols.string_table_bytes()),
+        }
+    };
+    debug!("Found {} kernel symbols", symbols.symbol_table.len());
+    SYMBOLS.call_once(|| symbols);
+}
+
+/// The name of the function or object *address* is in and how far into it the address is
+pub fn resolve(address: u64) -> Option<(&'static str, u64)> {
+    let symbols = SYMBOLS.get()?;
+    let symbol = symbols.symbol_table.find_by_address(address)?;
+    let name = symbol.name(&symbols.string_table)?;
+    return Some((name, address - symbol.st_value));
+}
+
+/// Logs *address* with the symbol it is in
+pub fn log_address(label: &str, address: u64) {
+    match resolve(address) {
+        Some((name, offset)) => error!("{} {:#018x} {}+{:#x}", label, address, name, offset),
+        None => error!("{} {:#018x}", label, address),
+    }
+}
+
+/// Logs the return addresses on the stack by following the frame pointer chain from
+/// *frame_pointer*. The kernel is built with frame pointers, and the chain ends at the null
+/// frame pointer _start and the AP entry point set up.
+pub fn log_backtrace(frame_pointer: u64) {
+    if IN_BACKTRACE.swap(true, Ordering::Acquire) {
+        return;
+    }
+
+    error!("Backtrace:");
+    let mut frame_pointer = frame_pointer;
+    for _ in 0..MAX_BACKTRACE_DEPTH {
+        if frame_pointer < KERNEL_SPACE_START || frame_pointer % 8 != 0 {
+            break;
+        }
+
+        //Each frame starts with the caller's frame pointer followed by the return address
+        let (next_frame_pointer, return_address) = unsafe { (*(frame_pointer as *const u64), *((frame_pointer + 8) as *const u64)) };
+        if return_address == 0 {
+            break;
+        }
+        log_address("  at", return_address);
+
+        //Stacks grow down so callers' frames are always higher up
+        if next_frame_pointer <= frame_pointer {
+            break;
+        }
+        frame_pointer = next_frame_pointer;
+    }
+
+    IN_BACKTRACE.store(false, Ordering::Release);
+}
+
+/// Logs the backtrace of the caller
+#[inline(always)]
+pub fn log_current_backtrace() {
+    let frame_pointer: u64;
+    unsafe { core::arch::asm!("mov {}, rbp", out(reg) frame_pointer, options(nomem, nostack, preserves_flags)); }
+    log_backtrace(frame_pointer);
+}
@@ -0,0 +1,48 @@
+use acpi_system_tables::{AddressSpace, SignatureType};
+use logging::{info, warn};
+use spin::Once;
+use x86_64_hardware::devices::hpet::{Hpet, HPET_REGISTER_BLOCK_SIZE};
+use x86_64_hardware::memory::{PAGE_SIZE, PhysicalAddress, VirtualAddress};
+
+use crate::acpi;
+use crate::memory::VIRTUAL_MEMORY_MANAGER;
+
+pub static HPET: Once<Hpet> = Once::new();
+
+/// Finds the HPET through ACPI, maps its registers and starts the main counter. Returns
+/// false when the system has no usable HPET.
+pub fn init_hpet() -> bool {
+    let table = match acpi::find_table(SignatureType::HPET).and_then(|table| table.as_hpet()) {
+        Some(table) => table,
+        None => {
+            warn!("No HPET found");
+            return false;
+        }
+    };
+
+    let base_address = table.base_address();
+    if base_address.address_space() != AddressSpace::SystemMemory || base_address.is_null() {
+        warn!("HPET registers are not in system memory");
+        return false;
+    }
+
+    let physical_address = PhysicalAddress::new(base_address.address());
+    let page_offset = base_address.address() - physical_address.as_u64();
+    let num_pages = (page_offset + HPET_REGISTER_BLOCK_SIZE + PAGE_SIZE - 1) / PAGE_SIZE;
+    let virtual_address = VIRTUAL_MEMORY_MANAGER.map_mmio(physical_address, num_pages);
+    let register_address = VirtualAddress::new(virtual_address.as_u64() + page_offset);
+
+    let hpet = match unsafe { Hpet::new(register_address, table.minimum_tick() as u64) } {
+        Some(hpet) => HPET.call_once(|| hpet),
+        None => {
+            warn!("Ignoring an HPET with an invalid counter period");
+            return false;
+        }
+    };
+    hpet.enable();
+
+    info!("HPET at {:#x}: {} Hz, {} comparators, {} bit counter",
+        base_address.address(), hpet.frequency(), hpet.num_comparators(),
+        if hpet.counter_is_64_bit() { 64 } else { 32 });
+    return true;
+}
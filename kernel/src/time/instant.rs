@@ -0,0 +1,115 @@
+use core::ops::{Add, Sub};
+use core::sync::atomic::{AtomicU8, Ordering};
+use core::time::Duration;
+
+use logging::info;
+use x86_64_hardware::cpu;
+
+use super::{tick_frequency, ticks, tsc_frequency, tsc_to_nanoseconds, HPET};
+
+/// The counter *Instant::now* is read from, best first
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ClockSource {
+    Tsc,
+    Hpet,
+    PitTick,
+    None,
+}
+
+impl ClockSource {
+    fn from_u8(value: u8) -> ClockSource {
+        match value {
+            0 => ClockSource::Tsc,
+            1 => ClockSource::Hpet,
+            2 => ClockSource::PitTick,
+            _ => ClockSource::None,
+        }
+    }
+
+    fn as_u8(&self) -> u8 {
+        match self {
+            ClockSource::Tsc => 0,
+            ClockSource::Hpet => 1,
+            ClockSource::PitTick => 2,
+            ClockSource::None => 3,
+        }
+    }
+}
+
+static CLOCK_SOURCE: AtomicU8 = AtomicU8::new(3);
+
+/// Picks the clock source for monotonic timestamps. The TSC is only used when it is invariant
+/// as otherwise its rate changes with power states. This must run after the TSC, HPET and
+/// PIT have been initialised.
+pub fn init_clock_source() {
+    let source = if cpu::has_invariant_tsc() && tsc_frequency() != 0 {
+        ClockSource::Tsc
+    } else if HPET.get().is_some() {
+        ClockSource::Hpet
+    } else if tick_frequency() != 0 {
+        ClockSource::PitTick
+    } else {
+        ClockSource::None
+    };
+
+    CLOCK_SOURCE.store(source.as_u8(), Ordering::Release);
+    info!("Using {:?} as the clock source", source);
+}
+
+pub fn clock_source() -> ClockSource {
+    return ClockSource::from_u8(CLOCK_SOURCE.load(Ordering::Acquire));
+}
+
+/// Nanoseconds since an arbitrary point early in boot. Always 0 before a clock source is
+/// chosen.
+pub fn monotonic_nanoseconds() -> u64 {
+    match clock_source() {
+        ClockSource::Tsc => tsc_to_nanoseconds(cpu::read_tsc()),
+        ClockSource::Hpet => HPET.get().unwrap().nanoseconds(),
+        ClockSource::PitTick => (ticks() as u128 * 1_000_000_000 / tick_frequency() as u128) as u64,
+        ClockSource::None => 0,
+    }
+}
+
+/// A point in time read from the monotonic clock. Instants never go backwards so the
+/// difference between two of them is always a valid duration.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct Instant(u64);
+
+impl Instant {
+    pub fn now() -> Instant {
+        return Instant(monotonic_nanoseconds());
+    }
+
+    /// The time from *earlier* to this instant, or zero if *earlier* is later
+    pub fn duration_since(&self, earlier: Instant) -> Duration {
+        return Duration::from_nanos(self.0.saturating_sub(earlier.0));
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        return Instant::now().duration_since(*self);
+    }
+
+    /// The instant *duration* after this one, or None if it cannot be represented
+    pub fn checked_add(&self, duration: Duration) -> Option<Instant> {
+        let nanoseconds = u64::try_from(duration.as_nanos()).ok()?;
+        return self.0.checked_add(nanoseconds).map(Instant);
+    }
+}
+
+impl Add<Duration> for Instant {
+    type Output = Instant;
+
+    /// Saturates at the latest representable instant rather than overflowing
+    fn add(self, duration: Duration) -> Instant {
+        return self.checked_add(duration).unwrap_or(Instant(u64::MAX));
+    }
+}
+
+impl Sub<Instant> for Instant {
+    type Output = Duration;
+
+    fn sub(self, earlier: Instant) -> Duration {
+        return self.duration_since(earlier);
+    }
+}
@@ -0,0 +1,11 @@
+mod hpet;
+mod instant;
+mod pit;
+mod tsc;
+mod wall_clock;
+
+pub use hpet::*;
+pub use instant::*;
+pub use pit::*;
+pub use tsc::*;
+pub use wall_clock::*;
@@ -0,0 +1,59 @@
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use logging::info;
+use x86_64_hardware::devices::pit_8254::{PIT, PIT_IRQ};
+use x86_64_hardware::tables::{wait_for_interrupt, InterruptFrame};
+
+use crate::irq;
+
+static TICKS: AtomicU64 = AtomicU64::new(0);
+static TICK_FREQUENCY: AtomicU64 = AtomicU64::new(0);
+
+/// Starts the PIT at *frequency* Hz and installs the IRQ0 handler that drives the kernel
+/// tick. Interrupts must be enabled separately for the tick to advance.
+pub fn init_pit(frequency: u32) {
+    let actual_frequency = PIT.lock().set_periodic(frequency);
+    irq::enable_isa_irq(PIT_IRQ, pit_interrupt_handler);
+
+    TICK_FREQUENCY.store(actual_frequency as u64, Ordering::Relaxed);
+    info!("PIT tick running at {} Hz", actual_frequency);
+}
+
+fn pit_interrupt_handler(_frame: &mut InterruptFrame) {
+    TICKS.fetch_add(1, Ordering::Relaxed);
+    irq::end_of_isa_irq(PIT_IRQ);
+}
+
+/// The number of ticks since the PIT was started
+pub fn ticks() -> u64 {
+    return TICKS.load(Ordering::Relaxed);
+}
+
+pub fn tick_frequency() -> u64 {
+    return TICK_FREQUENCY.load(Ordering::Relaxed);
+}
+
+/// The tick the kernel must reach for at least *ms* milliseconds to have passed. One extra
+/// tick is added as the current tick may be nearly over.
+fn target_tick(ms: u64) -> u64 {
+    let frequency = tick_frequency();
+    return ticks() + (ms * frequency).div_ceil(1000) + 1;
+}
+
+/// Spins for at least *ms* milliseconds. This keeps the processor busy so prefer *sleep_ms*
+/// unless interrupts are needed to stay responsive to something other than the tick.
+pub fn busy_sleep_ms(ms: u64) {
+    let target = target_tick(ms);
+    while ticks() < target {
+        core::hint::spin_loop();
+    }
+}
+
+/// Halts the processor until at least *ms* milliseconds have passed. Interrupts must be
+/// enabled or this never returns.
+pub fn sleep_ms(ms: u64) {
+    let target = target_tick(ms);
+    while ticks() < target {
+        wait_for_interrupt();
+    }
+}
@@ -0,0 +1,75 @@
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use logging::{info, warn};
+use x86_64_hardware::cpu;
+use x86_64_hardware::devices::hpet::Hpet;
+use x86_64_hardware::devices::pit_8254::{PIT, PIT_BASE_FREQUENCY};
+use x86_64_hardware::tables::without_interrupts;
+
+use super::HPET;
+
+const CALIBRATION_MS: u64 = 10;
+const CALIBRATION_RUNS: usize = 3;
+const NANOSECONDS_PER_SECOND: u64 = 1_000_000_000;
+
+static TSC_FREQUENCY: AtomicU64 = AtomicU64::new(0);
+
+/// Finds the TSC frequency from CPUID, or by measuring it against the HPET or the PIT. The
+/// PIT is reprogrammed for calibration so this must run before *init_pit*. Returns false if
+/// the processor has no TSC.
+pub fn init_tsc() -> bool {
+    if !cpu::has_tsc() {
+        warn!("No TSC found");
+        return false;
+    }
+
+    let (frequency, source) = match cpu::tsc_frequency_from_cpuid() {
+        Some(frequency) => (frequency, "CPUID"),
+        None => match HPET.get() {
+            Some(hpet) => (calibrate(|| calibrate_with_hpet(hpet)), "HPET"),
+            None => (calibrate(calibrate_with_pit), "PIT"),
+        },
+    };
+
+    TSC_FREQUENCY.store(frequency, Ordering::Relaxed);
+    info!("TSC running at {}.{:03} MHz ({}), invariant: {}",
+        frequency / 1_000_000, frequency / 1_000 % 1_000, source, cpu::has_invariant_tsc());
+    return true;
+}
+
+/// Takes the lowest of several calibration runs. Anything that delays a run, such as an SMI,
+/// can only make the measured frequency higher.
+fn calibrate(run: impl Fn() -> u64) -> u64 {
+    return (0..CALIBRATION_RUNS).map(|_| without_interrupts(&run)).min().unwrap();
+}
+
+fn calibrate_with_hpet(hpet: &Hpet) -> u64 {
+    let start_ns = hpet.nanoseconds();
+    let start_tsc = cpu::read_tsc();
+    hpet.busy_wait_ns(CALIBRATION_MS * 1_000_000);
+    let end_tsc = cpu::read_tsc();
+    let end_ns = hpet.nanoseconds();
+
+    return ((end_tsc - start_tsc) as u128 * NANOSECONDS_PER_SECOND as u128 / (end_ns - start_ns) as u128) as u64;
+}
+
+fn calibrate_with_pit() -> u64 {
+    let count = (PIT_BASE_FREQUENCY as u64 * CALIBRATION_MS / 1000) as u16;
+    let mut pit = PIT.lock();
+
+    let start_tsc = cpu::read_tsc();
+    pit.one_shot_wait(count);
+    let end_tsc = cpu::read_tsc();
+
+    return (end_tsc - start_tsc) * PIT_BASE_FREQUENCY as u64 / count as u64;
+}
+
+/// The TSC frequency in Hz or 0 if it is not known
+pub fn tsc_frequency() -> u64 {
+    return TSC_FREQUENCY.load(Ordering::Relaxed);
+}
+
+/// Converts a number of TSC ticks to nanoseconds. The TSC frequency must be known.
+pub fn tsc_to_nanoseconds(ticks: u64) -> u64 {
+    return (ticks as u128 * NANOSECONDS_PER_SECOND as u128 / tsc_frequency() as u128) as u64;
+}
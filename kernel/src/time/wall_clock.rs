@@ -0,0 +1,65 @@
+use acpi_system_tables::SignatureType;
+use date_time::DateTime;
+use logging::{info, warn};
+use spin::Once;
+use x86_64_hardware::devices::rtc_cmos::{RTC, RTC_IRQ};
+use x86_64_hardware::tables::InterruptFrame;
+
+use crate::{acpi, irq};
+use super::Instant;
+
+const NANOSECONDS_PER_SECOND: u64 = 1_000_000_000;
+
+/// The RTC reading the wall clock counts forward from, and the monotonic time it was taken at
+struct WallClockBase {
+    unix_seconds: u64,
+    instant: Instant,
+}
+
+static WALL_CLOCK: Once<WallClockBase> = Once::new();
+
+/// Reads the RTC once and from then on derives the wall clock from the monotonic clock, so
+/// reading it is as cheap as *Instant::now*. The clock source must already be chosen.
+/// Returns false if the platform has no CMOS RTC.
+pub fn init_wall_clock() -> bool {
+    let fadt = acpi::find_table(SignatureType::FACP).and_then(|table| table.as_fadt());
+    if let Some(fadt) = &fadt {
+        if fadt.is_hardware_reduced() || !fadt.has_cmos_rtc() {
+            warn!("No CMOS RTC. The wall clock is not available");
+            return false;
+        }
+    }
+
+    let mut rtc = RTC.lock();
+    rtc.set_century_register(fadt.and_then(|fadt| fadt.century_register()));
+    let date_time = rtc.read();
+    //Clear anything already pending as the RTC raises no interrupts until it is acknowledged
+    rtc.acknowledge_interrupt();
+    irq::enable_isa_irq(RTC_IRQ, rtc_interrupt_handler);
+
+    WALL_CLOCK.call_once(|| WallClockBase { unix_seconds: date_time.to_unix_seconds(), instant: Instant::now() });
+    info!("Wall clock set to {}", date_time);
+    return true;
+}
+
+/// Runs the handlers set on the RTC for the sources that raised the interrupt. As this takes
+/// the RTC lock, everything else must hold it with interrupts disabled.
+fn rtc_interrupt_handler(_frame: &mut InterruptFrame) {
+    let handlers = RTC.lock().take_interrupt();
+    for handler in handlers.into_iter().flatten() {
+        handler();
+    }
+    irq::end_of_isa_irq(RTC_IRQ);
+}
+
+/// Nanoseconds since 1970-01-01T00:00:00Z, or None before the wall clock is initialised.
+/// The RTC only has second resolution so this can be up to a second behind.
+pub fn unix_time_nanoseconds() -> Option<u64> {
+    let base = WALL_CLOCK.get()?;
+    return Some(base.unix_seconds * NANOSECONDS_PER_SECOND + base.instant.elapsed().as_nanos() as u64);
+}
+
+/// The current UTC date and time
+pub fn wall_clock_now() -> Option<DateTime> {
+    return Some(DateTime::from_unix_seconds(unix_time_nanoseconds()? / NANOSECONDS_PER_SECOND));
+}
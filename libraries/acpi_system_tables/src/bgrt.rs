@@ -0,0 +1,122 @@
+use crate::{SystemDescriptionTableHeader, SystemDescriptionTable};
+
+const STATUS_DISPLAYED: u8 = 0x01;
+const STATUS_ORIENTATION_SHIFT: u8 = 1;
+const STATUS_ORIENTATION_MASK: u8 = 0x03;
+
+/// The only image type defined by the specification
+pub const BGRT_IMAGE_TYPE_BITMAP: u8 = 0;
+/// Anything larger than this is taken to be a corrupt BGRT rather than a logo
+pub const MAX_BGRT_IMAGE_SIZE: u64 = 64 * 1024 * 1024;
+
+const BMP_SIGNATURE: [u8; 2] = *b"BM";
+/// The offset of bfSize in the BMP file header
+const BMP_FILE_SIZE_OFFSET: u64 = 2;
+
+#[repr(C, packed)]
+struct BgrtInternal {
+    header: SystemDescriptionTableHeader,
+    version: u16,
+    status: u8,
+    image_type: u8,
+    image_address: u64,
+    image_offset_x: u32,
+    image_offset_y: u32,
+}
+
+/// The Boot Graphics Resource Table, which points at the logo the firmware drew during
+/// boot so the OS can keep it on screen
+pub struct Bgrt {
+    bgrt_ptr: *mut BgrtInternal,
+}
+
+impl Bgrt {
+    /// Creates a new Bgrt that wraps a BGRT table pointer.
+    /// 
+    /// ## Safety
+    /// 
+    /// This is unsafe because we cannot know the address and offset are valid. The address
+    /// must come from the RSDT/XSDT and the offset from the virtual memory management logic of
+    /// the OS
+    pub unsafe fn new(physical_address: u64, offset: u64) -> Bgrt {
+        let virtual_address = physical_address + offset;
+
+        return Bgrt {
+            bgrt_ptr: virtual_address as *mut BgrtInternal,
+        }
+    }
+
+    pub fn version(&self) -> u16 {
+        return unsafe { core::ptr::read_unaligned(core::ptr::addr_of!((*self.bgrt_ptr).version)) };
+    }
+
+    fn status(&self) -> u8 {
+        return unsafe { (*self.bgrt_ptr).status };
+    }
+
+    /// True if the logo is still on screen. The firmware clears this if it draws over it,
+    /// and it says nothing about whether a mode change since has cleared the screen.
+    pub fn displayed(&self) -> bool {
+        return self.status() & STATUS_DISPLAYED != 0;
+    }
+
+    /// How far clockwise the logo was rotated when drawn, in degrees
+    pub fn orientation_degrees(&self) -> u16 {
+        return ((self.status() >> STATUS_ORIENTATION_SHIFT) & STATUS_ORIENTATION_MASK) as u16 * 90;
+    }
+
+    /// The format of the image. Only BGRT_IMAGE_TYPE_BITMAP, a BMP file, is defined.
+    pub fn image_type(&self) -> u8 {
+        return unsafe { (*self.bgrt_ptr).image_type };
+    }
+
+    /// The physical address of the image file. The firmware puts it in boot services data,
+    /// which is free memory once boot services have exited.
+    pub fn image_address(&self) -> u64 {
+        return unsafe { core::ptr::read_unaligned(core::ptr::addr_of!((*self.bgrt_ptr).image_address)) };
+    }
+
+    /// The size of the logo file from the bfSize field of its BMP file header, which counts
+    /// the whole file including the "BM" signature. Returns None if the logo is not a BMP
+    /// file or is larger than MAX_BGRT_IMAGE_SIZE.
+    ///
+    /// ## Safety
+    ///
+    /// The image must be mapped at its physical address plus *offset*.
+    pub unsafe fn image_size(&self, offset: u64) -> Option<u64> {
+        if self.image_type() != BGRT_IMAGE_TYPE_BITMAP || self.image_address() == 0 {
+            return None;
+        }
+
+        let image = (self.image_address() + offset) as *const u8;
+        if core::ptr::read_unaligned(image as *const [u8; 2]) != BMP_SIGNATURE {
+            return None;
+        }
+
+        let size = core::ptr::read_unaligned(image.add(BMP_FILE_SIZE_OFFSET as usize) as *const u32) as u64;
+        if size > MAX_BGRT_IMAGE_SIZE {
+            return None;
+        }
+        return Some(size);
+    }
+
+    /// The column of the top left corner of the logo on screen
+    pub fn image_offset_x(&self) -> u32 {
+        return unsafe { core::ptr::read_unaligned(core::ptr::addr_of!((*self.bgrt_ptr).image_offset_x)) };
+    }
+
+    /// The row of the top left corner of the logo on screen
+    pub fn image_offset_y(&self) -> u32 {
+        return unsafe { core::ptr::read_unaligned(core::ptr::addr_of!((*self.bgrt_ptr).image_offset_y)) };
+    }
+}
+
+impl SystemDescriptionTable {
+    pub fn as_bgrt(&self) -> Option<Bgrt> {
+        if self.get_signature() != crate::SignatureType::BGRT {
+            return None;
+        }
+
+        return unsafe { Some(Bgrt::new(self.physical_address(), self.mem_offset())) };
+    }
+}
@@ -0,0 +1,178 @@
+use crate::{GenericAddress, SerialInterfaceType, SystemDescriptionTableHeader, SystemDescriptionTable};
+
+pub const DBG2_PORT_TYPE_SERIAL: u16 = 0x8000;
+pub const DBG2_PORT_TYPE_1394: u16 = 0x8001;
+pub const DBG2_PORT_TYPE_USB: u16 = 0x8002;
+pub const DBG2_PORT_TYPE_NET: u16 = 0x8003;
+
+#[repr(C, packed)]
+struct Dbg2Internal {
+    header: SystemDescriptionTableHeader,
+    device_info_offset: u32,
+    device_info_count: u32,
+}
+
+#[repr(C, packed)]
+#[derive(Clone, Copy)]
+struct Dbg2DeviceInfo {
+    revision: u8,
+    length: u16,
+    num_generic_addresses: u8,
+    namespace_string_length: u16,
+    namespace_string_offset: u16,
+    oem_data_length: u16,
+    oem_data_offset: u16,
+    port_type: u16,
+    port_subtype: u16,
+    reserved: u16,
+    base_address_offset: u16,
+    address_size_offset: u16,
+}
+
+/// A debug device described by the DBG2. Each device can have several register blocks
+#[derive(Clone, Copy)]
+pub struct Dbg2Device {
+    device_ptr: *const u8,
+    info: Dbg2DeviceInfo,
+}
+
+impl Dbg2Device {
+    pub fn port_type(&self) -> u16 {
+        return self.info.port_type;
+    }
+
+    pub fn port_subtype(&self) -> u16 {
+        return self.info.port_subtype;
+    }
+
+    pub fn is_serial(&self) -> bool {
+        return self.port_type() == DBG2_PORT_TYPE_SERIAL;
+    }
+
+    /// The serial interface type if this is a serial port
+    pub fn serial_interface_type(&self) -> Option<SerialInterfaceType> {
+        if !self.is_serial() {
+            return None;
+        }
+        return Some(SerialInterfaceType::from_u16(self.port_subtype()));
+    }
+
+    pub fn num_base_addresses(&self) -> usize {
+        return self.info.num_generic_addresses as usize;
+    }
+
+    /// The register block at *index*. Returns None if it lies outside the device information
+    pub fn base_address(&self, index: usize) -> Option<GenericAddress> {
+        if index >= self.num_base_addresses() {
+            return None;
+        }
+
+        return self.read_field(self.info.base_address_offset as usize + index * core::mem::size_of::<GenericAddress>());
+    }
+
+    /// The size in bytes of the register block at *index*. Returns None if it lies outside
+    /// the device information
+    pub fn address_size(&self, index: usize) -> Option<u32> {
+        if index >= self.num_base_addresses() {
+            return None;
+        }
+
+        return self.read_field(self.info.address_size_offset as usize + index * core::mem::size_of::<u32>());
+    }
+
+    /// Reads a *T* at *offset* into the device information. The iterator checked the whole
+    /// structure is inside the table, so anything within its length can be read.
+    fn read_field<T: Copy>(&self, offset: usize) -> Option<T> {
+        if offset + core::mem::size_of::<T>() > self.info.length as usize {
+            return None;
+        }
+
+        return unsafe { Some(core::ptr::read_unaligned(self.device_ptr.add(offset) as *const T)) };
+    }
+}
+
+pub struct Dbg2 {
+    dbg2_ptr: *mut Dbg2Internal,
+}
+
+impl Dbg2 {
+    /// Creates a new Dbg2 that wraps a DBG2 table pointer. The device information structures
+    /// are variable length so we only hold the pointer internally.
+    /// 
+    /// ## Safety
+    /// 
+    /// This is unsafe because we cannot know the address and offset are valid. The address
+    /// must come from the RSDT/XSDT and the offset from the virtual memory management logic of
+    /// the OS
+    pub unsafe fn new(physical_address: u64, offset: u64) -> Dbg2 {
+        let virtual_address = physical_address + offset;
+
+        return Dbg2 {
+            dbg2_ptr: virtual_address as *mut Dbg2Internal,
+        }
+    }
+
+    pub fn num_devices(&self) -> usize {
+        return unsafe { core::ptr::read_unaligned(core::ptr::addr_of!((*self.dbg2_ptr).device_info_count)) as usize };
+    }
+
+    fn table_length(&self) -> usize {
+        return unsafe { (*(self.dbg2_ptr as *const SystemDescriptionTableHeader)).length() as usize };
+    }
+
+    fn first_device_offset(&self) -> usize {
+        return unsafe { core::ptr::read_unaligned(core::ptr::addr_of!((*self.dbg2_ptr).device_info_offset)) as usize };
+    }
+
+    /// Finds the first serial port the OS can drive as a 16550 with a readable register block
+    pub fn find_16550(&self) -> Option<Dbg2Device> {
+        return self.iter().find(|device| match device.serial_interface_type() {
+            Some(interface_type) => interface_type.is_16550_compatible() && device.base_address(0).is_some(),
+            None => false,
+        });
+    }
+
+    pub fn iter(&self) -> Dbg2Iterator<'_> {
+        Dbg2Iterator {
+            dbg2: self,
+            current_offset: self.first_device_offset(),
+            remaining_devices: self.num_devices(),
+        }
+    }
+}
+
+impl SystemDescriptionTable {
+    pub fn as_dbg2(&self) -> Option<Dbg2> {
+        if self.get_signature() != crate::SignatureType::DBG2 {
+            return None;
+        }
+
+        return unsafe { Some(Dbg2::new(self.physical_address(), self.mem_offset())) };
+    }
+}
+
+pub struct Dbg2Iterator<'a> {
+    dbg2: &'a Dbg2,
+    current_offset: usize,
+    remaining_devices: usize,
+}
+
+impl<'a> Iterator for Dbg2Iterator<'a> {
+    type Item = Dbg2Device;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining_devices == 0 || self.current_offset + core::mem::size_of::<Dbg2DeviceInfo>() > self.dbg2.table_length() {
+            return None;
+        }
+
+        let device_ptr = unsafe { (self.dbg2.dbg2_ptr as *const u8).add(self.current_offset) };
+        let info = unsafe { core::ptr::read_unaligned(device_ptr as *const Dbg2DeviceInfo) };
+        if (info.length as usize) < core::mem::size_of::<Dbg2DeviceInfo>() || self.current_offset + info.length as usize > self.dbg2.table_length() {
+            return None;
+        }
+
+        self.current_offset += info.length as usize;
+        self.remaining_devices -= 1;
+        return Some(Dbg2Device { device_ptr: device_ptr, info: info });
+    }
+}
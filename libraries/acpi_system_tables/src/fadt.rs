@@ -0,0 +1,192 @@
+use crate::{GenericAddress, SystemDescriptionTableHeader, SystemDescriptionTable};
+
+const BOOT_ARCH_LEGACY_DEVICES: u16 = 1 << 0;
+const BOOT_ARCH_8042: u16 = 1 << 1;
+const BOOT_ARCH_VGA_NOT_PRESENT: u16 = 1 << 2;
+const BOOT_ARCH_CMOS_RTC_NOT_PRESENT: u16 = 1 << 5;
+const FLAG_RESET_REGISTER_SUPPORTED: u32 = 1 << 10;
+const FLAG_HARDWARE_REDUCED: u32 = 1 << 20;
+
+#[repr(C, packed)]
+struct FadtInternal {
+    header: SystemDescriptionTableHeader,
+    firmware_ctrl: u32,
+    dsdt: u32,
+    reserved_1: u8,
+    preferred_pm_profile: u8,
+    sci_interrupt: u16,
+    smi_command_port: u32,
+    acpi_enable: u8,
+    acpi_disable: u8,
+    s4bios_request: u8,
+    pstate_control: u8,
+    pm1a_event_block: u32,
+    pm1b_event_block: u32,
+    pm1a_control_block: u32,
+    pm1b_control_block: u32,
+    pm2_control_block: u32,
+    pm_timer_block: u32,
+    gpe0_block: u32,
+    gpe1_block: u32,
+    pm1_event_length: u8,
+    pm1_control_length: u8,
+    pm2_control_length: u8,
+    pm_timer_length: u8,
+    gpe0_block_length: u8,
+    gpe1_block_length: u8,
+    gpe1_base: u8,
+    cstate_control: u8,
+    p_level2_latency: u16,
+    p_level3_latency: u16,
+    flush_size: u16,
+    flush_stride: u16,
+    duty_offset: u8,
+    duty_width: u8,
+    day_alarm: u8,
+    month_alarm: u8,
+    century: u8,
+    //Only present from revision 2
+    iapc_boot_arch: u16,
+    reserved_2: u8,
+    flags: u32,
+    reset_register: GenericAddress,
+    reset_value: u8,
+    arm_boot_arch: u16,
+    minor_version: u8,
+    x_firmware_ctrl: u64,
+    x_dsdt: u64,
+}
+
+/// The Fixed ACPI Description Table. Only the fields the kernel currently needs are exposed
+pub struct Fadt {
+    fadt_ptr: *mut FadtInternal,
+}
+
+impl Fadt {
+    /// Creates a new Fadt that wraps a FADT table pointer.
+    /// 
+    /// ## Safety
+    /// 
+    /// This is unsafe because we cannot know the address and offset are valid. The address
+    /// must come from the RSDT/XSDT and the offset from the virtual memory management logic of
+    /// the OS
+    pub unsafe fn new(physical_address: u64, offset: u64) -> Fadt {
+        let virtual_address = physical_address + offset;
+
+        return Fadt {
+            fadt_ptr: virtual_address as *mut FadtInternal,
+        }
+    }
+
+    fn table_length(&self) -> usize {
+        return unsafe { (*(self.fadt_ptr as *const SystemDescriptionTableHeader)).length() as usize };
+    }
+
+    /// Whether the table is long enough to contain a field ending at *end_offset*
+    fn has_field(&self, end_offset: usize) -> bool {
+        return self.table_length() >= end_offset;
+    }
+
+    /// The physical address of the DSDT, preferring the 64 bit field when present
+    pub fn dsdt_address(&self) -> u64 {
+        if self.has_field(core::mem::size_of::<FadtInternal>()) {
+            let x_dsdt = unsafe { core::ptr::read_unaligned(core::ptr::addr_of!((*self.fadt_ptr).x_dsdt)) };
+            if x_dsdt != 0 {
+                return x_dsdt;
+            }
+        }
+        return unsafe { core::ptr::read_unaligned(core::ptr::addr_of!((*self.fadt_ptr).dsdt)) } as u64;
+    }
+
+    pub fn preferred_pm_profile(&self) -> u8 {
+        return unsafe { (*self.fadt_ptr).preferred_pm_profile };
+    }
+
+    /// The ISA IRQ the SCI is wired to in 8259 mode
+    pub fn sci_interrupt(&self) -> u16 {
+        return unsafe { core::ptr::read_unaligned(core::ptr::addr_of!((*self.fadt_ptr).sci_interrupt)) };
+    }
+
+    /// The CMOS RAM index of the RTC day of month alarm, if supported
+    pub fn day_alarm_register(&self) -> Option<u8> {
+        return non_zero(unsafe { (*self.fadt_ptr).day_alarm });
+    }
+
+    /// The CMOS RAM index of the RTC month alarm, if supported
+    pub fn month_alarm_register(&self) -> Option<u8> {
+        return non_zero(unsafe { (*self.fadt_ptr).month_alarm });
+    }
+
+    /// The CMOS RAM index of the RTC century, if the RTC has one
+    pub fn century_register(&self) -> Option<u8> {
+        return non_zero(unsafe { (*self.fadt_ptr).century });
+    }
+
+    /// The IA-PC boot architecture flags. Revision 1 tables do not have them so they read as 0
+    fn iapc_boot_arch(&self) -> u16 {
+        let end_offset = core::mem::offset_of!(FadtInternal, iapc_boot_arch) + core::mem::size_of::<u16>();
+        if !self.has_field(end_offset) {
+            return 0;
+        }
+        return unsafe { core::ptr::read_unaligned(core::ptr::addr_of!((*self.fadt_ptr).iapc_boot_arch)) };
+    }
+
+    /// Whether the platform has ISA devices that are not enumerated through ACPI
+    pub fn has_legacy_devices(&self) -> bool {
+        return self.iapc_boot_arch() & BOOT_ARCH_LEGACY_DEVICES != 0;
+    }
+
+    pub fn has_8042(&self) -> bool {
+        return self.iapc_boot_arch() & BOOT_ARCH_8042 != 0;
+    }
+
+    pub fn has_vga(&self) -> bool {
+        return self.iapc_boot_arch() & BOOT_ARCH_VGA_NOT_PRESENT == 0;
+    }
+
+    /// Whether the CMOS RTC is present. The flag is inverted so older tables report it present
+    pub fn has_cmos_rtc(&self) -> bool {
+        return self.iapc_boot_arch() & BOOT_ARCH_CMOS_RTC_NOT_PRESENT == 0;
+    }
+
+    pub fn flags(&self) -> u32 {
+        let end_offset = core::mem::offset_of!(FadtInternal, flags) + core::mem::size_of::<u32>();
+        if !self.has_field(end_offset) {
+            return 0;
+        }
+        return unsafe { core::ptr::read_unaligned(core::ptr::addr_of!((*self.fadt_ptr).flags)) };
+    }
+
+    /// Hardware reduced platforms have none of the fixed ACPI hardware, including the RTC
+    pub fn is_hardware_reduced(&self) -> bool {
+        return self.flags() & FLAG_HARDWARE_REDUCED != 0;
+    }
+
+    /// The register and value that reset the system, if supported
+    pub fn reset_register(&self) -> Option<(GenericAddress, u8)> {
+        let end_offset = core::mem::offset_of!(FadtInternal, reset_value) + core::mem::size_of::<u8>();
+        if self.flags() & FLAG_RESET_REGISTER_SUPPORTED == 0 || !self.has_field(end_offset) {
+            return None;
+        }
+
+        let register = unsafe { core::ptr::read_unaligned(core::ptr::addr_of!((*self.fadt_ptr).reset_register)) };
+        return Some((register, unsafe { (*self.fadt_ptr).reset_value }));
+    }
+}
+
+fn non_zero(value: u8) -> Option<u8> {
+    if value == 0 {
+        return None;
+    }
+    return Some(value);
+}
+
+impl SystemDescriptionTable {
+    pub fn as_fadt(&self) -> Option<Fadt> {
+        if self.get_signature() != crate::SignatureType::FACP {
+            return None;
+        }
+
+        return unsafe { Some(Fadt::new(self.physical_address(), self.mem_offset())) };
+    }
+}
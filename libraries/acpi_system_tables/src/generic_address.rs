@@ -0,0 +1,76 @@
+#[derive(PartialEq, Debug)]
+pub enum AddressSpace {
+    SystemMemory,
+    SystemIo,
+    PciConfiguration,
+    EmbeddedController,
+    SmBus,
+    SystemCmos,
+    PciBarTarget,
+    Ipmi,
+    GeneralPurposeIo,
+    GenericSerialBus,
+    PlatformCommunicationsChannel,
+    FunctionalFixedHardware,
+    Unknown,
+}
+
+/// The ACPI Generic Address Structure. This describes the location of a register in one of
+/// several address spaces.
+#[repr(C, packed)]
+#[derive(Clone, Copy)]
+pub struct GenericAddress {
+    address_space_id: u8,
+    register_bit_width: u8,
+    register_bit_offset: u8,
+    access_size: u8,
+    address: u64,
+}
+
+impl GenericAddress {
+    pub fn address_space(&self) -> AddressSpace {
+        match self.address_space_id {
+            0x00 => AddressSpace::SystemMemory,
+            0x01 => AddressSpace::SystemIo,
+            0x02 => AddressSpace::PciConfiguration,
+            0x03 => AddressSpace::EmbeddedController,
+            0x04 => AddressSpace::SmBus,
+            0x05 => AddressSpace::SystemCmos,
+            0x06 => AddressSpace::PciBarTarget,
+            0x07 => AddressSpace::Ipmi,
+            0x08 => AddressSpace::GeneralPurposeIo,
+            0x09 => AddressSpace::GenericSerialBus,
+            0x0A => AddressSpace::PlatformCommunicationsChannel,
+            0x7F => AddressSpace::FunctionalFixedHardware,
+            _ => AddressSpace::Unknown,
+        }
+    }
+
+    pub fn register_bit_width(&self) -> u8 {
+        return self.register_bit_width;
+    }
+
+    pub fn register_bit_offset(&self) -> u8 {
+        return self.register_bit_offset;
+    }
+
+    /// The access size in bytes or 0 if undefined
+    pub fn access_size_bytes(&self) -> u8 {
+        match self.access_size {
+            1 => 1,
+            2 => 2,
+            3 => 4,
+            4 => 8,
+            _ => 0,
+        }
+    }
+
+    pub fn address(&self) -> u64 {
+        return self.address;
+    }
+
+    /// A null address means the register is not present
+    pub fn is_null(&self) -> bool {
+        return self.address() == 0;
+    }
+}
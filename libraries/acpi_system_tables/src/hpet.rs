@@ -0,0 +1,93 @@
+use crate::{GenericAddress, SystemDescriptionTableHeader, SystemDescriptionTable};
+
+const HARDWARE_REVISION_MASK: u32 = 0xFF;
+const COMPARATOR_COUNT_SHIFT: u32 = 8;
+const COMPARATOR_COUNT_MASK: u32 = 0x1F;
+const COUNTER_SIZE_64_BIT: u32 = 1 << 13;
+const LEGACY_REPLACEMENT_CAPABLE: u32 = 1 << 15;
+const PCI_VENDOR_ID_SHIFT: u32 = 16;
+
+#[repr(C, packed)]
+struct HpetInternal {
+    header: SystemDescriptionTableHeader,
+    event_timer_block_id: u32,
+    base_address: GenericAddress,
+    hpet_number: u8,
+    minimum_tick: u16,
+    page_protection: u8,
+}
+
+pub struct Hpet {
+    hpet_ptr: *mut HpetInternal,
+}
+
+impl Hpet {
+    /// Creates a new Hpet that wraps an HPET table pointer.
+    /// 
+    /// ## Safety
+    /// 
+    /// This is unsafe because we cannot know the address and offset are valid. The address
+    /// must come from the RSDT/XSDT and the offset from the virtual memory management logic of
+    /// the OS
+    pub unsafe fn new(physical_address: u64, offset: u64) -> Hpet {
+        let virtual_address = physical_address + offset;
+
+        return Hpet {
+            hpet_ptr: virtual_address as *mut HpetInternal,
+        }
+    }
+
+    fn event_timer_block_id(&self) -> u32 {
+        return unsafe { core::ptr::read_unaligned(core::ptr::addr_of!((*self.hpet_ptr).event_timer_block_id)) };
+    }
+
+    pub fn hardware_revision(&self) -> u8 {
+        return (self.event_timer_block_id() & HARDWARE_REVISION_MASK) as u8;
+    }
+
+    /// The number of comparators in the first timer block
+    pub fn comparator_count(&self) -> u8 {
+        return ((self.event_timer_block_id() >> COMPARATOR_COUNT_SHIFT) & COMPARATOR_COUNT_MASK) as u8 + 1;
+    }
+
+    pub fn counter_is_64_bit(&self) -> bool {
+        return self.event_timer_block_id() & COUNTER_SIZE_64_BIT != 0;
+    }
+
+    pub fn legacy_replacement_capable(&self) -> bool {
+        return self.event_timer_block_id() & LEGACY_REPLACEMENT_CAPABLE != 0;
+    }
+
+    pub fn pci_vendor_id(&self) -> u16 {
+        return (self.event_timer_block_id() >> PCI_VENDOR_ID_SHIFT) as u16;
+    }
+
+    /// The location of the HPET register block. This is always in system memory
+    pub fn base_address(&self) -> GenericAddress {
+        return unsafe { core::ptr::read_unaligned(core::ptr::addr_of!((*self.hpet_ptr).base_address)) };
+    }
+
+    pub fn hpet_number(&self) -> u8 {
+        return unsafe { (*self.hpet_ptr).hpet_number };
+    }
+
+    /// The minimum number of main counter ticks that can be used for a periodic comparator
+    /// without losing interrupts
+    pub fn minimum_tick(&self) -> u16 {
+        return unsafe { core::ptr::read_unaligned(core::ptr::addr_of!((*self.hpet_ptr).minimum_tick)) };
+    }
+
+    pub fn page_protection(&self) -> u8 {
+        return unsafe { (*self.hpet_ptr).page_protection };
+    }
+}
+
+impl SystemDescriptionTable {
+    pub fn as_hpet(&self) -> Option<Hpet> {
+        if self.get_signature() != crate::SignatureType::HPET {
+            return None;
+        }
+
+        return unsafe { Some(Hpet::new(self.physical_address(), self.mem_offset())) };
+    }
+}
@@ -1,10 +1,32 @@
 #![no_std]
+mod bgrt;
+mod dbg2;
+mod fadt;
+mod generic_address;
+mod hpet;
+mod madt;
+mod mcfg;
+mod root_table;
 mod rsdp;
 mod rsdt;
+mod slit;
+mod spcr;
+mod srat;
 mod system_description_table;
 mod xsdt;
 
+pub use bgrt::*;
+pub use dbg2::*;
+pub use fadt::*;
+pub use generic_address::*;
+pub use hpet::*;
+pub use madt::*;
+pub use mcfg::*;
+pub use root_table::*;
 pub use rsdp::*;
 pub use rsdt::*;
+pub use slit::*;
+pub use spcr::*;
+pub use srat::*;
 pub use system_description_table::*;
 pub use xsdt::*;
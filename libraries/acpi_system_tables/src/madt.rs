@@ -0,0 +1,395 @@
+use crate::{SystemDescriptionTableHeader, SystemDescriptionTable};
+
+const PROCESSOR_LOCAL_APIC: u8 = 0x0;
+const IO_APIC: u8 = 0x1;
+const INTERRUPT_SOURCE_OVERRIDE: u8 = 0x2;
+const NMI_SOURCE: u8 = 0x3;
+const LOCAL_APIC_NMI: u8 = 0x4;
+const LOCAL_APIC_ADDRESS_OVERRIDE: u8 = 0x5;
+const PROCESSOR_LOCAL_X2APIC: u8 = 0x9;
+const LOCAL_X2APIC_NMI: u8 = 0xA;
+
+/// The length of each entry type the parser reads. Longer entries from newer revisions are
+/// fine but shorter ones are skipped.
+fn entry_size(entry_type: u8) -> usize {
+    match entry_type {
+        PROCESSOR_LOCAL_APIC => 8,
+        IO_APIC => 12,
+        INTERRUPT_SOURCE_OVERRIDE => 10,
+        NMI_SOURCE => 8,
+        LOCAL_APIC_NMI => 6,
+        LOCAL_APIC_ADDRESS_OVERRIDE => 12,
+        PROCESSOR_LOCAL_X2APIC => 16,
+        LOCAL_X2APIC_NMI => 12,
+        _ => 2,
+    }
+}
+
+const MADT_PCAT_COMPATIBLE: u32 = 1 << 0;
+const PROCESSOR_ENABLED: u32 = 1 << 0;
+const PROCESSOR_ONLINE_CAPABLE: u32 = 1 << 1;
+
+const POLARITY_MASK: u16 = 0b11;
+const TRIGGER_MODE_SHIFT: u16 = 2;
+const TRIGGER_MODE_MASK: u16 = 0b11;
+
+/// The ACPI processor UID that means a local APIC NMI applies to every processor
+pub const ALL_PROCESSORS_UID: u32 = 0xFF;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum InterruptPolarity {
+    ConformsToBus,
+    ActiveHigh,
+    ActiveLow,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum InterruptTriggerMode {
+    ConformsToBus,
+    Edge,
+    Level,
+}
+
+fn polarity_from_flags(flags: u16) -> InterruptPolarity {
+    match flags & POLARITY_MASK {
+        0b01 => InterruptPolarity::ActiveHigh,
+        0b11 => InterruptPolarity::ActiveLow,
+        _ => InterruptPolarity::ConformsToBus,
+    }
+}
+
+fn trigger_mode_from_flags(flags: u16) -> InterruptTriggerMode {
+    match (flags >> TRIGGER_MODE_SHIFT) & TRIGGER_MODE_MASK {
+        0b01 => InterruptTriggerMode::Edge,
+        0b11 => InterruptTriggerMode::Level,
+        _ => InterruptTriggerMode::ConformsToBus,
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct ProcessorLocalApic {
+    processor_uid: u32,
+    apic_id: u32,
+    flags: u32,
+    x2apic: bool,
+}
+
+impl ProcessorLocalApic {
+    pub fn processor_uid(&self) -> u32 {
+        return self.processor_uid;
+    }
+
+    pub fn apic_id(&self) -> u32 {
+        return self.apic_id;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        return self.flags & PROCESSOR_ENABLED != 0;
+    }
+
+    /// A disabled processor that can be brought online at runtime
+    pub fn is_online_capable(&self) -> bool {
+        return self.flags & PROCESSOR_ONLINE_CAPABLE != 0;
+    }
+
+    /// True when this entry came from a Processor Local x2APIC structure
+    pub fn is_x2apic(&self) -> bool {
+        return self.x2apic;
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct IoApicEntry {
+    io_apic_id: u8,
+    address: u32,
+    global_system_interrupt_base: u32,
+}
+
+impl IoApicEntry {
+    pub fn io_apic_id(&self) -> u8 {
+        return self.io_apic_id;
+    }
+
+    /// The physical address of the IO APIC registers
+    pub fn address(&self) -> u64 {
+        return self.address as u64;
+    }
+
+    /// The first global system interrupt handled by this IO APIC
+    pub fn global_system_interrupt_base(&self) -> u32 {
+        return self.global_system_interrupt_base;
+    }
+}
+
+/// Describes an ISA IRQ that is not identity mapped to a global system interrupt or does
+/// not use the ISA polarity and trigger mode
+#[derive(Clone, Copy)]
+pub struct InterruptSourceOverride {
+    bus: u8,
+    source: u8,
+    global_system_interrupt: u32,
+    flags: u16,
+}
+
+impl InterruptSourceOverride {
+    /// Always 0 for ISA
+    pub fn bus(&self) -> u8 {
+        return self.bus;
+    }
+
+    /// The ISA IRQ being overridden
+    pub fn source(&self) -> u8 {
+        return self.source;
+    }
+
+    pub fn global_system_interrupt(&self) -> u32 {
+        return self.global_system_interrupt;
+    }
+
+    pub fn polarity(&self) -> InterruptPolarity {
+        return polarity_from_flags(self.flags);
+    }
+
+    pub fn trigger_mode(&self) -> InterruptTriggerMode {
+        return trigger_mode_from_flags(self.flags);
+    }
+}
+
+/// A global system interrupt that should be configured as an NMI
+#[derive(Clone, Copy)]
+pub struct NmiSource {
+    global_system_interrupt: u32,
+    flags: u16,
+}
+
+impl NmiSource {
+    pub fn global_system_interrupt(&self) -> u32 {
+        return self.global_system_interrupt;
+    }
+
+    pub fn polarity(&self) -> InterruptPolarity {
+        return polarity_from_flags(self.flags);
+    }
+
+    pub fn trigger_mode(&self) -> InterruptTriggerMode {
+        return trigger_mode_from_flags(self.flags);
+    }
+}
+
+/// A local APIC LINT pin that is connected to NMI
+#[derive(Clone, Copy)]
+pub struct LocalApicNmi {
+    processor_uid: u32,
+    flags: u16,
+    lint: u8,
+}
+
+impl LocalApicNmi {
+    /// The processor this applies to, or *ALL_PROCESSORS_UID* for every processor
+    pub fn processor_uid(&self) -> u32 {
+        return self.processor_uid;
+    }
+
+    pub fn polarity(&self) -> InterruptPolarity {
+        return polarity_from_flags(self.flags);
+    }
+
+    pub fn trigger_mode(&self) -> InterruptTriggerMode {
+        return trigger_mode_from_flags(self.flags);
+    }
+
+    /// The LINT pin, either 0 or 1
+    pub fn lint(&self) -> u8 {
+        return self.lint;
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum MadtEntry {
+    ProcessorLocalApic(ProcessorLocalApic),
+    IoApic(IoApicEntry),
+    InterruptSourceOverride(InterruptSourceOverride),
+    NmiSource(NmiSource),
+    LocalApicNmi(LocalApicNmi),
+    LocalApicAddressOverride(u64),
+    Unknown(u8),
+}
+
+#[repr(C, packed)]
+struct MadtInternal {
+    header: SystemDescriptionTableHeader,
+    local_apic_address: u32,
+    flags: u32,
+}
+
+pub struct Madt {
+    madt_ptr: *mut MadtInternal,
+}
+
+impl Madt {
+    /// Creates a new Madt that wraps an MADT pointer. The interrupt controller structures are
+    /// variable length and extend beyond the end of the header so we only hold the pointer
+    /// internally.
+    /// 
+    /// ## Safety
+    /// 
+    /// This is unsafe because we cannot know the address and offset are valid. The address
+    /// must come from the RSDT/XSDT and the offset from the virtual memory management logic of
+    /// the OS
+    pub unsafe fn new(physical_address: u64, offset: u64) -> Madt {
+        let virtual_address = physical_address + offset;
+
+        return Madt {
+            madt_ptr: virtual_address as *mut MadtInternal,
+        }
+    }
+
+    fn table_length(&self) -> usize {
+        return unsafe { (*(self.madt_ptr as *const SystemDescriptionTableHeader)).length() as usize };
+    }
+
+    /// The physical address of the local APIC. A *LocalApicAddressOverride* entry takes
+    /// precedence over this.
+    pub fn local_apic_address(&self) -> u64 {
+        let address_override = self.iter().find_map(|entry| match entry {
+            MadtEntry::LocalApicAddressOverride(address) => Some(address),
+            _ => None,
+        });
+
+        match address_override {
+            Some(address) => address,
+            None => unsafe { core::ptr::read_unaligned(core::ptr::addr_of!((*self.madt_ptr).local_apic_address)) as u64 },
+        }
+    }
+
+    /// True if the system also has dual 8259 PICs which must be masked before using the APICs
+    pub fn pcat_compatible(&self) -> bool {
+        let flags = unsafe { core::ptr::read_unaligned(core::ptr::addr_of!((*self.madt_ptr).flags)) };
+        return flags & MADT_PCAT_COMPATIBLE != 0;
+    }
+
+    /// Finds the override for an ISA IRQ if there is one
+    pub fn find_interrupt_source_override(&self, isa_irq: u8) -> Option<InterruptSourceOverride> {
+        return self.iter().find_map(|entry| match entry {
+            MadtEntry::InterruptSourceOverride(source_override) if source_override.source() == isa_irq => Some(source_override),
+            _ => None,
+        });
+    }
+
+    fn read_field<T: Copy>(&self, offset: usize) -> T {
+        //Fields within the entries are not naturally aligned so they must be read unaligned
+        return unsafe { core::ptr::read_unaligned((self.madt_ptr as *const u8).add(offset) as *const T) };
+    }
+
+    /// Parses the entry at the given byte offset from the start of the table
+    fn entry_at(&self, offset: usize) -> MadtEntry {
+        let entry_type: u8 = self.read_field(offset);
+        match entry_type {
+            PROCESSOR_LOCAL_APIC => {
+                return MadtEntry::ProcessorLocalApic(ProcessorLocalApic {
+                    processor_uid: self.read_field::<u8>(offset + 2) as u32,
+                    apic_id: self.read_field::<u8>(offset + 3) as u32,
+                    flags: self.read_field(offset + 4),
+                    x2apic: false,
+                });
+            },
+            IO_APIC => {
+                return MadtEntry::IoApic(IoApicEntry {
+                    io_apic_id: self.read_field(offset + 2),
+                    address: self.read_field(offset + 4),
+                    global_system_interrupt_base: self.read_field(offset + 8),
+                });
+            },
+            INTERRUPT_SOURCE_OVERRIDE => {
+                return MadtEntry::InterruptSourceOverride(InterruptSourceOverride {
+                    bus: self.read_field(offset + 2),
+                    source: self.read_field(offset + 3),
+                    global_system_interrupt: self.read_field(offset + 4),
+                    flags: self.read_field(offset + 8),
+                });
+            },
+            NMI_SOURCE => {
+                return MadtEntry::NmiSource(NmiSource {
+                    flags: self.read_field(offset + 2),
+                    global_system_interrupt: self.read_field(offset + 4),
+                });
+            },
+            LOCAL_APIC_NMI => {
+                return MadtEntry::LocalApicNmi(LocalApicNmi {
+                    processor_uid: self.read_field::<u8>(offset + 2) as u32,
+                    flags: self.read_field(offset + 3),
+                    lint: self.read_field(offset + 5),
+                });
+            },
+            LOCAL_APIC_ADDRESS_OVERRIDE => {
+                return MadtEntry::LocalApicAddressOverride(self.read_field(offset + 4));
+            },
+            PROCESSOR_LOCAL_X2APIC => {
+                return MadtEntry::ProcessorLocalApic(ProcessorLocalApic {
+                    processor_uid: self.read_field(offset + 12),
+                    apic_id: self.read_field(offset + 4),
+                    flags: self.read_field(offset + 8),
+                    x2apic: true,
+                });
+            },
+            LOCAL_X2APIC_NMI => {
+                return MadtEntry::LocalApicNmi(LocalApicNmi {
+                    flags: self.read_field(offset + 2),
+                    processor_uid: self.read_field(offset + 4),
+                    lint: self.read_field(offset + 8),
+                });
+            },
+            _ => { return MadtEntry::Unknown(entry_type); },
+        }
+    }
+
+    pub fn iter(&self) -> MadtIterator<'_> {
+        MadtIterator {
+            madt: self,
+            current_offset: core::mem::size_of::<MadtInternal>(),
+            table_length: self.table_length(),
+        }
+    }
+}
+
+impl SystemDescriptionTable {
+    pub fn as_madt(&self) -> Option<Madt> {
+        if self.get_signature() != crate::SignatureType::APIC {
+            return None;
+        }
+
+        return unsafe { Some(Madt::new(self.physical_address(), self.mem_offset())) };
+    }
+}
+
+pub struct MadtIterator<'a> {
+    madt: &'a Madt,
+    current_offset: usize,
+    table_length: usize,
+}
+
+impl<'a> Iterator for MadtIterator<'a> {
+    type Item = MadtEntry;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            //Every entry starts with a type and length byte
+            if self.current_offset + 2 > self.table_length {
+                return None;
+            }
+
+            let entry_length: u8 = self.madt.read_field(self.current_offset + 1);
+            if entry_length < 2 || self.current_offset + entry_length as usize > self.table_length {
+                return None;
+            }
+
+            let entry_type: u8 = self.madt.read_field(self.current_offset);
+            let offset = self.current_offset;
+            self.current_offset += entry_length as usize;
+            //A short entry would have its fields read from the next one
+            if entry_length as usize >= entry_size(entry_type) {
+                return Some(self.madt.entry_at(offset));
+            }
+        }
+    }
+}
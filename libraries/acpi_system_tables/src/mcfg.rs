@@ -0,0 +1,127 @@
+use crate::{SystemDescriptionTableHeader, SystemDescriptionTable};
+
+/// The MCFG has 8 reserved bytes between the standard header and the first
+/// configuration space allocation entry
+const MCFG_RESERVED_SIZE: usize = 8;
+
+#[repr(C, packed)]
+#[derive(Clone, Copy)]
+pub struct McfgEntry {
+    base_address: u64,
+    pci_segment_group: u16,
+    start_bus: u8,
+    end_bus: u8,
+    reserved: u32,
+}
+
+impl McfgEntry {
+    /// The physical base address of the ECAM region for this segment. Note this is the address
+    /// bus 0 would occupy even when *start_bus* is greater than 0
+    pub fn base_address(&self) -> u64 {
+        return self.base_address;
+    }
+
+    pub fn pci_segment_group(&self) -> u16 {
+        return self.pci_segment_group;
+    }
+
+    pub fn start_bus(&self) -> u8 {
+        return self.start_bus;
+    }
+
+    pub fn end_bus(&self) -> u8 {
+        return self.end_bus;
+    }
+}
+
+pub struct Mcfg {
+    mcfg_ptr: *mut SystemDescriptionTableHeader,
+}
+
+impl Mcfg {
+    /// Creates a new Mcfg that wraps an MCFG pointer. As with the RSDT the entries extend beyond
+    /// the end of the header so we only hold the pointer internally.
+    /// 
+    /// ## Safety
+    /// 
+    /// This is unsafe because we cannot know the address and offset are valid. The address
+    /// must come from the RSDT/XSDT and the offset from the virtual memory management logic of
+    /// the OS
+    pub unsafe fn new(physical_address: u64, offset: u64) -> Mcfg {
+        let virtual_address = physical_address + offset;
+
+        return Mcfg {
+            mcfg_ptr: virtual_address as *mut SystemDescriptionTableHeader,
+        }
+    }
+
+    pub fn num_entries(&self) -> usize {
+        let table_len = unsafe { (*self.mcfg_ptr).length() as usize };
+        //A table too short to hold the reserved bytes has no entries
+        let size_of_entries = table_len.saturating_sub(core::mem::size_of::<SystemDescriptionTableHeader>() + MCFG_RESERVED_SIZE);
+        return size_of_entries / core::mem::size_of::<McfgEntry>();
+    }
+
+    pub fn get_entry(&self, index: usize) -> Option<McfgEntry> {
+        if index >= self.num_entries() {
+            return None;
+        }
+
+        let mcfg_ptr_u8 = self.mcfg_ptr as *mut u8;
+        let first_entry_offset = core::mem::size_of::<SystemDescriptionTableHeader>() + MCFG_RESERVED_SIZE;
+        let entry_ptr = unsafe { mcfg_ptr_u8.add(first_entry_offset) } as *mut McfgEntry;
+
+        //Entries are only 4 byte aligned within the table so we cannot read them directly
+        return unsafe { Some(core::ptr::read_unaligned(entry_ptr.add(index))) };
+    }
+
+    /// Finds the entry covering the given segment and bus if there is one
+    pub fn find_entry(&self, segment: u16, bus: u8) -> Option<McfgEntry> {
+        for entry in self.iter() {
+            if entry.pci_segment_group() == segment && entry.start_bus() <= bus && bus <= entry.end_bus() {
+                return Some(entry);
+            }
+        }
+        return None;
+    }
+
+    pub fn iter(&self) -> McfgIterator<'_> {
+        McfgIterator {
+            mcfg: self,
+            current_index: 0,
+            max_index: self.num_entries(),
+        }
+    }
+}
+
+impl SystemDescriptionTable {
+    pub fn as_mcfg(&self) -> Option<Mcfg> {
+        if self.get_signature() != crate::SignatureType::MCFG {
+            return None;
+        }
+
+        return unsafe { Some(Mcfg::new(self.physical_address(), self.mem_offset())) };
+    }
+}
+
+pub struct McfgIterator<'a> {
+    mcfg: &'a Mcfg,
+    current_index: usize,
+    max_index: usize,
+}
+
+impl<'a> Iterator for McfgIterator<'a> {
+    type Item = McfgEntry;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.current_index == self.max_index {
+            return None;
+        } else {
+            let output = self.mcfg.get_entry(self.current_index);
+            if output.is_some() {
+                self.current_index += 1;
+            }
+            return output;
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use crate::*;
+
+/// The root of the ACPI tables. ACPI 1.0 firmware only provides the RSDT with 32 bit
+/// table addresses. Later revisions provide the XSDT which must be preferred when present.
+pub enum RootTable {
+    Rsdt(RootSystemDescriptionTable),
+    Xsdt(ExtendedSystemDescriptionTable),
+}
+
+impl RootTable {
+    /// Reads the RSDP at the given physical address and wraps whichever root table it
+    /// points at. Returns None if the RSDP fails validation.
+    /// 
+    /// ## Safety
+    /// 
+    /// This is unsafe because we cannot know the address and offset are valid. The address
+    /// must be the RSDP address provided by the firmware and the offset from the virtual
+    /// memory management logic of the OS
+    pub unsafe fn from_rsdp(rsdp_physical_address: u64, offset: u64) -> Option<RootTable> {
+        let rsdp_v1 = *((rsdp_physical_address + offset) as *const RsdpV1);
+        if !rsdp_v1.is_valid() {
+            return None;
+        }
+
+        if rsdp_v1.revision() >= 2 {
+            let rsdp_v2 = *((rsdp_physical_address + offset) as *const RsdpV2);
+            if rsdp_v2.is_valid() {
+                return Some(RootTable::Xsdt(rsdp_v2.get_xsdt(offset)));
+            }
+        }
+
+        return Some(RootTable::Rsdt(rsdp_v1.get_rsdt(offset)));
+    }
+
+    pub fn num_entries(&self) -> usize {
+        match self {
+            RootTable::Rsdt(rsdt) => rsdt.num_entries(),
+            RootTable::Xsdt(xsdt) => xsdt.num_entries(),
+        }
+    }
+
+    pub fn get_entry(&self, index: usize) -> Option<SystemDescriptionTable> {
+        match self {
+            RootTable::Rsdt(rsdt) => rsdt.get_entry(index),
+            RootTable::Xsdt(xsdt) => xsdt.get_entry(index),
+        }
+    }
+
+    /// Returns the first table with the given signature
+    pub fn find_table(&self, signature: SignatureType) -> Option<SystemDescriptionTable> {
+        for index in 0..self.num_entries() {
+            if let Some(table) = self.get_entry(index) {
+                if table.get_signature() == signature {
+                    return Some(table);
+                }
+            }
+        }
+        return None;
+    }
+}
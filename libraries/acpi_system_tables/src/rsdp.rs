@@ -17,6 +17,10 @@ impl RsdpV1 {
         return self.valid_signature() && self.valid_checksum();
     }
 
+    pub fn revision(&self) -> u8 {
+        return self.revision;
+    }
+
     pub fn get_rsdt(&self, offset: u64) -> RootSystemDescriptionTable {
         return unsafe { RootSystemDescriptionTable::new(self.rsdt_physical_address, offset) };
     }
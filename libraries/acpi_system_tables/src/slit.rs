@@ -0,0 +1,64 @@
+use crate::{SystemDescriptionTableHeader, SystemDescriptionTable};
+
+/// The distance reported from a locality to itself
+pub const SLIT_LOCAL_DISTANCE: u8 = 10;
+/// The distance reported between localities that cannot reach each other
+pub const SLIT_UNREACHABLE_DISTANCE: u8 = 0xFF;
+
+pub struct Slit {
+    slit_ptr: *mut SystemDescriptionTableHeader,
+}
+
+impl Slit {
+    /// Creates a new Slit that wraps a SLIT pointer. The distance matrix extends beyond the
+    /// end of the header so we only hold the pointer internally.
+    /// 
+    /// ## Safety
+    /// 
+    /// This is unsafe because we cannot know the address and offset are valid. The address
+    /// must come from the RSDT/XSDT and the offset from the virtual memory management logic of
+    /// the OS
+    pub unsafe fn new(physical_address: u64, offset: u64) -> Slit {
+        let virtual_address = physical_address + offset;
+
+        return Slit {
+            slit_ptr: virtual_address as *mut SystemDescriptionTableHeader,
+        }
+    }
+
+    pub fn num_localities(&self) -> u64 {
+        let count_ptr = unsafe { (self.slit_ptr as *const u8).add(core::mem::size_of::<SystemDescriptionTableHeader>()) };
+        return unsafe { core::ptr::read_unaligned(count_ptr as *const u64) };
+    }
+
+    /// The relative distance from locality *from* to locality *to*. Localities are the
+    /// proximity domains used by the SRAT.
+    pub fn distance(&self, from: u64, to: u64) -> Option<u8> {
+        let num_localities = self.num_localities();
+        if from >= num_localities || to >= num_localities {
+            return None;
+        }
+
+        let matrix_offset = core::mem::size_of::<SystemDescriptionTableHeader>() + core::mem::size_of::<u64>();
+        //A corrupt locality count can overflow the offset, which is as good as out of the table
+        let entry_offset = from.checked_mul(num_localities)
+            .and_then(|row| row.checked_add(to))
+            .and_then(|entry| entry.checked_add(matrix_offset as u64))?;
+        let table_len = unsafe { (*self.slit_ptr).length() as u64 };
+        if entry_offset >= table_len {
+            return None;
+        }
+
+        return unsafe { Some(*(self.slit_ptr as *const u8).add(entry_offset as usize)) };
+    }
+}
+
+impl SystemDescriptionTable {
+    pub fn as_slit(&self) -> Option<Slit> {
+        if self.get_signature() != crate::SignatureType::SLIT {
+            return None;
+        }
+
+        return unsafe { Some(Slit::new(self.physical_address(), self.mem_offset())) };
+    }
+}
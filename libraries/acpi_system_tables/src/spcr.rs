@@ -0,0 +1,186 @@
+use crate::{GenericAddress, SystemDescriptionTableHeader, SystemDescriptionTable};
+
+/// The baud rate the UART's input clock gives with a divisor of 1
+const DEFAULT_UART_CLOCK_BAUD: u32 = 115200;
+
+/// The serial port interface types shared by the SPCR and the serial subtypes of the DBG2
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SerialInterfaceType {
+    Full16550,
+    Subset16550,
+    Max311xE,
+    ArmPl011,
+    Generic16550,
+    Other(u16),
+}
+
+impl SerialInterfaceType {
+    pub fn from_u16(value: u16) -> SerialInterfaceType {
+        match value {
+            0x0000 => SerialInterfaceType::Full16550,
+            0x0001 => SerialInterfaceType::Subset16550,
+            0x0002 => SerialInterfaceType::Max311xE,
+            0x0003 => SerialInterfaceType::ArmPl011,
+            0x0012 => SerialInterfaceType::Generic16550,
+            _ => SerialInterfaceType::Other(value),
+        }
+    }
+
+    /// True for the interface types that can be driven as a 16550 UART
+    pub fn is_16550_compatible(&self) -> bool {
+        match self {
+            SerialInterfaceType::Full16550 | SerialInterfaceType::Subset16550 | SerialInterfaceType::Generic16550 => true,
+            _ => false,
+        }
+    }
+}
+
+#[repr(C, packed)]
+struct SpcrInternal {
+    header: SystemDescriptionTableHeader,
+    interface_type: u8,
+    reserved: [u8;3],
+    base_address: GenericAddress,
+    interrupt_type: u8,
+    irq: u8,
+    global_system_interrupt: u32,
+    configured_baud_rate: u8,
+    parity: u8,
+    stop_bits: u8,
+    flow_control: u8,
+    terminal_type: u8,
+    language: u8,
+    pci_device_id: u16,
+    pci_vendor_id: u16,
+    pci_bus: u8,
+    pci_device: u8,
+    pci_function: u8,
+    pci_flags: u32,
+    pci_segment: u8,
+    //Only present from revision 3
+    uart_clock_frequency: u32,
+    //Only present from revision 4
+    precise_baud_rate: u32,
+}
+
+pub struct Spcr {
+    spcr_ptr: *mut SpcrInternal,
+}
+
+impl Spcr {
+    /// Creates a new Spcr that wraps an SPCR table pointer.
+    /// 
+    /// ## Safety
+    /// 
+    /// This is unsafe because we cannot know the address and offset are valid. The address
+    /// must come from the RSDT/XSDT and the offset from the virtual memory management logic of
+    /// the OS
+    pub unsafe fn new(physical_address: u64, offset: u64) -> Spcr {
+        let virtual_address = physical_address + offset;
+
+        return Spcr {
+            spcr_ptr: virtual_address as *mut SpcrInternal,
+        }
+    }
+
+    fn table_length(&self) -> usize {
+        return unsafe { (*(self.spcr_ptr as *const SystemDescriptionTableHeader)).length() as usize };
+    }
+
+    pub fn interface_type(&self) -> SerialInterfaceType {
+        return SerialInterfaceType::from_u16(unsafe { (*self.spcr_ptr).interface_type } as u16);
+    }
+
+    /// The location of the UART registers. This is either in system memory or system IO space
+    pub fn base_address(&self) -> GenericAddress {
+        return unsafe { core::ptr::read_unaligned(core::ptr::addr_of!((*self.spcr_ptr).base_address)) };
+    }
+
+    /// Bit 0 is a PC-AT compatible IRQ, bit 1 an IO APIC interrupt and bit 3 an ARM GIC interrupt
+    pub fn interrupt_type(&self) -> u8 {
+        return unsafe { (*self.spcr_ptr).interrupt_type };
+    }
+
+    pub fn irq(&self) -> u8 {
+        return unsafe { (*self.spcr_ptr).irq };
+    }
+
+    pub fn global_system_interrupt(&self) -> u32 {
+        return unsafe { core::ptr::read_unaligned(core::ptr::addr_of!((*self.spcr_ptr).global_system_interrupt)) };
+    }
+
+    /// The baud rate the firmware configured the UART with. None means the OS should leave the
+    /// UART at whatever rate it is currently running.
+    pub fn baud_rate(&self) -> Option<u32> {
+        if self.table_length() >= core::mem::size_of::<SpcrInternal>() {
+            let precise_baud_rate = unsafe { core::ptr::read_unaligned(core::ptr::addr_of!((*self.spcr_ptr).precise_baud_rate)) };
+            if precise_baud_rate != 0 {
+                return Some(precise_baud_rate);
+            }
+        }
+
+        match unsafe { (*self.spcr_ptr).configured_baud_rate } {
+            3 => Some(9600),
+            4 => Some(19200),
+            6 => Some(57600),
+            7 => Some(115200),
+            _ => None,
+        }
+    }
+
+    /// The frequency of the UART input clock in Hz. Tables before revision 3 do not report it
+    /// so we assume the standard PC clock.
+    pub fn uart_clock_frequency(&self) -> u32 {
+        let clock_offset = core::mem::size_of::<SpcrInternal>() - core::mem::size_of::<u32>();
+        if self.table_length() >= clock_offset {
+            let uart_clock_frequency = unsafe { core::ptr::read_unaligned(core::ptr::addr_of!((*self.spcr_ptr).uart_clock_frequency)) };
+            if uart_clock_frequency != 0 {
+                return uart_clock_frequency;
+            }
+        }
+
+        return DEFAULT_UART_CLOCK_BAUD * 16;
+    }
+
+    pub fn parity(&self) -> u8 {
+        return unsafe { (*self.spcr_ptr).parity };
+    }
+
+    pub fn stop_bits(&self) -> u8 {
+        return unsafe { (*self.spcr_ptr).stop_bits };
+    }
+
+    pub fn flow_control(&self) -> u8 {
+        return unsafe { (*self.spcr_ptr).flow_control };
+    }
+
+    pub fn terminal_type(&self) -> u8 {
+        return unsafe { (*self.spcr_ptr).terminal_type };
+    }
+
+    /// The PCI vendor ID of the UART, or 0xFFFF if it is not a PCI device
+    pub fn pci_vendor_id(&self) -> u16 {
+        return unsafe { core::ptr::read_unaligned(core::ptr::addr_of!((*self.spcr_ptr).pci_vendor_id)) };
+    }
+
+    pub fn pci_device_id(&self) -> u16 {
+        return unsafe { core::ptr::read_unaligned(core::ptr::addr_of!((*self.spcr_ptr).pci_device_id)) };
+    }
+
+    pub fn pci_location(&self) -> (u8, u8, u8, u8) {
+        unsafe {
+            let spcr = &*self.spcr_ptr;
+            return (spcr.pci_segment, spcr.pci_bus, spcr.pci_device, spcr.pci_function);
+        }
+    }
+}
+
+impl SystemDescriptionTable {
+    pub fn as_spcr(&self) -> Option<Spcr> {
+        if self.get_signature() != crate::SignatureType::SPCR {
+            return None;
+        }
+
+        return unsafe { Some(Spcr::new(self.physical_address(), self.mem_offset())) };
+    }
+}
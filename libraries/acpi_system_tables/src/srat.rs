@@ -0,0 +1,192 @@
+use crate::{SystemDescriptionTableHeader, SystemDescriptionTable};
+
+/// The SRAT has 12 reserved bytes between the standard header and the first
+/// static resource allocation structure
+const SRAT_RESERVED_SIZE: usize = 12;
+
+const PROCESSOR_LOCAL_APIC_AFFINITY: u8 = 0;
+const MEMORY_AFFINITY: u8 = 1;
+const PROCESSOR_LOCAL_X2APIC_AFFINITY: u8 = 2;
+
+const AFFINITY_ENABLED: u32 = 1 << 0;
+const MEMORY_HOT_PLUGGABLE: u32 = 1 << 1;
+const MEMORY_NON_VOLATILE: u32 = 1 << 2;
+
+#[derive(Clone, Copy)]
+pub struct ProcessorAffinity {
+    apic_id: u32,
+    proximity_domain: u32,
+    flags: u32,
+    x2apic: bool,
+}
+
+impl ProcessorAffinity {
+    pub fn apic_id(&self) -> u32 {
+        return self.apic_id;
+    }
+
+    pub fn proximity_domain(&self) -> u32 {
+        return self.proximity_domain;
+    }
+
+    /// Disabled entries must be ignored by the OS
+    pub fn is_enabled(&self) -> bool {
+        return self.flags & AFFINITY_ENABLED != 0;
+    }
+
+    /// True when this entry came from a Processor Local x2APIC Affinity structure
+    pub fn is_x2apic(&self) -> bool {
+        return self.x2apic;
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct MemoryAffinity {
+    base_address: u64,
+    length: u64,
+    proximity_domain: u32,
+    flags: u32,
+}
+
+impl MemoryAffinity {
+    pub fn base_address(&self) -> u64 {
+        return self.base_address;
+    }
+
+    pub fn length(&self) -> u64 {
+        return self.length;
+    }
+
+    pub fn proximity_domain(&self) -> u32 {
+        return self.proximity_domain;
+    }
+
+    /// Disabled entries must be ignored by the OS
+    pub fn is_enabled(&self) -> bool {
+        return self.flags & AFFINITY_ENABLED != 0;
+    }
+
+    pub fn is_hot_pluggable(&self) -> bool {
+        return self.flags & MEMORY_HOT_PLUGGABLE != 0;
+    }
+
+    pub fn is_non_volatile(&self) -> bool {
+        return self.flags & MEMORY_NON_VOLATILE != 0;
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum SratEntry {
+    ProcessorAffinity(ProcessorAffinity),
+    MemoryAffinity(MemoryAffinity),
+    Unknown(u8),
+}
+
+pub struct Srat {
+    srat_ptr: *mut SystemDescriptionTableHeader,
+}
+
+impl Srat {
+    /// Creates a new Srat that wraps an SRAT pointer. The entries are variable length and
+    /// extend beyond the end of the header so we only hold the pointer internally.
+    /// 
+    /// ## Safety
+    /// 
+    /// This is unsafe because we cannot know the address and offset are valid. The address
+    /// must come from the RSDT/XSDT and the offset from the virtual memory management logic of
+    /// the OS
+    pub unsafe fn new(physical_address: u64, offset: u64) -> Srat {
+        let virtual_address = physical_address + offset;
+
+        return Srat {
+            srat_ptr: virtual_address as *mut SystemDescriptionTableHeader,
+        }
+    }
+
+    fn table_length(&self) -> usize {
+        return unsafe { (*self.srat_ptr).length() as usize };
+    }
+
+    fn read_field<T: Copy>(&self, offset: usize) -> T {
+        //Fields within the entries are not naturally aligned so they must be read unaligned
+        return unsafe { core::ptr::read_unaligned((self.srat_ptr as *const u8).add(offset) as *const T) };
+    }
+
+    /// Parses the entry at the given byte offset from the start of the table
+    fn entry_at(&self, offset: usize) -> SratEntry {
+        let entry_type: u8 = self.read_field(offset);
+        match entry_type {
+            PROCESSOR_LOCAL_APIC_AFFINITY => {
+                let domain_low: u8 = self.read_field(offset + 2);
+                let domain_high: [u8;3] = self.read_field(offset + 9);
+                return SratEntry::ProcessorAffinity(ProcessorAffinity {
+                    apic_id: self.read_field::<u8>(offset + 3) as u32,
+                    proximity_domain: u32::from_le_bytes([domain_low, domain_high[0], domain_high[1], domain_high[2]]),
+                    flags: self.read_field(offset + 4),
+                    x2apic: false,
+                });
+            },
+            MEMORY_AFFINITY => {
+                return SratEntry::MemoryAffinity(MemoryAffinity {
+                    base_address: self.read_field(offset + 8),
+                    length: self.read_field(offset + 16),
+                    proximity_domain: self.read_field(offset + 2),
+                    flags: self.read_field(offset + 28),
+                });
+            },
+            PROCESSOR_LOCAL_X2APIC_AFFINITY => {
+                return SratEntry::ProcessorAffinity(ProcessorAffinity {
+                    apic_id: self.read_field(offset + 8),
+                    proximity_domain: self.read_field(offset + 4),
+                    flags: self.read_field(offset + 12),
+                    x2apic: true,
+                });
+            },
+            _ => { return SratEntry::Unknown(entry_type); },
+        }
+    }
+
+    pub fn iter(&self) -> SratIterator<'_> {
+        SratIterator {
+            srat: self,
+            current_offset: core::mem::size_of::<SystemDescriptionTableHeader>() + SRAT_RESERVED_SIZE,
+            table_length: self.table_length(),
+        }
+    }
+}
+
+impl SystemDescriptionTable {
+    pub fn as_srat(&self) -> Option<Srat> {
+        if self.get_signature() != crate::SignatureType::SRAT {
+            return None;
+        }
+
+        return unsafe { Some(Srat::new(self.physical_address(), self.mem_offset())) };
+    }
+}
+
+pub struct SratIterator<'a> {
+    srat: &'a Srat,
+    current_offset: usize,
+    table_length: usize,
+}
+
+impl<'a> Iterator for SratIterator<'a> {
+    type Item = SratEntry;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        //Every entry starts with a type and length byte
+        if self.current_offset + 2 > self.table_length {
+            return None;
+        }
+
+        let entry_length: u8 = self.srat.read_field(self.current_offset + 1);
+        if entry_length < 2 || self.current_offset + entry_length as usize > self.table_length {
+            return None;
+        }
+
+        let output = self.srat.entry_at(self.current_offset);
+        self.current_offset += entry_length as usize;
+        return Some(output);
+    }
+}
@@ -206,6 +206,19 @@ impl SystemDescriptionTable {
         }
     }
 
+    /// The physical address of the table this wraps
+    pub fn physical_address(&self) -> u64 {
+        return self.sdt_ptr as u64 - self.mem_offset;
+    }
+
+    pub fn mem_offset(&self) -> u64 {
+        return self.mem_offset;
+    }
+
+    pub fn length(&self) -> u32 {
+        unsafe { (*self.sdt_ptr).length() }
+    }
+
     pub fn get_signature_array(&self) -> [u8;4] {
         unsafe { (*self.sdt_ptr).signature }
     }
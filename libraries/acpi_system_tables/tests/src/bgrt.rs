@@ -0,0 +1,54 @@
+#[cfg(test)]
+mod tests {
+    use acpi_system_tables::{Bgrt, BGRT_IMAGE_TYPE_BITMAP, MAX_BGRT_IMAGE_SIZE};
+
+    use crate::table::{address, build_table};
+
+    fn bgrt_body(status: u8, image_type: u8, image_address: u64) -> Vec<u8> {
+        let mut body = 1u16.to_le_bytes().to_vec();
+        body.push(status);
+        body.push(image_type);
+        body.extend_from_slice(&image_address.to_le_bytes());
+        body.extend_from_slice(&10u32.to_le_bytes());
+        body.extend_from_slice(&20u32.to_le_bytes());
+        return body;
+    }
+
+    fn bmp_header(signature: &[u8; 2], file_size: u32) -> Vec<u8> {
+        let mut header = signature.to_vec();
+        header.extend_from_slice(&file_size.to_le_bytes());
+        header.resize(14, 0);
+        return header;
+    }
+
+    #[test]
+    fn test_fields() {
+        let table = build_table(b"BGRT", &bgrt_body(0x03, BGRT_IMAGE_TYPE_BITMAP, 0x1234_5000));
+        let bgrt = unsafe { Bgrt::new(address(&table), 0) };
+        assert_eq!(1, bgrt.version());
+        assert_eq!(true, bgrt.displayed());
+        assert_eq!(90, bgrt.orientation_degrees());
+        assert_eq!(0x1234_5000, bgrt.image_address());
+        assert_eq!((10, 20), (bgrt.image_offset_x(), bgrt.image_offset_y()));
+    }
+
+    #[test]
+    fn test_image_size_is_the_whole_file() {
+        let image = bmp_header(b"BM", 0x1036);
+        let table = build_table(b"BGRT", &bgrt_body(0x01, BGRT_IMAGE_TYPE_BITMAP, image.as_ptr() as u64));
+        let bgrt = unsafe { Bgrt::new(address(&table), 0) };
+        assert_eq!(Some(0x1036), unsafe { bgrt.image_size(0) });
+    }
+
+    #[test]
+    fn test_invalid_images_have_no_size() {
+        let not_bmp = bmp_header(b"PN", 0x1036);
+        let too_large = bmp_header(b"BM", MAX_BGRT_IMAGE_SIZE as u32 + 1);
+        let bitmap = bmp_header(b"BM", 0x1036);
+        for (image_type, image_address) in [(BGRT_IMAGE_TYPE_BITMAP, not_bmp.as_ptr() as u64), (BGRT_IMAGE_TYPE_BITMAP, too_large.as_ptr() as u64), (1, bitmap.as_ptr() as u64), (BGRT_IMAGE_TYPE_BITMAP, 0)] {
+            let table = build_table(b"BGRT", &bgrt_body(0x01, image_type, image_address));
+            let bgrt = unsafe { Bgrt::new(address(&table), 0) };
+            assert_eq!(None, unsafe { bgrt.image_size(0) });
+        }
+    }
+}
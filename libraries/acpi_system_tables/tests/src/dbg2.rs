@@ -0,0 +1,123 @@
+#[cfg(test)]
+mod tests {
+    use acpi_system_tables::{AddressSpace, Dbg2, SerialInterfaceType, DBG2_PORT_TYPE_NET, DBG2_PORT_TYPE_SERIAL};
+
+    use crate::table::{address, build_table};
+
+    const DEVICE_INFO_SIZE: usize = 22;
+
+    fn generic_address(address_space: u8, address: u64) -> Vec<u8> {
+        let mut bytes = vec![address_space, 8, 0, 1];
+        bytes.extend_from_slice(&address.to_le_bytes());
+        return bytes;
+    }
+
+    /// A device information structure with its registers and a "." namespace string after
+    /// the fixed fields
+    fn device(port_type: u16, port_subtype: u16, registers: &[(u8, u64, u32)]) -> Vec<u8> {
+        let base_address_offset = DEVICE_INFO_SIZE;
+        let address_size_offset = base_address_offset + registers.len() * 12;
+        let namespace_offset = address_size_offset + registers.len() * 4;
+        let length = namespace_offset + 2;
+
+        let mut bytes = vec![0];
+        bytes.extend_from_slice(&(length as u16).to_le_bytes());
+        bytes.push(registers.len() as u8);
+        bytes.extend_from_slice(&2u16.to_le_bytes());
+        bytes.extend_from_slice(&(namespace_offset as u16).to_le_bytes());
+        bytes.extend_from_slice(&[0; 4]);
+        bytes.extend_from_slice(&port_type.to_le_bytes());
+        bytes.extend_from_slice(&port_subtype.to_le_bytes());
+        bytes.extend_from_slice(&[0; 2]);
+        bytes.extend_from_slice(&(base_address_offset as u16).to_le_bytes());
+        bytes.extend_from_slice(&(address_size_offset as u16).to_le_bytes());
+        for (address_space, address, _) in registers {
+            bytes.extend(generic_address(*address_space, *address));
+        }
+        for (_, _, size) in registers {
+            bytes.extend_from_slice(&size.to_le_bytes());
+        }
+        bytes.extend_from_slice(b".\0");
+        return bytes;
+    }
+
+    fn dbg2_table(devices: &[Vec<u8>]) -> Vec<u64> {
+        let mut body = 44u32.to_le_bytes().to_vec();
+        body.extend_from_slice(&(devices.len() as u32).to_le_bytes());
+        for device in devices {
+            body.extend_from_slice(device);
+        }
+        return build_table(b"DBG2", &body);
+    }
+
+    #[test]
+    fn test_devices() {
+        let table = dbg2_table(&[
+            device(DBG2_PORT_TYPE_NET, 0, &[(0, 0xFE00_0000, 0x1000)]),
+            device(DBG2_PORT_TYPE_SERIAL, 0x12, &[(1, 0x3F8, 8), (0, 0xFED0_0000, 0x100)]),
+        ]);
+        let dbg2 = unsafe { Dbg2::new(address(&table), 0) };
+        assert_eq!(2, dbg2.iter().count());
+
+        let serial = dbg2.find_16550().unwrap();
+        assert_eq!(Some(SerialInterfaceType::Generic16550), serial.serial_interface_type());
+        assert_eq!(2, serial.num_base_addresses());
+        let io_port = serial.base_address(0).unwrap();
+        assert_eq!(AddressSpace::SystemIo, io_port.address_space());
+        assert_eq!(0x3F8, io_port.address());
+        assert_eq!(Some(8), serial.address_size(0));
+        assert_eq!(0xFED0_0000, serial.base_address(1).unwrap().address());
+        assert_eq!(Some(0x100), serial.address_size(1));
+        assert_eq!(true, serial.base_address(2).is_none());
+        assert_eq!(None, serial.address_size(2));
+    }
+
+    #[test]
+    fn test_find_16550_skips_other_serial_ports() {
+        let table = dbg2_table(&[
+            device(DBG2_PORT_TYPE_SERIAL, 0x03, &[(0, 0x900_0000, 0x1000)]),
+            device(DBG2_PORT_TYPE_SERIAL, 0x00, &[]),
+            device(DBG2_PORT_TYPE_SERIAL, 0x01, &[(1, 0x2F8, 8)]),
+        ]);
+        let dbg2 = unsafe { Dbg2::new(address(&table), 0) };
+        let serial = dbg2.find_16550().unwrap();
+        assert_eq!(Some(SerialInterfaceType::Subset16550), serial.serial_interface_type());
+        assert_eq!(0x2F8, serial.base_address(0).unwrap().address());
+    }
+
+    #[test]
+    fn test_register_offsets_outside_device() {
+        let mut bad_base = device(DBG2_PORT_TYPE_SERIAL, 0, &[(1, 0x3F8, 8)]);
+        bad_base[18..20].copy_from_slice(&0x1000u16.to_le_bytes());
+        let mut bad_size = device(DBG2_PORT_TYPE_SERIAL, 0, &[(1, 0x3F8, 8)]);
+        //The size runs two bytes past the end of the device
+        let length = bad_size.len() as u16;
+        bad_size[20..22].copy_from_slice(&(length - 2).to_le_bytes());
+        let table = dbg2_table(&[bad_base, bad_size]);
+        let dbg2 = unsafe { Dbg2::new(address(&table), 0) };
+        let devices: Vec<_> = dbg2.iter().collect();
+        assert_eq!(2, devices.len());
+
+        assert_eq!(true, devices[0].base_address(0).is_none());
+        assert_eq!(Some(8), devices[0].address_size(0));
+        assert_eq!(0x3F8, devices[1].base_address(0).unwrap().address());
+        assert_eq!(None, devices[1].address_size(0));
+    }
+
+    #[test]
+    fn test_malformed_devices_end_iteration() {
+        //The second device claims to run past the end of the table
+        let mut long = device(DBG2_PORT_TYPE_SERIAL, 0, &[]);
+        long[1..3].copy_from_slice(&0x100u16.to_le_bytes());
+        let table = dbg2_table(&[device(DBG2_PORT_TYPE_NET, 0, &[]), long]);
+        let dbg2 = unsafe { Dbg2::new(address(&table), 0) };
+        assert_eq!(1, dbg2.iter().count());
+        assert_eq!(true, dbg2.find_16550().is_none());
+
+        let mut short = device(DBG2_PORT_TYPE_SERIAL, 0, &[]);
+        short[1..3].copy_from_slice(&4u16.to_le_bytes());
+        let table = dbg2_table(&[short]);
+        let dbg2 = unsafe { Dbg2::new(address(&table), 0) };
+        assert_eq!(0, dbg2.iter().count());
+    }
+}
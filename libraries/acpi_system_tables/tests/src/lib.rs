@@ -0,0 +1,7 @@
+mod bgrt;
+mod dbg2;
+mod madt;
+mod slit;
+mod spcr;
+mod srat;
+mod table;
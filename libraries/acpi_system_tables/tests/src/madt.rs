@@ -0,0 +1,174 @@
+#[cfg(test)]
+mod tests {
+    use acpi_system_tables::{InterruptPolarity, InterruptTriggerMode, Madt, MadtEntry};
+
+    use crate::table::{address, build_table};
+
+    const ACTIVE_LOW_LEVEL: u16 = 0b1111;
+
+    fn local_apic(processor_uid: u8, apic_id: u8, flags: u32) -> Vec<u8> {
+        let mut entry = vec![0, 8, processor_uid, apic_id];
+        entry.extend_from_slice(&flags.to_le_bytes());
+        return entry;
+    }
+
+    fn io_apic(io_apic_id: u8, io_apic_address: u32, gsi_base: u32) -> Vec<u8> {
+        let mut entry = vec![1, 12, io_apic_id, 0];
+        entry.extend_from_slice(&io_apic_address.to_le_bytes());
+        entry.extend_from_slice(&gsi_base.to_le_bytes());
+        return entry;
+    }
+
+    fn source_override(source: u8, gsi: u32, flags: u16) -> Vec<u8> {
+        let mut entry = vec![2, 10, 0, source];
+        entry.extend_from_slice(&gsi.to_le_bytes());
+        entry.extend_from_slice(&flags.to_le_bytes());
+        return entry;
+    }
+
+    fn x2apic(apic_id: u32, flags: u32, processor_uid: u32) -> Vec<u8> {
+        let mut entry = vec![9, 16, 0, 0];
+        entry.extend_from_slice(&apic_id.to_le_bytes());
+        entry.extend_from_slice(&flags.to_le_bytes());
+        entry.extend_from_slice(&processor_uid.to_le_bytes());
+        return entry;
+    }
+
+    fn madt_table(local_apic_address: u32, flags: u32, entries: &[Vec<u8>]) -> Vec<u64> {
+        let mut body = local_apic_address.to_le_bytes().to_vec();
+        body.extend_from_slice(&flags.to_le_bytes());
+        for entry in entries {
+            body.extend_from_slice(entry);
+        }
+        return build_table(b"APIC", &body);
+    }
+
+    #[test]
+    fn test_entries() {
+        let table = madt_table(0xFEE0_0000, 1, &[
+            local_apic(1, 2, 0b01),
+            x2apic(0x1_0000, 0b10, 7),
+            io_apic(3, 0xFEC0_0000, 24),
+            source_override(0, 2, ACTIVE_LOW_LEVEL),
+            vec![0x7F, 4, 0, 0],
+        ]);
+        let madt = unsafe { Madt::new(address(&table), 0) };
+        let entries: Vec<MadtEntry> = madt.iter().collect();
+        assert_eq!(5, entries.len());
+
+        match entries[0] {
+            MadtEntry::ProcessorLocalApic(processor) => {
+                assert_eq!((1, 2), (processor.processor_uid(), processor.apic_id()));
+                assert!(processor.is_enabled() && !processor.is_online_capable() && !processor.is_x2apic());
+            },
+            _ => panic!("expected a local APIC"),
+        }
+        match entries[1] {
+            MadtEntry::ProcessorLocalApic(processor) => {
+                assert_eq!((7, 0x1_0000), (processor.processor_uid(), processor.apic_id()));
+                assert!(!processor.is_enabled() && processor.is_online_capable() && processor.is_x2apic());
+            },
+            _ => panic!("expected a local x2APIC"),
+        }
+        match entries[2] {
+            MadtEntry::IoApic(io_apic) => {
+                assert_eq!(3, io_apic.io_apic_id());
+                assert_eq!(0xFEC0_0000, io_apic.address());
+                assert_eq!(24, io_apic.global_system_interrupt_base());
+            },
+            _ => panic!("expected an IO APIC"),
+        }
+        match entries[3] {
+            MadtEntry::InterruptSourceOverride(source_override) => {
+                assert_eq!((0, 0, 2), (source_override.bus(), source_override.source(), source_override.global_system_interrupt()));
+                assert_eq!(InterruptPolarity::ActiveLow, source_override.polarity());
+                assert_eq!(InterruptTriggerMode::Level, source_override.trigger_mode());
+            },
+            _ => panic!("expected an interrupt source override"),
+        }
+        assert!(matches!(entries[4], MadtEntry::Unknown(0x7F)));
+    }
+
+    #[test]
+    fn test_nmi_entries() {
+        let mut nmi_source = vec![3, 8];
+        nmi_source.extend_from_slice(&0b0101u16.to_le_bytes());
+        nmi_source.extend_from_slice(&9u32.to_le_bytes());
+        let local_nmi = vec![4, 6, 0xFF, 0, 0, 1];
+        let mut x2apic_nmi = vec![0xA, 12, 0, 0];
+        x2apic_nmi.extend_from_slice(&0x1_0000u32.to_le_bytes());
+        x2apic_nmi.extend_from_slice(&[0, 0, 0, 0]);
+        let table = madt_table(0xFEE0_0000, 0, &[nmi_source, local_nmi, x2apic_nmi]);
+        let madt = unsafe { Madt::new(address(&table), 0) };
+        let entries: Vec<MadtEntry> = madt.iter().collect();
+        assert_eq!(3, entries.len());
+
+        match entries[0] {
+            MadtEntry::NmiSource(nmi) => {
+                assert_eq!(9, nmi.global_system_interrupt());
+                assert_eq!(InterruptPolarity::ActiveHigh, nmi.polarity());
+                assert_eq!(InterruptTriggerMode::Edge, nmi.trigger_mode());
+            },
+            _ => panic!("expected an NMI source"),
+        }
+        match entries[1] {
+            MadtEntry::LocalApicNmi(nmi) => assert_eq!((0xFF, 1), (nmi.processor_uid(), nmi.lint())),
+            _ => panic!("expected a local APIC NMI"),
+        }
+        match entries[2] {
+            MadtEntry::LocalApicNmi(nmi) => assert_eq!((0x1_0000, 0), (nmi.processor_uid(), nmi.lint())),
+            _ => panic!("expected a local x2APIC NMI"),
+        }
+    }
+
+    #[test]
+    fn test_table_fields_and_address_override() {
+        let table = madt_table(0xFEE0_0000, 1, &[local_apic(0, 0, 1)]);
+        let madt = unsafe { Madt::new(address(&table), 0) };
+        assert_eq!(0xFEE0_0000, madt.local_apic_address());
+        assert_eq!(true, madt.pcat_compatible());
+
+        let mut address_override = vec![5, 12, 0, 0];
+        address_override.extend_from_slice(&0x1_FEE0_0000u64.to_le_bytes());
+        let table = madt_table(0xFEE0_0000, 0, &[address_override]);
+        let madt = unsafe { Madt::new(address(&table), 0) };
+        assert_eq!(0x1_FEE0_0000, madt.local_apic_address());
+        assert_eq!(false, madt.pcat_compatible());
+    }
+
+    #[test]
+    fn test_find_interrupt_source_override() {
+        let table = madt_table(0xFEE0_0000, 1, &[source_override(0, 2, 0), source_override(9, 9, ACTIVE_LOW_LEVEL)]);
+        let madt = unsafe { Madt::new(address(&table), 0) };
+        assert_eq!(Some(9), madt.find_interrupt_source_override(9).map(|source_override| source_override.global_system_interrupt()));
+        assert_eq!(true, madt.find_interrupt_source_override(4).is_none());
+    }
+
+    #[test]
+    fn test_short_entries_are_skipped() {
+        //Entries shorter than their type are skipped without reading into the next entry
+        let mut short_io_apic = io_apic(3, 0xFEC0_0000, 0);
+        short_io_apic[1] = 8;
+        short_io_apic.truncate(8);
+        let mut short_x2apic = x2apic(5, 1, 5);
+        short_x2apic[1] = 12;
+        short_x2apic.truncate(12);
+        let table = madt_table(0xFEE0_0000, 0, &[short_io_apic, short_x2apic, local_apic(4, 4, 1)]);
+        let madt = unsafe { Madt::new(address(&table), 0) };
+        let entries: Vec<MadtEntry> = madt.iter().collect();
+        assert_eq!(1, entries.len());
+        assert!(matches!(entries[0], MadtEntry::ProcessorLocalApic(processor) if processor.apic_id() == 4));
+    }
+
+    #[test]
+    fn test_malformed_entries_end_iteration() {
+        //A zero length entry would loop forever and a long one runs off the table
+        let zero_length = madt_table(0, 0, &[local_apic(0, 0, 1), vec![0, 0, 0, 0]]);
+        let madt = unsafe { Madt::new(address(&zero_length), 0) };
+        assert_eq!(1, madt.iter().count());
+
+        let too_long = madt_table(0, 0, &[local_apic(0, 0, 1), vec![0, 40, 0, 0]]);
+        let madt = unsafe { Madt::new(address(&too_long), 0) };
+        assert_eq!(1, madt.iter().count());
+    }
+}
@@ -0,0 +1,43 @@
+#[cfg(test)]
+mod tests {
+    use acpi_system_tables::{Slit, SLIT_LOCAL_DISTANCE};
+
+    use crate::table::{address, build_table};
+
+    fn slit_body(num_localities: u64, matrix: &[u8]) -> Vec<u8> {
+        let mut body = num_localities.to_le_bytes().to_vec();
+        body.extend_from_slice(matrix);
+        return body;
+    }
+
+    #[test]
+    fn test_distances() {
+        let table = build_table(b"SLIT", &slit_body(2, &[10, 21, 22, 10]));
+        let slit = unsafe { Slit::new(address(&table), 0) };
+        assert_eq!(2, slit.num_localities());
+        assert_eq!(Some(SLIT_LOCAL_DISTANCE), slit.distance(0, 0));
+        assert_eq!(Some(21), slit.distance(0, 1));
+        assert_eq!(Some(22), slit.distance(1, 0));
+        assert_eq!(None, slit.distance(2, 0));
+        assert_eq!(None, slit.distance(0, 2));
+    }
+
+    #[test]
+    fn test_matrix_past_table_end() {
+        //The count says 3 localities but only 4 distances are present
+        let table = build_table(b"SLIT", &slit_body(3, &[10, 21, 21, 21]));
+        let slit = unsafe { Slit::new(address(&table), 0) };
+        assert_eq!(Some(21), slit.distance(1, 0));
+        assert_eq!(None, slit.distance(1, 1));
+        assert_eq!(None, slit.distance(2, 2));
+    }
+
+    #[test]
+    fn test_corrupt_locality_count_does_not_overflow() {
+        let table = build_table(b"SLIT", &slit_body(u64::MAX, &[10]));
+        let slit = unsafe { Slit::new(address(&table), 0) };
+        assert_eq!(Some(10), slit.distance(0, 0));
+        assert_eq!(None, slit.distance(u64::MAX - 1, u64::MAX - 1));
+        assert_eq!(None, slit.distance(1 << 40, 0));
+    }
+}
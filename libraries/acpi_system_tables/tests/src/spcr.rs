@@ -0,0 +1,70 @@
+#[cfg(test)]
+mod tests {
+    use acpi_system_tables::{AddressSpace, SerialInterfaceType, Spcr};
+
+    use crate::table::{address, build_table};
+
+    //The table before revision 3, ending with four reserved bytes
+    const REVISION_2_BODY_SIZE: usize = 44;
+    const REVISION_4_BODY_SIZE: usize = 48;
+
+    fn spcr_body(interface_type: u8, address_space: u8, base_address: u64, baud_rate: u8, size: usize) -> Vec<u8> {
+        let mut body = vec![interface_type, 0, 0, 0, address_space, 8, 0, 1];
+        body.extend_from_slice(&base_address.to_le_bytes());
+        body.push(0x01);
+        body.push(4);
+        body.extend_from_slice(&0x24u32.to_le_bytes());
+        body.extend_from_slice(&[baud_rate, 0, 1, 0, 0, 0]);
+        body.extend_from_slice(&0xFFFFu16.to_le_bytes());
+        body.extend_from_slice(&0xFFFFu16.to_le_bytes());
+        body.resize(size, 0);
+        return body;
+    }
+
+    #[test]
+    fn test_fields() {
+        let table = build_table(b"SPCR", &spcr_body(0x00, 1, 0x3F8, 7, REVISION_2_BODY_SIZE));
+        let spcr = unsafe { Spcr::new(address(&table), 0) };
+        assert_eq!(SerialInterfaceType::Full16550, spcr.interface_type());
+        assert_eq!(AddressSpace::SystemIo, spcr.base_address().address_space());
+        assert_eq!(0x3F8, spcr.base_address().address());
+        assert_eq!(0x01, spcr.interrupt_type());
+        assert_eq!(4, spcr.irq());
+        assert_eq!(0x24, spcr.global_system_interrupt());
+        assert_eq!(Some(115200), spcr.baud_rate());
+        assert_eq!(1, spcr.stop_bits());
+        assert_eq!(0xFFFF, spcr.pci_vendor_id());
+        //Older tables have no clock so the standard PC one is assumed
+        assert_eq!(1_843_200, spcr.uart_clock_frequency());
+    }
+
+    #[test]
+    fn test_baud_rate_left_as_is() {
+        let table = build_table(b"SPCR", &spcr_body(0x00, 1, 0x3F8, 0, REVISION_2_BODY_SIZE));
+        let spcr = unsafe { Spcr::new(address(&table), 0) };
+        assert_eq!(None, spcr.baud_rate());
+    }
+
+    #[test]
+    fn test_revision_4_clock_and_precise_baud_rate() {
+        let mut body = spcr_body(0x12, 0, 0xFE03_0000, 7, REVISION_4_BODY_SIZE);
+        body[40..44].copy_from_slice(&48_000_000u32.to_le_bytes());
+        body[44..48].copy_from_slice(&3_000_000u32.to_le_bytes());
+        let table = build_table(b"SPCR", &body);
+        let spcr = unsafe { Spcr::new(address(&table), 0) };
+        assert_eq!(SerialInterfaceType::Generic16550, spcr.interface_type());
+        assert_eq!(AddressSpace::SystemMemory, spcr.base_address().address_space());
+        assert_eq!(48_000_000, spcr.uart_clock_frequency());
+        assert_eq!(Some(3_000_000), spcr.baud_rate());
+    }
+
+    #[test]
+    fn test_interface_types() {
+        assert_eq!(true, SerialInterfaceType::from_u16(0x00).is_16550_compatible());
+        assert_eq!(true, SerialInterfaceType::from_u16(0x01).is_16550_compatible());
+        assert_eq!(true, SerialInterfaceType::from_u16(0x12).is_16550_compatible());
+        assert_eq!(SerialInterfaceType::ArmPl011, SerialInterfaceType::from_u16(0x03));
+        assert_eq!(false, SerialInterfaceType::ArmPl011.is_16550_compatible());
+        assert_eq!(SerialInterfaceType::Other(0x0E), SerialInterfaceType::from_u16(0x0E));
+    }
+}
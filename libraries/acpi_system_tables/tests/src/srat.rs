@@ -0,0 +1,109 @@
+#[cfg(test)]
+mod tests {
+    use acpi_system_tables::{Srat, SratEntry};
+
+    use crate::table::{address, build_table};
+
+    fn processor_affinity(apic_id: u8, domain: u32, enabled: bool) -> Vec<u8> {
+        let domain = domain.to_le_bytes();
+        let mut entry = vec![0, 16, domain[0], apic_id];
+        entry.extend_from_slice(&(enabled as u32).to_le_bytes());
+        entry.push(0);
+        entry.extend_from_slice(&domain[1..]);
+        entry.resize(16, 0);
+        return entry;
+    }
+
+    fn memory_affinity(base: u64, length: u64, domain: u32, flags: u32) -> Vec<u8> {
+        let mut entry = vec![1, 40];
+        entry.extend_from_slice(&domain.to_le_bytes());
+        entry.resize(8, 0);
+        entry.extend_from_slice(&base.to_le_bytes());
+        entry.extend_from_slice(&length.to_le_bytes());
+        entry.resize(28, 0);
+        entry.extend_from_slice(&flags.to_le_bytes());
+        entry.resize(40, 0);
+        return entry;
+    }
+
+    fn x2apic_affinity(apic_id: u32, domain: u32) -> Vec<u8> {
+        let mut entry = vec![2, 24, 0, 0];
+        entry.extend_from_slice(&domain.to_le_bytes());
+        entry.extend_from_slice(&apic_id.to_le_bytes());
+        entry.extend_from_slice(&1u32.to_le_bytes());
+        entry.resize(24, 0);
+        return entry;
+    }
+
+    fn srat(entries: &[Vec<u8>]) -> Vec<u64> {
+        let mut body = vec![0u8; 12];
+        for entry in entries {
+            body.extend_from_slice(entry);
+        }
+        return build_table(b"SRAT", &body);
+    }
+
+    #[test]
+    fn test_entries() {
+        let table = srat(&[
+            processor_affinity(3, 0x0102_0304, true),
+            memory_affinity(0x1_0000_0000, 0x4000_0000, 1, 0b011),
+            x2apic_affinity(0x1234, 2),
+            vec![0x7F, 4, 0, 0],
+        ]);
+        let srat = unsafe { Srat::new(address(&table), 0) };
+        let entries: Vec<SratEntry> = srat.iter().collect();
+        assert_eq!(4, entries.len());
+
+        match entries[0] {
+            SratEntry::ProcessorAffinity(processor) => {
+                assert_eq!(3, processor.apic_id());
+                assert_eq!(0x0102_0304, processor.proximity_domain());
+                assert!(processor.is_enabled() && !processor.is_x2apic());
+            },
+            _ => panic!("expected a processor affinity"),
+        }
+        match entries[1] {
+            SratEntry::MemoryAffinity(memory) => {
+                assert_eq!(0x1_0000_0000, memory.base_address());
+                assert_eq!(0x4000_0000, memory.length());
+                assert_eq!(1, memory.proximity_domain());
+                assert!(memory.is_enabled() && memory.is_hot_pluggable() && !memory.is_non_volatile());
+            },
+            _ => panic!("expected a memory affinity"),
+        }
+        match entries[2] {
+            SratEntry::ProcessorAffinity(processor) => {
+                assert_eq!(0x1234, processor.apic_id());
+                assert_eq!(2, processor.proximity_domain());
+                assert!(processor.is_x2apic());
+            },
+            _ => panic!("expected an x2APIC affinity"),
+        }
+        assert!(matches!(entries[3], SratEntry::Unknown(0x7F)));
+    }
+
+    #[test]
+    fn test_disabled_processor() {
+        let table = srat(&[processor_affinity(1, 0, false)]);
+        let srat = unsafe { Srat::new(address(&table), 0) };
+        match srat.iter().next() {
+            Some(SratEntry::ProcessorAffinity(processor)) => assert!(!processor.is_enabled()),
+            _ => panic!("expected a processor affinity"),
+        }
+    }
+
+    #[test]
+    fn test_malformed_entries_end_iteration() {
+        //A zero length entry would loop forever and a long one runs off the table
+        let zero_length = srat(&[processor_affinity(1, 0, true), vec![0, 0, 0, 0]]);
+        let srat_zero = unsafe { Srat::new(address(&zero_length), 0) };
+        assert_eq!(1, srat_zero.iter().count());
+
+        let mut overlong = memory_affinity(0, 0x1000, 0, 1);
+        overlong[1] = 200;
+        let overlong = srat(&[overlong]);
+        let srat_overlong = unsafe { Srat::new(address(&overlong), 0) };
+        assert_eq!(0, srat_overlong.iter().count());
+    }
+}
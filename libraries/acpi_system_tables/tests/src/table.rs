@@ -0,0 +1,21 @@
+#![cfg(test)]
+
+/// Builds an ACPI table with a standard 36 byte header in front of *body*. The table is
+/// backed by u64s so it is 8 byte aligned like tables in firmware memory.
+pub fn build_table(signature: &[u8; 4], body: &[u8]) -> Vec<u64> {
+    let length = 36 + body.len();
+    let mut bytes = Vec::with_capacity(length);
+    bytes.extend_from_slice(signature);
+    bytes.extend_from_slice(&(length as u32).to_le_bytes());
+    bytes.resize(36, 0);
+    bytes.extend_from_slice(body);
+
+    let mut table = vec![0u64; (length + 7) / 8];
+    unsafe { core::ptr::copy_nonoverlapping(bytes.as_ptr(), table.as_mut_ptr() as *mut u8, length); }
+    return table;
+}
+
+/// The table's address, passed as the physical address with a mapping offset of 0
+pub fn address(table: &[u64]) -> u64 {
+    return table.as_ptr() as u64;
+}
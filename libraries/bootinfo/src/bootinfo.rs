@@ -1,17 +1,28 @@
 use x86_64_hardware::memory::VirtualAddress;
 
-use crate::{MemInfo, FrameBuffer};
+use crate::{MemInfo, FrameBuffer, KernelSymbols};
 
 //Randomly generated magic values. Replace with something fancy like the OS name once it has a name.
 const BOOTINFO_MAGIC: [u8;4] = [15, 106, 86, 167];
+pub const MAX_COMMAND_LINE_LEN: usize = 256;
+/// Stored in *video_mode* when the bootloader did not hand over a framebuffer
+pub const NO_VIDEO_MODE: u32 = u32::MAX;
 
 #[repr(C)]
 pub struct BootInfo {
     magic: [u8;4],
     pub framebuffer: FrameBuffer,
+    /// The GOP mode number the bootloader chose, or NO_VIDEO_MODE
+    pub video_mode: u32,
     pub page_table_memory_offset: u64,
     pub next_available_kernel_page: VirtualAddress,
     pub meminfo: MemInfo,
+    /// Physical address of the ACPI RSDP or 0 if the firmware did not provide one
+    pub rsdp_physical_address: u64,
+    /// The kernel's symbol table, for symbolizing addresses in backtraces
+    pub kernel_symbols: KernelSymbols,
+    command_line: [u8; MAX_COMMAND_LINE_LEN],
+    command_line_len: usize,
 }
 
 impl BootInfo {
@@ -19,6 +30,22 @@ impl BootInfo {
     pub fn valid_magic(&self) -> bool {
         return self.magic == BOOTINFO_MAGIC;
     }
+
+    /// The kernel command line from the boot configuration, empty if there was none
+    pub fn command_line(&self) -> &str {
+        return core::str::from_utf8(&self.command_line[..self.command_line_len]).unwrap_or("");
+    }
+
+    /// Sets the kernel command line. Returns false if it is longer than MAX_COMMAND_LINE_LEN.
+    pub fn set_command_line(&mut self, command_line: &str) -> bool {
+        if command_line.len() > MAX_COMMAND_LINE_LEN {
+            return false;
+        }
+
+        self.command_line[..command_line.len()].copy_from_slice(command_line.as_bytes());
+        self.command_line_len = command_line.len();
+        return true;
+    }
 }
 
 impl Default for BootInfo {
@@ -26,9 +53,14 @@ impl Default for BootInfo {
         BootInfo {
             magic: BOOTINFO_MAGIC,
             framebuffer: FrameBuffer::default(),
+            video_mode: NO_VIDEO_MODE,
             page_table_memory_offset: 0,
             next_available_kernel_page: VirtualAddress::new(0),
             meminfo: MemInfo::default(),
+            rsdp_physical_address: 0,
+            kernel_symbols: KernelSymbols::default(),
+            command_line: [0; MAX_COMMAND_LINE_LEN],
+            command_line_len: 0,
         }   
     }
 }
\ No newline at end of file
@@ -1,5 +1,102 @@
 use x86_64_hardware::memory::PhysicalAddress;
 
+/// The bits of a pixel that hold each colour, for framebuffers with a custom layout
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct PixelBitmask {
+    pub red: u32,
+    pub green: u32,
+    pub blue: u32,
+    pub reserved: u32,
+}
+
+/// How colours are laid out in a pixel, following the GOP pixel formats
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PixelFormat {
+    /// 32 bit pixels with red in the lowest byte
+    Rgb,
+    /// 32 bit pixels with blue in the lowest byte
+    Bgr,
+    Bitmask(PixelBitmask),
+    /// There is no linear framebuffer. The firmware can only draw through Blt, which is
+    /// gone after exiting boot services.
+    BltOnly,
+}
+
+impl PixelFormat {
+    /// The number of bytes in one pixel. Bitmask pixels are as wide as their highest
+    /// mask bit needs.
+    pub fn bytes_per_pixel(&self) -> usize {
+        match self {
+            PixelFormat::Rgb | PixelFormat::Bgr => 4,
+            PixelFormat::Bitmask(mask) => {
+                let bits = 32 - (mask.red | mask.green | mask.blue | mask.reserved).leading_zeros() as usize;
+                return (bits + 7) / 8;
+            },
+            PixelFormat::BltOnly => 0,
+        }
+    }
+
+    /// Packs an 8 bit per channel colour into a pixel value
+    pub fn pack(&self, red: u8, green: u8, blue: u8) -> u32 {
+        match self {
+            PixelFormat::Rgb => (red as u32) | (green as u32) << 8 | (blue as u32) << 16,
+            PixelFormat::Bgr => (blue as u32) | (green as u32) << 8 | (red as u32) << 16,
+            PixelFormat::Bitmask(mask) => pack_channel(red, mask.red) | pack_channel(green, mask.green) | pack_channel(blue, mask.blue),
+            PixelFormat::BltOnly => 0,
+        }
+    }
+
+    /// Packs a colour written as 0x00RRGGBB into a pixel value
+    pub fn pack_rgb(&self, rgb: u32) -> u32 {
+        return self.pack((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8);
+    }
+
+    /// Splits a pixel value back into 8 bit red, green and blue channels
+    pub fn unpack(&self, pixel: u32) -> (u8, u8, u8) {
+        match self {
+            PixelFormat::Rgb => (pixel as u8, (pixel >> 8) as u8, (pixel >> 16) as u8),
+            PixelFormat::Bgr => ((pixel >> 16) as u8, (pixel >> 8) as u8, pixel as u8),
+            PixelFormat::Bitmask(mask) => (unpack_channel(pixel, mask.red), unpack_channel(pixel, mask.green), unpack_channel(pixel, mask.blue)),
+            PixelFormat::BltOnly => (0, 0, 0),
+        }
+    }
+}
+
+/// Scales an 8 bit channel to the width of *mask* and moves it into place
+fn pack_channel(value: u8, mask: u32) -> u32 {
+    if mask == 0 {
+        return 0;
+    }
+
+    let shift = mask.trailing_zeros();
+    let bits = (mask >> shift).count_ones();
+    let scaled = if bits <= 8 {
+        (value as u32) >> (8 - bits)
+    } else {
+        //Repeat the top bits so full intensity stays full intensity
+        ((value as u32) << (bits - 8)) | ((value as u32) >> (16 - bits.min(16)))
+    };
+    return (scaled << shift) & mask;
+}
+
+/// Reads the channel of *pixel* under *mask* and scales it to 8 bits
+pub fn unpack_channel(pixel: u32, mask: u32) -> u8 {
+    if mask == 0 {
+        return 0;
+    }
+
+    let shift = mask.trailing_zeros();
+    let bits = (mask >> shift).count_ones();
+    let value = (pixel & mask) >> shift;
+    if bits >= 8 {
+        return (value >> (bits - 8)) as u8;
+    } else {
+        return (value * 255 / ((1 << bits) - 1)) as u8;
+    }
+}
+
 #[repr(C)]
 pub struct FrameBuffer {
     pub base_address: PhysicalAddress,
@@ -7,33 +104,56 @@ pub struct FrameBuffer {
     pub width: u32,
     pub height: u32,
     pub pixels_per_scan_line: u32,
+    pub pixel_format: PixelFormat,
 }
 
 impl FrameBuffer {
-    pub fn new(base_address: PhysicalAddress, buffer_size: usize, width: u32, height: u32, pixels_per_scan_line: u32) -> FrameBuffer {
+    pub fn new(base_address: PhysicalAddress, buffer_size: usize, width: u32, height: u32, pixels_per_scan_line: u32, pixel_format: PixelFormat) -> FrameBuffer {
         FrameBuffer {
             base_address: base_address,
             buffer_size: buffer_size,
             width: width,
             height: height,
             pixels_per_scan_line: pixels_per_scan_line,
+            pixel_format: pixel_format,
         }
     }
 
-    /// Clears the framebuffer
-    /// 
+    /// True if there is a linear framebuffer that can be drawn to directly
+    pub fn is_linear(&self) -> bool {
+        return self.bytes_per_pixel() != 0 && self.base_address.as_u64() != 0 && self.width != 0 && self.height != 0;
+    }
+
+    pub fn bytes_per_pixel(&self) -> usize {
+        return self.pixel_format.bytes_per_pixel();
+    }
+
+    /// Packs a colour written as 0x00RRGGBB into the pixel format of this framebuffer
+    pub fn pack_rgb(&self, rgb: u32) -> u32 {
+        return self.pixel_format.pack_rgb(rgb);
+    }
+
+    /// Clears the framebuffer to *colour*, written as 0x00RRGGBB. Does nothing if there is
+    /// no linear framebuffer.
+    ///
     /// ## Safety
-    /// 
+    ///
     /// This is unsafe as it makes an assumption that the virtual address is at a simple offset to the physical address.
     /// The caller must assure this is the case before making this call.
     pub unsafe fn clear_framebuffer(&self, colour: u32, memory_offset: u64) {
+        if !self.is_linear() {
+            return;
+        }
+
+        let pixel_value = self.pack_rgb(colour).to_le_bytes();
+        let bytes_per_pixel = self.bytes_per_pixel();
         let virt_addr = self.base_address.get_virtual_address_at_offset(memory_offset);
-        let base_pixel: *mut u32 = virt_addr.get_mut_ptr::<u32>();
-        for x_pos in 0..self.width {
-            for y_pos in 0..self.height {
+        let base_byte: *mut u8 = virt_addr.get_mut_ptr::<u8>();
+        for y_pos in 0..self.height as usize {
+            for x_pos in 0..self.width as usize {
                 unsafe {
-                    let pixel: *mut u32 = base_pixel.add((y_pos * self.pixels_per_scan_line + x_pos) as usize);
-                    (*pixel) = colour;
+                    let pixel = base_byte.add((y_pos * self.pixels_per_scan_line as usize + x_pos) * bytes_per_pixel);
+                    core::ptr::copy_nonoverlapping(pixel_value.as_ptr(), pixel, bytes_per_pixel);
                 }
             }
         }
@@ -48,6 +168,7 @@ impl Default for FrameBuffer {
             width: 0,
             height: 0,
             pixels_per_scan_line: 0,
+            pixel_format: PixelFormat::BltOnly,
         }
     }
-}
\ No newline at end of file
+}
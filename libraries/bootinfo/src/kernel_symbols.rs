@@ -0,0 +1,49 @@
+use x86_64_hardware::memory::VirtualAddress;
+
+/// Where the bootloader mapped the kernel's .symtab section and the string table holding
+/// its names. Both sizes are 0 if the kernel was stripped.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct KernelSymbols {
+    pub symbol_table: VirtualAddress,
+    pub symbol_table_size: u64,
+    pub string_table: VirtualAddress,
+    pub string_table_size: u64,
+}
+
+impl KernelSymbols {
+    pub fn is_present(&self) -> bool {
+        return self.symbol_table_size != 0 && self.string_table_size != 0;
+    }
+
+    /// The raw symbol table entries
+    /// 
+    /// ## Safety
+    /// 
+    /// This is unsafe as the caller must ensure the bootloader's mapping of the table is
+    /// still in place.
+    pub unsafe fn symbol_table_bytes(&self) -> &'static [u8] {
+        return core::slice::from_raw_parts(self.symbol_table.get_mut_ptr::<u8>() as *const u8, self.symbol_table_size as usize);
+    }
+
+    /// The raw string table
+    /// 
+    /// ## Safety
+    /// 
+    /// This is unsafe as the caller must ensure the bootloader's mapping of the table is
+    /// still in place.
+    pub unsafe fn string_table_bytes(&self) -> &'static [u8] {
+        return core::slice::from_raw_parts(self.string_table.get_mut_ptr::<u8>() as *const u8, self.string_table_size as usize);
+    }
+}
+
+impl Default for KernelSymbols {
+    fn default() -> KernelSymbols {
+        KernelSymbols {
+            symbol_table: VirtualAddress::new(0),
+            symbol_table_size: 0,
+            string_table: VirtualAddress::new(0),
+            string_table_size: 0,
+        }
+    }
+}
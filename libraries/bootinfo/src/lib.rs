@@ -1,8 +1,10 @@
 #![no_std]
 mod bootinfo;
 mod framebuffer;
+mod kernel_symbols;
 mod meminfo;
 
 pub use bootinfo::*;
 pub use framebuffer::*;
+pub use kernel_symbols::*;
 pub use meminfo::*;
@@ -0,0 +1 @@
+mod pixel_format;
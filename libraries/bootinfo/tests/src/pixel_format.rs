@@ -0,0 +1,58 @@
+#[cfg(test)]
+mod tests {
+    use bootinfo::{PixelBitmask, PixelFormat};
+
+    const RGB_565: PixelFormat = PixelFormat::Bitmask(PixelBitmask { red: 0xF800, green: 0x07E0, blue: 0x001F, reserved: 0 });
+    const XRGB_8888: PixelFormat = PixelFormat::Bitmask(PixelBitmask { red: 0x00FF_0000, green: 0x0000_FF00, blue: 0x0000_00FF, reserved: 0xFF00_0000 });
+    const RGB_101010: PixelFormat = PixelFormat::Bitmask(PixelBitmask { red: 0x3FF0_0000, green: 0x000F_FC00, blue: 0x0000_03FF, reserved: 0xC000_0000 });
+
+    #[test]
+    fn test_bytes_per_pixel() {
+        assert_eq!(4, PixelFormat::Rgb.bytes_per_pixel());
+        assert_eq!(4, PixelFormat::Bgr.bytes_per_pixel());
+        assert_eq!(2, RGB_565.bytes_per_pixel());
+        assert_eq!(4, XRGB_8888.bytes_per_pixel());
+        assert_eq!(0, PixelFormat::BltOnly.bytes_per_pixel());
+    }
+
+    #[test]
+    fn test_pack_rgb_and_bgr() {
+        assert_eq!(0x0033_2211, PixelFormat::Rgb.pack(0x11, 0x22, 0x33));
+        assert_eq!(0x0011_2233, PixelFormat::Bgr.pack(0x11, 0x22, 0x33));
+        assert_eq!(0x0011_2233, PixelFormat::Bgr.pack_rgb(0x0011_2233));
+        assert_eq!(0x0033_2211, PixelFormat::Rgb.pack_rgb(0x0011_2233));
+        assert_eq!(0, PixelFormat::BltOnly.pack_rgb(0x00FF_FFFF));
+    }
+
+    #[test]
+    fn test_pack_bitmask() {
+        assert_eq!(0x0011_2233, XRGB_8888.pack(0x11, 0x22, 0x33));
+        assert_eq!(0xFFFF, RGB_565.pack(0xFF, 0xFF, 0xFF));
+        assert_eq!(0xF800, RGB_565.pack(0xFF, 0x00, 0x00));
+        assert_eq!(0x07E0, RGB_565.pack(0x00, 0xFF, 0x00));
+        assert_eq!(0x8410, RGB_565.pack(0x80, 0x80, 0x80));
+
+        //Channels wider than 8 bits stay at full intensity when full
+        assert_eq!(0x3FFF_FFFF, RGB_101010.pack(0xFF, 0xFF, 0xFF));
+        assert_eq!(0x0000_0000, RGB_101010.pack(0x00, 0x00, 0x00));
+    }
+
+    #[test]
+    fn test_unpack() {
+        assert_eq!((0x11, 0x22, 0x33), PixelFormat::Rgb.unpack(0x0033_2211));
+        assert_eq!((0x11, 0x22, 0x33), PixelFormat::Bgr.unpack(0x0011_2233));
+        assert_eq!((0xFF, 0xFF, 0xFF), RGB_565.unpack(0xFFFF));
+        assert_eq!((0xFF, 0x00, 0x00), RGB_565.unpack(0xF800));
+        assert_eq!((0x11, 0x22, 0x33), XRGB_8888.unpack(0xFF11_2233));
+        assert_eq!((0, 0, 0), PixelFormat::BltOnly.unpack(0xFFFF_FFFF));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for value in 0..=255u8 {
+            for format in [PixelFormat::Rgb, PixelFormat::Bgr, XRGB_8888, RGB_101010] {
+                assert_eq!((value, value, value), format.unpack(format.pack(value, value, value)));
+            }
+        }
+    }
+}
@@ -0,0 +1,9 @@
+/// Converts a packed binary coded decimal byte, e.g. 0x59, to its value, e.g. 59
+pub fn bcd_to_binary(value: u8) -> u8 {
+    return (value >> 4) * 10 + (value & 0x0F);
+}
+
+/// Converts a value below 100 to packed binary coded decimal
+pub fn binary_to_bcd(value: u8) -> u8 {
+    return (value / 10) << 4 | (value % 10);
+}
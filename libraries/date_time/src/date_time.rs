@@ -0,0 +1,78 @@
+use core::fmt;
+
+/// Years without a century are assumed to be in this century
+pub const DEFAULT_CENTURY: u16 = 20;
+
+/// The full year from a two digit *year* and the century it is in, if known
+pub fn full_year(century: Option<u8>, year: u8) -> u16 {
+    let century = match century {
+        Some(century) => century as u16,
+        None => DEFAULT_CENTURY,
+    };
+    return century * 100 + year as u16;
+}
+
+/// Years divisible by 4 are leap years, except centuries not divisible by 400, so 2000 is
+/// a leap year and 2100 is not
+pub fn is_leap_year(year: u16) -> bool {
+    return year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+}
+
+/// A calendar date and time. There is no time zone so this is assumed to be UTC.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DateTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl DateTime {
+    /// Seconds since 1970-01-01T00:00:00Z. Dates before then give 0.
+    pub fn to_unix_seconds(&self) -> u64 {
+        //Shift the year to start in March so the leap day is the last day of the year
+        let year = self.year as i64 - if self.month <= 2 { 1 } else { 0 };
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let month = self.month as i64;
+        let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + self.day as i64 - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        let days = era * 146097 + day_of_era - 719468;
+
+        let seconds = days * 86400 + self.hour as i64 * 3600 + self.minute as i64 * 60 + self.second as i64;
+        return seconds.max(0) as u64;
+    }
+
+    /// The date and time *seconds* after 1970-01-01T00:00:00Z
+    pub fn from_unix_seconds(seconds: u64) -> DateTime {
+        let days = (seconds / 86400) as i64 + 719468;
+        let seconds_of_day = seconds % 86400;
+
+        let era = days.div_euclid(146097);
+        let day_of_era = days - era * 146097;
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+        let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+        let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+        return DateTime {
+            year: year as u16,
+            month: month as u8,
+            day: day as u8,
+            hour: (seconds_of_day / 3600) as u8,
+            minute: (seconds_of_day / 60 % 60) as u8,
+            second: (seconds_of_day % 60) as u8,
+        };
+    }
+}
+
+/// Formats as ISO 8601, e.g. 2024-01-31T23:59:59Z
+impl fmt::Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", self.year, self.month, self.day, self.hour, self.minute, self.second);
+    }
+}
@@ -0,0 +1,37 @@
+use crate::{bcd_to_binary, binary_to_bcd};
+
+/// Set in an hour register in 12 hour mode for times after noon
+pub const HOUR_PM: u8 = 1 << 7;
+
+/// Decodes an RTC hour register to a 24 hour value. In 12 hour mode bit 7 marks PM and 12AM
+/// is midnight.
+pub fn decode_hour(value: u8, binary: bool, twenty_four_hour: bool) -> u8 {
+    let pm = !twenty_four_hour && value & HOUR_PM != 0;
+    let value = value & !HOUR_PM;
+    let hour = if binary { value } else { bcd_to_binary(value) };
+
+    if twenty_four_hour {
+        return hour;
+    }
+    return match (hour, pm) {
+        (12, false) => 0,
+        (12, true) => 12,
+        (hour, true) => hour + 12,
+        (hour, false) => hour,
+    };
+}
+
+/// Encodes a 24 hour value for an RTC hour register, the reverse of *decode_hour*
+pub fn encode_hour(hour: u8, binary: bool, twenty_four_hour: bool) -> u8 {
+    let encode = |value: u8| if binary { value } else { binary_to_bcd(value) };
+    if twenty_four_hour {
+        return encode(hour);
+    }
+
+    return match hour {
+        0 => encode(12),
+        1..=11 => encode(hour),
+        12 => encode(12) | HOUR_PM,
+        _ => encode(hour - 12) | HOUR_PM,
+    };
+}
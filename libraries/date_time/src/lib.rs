@@ -0,0 +1,9 @@
+#![no_std]
+
+mod bcd;
+mod date_time;
+mod hour;
+
+pub use bcd::*;
+pub use date_time::*;
+pub use hour::*;
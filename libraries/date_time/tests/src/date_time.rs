@@ -0,0 +1,65 @@
+#[cfg(test)]
+mod tests {
+    use date_time::{full_year, is_leap_year, DateTime};
+
+    fn date_time(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> DateTime {
+        return DateTime { year: year, month: month, day: day, hour: hour, minute: minute, second: second };
+    }
+
+    #[test]
+    fn test_unix_epoch() {
+        let epoch = date_time(1970, 1, 1, 0, 0, 0);
+
+        assert_eq!(0, epoch.to_unix_seconds());
+        assert_eq!(epoch, DateTime::from_unix_seconds(0));
+    }
+
+    #[test]
+    fn test_known_timestamps() {
+        assert_eq!(951_782_400, date_time(2000, 2, 29, 0, 0, 0).to_unix_seconds());
+        assert_eq!(1_706_745_599, date_time(2024, 1, 31, 23, 59, 59).to_unix_seconds());
+        assert_eq!(4_107_542_400, date_time(2100, 3, 1, 0, 0, 0).to_unix_seconds());
+        assert_eq!(date_time(2038, 1, 19, 3, 14, 8), DateTime::from_unix_seconds(1 << 31));
+    }
+
+    #[test]
+    fn test_before_epoch_is_zero() {
+        assert_eq!(0, date_time(1969, 12, 31, 23, 59, 59).to_unix_seconds());
+    }
+
+    #[test]
+    fn test_leap_years() {
+        assert!(is_leap_year(2000));
+        assert!(is_leap_year(2024));
+        assert!(!is_leap_year(2023));
+        assert!(!is_leap_year(2100));
+
+        //2000 has a 29th of February but 2100 goes straight to the 1st of March
+        let leap_day = date_time(2000, 2, 29, 12, 0, 0).to_unix_seconds();
+        assert_eq!(date_time(2000, 3, 1, 12, 0, 0), DateTime::from_unix_seconds(leap_day + 86400));
+        let end_of_february = date_time(2100, 2, 28, 12, 0, 0).to_unix_seconds();
+        assert_eq!(date_time(2100, 3, 1, 12, 0, 0), DateTime::from_unix_seconds(end_of_february + 86400));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        //Every day from 1970 to past 2100 at a time that changes each day
+        for day in 0..(131 * 366) {
+            let seconds = day * 86400 + (day * 7919) % 86400;
+            assert_eq!(seconds, DateTime::from_unix_seconds(seconds).to_unix_seconds());
+        }
+    }
+
+    #[test]
+    fn test_full_year() {
+        assert_eq!(2024, full_year(None, 24));
+        assert_eq!(1999, full_year(Some(19), 99));
+        assert_eq!(2100, full_year(Some(21), 0));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!("2024-01-31T23:59:59Z", format!("{}", date_time(2024, 1, 31, 23, 59, 59)));
+        assert_eq!("0999-09-09T09:09:09Z", format!("{}", date_time(999, 9, 9, 9, 9, 9)));
+    }
+}
@@ -0,0 +1,2 @@
+mod date_time;
+mod registers;
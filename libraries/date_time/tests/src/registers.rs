@@ -0,0 +1,49 @@
+#[cfg(test)]
+mod tests {
+    use date_time::{bcd_to_binary, binary_to_bcd, decode_hour, encode_hour, HOUR_PM};
+
+    #[test]
+    fn test_bcd() {
+        assert_eq!(0, bcd_to_binary(0x00));
+        assert_eq!(59, bcd_to_binary(0x59));
+        assert_eq!(0x59, binary_to_bcd(59));
+        for value in 0..100 {
+            assert_eq!(value, bcd_to_binary(binary_to_bcd(value)));
+        }
+    }
+
+    #[test]
+    fn test_decode_24_hour() {
+        assert_eq!(0, decode_hour(0x00, false, true));
+        assert_eq!(23, decode_hour(0x23, false, true));
+        assert_eq!(23, decode_hour(23, true, true));
+    }
+
+    #[test]
+    fn test_decode_12_hour() {
+        assert_eq!(0, decode_hour(0x12, false, false));
+        assert_eq!(1, decode_hour(0x01, false, false));
+        assert_eq!(12, decode_hour(0x12 | HOUR_PM, false, false));
+        assert_eq!(13, decode_hour(0x01 | HOUR_PM, false, false));
+        assert_eq!(23, decode_hour(11 | HOUR_PM, true, false));
+    }
+
+    #[test]
+    fn test_encode_12_hour() {
+        assert_eq!(0x12, encode_hour(0, false, false));
+        assert_eq!(0x11, encode_hour(11, false, false));
+        assert_eq!(0x12 | HOUR_PM, encode_hour(12, false, false));
+        assert_eq!(11 | HOUR_PM, encode_hour(23, true, false));
+    }
+
+    #[test]
+    fn test_hour_round_trip() {
+        for hour in 0..24 {
+            for binary in [false, true] {
+                for twenty_four_hour in [false, true] {
+                    assert_eq!(hour, decode_hour(encode_hour(hour, binary, twenty_four_hour), binary, twenty_four_hour));
+                }
+            }
+        }
+    }
+}
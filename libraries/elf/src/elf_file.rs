@@ -0,0 +1,328 @@
+use core::fmt;
+use core::mem::size_of;
+
+use crate::{ElfClass, ElfData, ElfHeader64, ElfHeaderCommon, ElfPhysicalHeader64, ElfSectionHeader64, ElfSectionType, ElfStringTable, ElfSymbol64, ElfSymbolTable, SHN_UNDEF};
+
+/// Why an ELF file could not be read
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ElfError {
+    /// *size* bytes are needed at *offset* but the file ends first
+    Truncated { offset: u64, size: u64 },
+    InvalidMagic,
+    UnsupportedClass(ElfClass),
+    UnsupportedDataEncoding(ElfData),
+    /// e_phentsize is smaller than a 64 bit program header
+    InvalidProgramHeaderSize(u16),
+    /// e_shentsize is smaller than a 64 bit section header
+    InvalidSectionHeaderSize(u16),
+    /// A symbol table's sh_entsize is not the size of a 64 bit symbol
+    InvalidSymbolSize(u64),
+    SectionIndexOutOfRange(usize),
+    /// The section is used as a string table but is not one
+    NotAStringTable(usize),
+    /// The name at this offset is outside its string table, unterminated or not UTF-8
+    InvalidString(u32),
+    /// The note at this offset into a notes segment or section runs past its end
+    TruncatedNote(usize),
+}
+
+impl fmt::Display for ElfError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ElfError::Truncated { offset, size } => write!(f, "{:#x} bytes at {:#x} are past the end of the file", size, offset),
+            ElfError::InvalidMagic => write!(f, "not an ELF file"),
+            ElfError::UnsupportedClass(class) => write!(f, "unsupported class {:?}", class),
+            ElfError::UnsupportedDataEncoding(encoding) => write!(f, "unsupported data encoding {:?}", encoding),
+            ElfError::InvalidProgramHeaderSize(size) => write!(f, "program headers are {} bytes", size),
+            ElfError::InvalidSectionHeaderSize(size) => write!(f, "section headers are {} bytes", size),
+            ElfError::InvalidSymbolSize(size) => write!(f, "symbols are {} bytes", size),
+            ElfError::SectionIndexOutOfRange(index) => write!(f, "there is no section {}", index),
+            ElfError::NotAStringTable(index) => write!(f, "section {} is not a string table", index),
+            ElfError::InvalidString(offset) => write!(f, "invalid string at {:#x}", offset),
+            ElfError::TruncatedNote(offset) => write!(f, "the note at {:#x} is truncated", offset),
+        }
+    }
+}
+
+/// A 64 bit little endian ELF file read in place. Only the header and the extent of the
+/// header tables are checked up front; everything else is bounds checked as it is read, so
+/// any input gives either data from inside the slice or an ElfError. Extended section
+/// numbering, for files of 0xFF00 sections or more, is not supported.
+#[derive(Clone, Copy)]
+pub struct ElfFile<'a> {
+    data: &'a [u8],
+    header: ElfHeader64,
+}
+
+impl<'a> ElfFile<'a> {
+    pub fn parse(data: &'a [u8]) -> Result<ElfFile<'a>, ElfError> {
+        let common: ElfHeaderCommon = read(data, 0)?;
+        if !common.valid_magic() {
+            return Err(ElfError::InvalidMagic);
+        }
+        if common.class() != ElfClass::ElfClass64 {
+            return Err(ElfError::UnsupportedClass(common.class()));
+        }
+        if common.data_encoding() != ElfData::ElfData2Lsb {
+            return Err(ElfError::UnsupportedDataEncoding(common.data_encoding()));
+        }
+
+        let header: ElfHeader64 = read(data, 0)?;
+        if header.e_phnum != 0 {
+            if (header.e_phentsize as usize) < size_of::<ElfPhysicalHeader64>() {
+                return Err(ElfError::InvalidProgramHeaderSize(header.e_phentsize));
+            }
+            slice(data, header.e_phoff, u64::from(header.e_phnum) * u64::from(header.e_phentsize))?;
+        }
+
+        if header.e_shnum != 0 {
+            if (header.e_shentsize as usize) < size_of::<ElfSectionHeader64>() {
+                return Err(ElfError::InvalidSectionHeaderSize(header.e_shentsize));
+            }
+            slice(data, header.e_shoff, u64::from(header.e_shnum) * u64::from(header.e_shentsize))?;
+        }
+        if header.e_shstrndx != SHN_UNDEF && header.e_shstrndx >= header.e_shnum {
+            return Err(ElfError::SectionIndexOutOfRange(header.e_shstrndx as usize));
+        }
+
+        return Ok(ElfFile {
+            data: data,
+            header: header,
+        });
+    }
+
+    /// The whole file
+    pub fn data(&self) -> &'a [u8] {
+        return self.data;
+    }
+
+    pub fn header(&self) -> &ElfHeader64 {
+        return &self.header;
+    }
+
+    pub fn program_header_count(&self) -> usize {
+        return self.header.e_phnum as usize;
+    }
+
+    pub fn program_header(&self, index: usize) -> Option<ElfPhysicalHeader64> {
+        if index >= self.program_header_count() {
+            return None;
+        }
+
+        //parse checked the whole table is inside the file
+        return read(self.data, self.header.e_phoff + index as u64 * u64::from(self.header.e_phentsize)).ok();
+    }
+
+    pub fn program_headers(&self) -> ElfProgramHeaderIterator<'a> {
+        return ElfProgramHeaderIterator {
+            file: *self,
+            current_index: 0,
+        };
+    }
+
+    /// The part of the segment stored in the file. Any memory past p_filesz is zero filled
+    /// and not included.
+    pub fn segment_data(&self, header: &ElfPhysicalHeader64) -> Result<&'a [u8], ElfError> {
+        return slice(self.data, header.p_offset, header.p_filesz);
+    }
+
+    /// The notes in a PT_NOTE segment
+    pub fn segment_notes(&self, header: &ElfPhysicalHeader64) -> Result<ElfNoteIterator<'a>, ElfError> {
+        return Ok(ElfNoteIterator::new(self.segment_data(header)?, header.p_align));
+    }
+
+    pub fn section_header_count(&self) -> usize {
+        return self.header.e_shnum as usize;
+    }
+
+    pub fn section_header(&self, index: usize) -> Result<ElfSectionHeader64, ElfError> {
+        if index >= self.section_header_count() {
+            return Err(ElfError::SectionIndexOutOfRange(index));
+        }
+
+        //parse checked the whole table is inside the file
+        return read(self.data, self.header.e_shoff + index as u64 * u64::from(self.header.e_shentsize));
+    }
+
+    pub fn section_headers(&self) -> ElfSectionHeaderIterator<'a> {
+        return ElfSectionHeaderIterator {
+            file: *self,
+            current_index: 0,
+        };
+    }
+
+    /// The contents of a section, which is empty for SHT_NOBITS sections such as .bss
+    pub fn section_data(&self, header: &ElfSectionHeader64) -> Result<&'a [u8], ElfError> {
+        if header.sh_type() == ElfSectionType::ElfSectionTypeNoBits {
+            return Ok(&[]);
+        }
+        return slice(self.data, header.sh_offset, header.sh_size);
+    }
+
+    /// The notes in a SHT_NOTE section
+    pub fn section_notes(&self, header: &ElfSectionHeader64) -> Result<ElfNoteIterator<'a>, ElfError> {
+        return Ok(ElfNoteIterator::new(self.section_data(header)?, header.sh_addralign));
+    }
+
+    /// The string table section at *index*
+    pub fn string_table(&self, index: usize) -> Result<ElfStringTable<'a>, ElfError> {
+        let header = self.section_header(index)?;
+        if header.sh_type() != ElfSectionType::ElfSectionTypeStrTab {
+            return Err(ElfError::NotAStringTable(index));
+        }
+        return Ok(ElfStringTable::new(self.section_data(&header)?));
+    }
+
+    /// The table holding the section names. It is empty if the file has none.
+    pub fn section_names(&self) -> Result<ElfStringTable<'a>, ElfError> {
+        if self.header.e_shstrndx == SHN_UNDEF {
+            return Ok(ElfStringTable::new(&[]));
+        }
+        return self.string_table(self.header.e_shstrndx as usize);
+    }
+
+    pub fn section_name(&self, header: &ElfSectionHeader64) -> Result<&'a str, ElfError> {
+        return header.name(&self.section_names()?).ok_or(ElfError::InvalidString(header.sh_name));
+    }
+
+    /// The first section called *name*
+    pub fn find_section(&self, name: &str) -> Result<Option<ElfSectionHeader64>, ElfError> {
+        let section_names = self.section_names()?;
+        return Ok(self.section_headers().find(|header| header.name(&section_names) == Some(name)));
+    }
+
+    /// The first SHT_SYMTAB section and the string table holding its names, or None if the
+    /// file has been stripped
+    pub fn symbol_table(&self) -> Result<Option<(ElfSymbolTable<'a>, ElfStringTable<'a>)>, ElfError> {
+        let header = match self.section_headers().find(|header| header.sh_type() == ElfSectionType::ElfSectionTypeSymTab) {
+            Some(header) => header,
+            None => return Ok(None),
+        };
+
+        if header.sh_entsize != 0 && header.sh_entsize != size_of::<ElfSymbol64>() as u64 {
+            return Err(ElfError::InvalidSymbolSize(header.sh_entsize));
+        }
+        let symbols = ElfSymbolTable::new(self.section_data(&header)?);
+        let strings = self.string_table(header.sh_link as usize)?;
+        return Ok(Some((symbols, strings)));
+    }
+}
+
+pub struct ElfProgramHeaderIterator<'a> {
+    file: ElfFile<'a>,
+    current_index: usize,
+}
+
+impl<'a> Iterator for ElfProgramHeaderIterator<'a> {
+    type Item = ElfPhysicalHeader64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let output = self.file.program_header(self.current_index);
+        if output.is_some() {
+            self.current_index += 1;
+        }
+        return output;
+    }
+}
+
+pub struct ElfSectionHeaderIterator<'a> {
+    file: ElfFile<'a>,
+    current_index: usize,
+}
+
+impl<'a> Iterator for ElfSectionHeaderIterator<'a> {
+    type Item = ElfSectionHeader64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let output = self.file.section_header(self.current_index).ok();
+        if output.is_some() {
+            self.current_index += 1;
+        }
+        return output;
+    }
+}
+
+/// An entry in a PT_NOTE segment or SHT_NOTE section
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ElfNote<'a> {
+    /// The owner of the note, such as "GNU", without its nul terminator
+    pub name: &'a [u8],
+    pub note_type: u32,
+    pub descriptor: &'a [u8],
+}
+
+/// Reads notes one at a time. A malformed note is returned as an error and ends the
+/// iteration.
+pub struct ElfNoteIterator<'a> {
+    data: &'a [u8],
+    offset: usize,
+    align: usize,
+}
+
+impl<'a> ElfNoteIterator<'a> {
+    /// Notes are 4 byte aligned, except in segments and sections aligned to 8 where they
+    /// are 8 byte aligned
+    pub fn new(data: &'a [u8], align: u64) -> ElfNoteIterator<'a> {
+        return ElfNoteIterator {
+            data: data,
+            offset: 0,
+            align: if align == 8 { 8 } else { 4 },
+        };
+    }
+
+    fn read_note(&self) -> Option<(ElfNote<'a>, usize)> {
+        let name_size = read::<u32>(self.data, self.offset as u64).ok()? as usize;
+        let descriptor_size = read::<u32>(self.data, self.offset as u64 + 4).ok()? as usize;
+        let note_type = read::<u32>(self.data, self.offset as u64 + 8).ok()?;
+
+        let name_start = self.offset + 12;
+        let descriptor_start = name_start.checked_add(name_size)?.checked_next_multiple_of(self.align)?;
+        let descriptor_end = descriptor_start.checked_add(descriptor_size)?;
+        let name = self.data.get(name_start..name_start + name_size)?;
+        let descriptor = self.data.get(descriptor_start..descriptor_end)?;
+
+        //The padding after the last note may be left off
+        let next_offset = descriptor_end.checked_next_multiple_of(self.align)?.min(self.data.len());
+        let name = name.strip_suffix(&[0]).unwrap_or(name);
+        return Some((ElfNote { name: name, note_type: note_type, descriptor: descriptor }, next_offset));
+    }
+}
+
+impl<'a> Iterator for ElfNoteIterator<'a> {
+    type Item = Result<ElfNote<'a>, ElfError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset >= self.data.len() {
+            return None;
+        }
+
+        match self.read_note() {
+            Some((note, next_offset)) => {
+                self.offset = next_offset;
+                return Some(Ok(note));
+            },
+            None => {
+                let error = ElfError::TruncatedNote(self.offset);
+                self.offset = self.data.len();
+                return Some(Err(error));
+            }
+        }
+    }
+}
+
+/// The *size* bytes at *offset*
+fn slice(data: &[u8], offset: u64, size: u64) -> Result<&[u8], ElfError> {
+    let truncated = ElfError::Truncated { offset: offset, size: size };
+    let end = offset.checked_add(size).ok_or(truncated)?;
+    if end > data.len() as u64 {
+        return Err(truncated);
+    }
+    return Ok(&data[offset as usize..end as usize]);
+}
+
+/// Copies a structure out of *data*. Only used for integers and the #[repr(C)] headers of
+/// this crate, which are plain integers and valid for any bytes.
+fn read<T: Copy>(data: &[u8], offset: u64) -> Result<T, ElfError> {
+    let bytes = slice(data, offset, size_of::<T>() as u64)?;
+    return Ok(unsafe { core::ptr::read_unaligned(bytes.as_ptr() as *const T) });
+}
@@ -2,6 +2,7 @@ use core::default::Default;
 use crate::ElfHeaderCommon;
 
 #[repr(C)]
+#[derive(Clone, Copy)]
 pub struct ElfHeader64 {
     pub common: ElfHeaderCommon,
     pub e_entry: u64,
@@ -2,21 +2,21 @@ use core::default::Default;
 
 const ELF_MAGIC: [u8;4] = [0x7Fu8, b'E', b'L', b'F'];
 
-#[derive(PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum ElfClass {
     ElfClassNone,
     ElfClass32,
     ElfClass64,
 }
 
-#[derive(PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum ElfData {
     ElfDataNone,
     ElfData2Lsb,
     ElfData2Msb,
 }
 
-#[derive(PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum ElfType {
     ElfTypeNone,
     ElfTypeRel,
@@ -29,7 +29,7 @@ pub enum ElfType {
     ElfTypeHiProc,
 }
 
-#[derive(PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum ElfMachine {
     ElfMachineNONE,
     ElfMachineM32,
@@ -213,13 +213,14 @@ pub enum ElfMachine {
     ElfMachineCSKY,
 }
 
-#[derive(PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum ElfVersion {
     ElfVersionNone,
     ElfVersionCurrent,
 }
 
 #[repr(C)]
+#[derive(Clone, Copy)]
 pub struct ElfHeaderCommon {
     magic: [u8;4],
     _class: u8,
@@ -23,7 +23,15 @@ pub enum ElfPhysicalType {
     ElfPhysicalTypeHiProc,
 }
 
+/// The segment may be executed
+pub const PF_X: u32 = 0x1;
+/// The segment may be written
+pub const PF_W: u32 = 0x2;
+/// The segment may be read
+pub const PF_R: u32 = 0x4;
+
 #[repr(C)]
+#[derive(Clone, Copy)]
 pub struct ElfPhysicalHeader64 {
     pub _p_type: u32,
     pub p_flags: u32,
@@ -59,6 +67,18 @@ impl ElfPhysicalHeader64 {
             _ => ElfPhysicalType::ElfPhysicalTypeNull,
         }
     }
+
+    pub fn readable(&self) -> bool {
+        return self.p_flags & PF_R != 0;
+    }
+
+    pub fn writable(&self) -> bool {
+        return self.p_flags & PF_W != 0;
+    }
+
+    pub fn executable(&self) -> bool {
+        return self.p_flags & PF_X != 0;
+    }
 }
 
 
@@ -0,0 +1,123 @@
+use core::default::Default;
+
+use crate::ElfStringTable;
+
+/// The section index of an undefined symbol
+pub const SHN_UNDEF: u16 = 0;
+/// The section index of a symbol with an absolute value
+pub const SHN_ABS: u16 = 0xFFF1;
+/// The section index of a common symbol
+pub const SHN_COMMON: u16 = 0xFFF2;
+
+/// The section is writable at run time
+pub const SHF_WRITE: u64 = 0x1;
+/// The section is loaded into memory
+pub const SHF_ALLOC: u64 = 0x2;
+/// The section holds executable code
+pub const SHF_EXECINSTR: u64 = 0x4;
+
+#[derive(PartialEq, Debug)]
+pub enum ElfSectionType {
+    ElfSectionTypeNull,
+    ElfSectionTypeProgBits,
+    ElfSectionTypeSymTab,
+    ElfSectionTypeStrTab,
+    ElfSectionTypeRela,
+    ElfSectionTypeHash,
+    ElfSectionTypeDynamic,
+    ElfSectionTypeNote,
+    ElfSectionTypeNoBits,
+    ElfSectionTypeRel,
+    ElfSectionTypeShLib,
+    ElfSectionTypeDynSym,
+    ElfSectionTypeInitArray,
+    ElfSectionTypeFiniArray,
+    ElfSectionTypePreInitArray,
+    ElfSectionTypeGroup,
+    ElfSectionTypeSymTabShndx,
+    ElfSectionTypeGnuHash,
+    ElfSectionTypeGnuVerDef,
+    ElfSectionTypeGnuVerNeed,
+    ElfSectionTypeGnuVerSym,
+    ElfSectionTypeOther(u32),
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct ElfSectionHeader64 {
+    /// Offset of the name in the section name string table, see *e_shstrndx*
+    pub sh_name: u32,
+    pub _sh_type: u32,
+    pub sh_flags: u64,
+    pub sh_addr: u64,
+    pub sh_offset: u64,
+    pub sh_size: u64,
+    /// For symbol tables this is the index of the string table holding the symbol names
+    pub sh_link: u32,
+    pub sh_info: u32,
+    pub sh_addralign: u64,
+    pub sh_entsize: u64,
+}
+
+impl ElfSectionHeader64 {
+    pub fn sh_type(&self) -> ElfSectionType {
+        match self._sh_type {
+            0 => ElfSectionType::ElfSectionTypeNull,
+            1 => ElfSectionType::ElfSectionTypeProgBits,
+            2 => ElfSectionType::ElfSectionTypeSymTab,
+            3 => ElfSectionType::ElfSectionTypeStrTab,
+            4 => ElfSectionType::ElfSectionTypeRela,
+            5 => ElfSectionType::ElfSectionTypeHash,
+            6 => ElfSectionType::ElfSectionTypeDynamic,
+            7 => ElfSectionType::ElfSectionTypeNote,
+            8 => ElfSectionType::ElfSectionTypeNoBits,
+            9 => ElfSectionType::ElfSectionTypeRel,
+            10 => ElfSectionType::ElfSectionTypeShLib,
+            11 => ElfSectionType::ElfSectionTypeDynSym,
+            14 => ElfSectionType::ElfSectionTypeInitArray,
+            15 => ElfSectionType::ElfSectionTypeFiniArray,
+            16 => ElfSectionType::ElfSectionTypePreInitArray,
+            17 => ElfSectionType::ElfSectionTypeGroup,
+            18 => ElfSectionType::ElfSectionTypeSymTabShndx,
+            0x6ffffff6 => ElfSectionType::ElfSectionTypeGnuHash,
+            0x6ffffffd => ElfSectionType::ElfSectionTypeGnuVerDef,
+            0x6ffffffe => ElfSectionType::ElfSectionTypeGnuVerNeed,
+            0x6fffffff => ElfSectionType::ElfSectionTypeGnuVerSym,
+            other => ElfSectionType::ElfSectionTypeOther(other),
+        }
+    }
+
+    pub fn writable(&self) -> bool {
+        return self.sh_flags & SHF_WRITE != 0;
+    }
+
+    pub fn allocated(&self) -> bool {
+        return self.sh_flags & SHF_ALLOC != 0;
+    }
+
+    pub fn executable(&self) -> bool {
+        return self.sh_flags & SHF_EXECINSTR != 0;
+    }
+
+    /// Looks up the name of this section in the section name string table
+    pub fn name<'a>(&self, section_names: &ElfStringTable<'a>) -> Option<&'a str> {
+        return section_names.get(self.sh_name);
+    }
+}
+
+impl Default for ElfSectionHeader64 {
+    fn default() -> ElfSectionHeader64 {
+        ElfSectionHeader64 {
+            sh_name: 0,
+            _sh_type: 0,
+            sh_flags: 0,
+            sh_addr: 0,
+            sh_offset: 0,
+            sh_size: 0,
+            sh_link: 0,
+            sh_info: 0,
+            sh_addralign: 0,
+            sh_entsize: 0,
+        }
+    }
+}
@@ -0,0 +1,27 @@
+/// A string table section, a run of nul terminated strings referred to by their offset
+#[derive(Clone, Copy)]
+pub struct ElfStringTable<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> ElfStringTable<'a> {
+    pub const fn new(data: &'a [u8]) -> ElfStringTable<'a> {
+        return ElfStringTable { data: data };
+    }
+
+    pub fn size(&self) -> usize {
+        return self.data.len();
+    }
+
+    pub fn as_bytes(&self) -> &'a [u8] {
+        return self.data;
+    }
+
+    /// The string starting at *offset*. Returns None if the offset is outside the table, the
+    /// string is not terminated or it is not valid UTF-8.
+    pub fn get(&self, offset: u32) -> Option<&'a str> {
+        let start = self.data.get(offset as usize..)?;
+        let length = start.iter().position(|&byte| byte == 0)?;
+        return core::str::from_utf8(&start[..length]).ok();
+    }
+}
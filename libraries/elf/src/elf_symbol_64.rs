@@ -0,0 +1,177 @@
+use core::default::Default;
+
+use crate::{ElfStringTable, SHN_UNDEF};
+
+#[derive(PartialEq, Debug)]
+pub enum ElfSymbolBinding {
+    ElfSymbolBindingLocal,
+    ElfSymbolBindingGlobal,
+    ElfSymbolBindingWeak,
+    ElfSymbolBindingGnuUnique,
+    ElfSymbolBindingOther(u8),
+}
+
+#[derive(PartialEq, Debug)]
+pub enum ElfSymbolType {
+    ElfSymbolTypeNoType,
+    ElfSymbolTypeObject,
+    ElfSymbolTypeFunc,
+    ElfSymbolTypeSection,
+    ElfSymbolTypeFile,
+    ElfSymbolTypeCommon,
+    ElfSymbolTypeTls,
+    ElfSymbolTypeGnuIFunc,
+    ElfSymbolTypeOther(u8),
+}
+
+#[derive(PartialEq, Debug)]
+pub enum ElfSymbolVisibility {
+    ElfSymbolVisibilityDefault,
+    ElfSymbolVisibilityInternal,
+    ElfSymbolVisibilityHidden,
+    ElfSymbolVisibilityProtected,
+}
+
+/// An entry in a .symtab or .dynsym section
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct ElfSymbol64 {
+    /// Offset of the name in the string table linked to the symbol table section
+    pub st_name: u32,
+    pub st_info: u8,
+    pub st_other: u8,
+    pub st_shndx: u16,
+    pub st_value: u64,
+    pub st_size: u64,
+}
+
+impl ElfSymbol64 {
+    pub fn binding(&self) -> ElfSymbolBinding {
+        match self.st_info >> 4 {
+            0 => ElfSymbolBinding::ElfSymbolBindingLocal,
+            1 => ElfSymbolBinding::ElfSymbolBindingGlobal,
+            2 => ElfSymbolBinding::ElfSymbolBindingWeak,
+            10 => ElfSymbolBinding::ElfSymbolBindingGnuUnique,
+            other => ElfSymbolBinding::ElfSymbolBindingOther(other),
+        }
+    }
+
+    pub fn symbol_type(&self) -> ElfSymbolType {
+        match self.st_info & 0xF {
+            0 => ElfSymbolType::ElfSymbolTypeNoType,
+            1 => ElfSymbolType::ElfSymbolTypeObject,
+            2 => ElfSymbolType::ElfSymbolTypeFunc,
+            3 => ElfSymbolType::ElfSymbolTypeSection,
+            4 => ElfSymbolType::ElfSymbolTypeFile,
+            5 => ElfSymbolType::ElfSymbolTypeCommon,
+            6 => ElfSymbolType::ElfSymbolTypeTls,
+            10 => ElfSymbolType::ElfSymbolTypeGnuIFunc,
+            other => ElfSymbolType::ElfSymbolTypeOther(other),
+        }
+    }
+
+    pub fn visibility(&self) -> ElfSymbolVisibility {
+        match self.st_other & 0x3 {
+            0 => ElfSymbolVisibility::ElfSymbolVisibilityDefault,
+            1 => ElfSymbolVisibility::ElfSymbolVisibilityInternal,
+            2 => ElfSymbolVisibility::ElfSymbolVisibilityHidden,
+            _ => ElfSymbolVisibility::ElfSymbolVisibilityProtected,
+        }
+    }
+
+    pub fn is_defined(&self) -> bool {
+        return self.st_shndx != SHN_UNDEF;
+    }
+
+    /// True if *address* is inside the symbol. Symbols without a size only contain their
+    /// own address.
+    pub fn contains(&self, address: u64) -> bool {
+        return address == self.st_value || (address > self.st_value && address - self.st_value < self.st_size);
+    }
+
+    pub fn name<'a>(&self, strings: &ElfStringTable<'a>) -> Option<&'a str> {
+        return strings.get(self.st_name);
+    }
+}
+
+impl Default for ElfSymbol64 {
+    fn default() -> ElfSymbol64 {
+        ElfSymbol64 {
+            st_name: 0,
+            st_info: 0,
+            st_other: 0,
+            st_shndx: 0,
+            st_value: 0,
+            st_size: 0,
+        }
+    }
+}
+
+/// The contents of a symbol table section. Entries are read out by value as the section data
+/// need not be aligned.
+#[derive(Clone, Copy)]
+pub struct ElfSymbolTable<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> ElfSymbolTable<'a> {
+    pub const fn new(data: &'a [u8]) -> ElfSymbolTable<'a> {
+        return ElfSymbolTable { data: data };
+    }
+
+    pub fn len(&self) -> usize {
+        return self.data.len() / core::mem::size_of::<ElfSymbol64>();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.len() == 0;
+    }
+
+    pub fn as_bytes(&self) -> &'a [u8] {
+        return self.data;
+    }
+
+    pub fn get(&self, index: usize) -> Option<ElfSymbol64> {
+        if index >= self.len() {
+            return None;
+        }
+
+        //This is safe as the entry was checked to be inside the slice
+        let entry = unsafe { self.data.as_ptr().add(index * core::mem::size_of::<ElfSymbol64>()) as *const ElfSymbol64 };
+        return Some(unsafe { core::ptr::read_unaligned(entry) });
+    }
+
+    pub fn iter(&self) -> ElfSymbolTableIterator<'a> {
+        return ElfSymbolTableIterator {
+            symbol_table: *self,
+            current_index: 0,
+        };
+    }
+
+    /// The defined function or object symbol that *address* is inside, if there is one
+    pub fn find_by_address(&self, address: u64) -> Option<ElfSymbol64> {
+        return self.iter().find(|symbol| {
+            let symbol_type = symbol.symbol_type();
+            symbol.is_defined()
+                && (symbol_type == ElfSymbolType::ElfSymbolTypeFunc || symbol_type == ElfSymbolType::ElfSymbolTypeObject)
+                && symbol.contains(address)
+        });
+    }
+}
+
+pub struct ElfSymbolTableIterator<'a> {
+    symbol_table: ElfSymbolTable<'a>,
+    current_index: usize,
+}
+
+impl<'a> Iterator for ElfSymbolTableIterator<'a> {
+    type Item = ElfSymbol64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let output = self.symbol_table.get(self.current_index);
+        if output.is_some() {
+            self.current_index += 1;
+        }
+        return output;
+    }
+}
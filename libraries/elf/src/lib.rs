@@ -1,9 +1,17 @@
 #![no_std]
 
+mod elf_file;
 mod elf_header_64;
 mod elf_header_common;
 mod elf_physical_header_64;
+mod elf_section_header_64;
+mod elf_string_table;
+mod elf_symbol_64;
 
+pub use elf_file::*;
 pub use elf_header_64::*;
 pub use elf_header_common::*;
 pub use elf_physical_header_64::*;
+pub use elf_section_header_64::*;
+pub use elf_string_table::*;
+pub use elf_symbol_64::*;
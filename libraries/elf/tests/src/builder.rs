@@ -0,0 +1,39 @@
+#![cfg(test)]
+
+//Little endian writers for building ELF structures byte by byte
+
+pub fn push_u16(data: &mut Vec<u8>, value: u16) {
+    data.extend_from_slice(&value.to_le_bytes());
+}
+
+pub fn push_u32(data: &mut Vec<u8>, value: u32) {
+    data.extend_from_slice(&value.to_le_bytes());
+}
+
+pub fn push_u64(data: &mut Vec<u8>, value: u64) {
+    data.extend_from_slice(&value.to_le_bytes());
+}
+
+/// Appends a 64 byte section header aligned to 8 bytes
+pub fn push_section(data: &mut Vec<u8>, name: u32, section_type: u32, flags: u64, address: u64, offset: u64, size: u64, link: u32, entry_size: u64) {
+    push_u32(data, name);
+    push_u32(data, section_type);
+    push_u64(data, flags);
+    push_u64(data, address);
+    push_u64(data, offset);
+    push_u64(data, size);
+    push_u32(data, link);
+    push_u32(data, 0);
+    push_u64(data, 8);
+    push_u64(data, entry_size);
+}
+
+/// Appends a 24 byte symbol table entry
+pub fn push_symbol(data: &mut Vec<u8>, name: u32, info: u8, other: u8, section: u16, value: u64, size: u64) {
+    push_u32(data, name);
+    data.push(info);
+    data.push(other);
+    push_u16(data, section);
+    push_u64(data, value);
+    push_u64(data, size);
+}
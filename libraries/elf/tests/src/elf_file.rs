@@ -0,0 +1,178 @@
+#[cfg(test)]
+mod tests {
+    use elf::{ElfClass, ElfError, ElfFile, ElfPhysicalType, ElfSectionType, ElfSymbolBinding, ElfSymbolType, ElfSymbolVisibility};
+
+    use crate::builder::{push_section, push_symbol, push_u16, push_u32, push_u64};
+
+    const TEXT_ADDRESS: u64 = 0xFFFF_FFFF_8000_0000;
+    const TEXT: [u8; 4] = [0x90, 0x90, 0xC3, 0x00];
+    const STRINGS: &[u8] = b"\0_start\0data_object\0";
+    const SECTION_NAMES: &[u8] = b"\0.text\0.symtab\0.strtab\0.shstrtab\0";
+
+    /// A small x86_64 executable with one RX PT_LOAD segment holding .text, a symbol table
+    /// with a function and an object, and section names
+    fn build() -> Vec<u8> {
+        let text_offset: u64 = 64 + 56;
+        let strings_offset = text_offset + TEXT.len() as u64;
+        let section_names_offset = strings_offset + STRINGS.len() as u64;
+        let symbols_offset = (section_names_offset + SECTION_NAMES.len() as u64 + 7) & !7;
+        let section_headers_offset = symbols_offset + 3 * 24;
+
+        let mut data = Vec::new();
+        data.extend_from_slice(&[0x7F, b'E', b'L', b'F', 2, 1, 1, 0]);
+        data.resize(16, 0);
+        push_u16(&mut data, 2);
+        push_u16(&mut data, 62);
+        push_u32(&mut data, 1);
+        push_u64(&mut data, TEXT_ADDRESS);
+        push_u64(&mut data, 64);
+        push_u64(&mut data, section_headers_offset);
+        push_u32(&mut data, 0);
+        push_u16(&mut data, 64);
+        push_u16(&mut data, 56);
+        push_u16(&mut data, 1);
+        push_u16(&mut data, 64);
+        push_u16(&mut data, 5);
+        push_u16(&mut data, 4);
+
+        push_u32(&mut data, 1);
+        push_u32(&mut data, 0x5);
+        push_u64(&mut data, text_offset);
+        push_u64(&mut data, TEXT_ADDRESS);
+        push_u64(&mut data, TEXT_ADDRESS);
+        push_u64(&mut data, TEXT.len() as u64);
+        push_u64(&mut data, 0x1000);
+        push_u64(&mut data, 0x1000);
+
+        data.extend_from_slice(&TEXT);
+        data.extend_from_slice(STRINGS);
+        data.extend_from_slice(SECTION_NAMES);
+        data.resize(symbols_offset as usize, 0);
+        push_symbol(&mut data, 0, 0, 0, 0, 0, 0);
+        push_symbol(&mut data, 1, 0x12, 0, 1, TEXT_ADDRESS, 3);
+        push_symbol(&mut data, 8, 0x01, 2, 1, TEXT_ADDRESS + 3, 1);
+
+        push_section(&mut data, 0, 0, 0, 0, 0, 0, 0, 0);
+        push_section(&mut data, 1, 1, 0x6, TEXT_ADDRESS, text_offset, TEXT.len() as u64, 0, 0);
+        push_section(&mut data, 7, 2, 0, 0, symbols_offset, 3 * 24, 3, 24);
+        push_section(&mut data, 15, 3, 0, 0, strings_offset, STRINGS.len() as u64, 0, 0);
+        push_section(&mut data, 23, 3, 0, 0, section_names_offset, SECTION_NAMES.len() as u64, 0, 0);
+        return data;
+    }
+
+    #[test]
+    fn test_program_headers() {
+        let data = build();
+        let file = ElfFile::parse(&data).unwrap();
+        assert_eq!(TEXT_ADDRESS, file.header().e_entry);
+        assert_eq!(1, file.program_headers().count());
+
+        let text = file.program_header(0).unwrap();
+        assert_eq!(ElfPhysicalType::ElfPhysicalTypeLoad, text.p_type());
+        assert!(text.readable() && text.executable() && !text.writable());
+        assert_eq!(&TEXT, file.segment_data(&text).unwrap());
+        assert!(file.program_header(1).is_none());
+    }
+
+    #[test]
+    fn test_sections() {
+        let data = build();
+        let file = ElfFile::parse(&data).unwrap();
+        let names: Vec<&str> = file.section_headers().map(|header| file.section_name(&header).unwrap()).collect();
+        assert_eq!(vec!["", ".text", ".symtab", ".strtab", ".shstrtab"], names);
+
+        let text = file.find_section(".text").unwrap().unwrap();
+        assert_eq!(ElfSectionType::ElfSectionTypeProgBits, text.sh_type());
+        assert!(text.allocated() && text.executable() && !text.writable());
+        assert_eq!(&TEXT, file.section_data(&text).unwrap());
+        assert!(file.find_section(".data").unwrap().is_none());
+
+        assert_eq!(Err(ElfError::SectionIndexOutOfRange(5)), file.section_header(5).map(|_| ()));
+        assert_eq!(Err(ElfError::NotAStringTable(1)), file.string_table(1).map(|_| ()));
+    }
+
+    #[test]
+    fn test_symbols() {
+        let data = build();
+        let file = ElfFile::parse(&data).unwrap();
+        let (symbols, strings) = file.symbol_table().unwrap().unwrap();
+        assert_eq!(3, symbols.len());
+
+        let start = symbols.get(1).unwrap();
+        assert_eq!(Some("_start"), start.name(&strings));
+        assert_eq!(ElfSymbolBinding::ElfSymbolBindingGlobal, start.binding());
+        assert_eq!(ElfSymbolType::ElfSymbolTypeFunc, start.symbol_type());
+        assert_eq!(ElfSymbolVisibility::ElfSymbolVisibilityDefault, start.visibility());
+
+        let object = symbols.get(2).unwrap();
+        assert_eq!(Some("data_object"), object.name(&strings));
+        assert_eq!(ElfSymbolBinding::ElfSymbolBindingLocal, object.binding());
+        assert_eq!(ElfSymbolType::ElfSymbolTypeObject, object.symbol_type());
+        assert_eq!(ElfSymbolVisibility::ElfSymbolVisibilityHidden, object.visibility());
+
+        assert_eq!(Some("_start"), symbols.find_by_address(TEXT_ADDRESS + 2).and_then(|symbol| symbol.name(&strings)));
+        assert_eq!(Some("data_object"), symbols.find_by_address(TEXT_ADDRESS + 3).and_then(|symbol| symbol.name(&strings)));
+        assert!(symbols.find_by_address(TEXT_ADDRESS + 4).is_none());
+        assert!(strings.get(STRINGS.len() as u32).is_none());
+    }
+
+    #[test]
+    fn test_invalid_headers() {
+        let data = build();
+        assert_eq!(Err(ElfError::Truncated { offset: 0, size: 24 }), ElfFile::parse(&data[..10]).map(|_| ()));
+
+        let mut bad_magic = data.clone();
+        bad_magic[1] = b'X';
+        assert_eq!(Err(ElfError::InvalidMagic), ElfFile::parse(&bad_magic).map(|_| ()));
+
+        let mut class_32 = data.clone();
+        class_32[4] = 1;
+        assert_eq!(Err(ElfError::UnsupportedClass(ElfClass::ElfClass32)), ElfFile::parse(&class_32).map(|_| ()));
+
+        let mut small_program_headers = data.clone();
+        small_program_headers[54] = 32;
+        assert_eq!(Err(ElfError::InvalidProgramHeaderSize(32)), ElfFile::parse(&small_program_headers).map(|_| ()));
+
+        //Section headers are last so dropping the final byte cuts off the table
+        assert_eq!(Err(ElfError::Truncated { offset: data.len() as u64 - 5 * 64, size: 5 * 64 }), ElfFile::parse(&data[..data.len() - 1]).map(|_| ()));
+
+        let mut huge_segment = data.clone();
+        huge_segment[64 + 32..64 + 40].copy_from_slice(&u64::MAX.to_le_bytes());
+        let file = ElfFile::parse(&huge_segment).unwrap();
+        assert!(matches!(file.segment_data(&file.program_header(0).unwrap()), Err(ElfError::Truncated { .. })));
+    }
+
+    /// Every prefix and every single byte change of a valid file must give data or an
+    /// error rather than a panic
+    #[test]
+    fn test_corrupt_files_do_not_panic() {
+        let data = build();
+        let mut inputs: Vec<Vec<u8>> = (0..data.len()).map(|length| data[..length].to_vec()).collect();
+        for index in 0..data.len() {
+            for value in [0x00, 0x7F, 0xFF] {
+                let mut corrupt = data.clone();
+                corrupt[index] = value;
+                inputs.push(corrupt);
+            }
+        }
+
+        for input in &inputs {
+            if let Ok(file) = ElfFile::parse(input) {
+                for header in file.program_headers() {
+                    let _ = file.segment_data(&header);
+                    let _ = file.segment_notes(&header).map(|notes| notes.count());
+                }
+                for header in file.section_headers() {
+                    let _ = file.section_name(&header);
+                    let _ = file.section_data(&header);
+                }
+                if let Ok(Some((symbols, strings))) = file.symbol_table() {
+                    for symbol in symbols.iter() {
+                        let _ = symbol.name(&strings);
+                    }
+                    let _ = symbols.find_by_address(TEXT_ADDRESS);
+                }
+            }
+        }
+    }
+}
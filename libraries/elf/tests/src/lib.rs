@@ -0,0 +1,4 @@
+mod builder;
+mod elf_file;
+mod notes;
+mod symbols;
@@ -0,0 +1,50 @@
+#[cfg(test)]
+mod tests {
+    use elf::{ElfError, ElfNote, ElfNoteIterator};
+
+    fn note(name: &[u8], note_type: u32, descriptor: &[u8], align: usize) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&(name.len() as u32).to_le_bytes());
+        data.extend_from_slice(&(descriptor.len() as u32).to_le_bytes());
+        data.extend_from_slice(&note_type.to_le_bytes());
+        data.extend_from_slice(name);
+        data.resize((data.len() + align - 1) / align * align, 0);
+        data.extend_from_slice(descriptor);
+        data.resize((data.len() + align - 1) / align * align, 0);
+        return data;
+    }
+
+    #[test]
+    fn test_read_notes() {
+        let mut data = note(b"GNU\0", 3, &[1, 2, 3, 4, 5], 4);
+        data.extend(note(b"Xen\0", 1, &[], 4));
+        let notes: Vec<ElfNote> = ElfNoteIterator::new(&data, 4).map(|note| note.unwrap()).collect();
+        assert_eq!(vec![
+            ElfNote { name: b"GNU", note_type: 3, descriptor: &[1, 2, 3, 4, 5] },
+            ElfNote { name: b"Xen", note_type: 1, descriptor: &[] },
+        ], notes);
+    }
+
+    #[test]
+    fn test_eight_byte_aligned_notes() {
+        let mut data = note(b"GNU\0", 5, &[0xAA; 4], 8);
+        data.extend(note(b"GNU\0", 3, &[0xBB; 8], 8));
+        let notes: Vec<ElfNote> = ElfNoteIterator::new(&data, 8).map(|note| note.unwrap()).collect();
+        assert_eq!(2, notes.len());
+        assert_eq!(&[0xAA; 4], notes[0].descriptor);
+        assert_eq!(&[0xBB; 8], notes[1].descriptor);
+    }
+
+    #[test]
+    fn test_truncated_note() {
+        let mut data = note(b"GNU\0", 3, &[1, 2, 3, 4], 4);
+        let second = data.len();
+        data.extend(note(b"GNU\0", 3, &[1, 2, 3, 4], 4));
+        data.truncate(data.len() - 2);
+
+        let mut notes = ElfNoteIterator::new(&data, 4);
+        assert!(notes.next().unwrap().is_ok());
+        assert_eq!(Some(Err(ElfError::TruncatedNote(second))), notes.next());
+        assert_eq!(None, notes.next());
+    }
+}
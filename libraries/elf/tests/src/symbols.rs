@@ -0,0 +1,110 @@
+#[cfg(test)]
+mod tests {
+    use elf::{ElfStringTable, ElfSymbol64, ElfSymbolBinding, ElfSymbolTable, ElfSymbolType, ElfSymbolVisibility};
+
+    use crate::builder::push_symbol;
+
+    const STT_FUNC: u8 = 2;
+    const STT_OBJECT: u8 = 1;
+    const STT_SECTION: u8 = 3;
+
+    fn symbol(info: u8, other: u8) -> ElfSymbol64 {
+        return ElfSymbol64 { st_info: info, st_other: other, ..Default::default() };
+    }
+
+    #[test]
+    fn test_binding() {
+        assert_eq!(ElfSymbolBinding::ElfSymbolBindingLocal, symbol(0x02, 0).binding());
+        assert_eq!(ElfSymbolBinding::ElfSymbolBindingGlobal, symbol(0x12, 0).binding());
+        assert_eq!(ElfSymbolBinding::ElfSymbolBindingWeak, symbol(0x22, 0).binding());
+        assert_eq!(ElfSymbolBinding::ElfSymbolBindingGnuUnique, symbol(0xA1, 0).binding());
+        assert_eq!(ElfSymbolBinding::ElfSymbolBindingOther(3), symbol(0x32, 0).binding());
+        assert_eq!(ElfSymbolBinding::ElfSymbolBindingOther(15), symbol(0xF2, 0).binding());
+    }
+
+    #[test]
+    fn test_symbol_type() {
+        let expected = [
+            (0, ElfSymbolType::ElfSymbolTypeNoType),
+            (1, ElfSymbolType::ElfSymbolTypeObject),
+            (2, ElfSymbolType::ElfSymbolTypeFunc),
+            (3, ElfSymbolType::ElfSymbolTypeSection),
+            (4, ElfSymbolType::ElfSymbolTypeFile),
+            (5, ElfSymbolType::ElfSymbolTypeCommon),
+            (6, ElfSymbolType::ElfSymbolTypeTls),
+            (10, ElfSymbolType::ElfSymbolTypeGnuIFunc),
+            (7, ElfSymbolType::ElfSymbolTypeOther(7)),
+            (15, ElfSymbolType::ElfSymbolTypeOther(15)),
+        ];
+
+        for (value, symbol_type) in expected {
+            //The binding in the high nibble must not change the type
+            assert_eq!(symbol_type, symbol(0x10 | value, 0).symbol_type());
+        }
+    }
+
+    #[test]
+    fn test_visibility_ignores_other_bits() {
+        assert_eq!(ElfSymbolVisibility::ElfSymbolVisibilityDefault, symbol(0, 0).visibility());
+        assert_eq!(ElfSymbolVisibility::ElfSymbolVisibilityInternal, symbol(0, 1).visibility());
+        assert_eq!(ElfSymbolVisibility::ElfSymbolVisibilityHidden, symbol(0, 0xFA).visibility());
+        assert_eq!(ElfSymbolVisibility::ElfSymbolVisibilityProtected, symbol(0, 3).visibility());
+    }
+
+    #[test]
+    fn test_find_by_address() {
+        let mut data = Vec::new();
+        push_symbol(&mut data, 0, 0, 0, 0, 0, 0);
+        //A section symbol and an undefined function covering the same range are skipped
+        push_symbol(&mut data, 1, STT_SECTION, 0, 1, 0x1000, 0x100);
+        push_symbol(&mut data, 2, 0x10 | STT_FUNC, 0, 0, 0x1000, 0x100);
+        push_symbol(&mut data, 3, 0x10 | STT_FUNC, 0, 1, 0x1010, 0x10);
+        push_symbol(&mut data, 4, 0x10 | STT_OBJECT, 0, 1, 0x1040, 0);
+        push_symbol(&mut data, 5, 0x10 | STT_FUNC, 0, 1, u64::MAX - 0xF, 0x10);
+        let symbols = ElfSymbolTable::new(&data);
+        let found = |address: u64| symbols.find_by_address(address).map(|symbol| symbol.st_name);
+
+        assert_eq!(None, found(0x1000));
+        assert_eq!(Some(3), found(0x1010));
+        assert_eq!(Some(3), found(0x101F));
+        assert_eq!(None, found(0x1020));
+        assert_eq!(Some(4), found(0x1040));
+        assert_eq!(None, found(0x1041));
+        assert_eq!(Some(5), found(u64::MAX));
+        assert_eq!(true, ElfSymbolTable::new(&[]).find_by_address(0).is_none());
+    }
+
+    #[test]
+    fn test_symbol_table_ignores_partial_entry() {
+        let mut data = Vec::new();
+        push_symbol(&mut data, 1, 0x10 | STT_FUNC, 0, 1, 0x1000, 0x10);
+        data.extend_from_slice(&[0; 23]);
+        let symbols = ElfSymbolTable::new(&data);
+        assert_eq!(1, symbols.len());
+        assert_eq!(true, symbols.get(1).is_none());
+        assert_eq!(1, symbols.iter().count());
+    }
+
+    #[test]
+    fn test_string_table_bounds() {
+        let strings = ElfStringTable::new(b"\0main\0data");
+        assert_eq!(Some(""), strings.get(0));
+        assert_eq!(Some("main"), strings.get(1));
+        //Offsets into the middle of a string are allowed and give its tail
+        assert_eq!(Some("ain"), strings.get(2));
+        assert_eq!(Some(""), strings.get(5));
+        //The last string is missing its nul
+        assert_eq!(None, strings.get(6));
+        assert_eq!(None, strings.get(10));
+        assert_eq!(None, strings.get(11));
+        assert_eq!(None, strings.get(u32::MAX));
+    }
+
+    #[test]
+    fn test_string_table_rejects_invalid_utf8() {
+        let strings = ElfStringTable::new(b"ok\0\xFF\xFE\0");
+        assert_eq!(Some("ok"), strings.get(0));
+        assert_eq!(None, strings.get(3));
+        assert_eq!(None, ElfStringTable::new(&[]).get(0));
+    }
+}
@@ -0,0 +1,92 @@
+use crate::Colour;
+
+/// Anything that can be drawn onto a canvas with *Canvas::blit*
+pub trait Bitmap {
+    fn width(&self) -> u32;
+    fn height(&self) -> u32;
+
+    /// The colour at (*x*, *y*). Callers keep within *width* and *height*.
+    fn pixel(&self, x: u32, y: u32) -> Colour;
+}
+
+/// A bitmap held as 0xAARRGGBB values, one per pixel, row by row
+pub struct RgbaBitmap<'a> {
+    pixels: &'a [u32],
+    width: u32,
+    height: u32,
+}
+
+impl<'a> RgbaBitmap<'a> {
+    /// Returns None if *pixels* holds fewer than *width* x *height* values
+    pub fn new(pixels: &'a [u32], width: u32, height: u32) -> Option<RgbaBitmap<'a>> {
+        if (pixels.len() as u64) < width as u64 * height as u64 {
+            return None;
+        }
+
+        return Some(RgbaBitmap {
+            pixels: pixels,
+            width: width,
+            height: height,
+        });
+    }
+}
+
+impl<'a> Bitmap for RgbaBitmap<'a> {
+    fn width(&self) -> u32 {
+        return self.width;
+    }
+
+    fn height(&self) -> u32 {
+        return self.height;
+    }
+
+    fn pixel(&self, x: u32, y: u32) -> Colour {
+        return Colour::argb(self.pixels[y as usize * self.width as usize + x as usize]);
+    }
+}
+
+/// Another bitmap turned clockwise by a multiple of 90 degrees
+pub struct Rotated<'a, B: Bitmap> {
+    bitmap: &'a B,
+    quarter_turns: u16,
+}
+
+impl<'a, B: Bitmap> Rotated<'a, B> {
+    /// Returns None if *clockwise_degrees* is not 0, 90, 180 or 270
+    pub fn new(bitmap: &'a B, clockwise_degrees: u16) -> Option<Rotated<'a, B>> {
+        if clockwise_degrees % 90 != 0 || clockwise_degrees >= 360 {
+            return None;
+        }
+
+        return Some(Rotated {
+            bitmap: bitmap,
+            quarter_turns: clockwise_degrees / 90,
+        });
+    }
+}
+
+impl<'a, B: Bitmap> Bitmap for Rotated<'a, B> {
+    fn width(&self) -> u32 {
+        if self.quarter_turns % 2 == 1 {
+            return self.bitmap.height();
+        }
+        return self.bitmap.width();
+    }
+
+    fn height(&self) -> u32 {
+        if self.quarter_turns % 2 == 1 {
+            return self.bitmap.width();
+        }
+        return self.bitmap.height();
+    }
+
+    fn pixel(&self, x: u32, y: u32) -> Colour {
+        let (width, height) = (self.bitmap.width(), self.bitmap.height());
+        return match self.quarter_turns {
+            1 => self.bitmap.pixel(y, height - 1 - x),
+            2 => self.bitmap.pixel(width - 1 - x, height - 1 - y),
+            3 => self.bitmap.pixel(width - 1 - y, x),
+            _ => self.bitmap.pixel(x, y),
+        };
+    }
+}
@@ -0,0 +1,265 @@
+use bootinfo::PixelFormat;
+
+use crate::{Bitmap, Colour, Rect};
+
+/// A surface to draw on. Pixels are held packed in a framebuffer pixel format so they can be
+/// copied to the screen as they are. Everything drawn is clipped to the clip rectangle and
+/// the area touched is recorded as dirty.
+pub struct Canvas<'a> {
+    pixels: &'a mut [u32],
+    width: u32,
+    height: u32,
+    stride: usize,
+    format: PixelFormat,
+    clip: Rect,
+    dirty: Rect,
+}
+
+impl<'a> Canvas<'a> {
+    /// *stride* is the number of pixels from the start of one row to the start of the next.
+    /// Returns None if *pixels* is too short or the format has no pixel layout.
+    pub fn new(pixels: &'a mut [u32], width: u32, height: u32, stride: usize, format: PixelFormat) -> Option<Canvas<'a>> {
+        if format == PixelFormat::BltOnly || stride < width as usize || width > i32::MAX as u32 || height > i32::MAX as u32 {
+            return None;
+        }
+
+        let needed = match height {
+            0 => 0,
+            _ => (height as usize - 1).checked_mul(stride)?.checked_add(width as usize)?,
+        };
+        if pixels.len() < needed {
+            return None;
+        }
+
+        let bounds = Rect::new(0, 0, width, height);
+        return Some(Canvas {
+            pixels: pixels,
+            width: width,
+            height: height,
+            stride: stride,
+            format: format,
+            clip: bounds,
+            dirty: Rect::EMPTY,
+        });
+    }
+
+    pub fn width(&self) -> u32 {
+        return self.width;
+    }
+
+    pub fn height(&self) -> u32 {
+        return self.height;
+    }
+
+    pub fn stride(&self) -> usize {
+        return self.stride;
+    }
+
+    pub fn format(&self) -> PixelFormat {
+        return self.format;
+    }
+
+    pub fn bounds(&self) -> Rect {
+        return Rect::new(0, 0, self.width, self.height);
+    }
+
+    /// The packed pixel values, *stride* to a row
+    pub fn pixels(&self) -> &[u32] {
+        return self.pixels;
+    }
+
+    pub fn clip(&self) -> Rect {
+        return self.clip;
+    }
+
+    /// Limits drawing to *clip*, or the part of it that is on the canvas
+    pub fn set_clip(&mut self, clip: Rect) {
+        self.clip = clip.intersection(&self.bounds());
+    }
+
+    pub fn reset_clip(&mut self) {
+        self.clip = self.bounds();
+    }
+
+    /// The area drawn to since the dirty rectangle was last taken
+    pub fn dirty(&self) -> Rect {
+        return self.dirty;
+    }
+
+    pub fn take_dirty(&mut self) -> Rect {
+        let dirty = self.dirty;
+        self.dirty = Rect::EMPTY;
+        return dirty;
+    }
+
+    /// Marks *area* as changed, for callers that write to the pixels some other way
+    pub fn mark_dirty(&mut self, area: Rect) {
+        self.dirty = self.dirty.union(&area.intersection(&self.bounds()));
+    }
+
+    /// The colour at (*x*, *y*), or None if it is off the canvas
+    pub fn pixel(&self, x: i32, y: i32) -> Option<Colour> {
+        if !self.bounds().contains(x, y) {
+            return None;
+        }
+
+        let (red, green, blue) = self.format.unpack(self.pixels[self.index(x, y)]);
+        return Some(Colour::rgba(red, green, blue, 0xFF));
+    }
+
+    pub fn set_pixel(&mut self, x: i32, y: i32, colour: Colour) {
+        if self.clip.contains(x, y) {
+            self.plot(self.index(x, y), colour);
+            self.dirty = self.dirty.union(&Rect::new(x, y, 1, 1));
+        }
+    }
+
+    /// Fills everything inside the clip rectangle
+    pub fn clear(&mut self, colour: Colour) {
+        self.fill_rect(self.clip, colour);
+    }
+
+    pub fn fill_rect(&mut self, rect: Rect, colour: Colour) {
+        let area = rect.intersection(&self.clip);
+        if area.is_empty() || colour.is_transparent() {
+            return;
+        }
+
+        for y in area.y..area.bottom() {
+            let start = self.index(area.x, y);
+            let row = &mut self.pixels[start..start + area.width as usize];
+            if colour.is_opaque() {
+                row.fill(self.format.pack(colour.red, colour.green, colour.blue));
+            } else {
+                for pixel in row.iter_mut() {
+                    *pixel = blend(self.format, *pixel, colour);
+                }
+            }
+        }
+        self.dirty = self.dirty.union(&area);
+    }
+
+    /// Draws the one pixel wide outline of *rect*
+    pub fn draw_rect(&mut self, rect: Rect, colour: Colour) {
+        if rect.is_empty() {
+            return;
+        }
+
+        self.fill_rect(Rect::new(rect.x, rect.y, rect.width, 1), colour);
+        if rect.height > 1 {
+            self.fill_rect(Rect::new(rect.x, rect.bottom() - 1, rect.width, 1), colour);
+        }
+        if rect.height > 2 {
+            self.fill_rect(Rect::new(rect.x, rect.y + 1, 1, rect.height - 2), colour);
+            if rect.width > 1 {
+                self.fill_rect(Rect::new(rect.right() - 1, rect.y + 1, 1, rect.height - 2), colour);
+            }
+        }
+    }
+
+    /// Draws a line from (*x0*, *y0*) to (*x1*, *y1*), both ends included
+    pub fn draw_line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, colour: Colour) {
+        if y0 == y1 || x0 == x1 {
+            let bounds = Rect::new(x0.min(x1), y0.min(y1), x0.abs_diff(x1).saturating_add(1), y0.abs_diff(y1).saturating_add(1));
+            self.fill_rect(bounds, colour);
+            return;
+        }
+
+        //This gives the same pixels as Bresenham's algorithm. Each step along the longer axis
+        //moves one pixel and the shorter axis follows the ideal line rounded half up, so the
+        //steps can start and stop at the clip rectangle instead of walking the whole line.
+        let (dx, dy) = (x1 as i64 - x0 as i64, y1 as i64 - y0 as i64);
+        let x_major = dx.abs() >= dy.abs();
+        let (major_start, major_delta, minor_start, minor_delta) = if x_major { (x0 as i64, dx, y0 as i64, dy) } else { (y0 as i64, dy, x0 as i64, dx) };
+        let (clip_start, clip_end) = if x_major {
+            (self.clip.x as i64, self.clip.x as i64 + self.clip.width as i64)
+        } else {
+            (self.clip.y as i64, self.clip.y as i64 + self.clip.height as i64)
+        };
+
+        let (major_length, minor_length) = (major_delta.abs(), minor_delta.abs());
+        let (first_step, last_step) = if major_delta > 0 {
+            (clip_start - major_start, clip_end - 1 - major_start)
+        } else {
+            (major_start - (clip_end - 1), major_start - clip_start)
+        };
+        for step in first_step.max(0)..=last_step.min(major_length) {
+            let minor_step = ((2 * step as i128 * minor_length as i128 + major_length as i128) / (2 * major_length as i128)) as i64;
+            let major = major_start + step * major_delta.signum();
+            let minor = minor_start + minor_step * minor_delta.signum();
+            let (x, y) = if x_major { (major as i32, minor as i32) } else { (minor as i32, major as i32) };
+            if self.clip.contains(x, y) {
+                self.plot(self.index(x, y), colour);
+            }
+        }
+
+        let bounds = Rect::new(x0.min(x1), y0.min(y1), x0.abs_diff(x1).saturating_add(1), y0.abs_diff(y1).saturating_add(1));
+        self.dirty = self.dirty.union(&bounds.intersection(&self.clip));
+    }
+
+    /// Draws *bitmap* with its top left corner at (*x*, *y*), blending by its alpha
+    pub fn blit<B: Bitmap + ?Sized>(&mut self, x: i32, y: i32, bitmap: &B) {
+        let area = Rect::new(x, y, bitmap.width(), bitmap.height()).intersection(&self.clip);
+        if area.is_empty() {
+            return;
+        }
+
+        for dest_y in area.y..area.bottom() {
+            let start = self.index(area.x, dest_y);
+            for dest_x in area.x..area.right() {
+                let colour = bitmap.pixel(dest_x.abs_diff(x), dest_y.abs_diff(y));
+                self.plot(start + dest_x.abs_diff(area.x) as usize, colour);
+            }
+        }
+        self.dirty = self.dirty.union(&area);
+    }
+
+    /// Draws a one bit per pixel glyph with its top left corner at (*x*, *y*). Rows are
+    /// padded to whole bytes with the most significant bit on the left, as in PSF fonts.
+    /// Clear bits are filled with *background*, or left alone if there is none.
+    pub fn draw_glyph(&mut self, x: i32, y: i32, glyph: &[u8], width: u32, height: u32, foreground: Colour, background: Option<Colour>) {
+        let bytes_per_row = (width as usize + 7) / 8;
+        if glyph.len() < bytes_per_row * height as usize {
+            return;
+        }
+
+        let area = Rect::new(x, y, width, height).intersection(&self.clip);
+        if area.is_empty() {
+            return;
+        }
+
+        for dest_y in area.y..area.bottom() {
+            let row = &glyph[dest_y.abs_diff(y) as usize * bytes_per_row..];
+            let start = self.index(area.x, dest_y);
+            for dest_x in area.x..area.right() {
+                let column = dest_x.abs_diff(x) as usize;
+                if row[column / 8] & (0x80 >> (column % 8)) != 0 {
+                    self.plot(start + dest_x.abs_diff(area.x) as usize, foreground);
+                } else if let Some(background) = background {
+                    self.plot(start + dest_x.abs_diff(area.x) as usize, background);
+                }
+            }
+        }
+        self.dirty = self.dirty.union(&area);
+    }
+
+    /// The index of (*x*, *y*). Callers have already checked it is on the canvas.
+    fn index(&self, x: i32, y: i32) -> usize {
+        return y as usize * self.stride + x as usize;
+    }
+
+    fn plot(&mut self, index: usize, colour: Colour) {
+        if colour.is_opaque() {
+            self.pixels[index] = self.format.pack(colour.red, colour.green, colour.blue);
+        } else if !colour.is_transparent() {
+            self.pixels[index] = blend(self.format, self.pixels[index], colour);
+        }
+    }
+}
+
+/// Draws *colour* over the packed pixel *pixel*
+fn blend(format: PixelFormat, pixel: u32, colour: Colour) -> u32 {
+    let (red, green, blue) = format.unpack(pixel);
+    let blended = colour.over(Colour::rgba(red, green, blue, 0xFF));
+    return format.pack(blended.red, blended.green, blended.blue);
+}
@@ -0,0 +1,58 @@
+/// An 8 bit per channel colour with straight (not premultiplied) alpha
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Colour {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+    pub alpha: u8,
+}
+
+impl Colour {
+    pub const BLACK: Colour = Colour::rgb(0x000000);
+    pub const WHITE: Colour = Colour::rgb(0xFFFFFF);
+    pub const TRANSPARENT: Colour = Colour::rgba(0, 0, 0, 0);
+
+    /// An opaque colour written as 0x00RRGGBB
+    pub const fn rgb(rgb: u32) -> Colour {
+        return Colour::rgba((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8, 0xFF);
+    }
+
+    /// A colour written as 0xAARRGGBB
+    pub const fn argb(argb: u32) -> Colour {
+        return Colour::rgba((argb >> 16) as u8, (argb >> 8) as u8, argb as u8, (argb >> 24) as u8);
+    }
+
+    pub const fn rgba(red: u8, green: u8, blue: u8, alpha: u8) -> Colour {
+        return Colour {
+            red: red,
+            green: green,
+            blue: blue,
+            alpha: alpha,
+        };
+    }
+
+    pub const fn is_opaque(&self) -> bool {
+        return self.alpha == 0xFF;
+    }
+
+    pub const fn is_transparent(&self) -> bool {
+        return self.alpha == 0;
+    }
+
+    /// The colour as 0xAARRGGBB
+    pub const fn to_argb(&self) -> u32 {
+        return (self.alpha as u32) << 24 | (self.red as u32) << 16 | (self.green as u32) << 8 | self.blue as u32;
+    }
+
+    /// Draws *self* over an opaque *background* and returns the opaque result
+    pub fn over(&self, background: Colour) -> Colour {
+        let alpha = self.alpha as u32;
+        let blend = |foreground: u8, background: u8| -> u8 {
+            //Divide by 255 with rounding without a division
+            let value = foreground as u32 * alpha + background as u32 * (255 - alpha) + 128;
+            return ((value + (value >> 8)) >> 8) as u8;
+        };
+
+        return Colour::rgba(blend(self.red, background.red), blend(self.green, background.green), blend(self.blue, background.blue), 0xFF);
+    }
+}
@@ -0,0 +1,90 @@
+use bootinfo::FrameBuffer;
+
+use crate::{Canvas, Rect};
+
+/// A canvas drawn off screen and copied to the framebuffer when a frame is finished, so
+/// the screen never shows half drawn frames. Only the dirty part of the canvas is copied
+/// as reads and writes to framebuffer memory are slow.
+pub struct DoubleBuffer<'a> {
+    canvas: Canvas<'a>,
+    front: *mut u8,
+    front_stride: usize,
+    bytes_per_pixel: usize,
+}
+
+impl<'a> DoubleBuffer<'a> {
+    /// Pairs *canvas* with a front buffer *front_stride* bytes to a row, in the canvas's
+    /// pixel format.
+    ///
+    /// ## Safety
+    ///
+    /// *front* must be writable for as many rows as the canvas has and stay valid for the
+    /// life of the double buffer. Nothing else may be writing to it while *present* runs.
+    pub unsafe fn new(canvas: Canvas<'a>, front: *mut u8, front_stride: usize) -> DoubleBuffer<'a> {
+        let bytes_per_pixel = canvas.format().bytes_per_pixel();
+        return DoubleBuffer {
+            canvas: canvas,
+            front: front,
+            front_stride: front_stride,
+            bytes_per_pixel: bytes_per_pixel,
+        };
+    }
+
+    /// Builds a double buffer the size of *framebuffer*, drawing into *back*. Returns None
+    /// if there is no linear framebuffer or *back* is too small.
+    ///
+    /// ## Safety
+    ///
+    /// *front* must be the virtual address the framebuffer is mapped at, with the whole
+    /// framebuffer mapped, and the rules of *new* apply.
+    pub unsafe fn from_framebuffer(framebuffer: &FrameBuffer, front: *mut u8, back: &'a mut [u32]) -> Option<DoubleBuffer<'a>> {
+        if !framebuffer.is_linear() {
+            return None;
+        }
+
+        let canvas = Canvas::new(back, framebuffer.width, framebuffer.height, framebuffer.width as usize, framebuffer.pixel_format)?;
+        let front_stride = framebuffer.pixels_per_scan_line as usize * framebuffer.bytes_per_pixel();
+        return Some(unsafe { DoubleBuffer::new(canvas, front, front_stride) });
+    }
+
+    pub fn canvas(&mut self) -> &mut Canvas<'a> {
+        return &mut self.canvas;
+    }
+
+    /// Copies everything drawn since the last present to the front buffer. Returns the
+    /// area copied.
+    pub fn present(&mut self) -> Rect {
+        let dirty = self.canvas.take_dirty();
+        self.copy_to_front(dirty);
+        return dirty;
+    }
+
+    /// Copies the whole canvas to the front buffer, for when the screen may have been
+    /// drawn over by something else
+    pub fn present_all(&mut self) {
+        self.canvas.take_dirty();
+        self.copy_to_front(self.canvas.bounds());
+    }
+
+    fn copy_to_front(&mut self, area: Rect) {
+        if area.is_empty() {
+            return;
+        }
+
+        let (x, width) = (area.x as usize, area.width as usize);
+        for y in area.y as usize..area.bottom() as usize {
+            let start = y * self.canvas.stride() + x;
+            let row = &self.canvas.pixels()[start..start + width];
+            //Safe as new's caller promised the front buffer covers the canvas
+            let dest = unsafe { self.front.add(y * self.front_stride + x * self.bytes_per_pixel) };
+            if self.bytes_per_pixel == 4 {
+                unsafe { core::ptr::copy_nonoverlapping(row.as_ptr() as *const u8, dest, width * 4); }
+            } else {
+                for (index, pixel) in row.iter().enumerate() {
+                    let bytes = pixel.to_le_bytes();
+                    unsafe { core::ptr::copy_nonoverlapping(bytes.as_ptr(), dest.add(index * self.bytes_per_pixel), self.bytes_per_pixel); }
+                }
+            }
+        }
+    }
+}
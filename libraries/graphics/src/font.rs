@@ -0,0 +1,211 @@
+const PSF1_MAGIC: [u8; 2] = [0x36, 0x04];
+const PSF1_MODE_512: u8 = 0x01;
+const PSF1_MODE_HAS_TABLE: u8 = 0x02;
+const PSF1_MODE_HAS_SEQUENCES: u8 = 0x04;
+const PSF1_HEADER_SIZE: usize = 4;
+const PSF1_TABLE_SEPARATOR: u16 = 0xFFFF;
+const PSF1_TABLE_SEQUENCE_START: u16 = 0xFFFE;
+
+const PSF2_MAGIC: [u8; 4] = [0x72, 0xb5, 0x4a, 0x86];
+const PSF2_HAS_UNICODE_TABLE: u32 = 0x01;
+const PSF2_HEADER_SIZE: usize = 32;
+const PSF2_TABLE_SEPARATOR: u8 = 0xFF;
+const PSF2_TABLE_SEQUENCE_START: u8 = 0xFE;
+
+const NO_GLYPH: u16 = u16::MAX;
+
+#[derive(Clone, Copy, PartialEq)]
+enum UnicodeTable<'a> {
+    None,
+    Psf1(&'a [u8]),
+    Psf2(&'a [u8]),
+}
+
+/// A PC Screen Font in either the PSF1 or PSF2 format. Glyphs are bitmaps with one bit per
+/// pixel, most significant bit on the left, and each row padded to a whole byte.
+pub struct PsfFont<'a> {
+    glyphs: &'a [u8],
+    num_glyphs: usize,
+    bytes_per_glyph: usize,
+    width: usize,
+    height: usize,
+    unicode_table: UnicodeTable<'a>,
+    /// The glyph of each ASCII character, looked up once as nearly all output is ASCII
+    ascii_glyphs: [u16; 128],
+    replacement_glyph: usize,
+}
+
+impl<'a> PsfFont<'a> {
+    /// Parses a PSF1 or PSF2 font. Returns None if the header is not recognised, the font has
+    /// no glyphs, the glyphs are empty or they run past the end of *data*.
+    pub fn parse(data: &'a [u8]) -> Option<PsfFont<'a>> {
+        if data.starts_with(&PSF2_MAGIC) {
+            return PsfFont::parse_psf2(data);
+        } else if data.starts_with(&PSF1_MAGIC) {
+            return PsfFont::parse_psf1(data);
+        } else {
+            return None;
+        }
+    }
+
+    fn parse_psf1(data: &'a [u8]) -> Option<PsfFont<'a>> {
+        if data.len() < PSF1_HEADER_SIZE {
+            return None;
+        }
+
+        let mode = data[2];
+        let height = data[3] as usize;
+        if height == 0 {
+            return None;
+        }
+        let num_glyphs = if mode & PSF1_MODE_512 != 0 { 512 } else { 256 };
+        let glyphs_end = PSF1_HEADER_SIZE + num_glyphs * height;
+        let glyphs = data.get(PSF1_HEADER_SIZE..glyphs_end)?;
+        let unicode_table = if mode & (PSF1_MODE_HAS_TABLE | PSF1_MODE_HAS_SEQUENCES) != 0 {
+            UnicodeTable::Psf1(&data[glyphs_end..])
+        } else {
+            UnicodeTable::None
+        };
+
+        return Some(PsfFont::new(glyphs, num_glyphs, height, 8, height, unicode_table));
+    }
+
+    fn parse_psf2(data: &'a [u8]) -> Option<PsfFont<'a>> {
+        let read_u32 = |offset: usize| -> Option<u32> {
+            let bytes = data.get(offset..offset + 4)?;
+            return Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+        };
+
+        let header_size = read_u32(8)? as usize;
+        let flags = read_u32(12)?;
+        let num_glyphs = read_u32(16)? as usize;
+        let bytes_per_glyph = read_u32(20)? as usize;
+        let height = read_u32(24)? as usize;
+        let width = read_u32(28)? as usize;
+        if header_size < PSF2_HEADER_SIZE || num_glyphs == 0 || width == 0 || height == 0 || bytes_per_glyph < height.checked_mul((width + 7) / 8)? {
+            return None;
+        }
+
+        let glyphs_end = header_size.checked_add(num_glyphs.checked_mul(bytes_per_glyph)?)?;
+        let glyphs = data.get(header_size..glyphs_end)?;
+        let unicode_table = if flags & PSF2_HAS_UNICODE_TABLE != 0 {
+            UnicodeTable::Psf2(&data[glyphs_end..])
+        } else {
+            UnicodeTable::None
+        };
+
+        return Some(PsfFont::new(glyphs, num_glyphs, bytes_per_glyph, width, height, unicode_table));
+    }
+
+    fn new(glyphs: &'a [u8], num_glyphs: usize, bytes_per_glyph: usize, width: usize, height: usize, unicode_table: UnicodeTable<'a>) -> PsfFont<'a> {
+        let mut font = PsfFont {
+            glyphs: glyphs,
+            num_glyphs: num_glyphs,
+            bytes_per_glyph: bytes_per_glyph,
+            width: width,
+            height: height,
+            unicode_table: unicode_table,
+            ascii_glyphs: [NO_GLYPH; 128],
+            replacement_glyph: 0,
+        };
+
+        for character in 0..128u8 {
+            if let Some(glyph) = font.find_glyph(character as char) {
+                font.ascii_glyphs[character as usize] = glyph as u16;
+            }
+        }
+        font.replacement_glyph = font.find_glyph('\u{FFFD}').or(font.find_glyph('?')).unwrap_or(0);
+        return font;
+    }
+
+    pub fn width(&self) -> usize {
+        return self.width;
+    }
+
+    pub fn height(&self) -> usize {
+        return self.height;
+    }
+
+    pub fn bytes_per_row(&self) -> usize {
+        return (self.width + 7) / 8;
+    }
+
+    /// The bitmap of *character*, or of a replacement character if the font has no glyph
+    /// for it
+    pub fn glyph(&self, character: char) -> &'a [u8] {
+        let index = if (character as u32) < 128 && self.ascii_glyphs[character as usize] != NO_GLYPH {
+            self.ascii_glyphs[character as usize] as usize
+        } else if (character as u32) < 128 {
+            self.replacement_glyph
+        } else {
+            self.find_glyph(character).unwrap_or(self.replacement_glyph)
+        };
+
+        let start = index * self.bytes_per_glyph;
+        return &self.glyphs[start..start + self.bytes_per_glyph];
+    }
+
+    /// Looks *character* up in the unicode table. Fonts without a table are assumed to be
+    /// in code point order. Multi character sequences are skipped as they cannot be matched
+    /// one character at a time.
+    fn find_glyph(&self, character: char) -> Option<usize> {
+        match self.unicode_table {
+            UnicodeTable::None => {
+                let index = character as usize;
+                return if index < self.num_glyphs { Some(index) } else { None };
+            },
+            UnicodeTable::Psf1(table) => {
+                let mut glyph = 0;
+                let mut in_sequence = false;
+                for entry in table.chunks_exact(2).map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]])) {
+                    match entry {
+                        PSF1_TABLE_SEPARATOR => {
+                            glyph += 1;
+                            in_sequence = false;
+                        },
+                        PSF1_TABLE_SEQUENCE_START => { in_sequence = true; },
+                        code_point => {
+                            if !in_sequence && code_point as u32 == character as u32 && glyph < self.num_glyphs {
+                                return Some(glyph);
+                            }
+                        }
+                    }
+                }
+                return None;
+            },
+            UnicodeTable::Psf2(table) => {
+                let mut encoded = [0u8; 4];
+                let encoded = character.encode_utf8(&mut encoded).as_bytes();
+                for (glyph, entry) in table.split(|&byte| byte == PSF2_TABLE_SEPARATOR).enumerate() {
+                    if glyph >= self.num_glyphs {
+                        break;
+                    }
+
+                    let single_characters = entry.split(|&byte| byte == PSF2_TABLE_SEQUENCE_START).next().unwrap_or(&[]);
+                    if contains_character(single_characters, encoded) {
+                        return Some(glyph);
+                    }
+                }
+                return None;
+            }
+        }
+    }
+}
+
+/// True if the UTF-8 string *characters* contains the encoded character *encoded*
+fn contains_character(characters: &[u8], encoded: &[u8]) -> bool {
+    let mut offset = 0;
+    while offset < characters.len() {
+        let length = match characters[offset] {
+            0x00..=0x7F => 1,
+            0xC0..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            _ => 4,
+        };
+        if characters[offset..].starts_with(encoded) && length == encoded.len() {
+            return true;
+        }
+        offset += length;
+    }
+    return false;
+}
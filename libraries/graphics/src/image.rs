@@ -0,0 +1,197 @@
+use bootinfo::unpack_channel;
+
+use crate::{Bitmap, Colour};
+
+const BMP_MAGIC: &[u8] = b"BM";
+const BMP_FILE_HEADER_SIZE: usize = 14;
+const BMP_INFO_HEADER_SIZE: usize = 40;
+const BMP_COMPRESSION_RGB: u32 = 0;
+const BMP_COMPRESSION_BITFIELDS: u32 = 3;
+const BMP_COMPRESSION_ALPHA_BITFIELDS: u32 = 6;
+
+const TGA_HEADER_SIZE: usize = 18;
+const TGA_TYPE_TRUE_COLOUR: u8 = 2;
+const TGA_TYPE_GREYSCALE: u8 = 3;
+const TGA_DESCRIPTOR_RIGHT_TO_LEFT: u8 = 0x10;
+const TGA_DESCRIPTOR_TOP_TO_BOTTOM: u8 = 0x20;
+const TGA_DESCRIPTOR_ALPHA_BITS: u8 = 0x0F;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ImageError {
+    /// The file ends before the header or pixel data does
+    Truncated,
+    /// The header does not describe an image
+    InvalidHeader,
+    /// A valid image in a variant that is not supported, such as a compressed or paletted one
+    Unsupported,
+}
+
+/// How each pixel is stored in the file
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Layout {
+    /// Blue, green and red bytes, with alpha after them if *alpha* is set
+    Bgr { alpha: bool },
+    /// One grey byte
+    Grey,
+    /// A little endian value with each channel under a mask
+    Bitfields { red: u32, green: u32, blue: u32, alpha: u32 },
+}
+
+/// An uncompressed BMP or TGA image, read in place from the file without copying the pixels
+pub struct Image<'a> {
+    pixels: &'a [u8],
+    width: u32,
+    height: u32,
+    bytes_per_pixel: usize,
+    row_stride: usize,
+    top_to_bottom: bool,
+    right_to_left: bool,
+    layout: Layout,
+}
+
+impl<'a> Image<'a> {
+    /// Reads a BMP file, or a TGA file if it does not start with the BMP signature
+    pub fn parse(data: &'a [u8]) -> Result<Image<'a>, ImageError> {
+        if data.starts_with(BMP_MAGIC) {
+            return Image::parse_bmp(data);
+        } else {
+            return Image::parse_tga(data);
+        }
+    }
+
+    /// Reads a BMP file with 24 or 32 bit pixels, either plain or with bitfields
+    pub fn parse_bmp(data: &'a [u8]) -> Result<Image<'a>, ImageError> {
+        if !data.starts_with(BMP_MAGIC) {
+            return Err(ImageError::InvalidHeader);
+        }
+
+        let pixel_offset = read_u32(data, 10)? as usize;
+        let info_header_size = read_u32(data, 14)? as usize;
+        if info_header_size < BMP_INFO_HEADER_SIZE {
+            //The old OS/2 core header has no compression field
+            return Err(ImageError::Unsupported);
+        }
+
+        let width = read_u32(data, 18)? as i32;
+        let height = read_u32(data, 22)? as i32;
+        let bits_per_pixel = read_u16(data, 28)?;
+        let compression = read_u32(data, 30)?;
+        if width <= 0 || height == 0 || height == i32::MIN {
+            return Err(ImageError::InvalidHeader);
+        }
+
+        let layout = match (compression, bits_per_pixel) {
+            (BMP_COMPRESSION_RGB, 24) => Layout::Bgr { alpha: false },
+            //The fourth byte of a plain 32 bit BMP is unused rather than alpha
+            (BMP_COMPRESSION_RGB, 32) => Layout::Bitfields { red: 0x00FF0000, green: 0x0000FF00, blue: 0x000000FF, alpha: 0 },
+            (BMP_COMPRESSION_BITFIELDS | BMP_COMPRESSION_ALPHA_BITFIELDS, 32) => {
+                //The masks follow the 40 byte header, and are part of the longer headers
+                let masks = BMP_FILE_HEADER_SIZE + BMP_INFO_HEADER_SIZE;
+                let has_alpha = compression == BMP_COMPRESSION_ALPHA_BITFIELDS || info_header_size > BMP_INFO_HEADER_SIZE + 12;
+                Layout::Bitfields {
+                    red: read_u32(data, masks)?,
+                    green: read_u32(data, masks + 4)?,
+                    blue: read_u32(data, masks + 8)?,
+                    alpha: if has_alpha { read_u32(data, masks + 12)? } else { 0 },
+                }
+            },
+            _ => return Err(ImageError::Unsupported),
+        };
+
+        //Rows are padded to a multiple of 4 bytes. A negative height means the top row is first.
+        let bytes_per_pixel = bits_per_pixel as usize / 8;
+        let row_stride = (width as usize * bytes_per_pixel + 3) & !3;
+        return Image::new(data, pixel_offset, width as u32, height.unsigned_abs(), bytes_per_pixel, row_stride, height < 0, false, layout);
+    }
+
+    /// Reads an uncompressed true colour or greyscale TGA file
+    pub fn parse_tga(data: &'a [u8]) -> Result<Image<'a>, ImageError> {
+        let header = data.get(..TGA_HEADER_SIZE).ok_or(ImageError::Truncated)?;
+        let id_length = header[0] as usize;
+        let colour_map_type = header[1];
+        let image_type = header[2];
+        let colour_map_length = read_u16(header, 5)? as usize;
+        let colour_map_entry_bits = header[7] as usize;
+        let width = read_u16(header, 12)? as u32;
+        let height = read_u16(header, 14)? as u32;
+        let bits_per_pixel = header[16];
+        let descriptor = header[17];
+        if colour_map_type > 1 || width == 0 || height == 0 {
+            return Err(ImageError::InvalidHeader);
+        }
+
+        let alpha_bits = descriptor & TGA_DESCRIPTOR_ALPHA_BITS;
+        let layout = match (image_type, bits_per_pixel) {
+            (TGA_TYPE_TRUE_COLOUR, 24) => Layout::Bgr { alpha: false },
+            (TGA_TYPE_TRUE_COLOUR, 32) => Layout::Bgr { alpha: alpha_bits != 0 },
+            (TGA_TYPE_GREYSCALE, 8) => Layout::Grey,
+            _ => return Err(ImageError::Unsupported),
+        };
+
+        //True colour images may still carry a colour map, which is skipped
+        let colour_map_size = if colour_map_type == 1 { colour_map_length * ((colour_map_entry_bits + 7) / 8) } else { 0 };
+        let pixel_offset = TGA_HEADER_SIZE + id_length + colour_map_size;
+        let bytes_per_pixel = bits_per_pixel as usize / 8;
+        let row_stride = width as usize * bytes_per_pixel;
+        let top_to_bottom = descriptor & TGA_DESCRIPTOR_TOP_TO_BOTTOM != 0;
+        let right_to_left = descriptor & TGA_DESCRIPTOR_RIGHT_TO_LEFT != 0;
+        return Image::new(data, pixel_offset, width, height, bytes_per_pixel, row_stride, top_to_bottom, right_to_left, layout);
+    }
+
+    fn new(data: &'a [u8], pixel_offset: usize, width: u32, height: u32, bytes_per_pixel: usize, row_stride: usize, top_to_bottom: bool, right_to_left: bool, layout: Layout) -> Result<Image<'a>, ImageError> {
+        //The last row only needs its pixels, not its padding
+        let size = (height as usize - 1).checked_mul(row_stride)
+            .and_then(|size| size.checked_add(width as usize * bytes_per_pixel))
+            .ok_or(ImageError::InvalidHeader)?;
+        let end = pixel_offset.checked_add(size).ok_or(ImageError::InvalidHeader)?;
+        let pixels = data.get(pixel_offset..end).ok_or(ImageError::Truncated)?;
+
+        return Ok(Image {
+            pixels: pixels,
+            width: width,
+            height: height,
+            bytes_per_pixel: bytes_per_pixel,
+            row_stride: row_stride,
+            top_to_bottom: top_to_bottom,
+            right_to_left: right_to_left,
+            layout: layout,
+        });
+    }
+}
+
+impl<'a> Bitmap for Image<'a> {
+    fn width(&self) -> u32 {
+        return self.width;
+    }
+
+    fn height(&self) -> u32 {
+        return self.height;
+    }
+
+    fn pixel(&self, x: u32, y: u32) -> Colour {
+        let row = if self.top_to_bottom { y } else { self.height - 1 - y };
+        let column = if self.right_to_left { self.width - 1 - x } else { x };
+        let offset = row as usize * self.row_stride + column as usize * self.bytes_per_pixel;
+        let bytes = &self.pixels[offset..offset + self.bytes_per_pixel];
+
+        match self.layout {
+            Layout::Bgr { alpha } => Colour::rgba(bytes[2], bytes[1], bytes[0], if alpha { bytes[3] } else { 0xFF }),
+            Layout::Grey => Colour::rgba(bytes[0], bytes[0], bytes[0], 0xFF),
+            Layout::Bitfields { red, green, blue, alpha } => {
+                let value = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                let alpha = if alpha == 0 { 0xFF } else { unpack_channel(value, alpha) };
+                Colour::rgba(unpack_channel(value, red), unpack_channel(value, green), unpack_channel(value, blue), alpha)
+            }
+        }
+    }
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, ImageError> {
+    let bytes = data.get(offset..offset + 2).ok_or(ImageError::Truncated)?;
+    return Ok(u16::from_le_bytes([bytes[0], bytes[1]]));
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, ImageError> {
+    let bytes = data.get(offset..offset + 4).ok_or(ImageError::Truncated)?;
+    return Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+}
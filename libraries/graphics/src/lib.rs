@@ -0,0 +1,17 @@
+#![no_std]
+
+mod bitmap;
+mod canvas;
+mod colour;
+mod double_buffer;
+mod font;
+mod image;
+mod rect;
+
+pub use bitmap::*;
+pub use canvas::*;
+pub use colour::*;
+pub use double_buffer::*;
+pub use font::*;
+pub use image::*;
+pub use rect::*;
@@ -0,0 +1,71 @@
+use core::cmp::{max, min};
+
+/// A rectangle of pixels. *x* and *y* are the top left corner and may be negative for
+/// shapes that hang off the edge of a canvas.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    pub const EMPTY: Rect = Rect::new(0, 0, 0, 0);
+
+    pub const fn new(x: i32, y: i32, width: u32, height: u32) -> Rect {
+        return Rect {
+            x: x,
+            y: y,
+            width: width,
+            height: height,
+        };
+    }
+
+    /// One past the right most column
+    pub const fn right(&self) -> i32 {
+        return self.x.saturating_add_unsigned(self.width);
+    }
+
+    /// One past the bottom row
+    pub const fn bottom(&self) -> i32 {
+        return self.y.saturating_add_unsigned(self.height);
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        return self.width == 0 || self.height == 0;
+    }
+
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        return x >= self.x && x < self.right() && y >= self.y && y < self.bottom();
+    }
+
+    /// The area covered by both rectangles, which is empty if they do not overlap
+    pub fn intersection(&self, other: &Rect) -> Rect {
+        let left = max(self.x, other.x);
+        let top = max(self.y, other.y);
+        let right = min(self.right(), other.right());
+        let bottom = min(self.bottom(), other.bottom());
+        if right <= left || bottom <= top {
+            return Rect::EMPTY;
+        }
+
+        return Rect::from_edges(left, top, right, bottom);
+    }
+
+    /// The smallest rectangle covering both. Empty rectangles are ignored.
+    pub fn union(&self, other: &Rect) -> Rect {
+        if self.is_empty() {
+            return *other;
+        }
+        if other.is_empty() {
+            return *self;
+        }
+
+        return Rect::from_edges(min(self.x, other.x), min(self.y, other.y), max(self.right(), other.right()), max(self.bottom(), other.bottom()));
+    }
+
+    fn from_edges(left: i32, top: i32, right: i32, bottom: i32) -> Rect {
+        return Rect::new(left, top, right.abs_diff(left), bottom.abs_diff(top));
+    }
+}
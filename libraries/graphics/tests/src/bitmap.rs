@@ -0,0 +1,46 @@
+#[cfg(test)]
+mod tests {
+    use graphics::{Bitmap, Colour, RgbaBitmap, Rotated};
+
+    //A B C
+    //D E F
+    const SOURCE: [u32; 6] = [0xA, 0xB, 0xC, 0xD, 0xE, 0xF];
+
+    fn read_pixels<B: Bitmap>(bitmap: &B) -> Vec<Colour> {
+        let mut pixels = Vec::new();
+        for y in 0..bitmap.height() {
+            for x in 0..bitmap.width() {
+                pixels.push(bitmap.pixel(x, y));
+            }
+        }
+        return pixels;
+    }
+
+    fn colours(values: &[u32]) -> Vec<Colour> {
+        return values.iter().map(|value| Colour::argb(*value)).collect();
+    }
+
+    #[test]
+    fn test_rotated_clockwise() {
+        let bitmap = RgbaBitmap::new(&SOURCE, 3, 2).unwrap();
+        let expected: [(u16, u32, u32, [u32; 6]); 4] = [
+            (0, 3, 2, [0xA, 0xB, 0xC, 0xD, 0xE, 0xF]),
+            (90, 2, 3, [0xD, 0xA, 0xE, 0xB, 0xF, 0xC]),
+            (180, 3, 2, [0xF, 0xE, 0xD, 0xC, 0xB, 0xA]),
+            (270, 2, 3, [0xC, 0xF, 0xB, 0xE, 0xA, 0xD]),
+        ];
+
+        for (degrees, width, height, pixels) in expected {
+            let rotated = Rotated::new(&bitmap, degrees).unwrap();
+            assert_eq!((width, height), (rotated.width(), rotated.height()), "{} degrees", degrees);
+            assert_eq!(colours(&pixels), read_pixels(&rotated), "{} degrees", degrees);
+        }
+    }
+
+    #[test]
+    fn test_rotated_rejects_other_angles() {
+        let bitmap = RgbaBitmap::new(&SOURCE, 3, 2).unwrap();
+        assert_eq!(true, Rotated::new(&bitmap, 45).is_none());
+        assert_eq!(true, Rotated::new(&bitmap, 360).is_none());
+    }
+}
@@ -0,0 +1,160 @@
+#[cfg(test)]
+mod tests {
+    use bootinfo::{PixelBitmask, PixelFormat};
+    use graphics::{Canvas, Colour, Rect, RgbaBitmap};
+
+    use crate::golden::{assert_golden, render};
+
+    const RED: Colour = Colour::rgb(0xFF0000);
+    const GREEN: Colour = Colour::rgb(0x00FF00);
+    const BLUE: Colour = Colour::rgb(0x0000FF);
+
+    #[test]
+    fn test_fill_rect_clipped_to_canvas() {
+        let pixels = render(8, 6, |canvas| {
+            canvas.fill_rect(Rect::new(-2, -2, 5, 4), RED);
+            canvas.fill_rect(Rect::new(5, 3, 10, 10), BLUE);
+            canvas.fill_rect(Rect::new(2, 2, 3, 2), GREEN);
+        });
+
+        assert_golden("fill_rect", 8, &pixels);
+    }
+
+    #[test]
+    fn test_lines_and_outlines() {
+        let pixels = render(12, 10, |canvas| {
+            canvas.draw_rect(Rect::new(0, 0, 12, 10), Colour::WHITE);
+            canvas.draw_line(1, 1, 10, 4, RED);
+            canvas.draw_line(2, 8, 4, 1, GREEN);
+            canvas.draw_line(9, 8, 6, 8, BLUE);
+            canvas.draw_line(10, 2, 10, 8, Colour::rgb(0xFFFF00));
+        });
+
+        assert_golden("lines", 12, &pixels);
+    }
+
+    #[test]
+    fn test_clip_limits_drawing() {
+        let pixels = render(10, 8, |canvas| {
+            canvas.set_clip(Rect::new(2, 2, 6, 4));
+            canvas.clear(BLUE);
+            canvas.draw_line(0, 0, 9, 7, Colour::WHITE);
+            canvas.set_pixel(1, 1, RED);
+        });
+
+        assert_golden("clip", 10, &pixels);
+    }
+
+    /// The pixels of a line from Bresenham's algorithm, walked from end to end
+    fn bresenham(x0: i32, y0: i32, x1: i32, y1: i32) -> Vec<(i32, i32)> {
+        let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+        let (step_x, step_y) = ((x1 - x0).signum(), (y1 - y0).signum());
+        let mut error = dx + dy;
+        let (mut x, mut y) = (x0, y0);
+        let mut points = vec![(x, y)];
+        while x != x1 || y != y1 {
+            let doubled_error = 2 * error;
+            if doubled_error >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if doubled_error <= dx {
+                error += dx;
+                y += step_y;
+            }
+            points.push((x, y));
+        }
+        return points;
+    }
+
+    #[test]
+    fn test_clipped_lines_match_bresenham() {
+        let (width, height) = (10, 8);
+        for (x0, y0) in [(-7, -3), (-2, 5), (3, -9), (4, 4), (12, 2)] {
+            for (x1, y1) in [(15, 11), (9, -4), (-5, 9), (1, 2), (6, 20), (-9, -1)] {
+                let pixels = render(width, height, |canvas| canvas.draw_line(x0, y0, x1, y1, Colour::WHITE));
+
+                let mut expected = vec![0u32; (width * height) as usize];
+                for (x, y) in bresenham(x0, y0, x1, y1) {
+                    if x >= 0 && y >= 0 && x < width as i32 && y < height as i32 {
+                        expected[(y * width as i32 + x) as usize] = 0xFFFFFF;
+                    }
+                }
+                assert_eq!(expected, pixels, "line ({}, {}) to ({}, {})", x0, y0, x1, y1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_line_far_off_canvas() {
+        //Only the steps inside the canvas are walked so this finishes straight away
+        let pixels = render(6, 6, |canvas| canvas.draw_line(i32::MIN + 1, i32::MIN + 1, i32::MAX, i32::MAX, Colour::WHITE));
+
+        for y in 0..6 {
+            for x in 0..6 {
+                let expected = if x == y { 0xFFFFFF } else { 0 };
+                assert_eq!(expected, pixels[y * 6 + x]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_glyph_with_and_without_background() {
+        //An 8x8 "A" and a 10 pixel wide bar to check rows padded past one byte
+        let glyph_a: [u8; 8] = [0x18, 0x24, 0x42, 0x42, 0x7E, 0x42, 0x42, 0x00];
+        let glyph_bar: [u8; 4] = [0xC0, 0x40, 0xFF, 0xC0];
+        let pixels = render(20, 10, |canvas| {
+            canvas.fill_rect(Rect::new(10, 0, 10, 10), RED);
+            canvas.draw_glyph(1, 1, &glyph_a, 8, 8, Colour::WHITE, Some(BLUE));
+            canvas.draw_glyph(10, 1, &glyph_a, 8, 8, Colour::WHITE, None);
+            canvas.draw_glyph(8, 8, &glyph_bar, 10, 2, GREEN, None);
+        });
+
+        assert_golden("glyph", 20, &pixels);
+    }
+
+    #[test]
+    fn test_blit_blends_by_alpha() {
+        let source = [
+            0xFFFF0000, 0x80FF0000, 0x00FF0000,
+            0xFF0000FF, 0x800000FF, 0x000000FF,
+        ];
+        let bitmap = RgbaBitmap::new(&source, 3, 2).unwrap();
+        let pixels = render(6, 4, |canvas| {
+            canvas.fill_rect(Rect::new(0, 0, 6, 2), Colour::WHITE);
+            canvas.blit(1, 1, &bitmap);
+            canvas.blit(4, 3, &bitmap);
+        });
+
+        assert_golden("blit", 6, &pixels);
+    }
+
+    #[test]
+    fn test_dirty_covers_drawing() {
+        let mut pixels = vec![0u32; 100];
+        let mut canvas = Canvas::new(&mut pixels, 10, 10, 10, PixelFormat::Rgb).unwrap();
+        assert_eq!(true, canvas.dirty().is_empty());
+
+        canvas.fill_rect(Rect::new(-5, 2, 8, 2), RED);
+        canvas.set_pixel(6, 7, RED);
+        assert_eq!(Rect::new(0, 2, 7, 6), canvas.take_dirty());
+        assert_eq!(true, canvas.dirty().is_empty());
+
+        canvas.fill_rect(Rect::new(20, 20, 5, 5), RED);
+        assert_eq!(true, canvas.dirty().is_empty());
+    }
+
+    #[test]
+    fn test_bitmask_format_round_trip() {
+        //16 bit 5:6:5, as some older hardware reports
+        let format = PixelFormat::Bitmask(PixelBitmask { red: 0xF800, green: 0x07E0, blue: 0x001F, reserved: 0 });
+        let mut pixels = vec![0u32; 4];
+        let mut canvas = Canvas::new(&mut pixels, 2, 2, 2, format).unwrap();
+        canvas.set_pixel(0, 0, Colour::WHITE);
+        canvas.set_pixel(1, 0, Colour::rgba(0xFF, 0x00, 0x00, 0x80));
+
+        assert_eq!(Some(Colour::WHITE), canvas.pixel(0, 0));
+        assert_eq!(Some(Colour::rgb(0x830000)), canvas.pixel(1, 0));
+        assert_eq!(0xFFFF, pixels[0]);
+    }
+}
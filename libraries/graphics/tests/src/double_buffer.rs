@@ -0,0 +1,39 @@
+#[cfg(test)]
+mod tests {
+    use bootinfo::{PixelBitmask, PixelFormat};
+    use graphics::{Canvas, Colour, DoubleBuffer, Rect};
+
+    #[test]
+    fn test_present_copies_only_dirty_area() {
+        let mut back = vec![0u32; 16];
+        //The front buffer is wider than the canvas, as when the scan line is padded
+        let mut front = vec![0xAAu8; 6 * 4 * 4];
+        let canvas = Canvas::new(&mut back, 4, 4, 4, PixelFormat::Bgr).unwrap();
+        let mut double_buffer = unsafe { DoubleBuffer::new(canvas, front.as_mut_ptr(), 6 * 4) };
+
+        double_buffer.canvas().fill_rect(Rect::new(1, 1, 2, 2), Colour::rgb(0x123456));
+        assert_eq!(Rect::new(1, 1, 2, 2), double_buffer.present());
+        assert_eq!(Rect::EMPTY, double_buffer.present());
+
+        for y in 0..4 {
+            for x in 0..6 {
+                let pixel = &front[(y * 6 + x) * 4..(y * 6 + x + 1) * 4];
+                let expected: [u8; 4] = if (1..3).contains(&x) && (1..3).contains(&y) { [0x56, 0x34, 0x12, 0x00] } else { [0xAA; 4] };
+                assert_eq!(expected, pixel, "pixel {},{}", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn test_present_all_to_24_bit_front_buffer() {
+        let format = PixelFormat::Bitmask(PixelBitmask { red: 0xFF0000, green: 0x00FF00, blue: 0x0000FF, reserved: 0 });
+        let mut back = vec![0u32; 2];
+        let mut front = vec![0xAAu8; 2 * 3 + 1];
+        let mut canvas = Canvas::new(&mut back, 2, 1, 2, format).unwrap();
+        canvas.set_pixel(1, 0, Colour::rgb(0x010203));
+        let mut double_buffer = unsafe { DoubleBuffer::new(canvas, front.as_mut_ptr(), 6) };
+        double_buffer.present_all();
+
+        assert_eq!(vec![0x00, 0x00, 0x00, 0x03, 0x02, 0x01, 0xAA], front);
+    }
+}
@@ -0,0 +1,72 @@
+#[cfg(test)]
+mod tests {
+    use graphics::PsfFont;
+
+    const FIXED_8X13: &[u8] = include_bytes!("../../../../kernel/fonts/fixed-8x13.psfu");
+
+    fn with_num_glyphs(num_glyphs: u32) -> Vec<u8> {
+        let mut data = FIXED_8X13.to_vec();
+        data[16..20].copy_from_slice(&num_glyphs.to_le_bytes());
+        return data;
+    }
+
+    #[test]
+    fn test_parse_default_font() {
+        let font = PsfFont::parse(FIXED_8X13).unwrap();
+
+        assert_eq!(8, font.width());
+        assert_eq!(13, font.height());
+        assert_eq!(1, font.bytes_per_row());
+        assert_eq!(13, font.glyph('A').len());
+        assert!(font.glyph('A').iter().any(|&row| row != 0));
+        assert_ne!(font.glyph('A'), font.glyph('B'));
+        assert!(font.glyph(' ').iter().all(|&row| row == 0));
+    }
+
+    #[test]
+    fn test_missing_character_uses_replacement() {
+        let font = PsfFont::parse(FIXED_8X13).unwrap();
+
+        assert_eq!(font.glyph('\u{FFFD}'), font.glyph('\u{10FFFF}'));
+        assert_eq!(font.glyph('\u{FFFD}'), font.glyph('\u{7F}'));
+    }
+
+    #[test]
+    fn test_reject_no_glyphs() {
+        assert!(PsfFont::parse(&with_num_glyphs(0)).is_none());
+        assert!(PsfFont::parse(&with_num_glyphs(1)).is_some());
+    }
+
+    #[test]
+    fn test_reject_zero_height() {
+        let mut psf2 = FIXED_8X13.to_vec();
+        psf2[24..28].copy_from_slice(&0u32.to_le_bytes());
+        assert!(PsfFont::parse(&psf2).is_none());
+
+        let mut psf1 = vec![0x36, 0x04, 0x00, 0];
+        psf1.resize(1024, 0);
+        assert!(PsfFont::parse(&psf1).is_none());
+    }
+
+    #[test]
+    fn test_reject_truncated_font() {
+        assert!(PsfFont::parse(&FIXED_8X13[..31]).is_none());
+        assert!(PsfFont::parse(&with_num_glyphs(u32::MAX)).is_none());
+        assert!(PsfFont::parse(b"not a font").is_none());
+    }
+
+    #[test]
+    fn test_psf1_without_table() {
+        let mut data = vec![0x36, 0x04, 0x00, 2];
+        for glyph in 0..256u32 {
+            data.extend_from_slice(&[glyph as u8, !(glyph as u8)]);
+        }
+        let font = PsfFont::parse(&data).unwrap();
+
+        assert_eq!(8, font.width());
+        assert_eq!(2, font.height());
+        assert_eq!(&[b'A', !b'A'], font.glyph('A'));
+        assert_eq!(font.glyph('?'), font.glyph('\u{263A}'));
+        assert!(PsfFont::parse(&data[..data.len() - 1]).is_none());
+    }
+}
@@ -0,0 +1,78 @@
+#![cfg(test)]
+
+//Golden images are text files in tests/golden. They start with a palette, one
+//"<character> = RRGGBB" line per colour, then a blank line and a row of characters for each
+//row of pixels. Run the tests with UPDATE_GOLDEN=1 to write the images from the current
+//output, then check the diff by eye before committing.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use bootinfo::PixelFormat;
+use graphics::Canvas;
+
+const PALETTE: [(char, u32); 12] = [
+    ('.', 0x000000),
+    ('#', 0xFFFFFF),
+    ('r', 0xFF0000),
+    ('g', 0x00FF00),
+    ('b', 0x0000FF),
+    ('y', 0xFFFF00),
+    ('c', 0x00FFFF),
+    ('m', 0xFF00FF),
+    ('R', 0x800000),
+    ('B', 0x000080),
+    ('p', 0xFF7F7F),
+    ('l', 0x7F7FFF),
+];
+
+/// Runs *draw* on a black canvas of the given size, rendered into a Vec<u32> in the BGR
+/// format so each pixel is 0x00RRGGBB, and returns the pixels
+pub fn render(width: u32, height: u32, draw: impl FnOnce(&mut Canvas)) -> Vec<u32> {
+    let mut pixels = vec![0u32; (width * height) as usize];
+    let mut canvas = Canvas::new(&mut pixels, width, height, width as usize, PixelFormat::Bgr).unwrap();
+    draw(&mut canvas);
+    return pixels;
+}
+
+pub fn assert_golden(name: &str, width: u32, pixels: &[u32]) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("golden").join(format!("{}.txt", name));
+    let actual = to_text(width, pixels);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(&path, &actual).unwrap();
+        return;
+    }
+
+    let expected = std::fs::read_to_string(&path).unwrap_or_else(|error| panic!("cannot read {}: {}", path.display(), error));
+    let expected_pixels = from_text(&expected);
+    if expected_pixels != pixels {
+        panic!("{} does not match the golden image\nexpected:\n{}\nactual:\n{}", name, expected, actual);
+    }
+}
+
+fn to_text(width: u32, pixels: &[u32]) -> String {
+    let mut text = String::new();
+    for (character, rgb) in PALETTE {
+        text.push_str(&format!("{} = {:06X}\n", character, rgb));
+    }
+    text.push('\n');
+
+    for row in pixels.chunks(width as usize) {
+        for pixel in row {
+            let character = PALETTE.iter().find(|(_, rgb)| rgb == pixel).map(|(character, _)| *character);
+            text.push(character.unwrap_or_else(|| panic!("colour {:06X} is not in the golden palette", pixel)));
+        }
+        text.push('\n');
+    }
+    return text;
+}
+
+fn from_text(text: &str) -> Vec<u32> {
+    let (palette_text, image_text) = text.split_once("\n\n").unwrap();
+    let palette: BTreeMap<char, u32> = palette_text.lines().map(|line| {
+        let (character, rgb) = line.split_once(" = ").unwrap();
+        return (character.chars().next().unwrap(), u32::from_str_radix(rgb, 16).unwrap());
+    }).collect();
+
+    return image_text.lines().flat_map(|line| line.chars()).map(|character| palette[&character]).collect();
+}
@@ -0,0 +1,123 @@
+#[cfg(test)]
+mod tests {
+    use graphics::{Bitmap, Colour, Image, ImageError};
+
+    use crate::golden::{assert_golden, render};
+
+    //A 3x2 test card, top row first, as 0xAARRGGBB
+    const CARD: [[u32; 3]; 2] = [
+        [0xFFFF0000, 0xFF00FF00, 0xFF0000FF],
+        [0xFFFFFFFF, 0x80FF0000, 0x00000000],
+    ];
+
+    /// Encodes CARD as a BMP. 24 bit pixels are stored plain and 32 bit ones with
+    /// bitfields in a V4 header so they keep their alpha.
+    fn bmp(bits_per_pixel: u16, top_to_bottom: bool) -> Vec<u8> {
+        let info_header_size: u32 = if bits_per_pixel == 32 { 108 } else { 40 };
+        let pixel_offset = 14 + info_header_size;
+        let height: i32 = if top_to_bottom { -2 } else { 2 };
+        let mut data = Vec::new();
+        data.extend_from_slice(b"BM");
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(&pixel_offset.to_le_bytes());
+        data.extend_from_slice(&info_header_size.to_le_bytes());
+        data.extend_from_slice(&3i32.to_le_bytes());
+        data.extend_from_slice(&height.to_le_bytes());
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&bits_per_pixel.to_le_bytes());
+        data.extend_from_slice(&(if bits_per_pixel == 32 { 3u32 } else { 0u32 }).to_le_bytes());
+        data.resize(14 + 40, 0);
+        if bits_per_pixel == 32 {
+            for mask in [0x00FF0000u32, 0x0000FF00, 0x000000FF, 0xFF000000] {
+                data.extend_from_slice(&mask.to_le_bytes());
+            }
+        }
+        data.resize(pixel_offset as usize, 0);
+
+        let rows: Vec<&[u32; 3]> = if top_to_bottom { CARD.iter().collect() } else { CARD.iter().rev().collect() };
+        for row in rows {
+            for pixel in row {
+                data.extend_from_slice(&pixel.to_le_bytes()[..bits_per_pixel as usize / 8]);
+            }
+            while (data.len() - pixel_offset as usize) % 4 != 0 {
+                data.push(0);
+            }
+        }
+        return data;
+    }
+
+    /// Encodes CARD as a 32 bit true colour TGA with an image ID
+    fn tga(top_to_bottom: bool) -> Vec<u8> {
+        let descriptor: u8 = 8 | if top_to_bottom { 0x20 } else { 0 };
+        let mut data = vec![3, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 2, 0, 32, descriptor];
+        data.extend_from_slice(b"id!");
+        let rows: Vec<&[u32; 3]> = if top_to_bottom { CARD.iter().collect() } else { CARD.iter().rev().collect() };
+        for row in rows {
+            for pixel in row {
+                data.extend_from_slice(&pixel.to_le_bytes());
+            }
+        }
+        return data;
+    }
+
+    fn assert_card(image: &Image, with_alpha: bool) {
+        assert_eq!((3, 2), (image.width(), image.height()));
+        for y in 0..2 {
+            for x in 0..3 {
+                let mut expected = Colour::argb(CARD[y as usize][x as usize]);
+                if !with_alpha {
+                    expected.alpha = 0xFF;
+                }
+                assert_eq!(expected, image.pixel(x, y), "pixel {},{}", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn test_bmp_24_bit_bottom_up() {
+        let data = bmp(24, false);
+        assert_card(&Image::parse(&data).unwrap(), false);
+    }
+
+    #[test]
+    fn test_bmp_32_bit_bitfields_top_down() {
+        let data = bmp(32, true);
+        assert_card(&Image::parse(&data).unwrap(), true);
+    }
+
+    #[test]
+    fn test_tga_both_origins() {
+        let bottom_up = tga(false);
+        let top_down = tga(true);
+
+        assert_card(&Image::parse(&bottom_up).unwrap(), true);
+        assert_card(&Image::parse(&top_down).unwrap(), true);
+    }
+
+    #[test]
+    fn test_rejects_bad_files() {
+        let mut compressed = bmp(24, false);
+        compressed[30] = 1;
+        let mut run_length_tga = tga(false);
+        run_length_tga[2] = 10;
+        let truncated = bmp(24, false);
+
+        assert_eq!(Some(ImageError::Unsupported), Image::parse(&compressed).err());
+        assert_eq!(Some(ImageError::Unsupported), Image::parse(&run_length_tga).err());
+        assert_eq!(Some(ImageError::Truncated), Image::parse(&truncated[..truncated.len() - 4]).err());
+        assert_eq!(Some(ImageError::Truncated), Image::parse(b"BM").err());
+    }
+
+    #[test]
+    fn test_blit_image() {
+        let data = bmp(32, false);
+        let image = Image::parse(&data).unwrap();
+        let pixels = render(5, 4, |canvas| {
+            canvas.blit(1, 1, &image);
+            canvas.blit(-1, 3, &image);
+        });
+
+        assert_golden("image", 5, &pixels);
+    }
+}
@@ -0,0 +1,6 @@
+mod bitmap;
+mod canvas;
+mod double_buffer;
+mod font;
+mod golden;
+mod image;
@@ -0,0 +1,155 @@
+use crate::{Level, LevelFilter};
+
+pub const MAX_FILTER_DIRECTIVES: usize = 8;
+pub const MAX_MODULE_NAME_LEN: usize = 64;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FilterError {
+    UnknownLevel,
+    EmptyModuleName,
+    ModuleNameTooLong,
+    TooManyDirectives,
+}
+
+/// Sets the level of one module and everything below it
+#[derive(Clone, Copy)]
+struct Directive {
+    module: [u8; MAX_MODULE_NAME_LEN],
+    module_len: usize,
+    level: LevelFilter,
+}
+
+impl Directive {
+    const EMPTY: Directive = Directive { module: [0; MAX_MODULE_NAME_LEN], module_len: 0, level: LevelFilter::Off };
+
+    fn module(&self) -> &[u8] {
+        return &self.module[..self.module_len];
+    }
+
+    /// True if *module* is the directive's module or one of its submodules. "kernel::pci"
+    /// matches "kernel::pci::msi" but not "kernel::pcie".
+    fn matches(&self, module: &str) -> bool {
+        let module = module.as_bytes();
+        let prefix = self.module();
+        if !module.starts_with(prefix) {
+            return false;
+        }
+
+        return module.len() == prefix.len() || module[prefix.len()..].starts_with(b"::");
+    }
+}
+
+/// Decides which messages are logged. Each module gets the level of the most specific
+/// directive that matches its path, or the default level if none do.
+#[derive(Clone, Copy)]
+pub struct Filter {
+    default_level: LevelFilter,
+    directives: [Directive; MAX_FILTER_DIRECTIVES],
+    num_directives: usize,
+}
+
+impl Filter {
+    pub const fn new(default_level: LevelFilter) -> Filter {
+        return Filter {
+            default_level: default_level,
+            directives: [Directive::EMPTY; MAX_FILTER_DIRECTIVES],
+            num_directives: 0,
+        };
+    }
+
+    /// Parses a comma separated list of directives, for example "info,kernel::pci=trace".
+    /// A bare level sets the default level and "module=level" sets the level of a module
+    /// and its submodules. Anything not mentioned defaults to *Info*.
+    pub fn parse(spec: &str) -> Result<Filter, FilterError> {
+        let mut filter = Filter::new(LevelFilter::Info);
+        for directive in spec.split(',').map(|directive| directive.trim()).filter(|directive| !directive.is_empty()) {
+            match directive.split_once('=') {
+                Some((module, level)) => {
+                    let level = LevelFilter::parse(level.trim()).ok_or(FilterError::UnknownLevel)?;
+                    filter.set_module_level(module.trim(), level)?;
+                },
+                None => {
+                    filter.default_level = LevelFilter::parse(directive).ok_or(FilterError::UnknownLevel)?;
+                }
+            }
+        }
+
+        return Ok(filter);
+    }
+
+    /// Builds the filter from the logging options on a kernel command line. "loglevel=debug"
+    /// sets the default level and "log=" takes a directive list as accepted by *parse*. When
+    /// both are given loglevel wins for the default level.
+    pub fn from_command_line(command_line: &str) -> Result<Filter, FilterError> {
+        let mut filter = Filter::new(LevelFilter::Info);
+        let mut default_level = None;
+        for option in command_line.split_ascii_whitespace() {
+            match option.split_once('=') {
+                Some(("log", spec)) => { filter = Filter::parse(spec)?; },
+                Some(("loglevel", level)) => { default_level = Some(LevelFilter::parse(level).ok_or(FilterError::UnknownLevel)?); },
+                _ => {}
+            }
+        }
+
+        if let Some(level) = default_level {
+            filter.set_default_level(level);
+        }
+        return Ok(filter);
+    }
+
+    pub fn default_level(&self) -> LevelFilter {
+        return self.default_level;
+    }
+
+    pub fn set_default_level(&mut self, level: LevelFilter) {
+        self.default_level = level;
+    }
+
+    /// Sets the level of *module* and its submodules, replacing any earlier directive for
+    /// the same module
+    pub fn set_module_level(&mut self, module: &str, level: LevelFilter) -> Result<(), FilterError> {
+        if module.is_empty() {
+            return Err(FilterError::EmptyModuleName);
+        }
+        if module.len() > MAX_MODULE_NAME_LEN {
+            return Err(FilterError::ModuleNameTooLong);
+        }
+
+        let existing = self.directives[..self.num_directives].iter_mut().find(|directive| directive.module() == module.as_bytes());
+        let directive = match existing {
+            Some(directive) => directive,
+            None => {
+                if self.num_directives == MAX_FILTER_DIRECTIVES {
+                    return Err(FilterError::TooManyDirectives);
+                }
+                self.num_directives += 1;
+                &mut self.directives[self.num_directives - 1]
+            }
+        };
+
+        directive.module[..module.len()].copy_from_slice(module.as_bytes());
+        directive.module_len = module.len();
+        directive.level = level;
+        return Ok(());
+    }
+
+    /// The level that applies to messages from *module*
+    pub fn level_for(&self, module: &str) -> LevelFilter {
+        return self.directives[..self.num_directives].iter()
+            .filter(|directive| directive.matches(module))
+            .max_by_key(|directive| directive.module_len)
+            .map_or(self.default_level, |directive| directive.level);
+    }
+
+    pub fn enabled(&self, level: Level, module: &str) -> bool {
+        return self.level_for(module).allows(level);
+    }
+
+    /// The most verbose level any module is allowed. Messages above it can be dropped
+    /// without looking at the module.
+    pub fn max_level(&self) -> LevelFilter {
+        return self.directives[..self.num_directives].iter()
+            .map(|directive| directive.level)
+            .fold(self.default_level, |max, level| max.max(level));
+    }
+}
@@ -0,0 +1,80 @@
+use core::fmt;
+
+/// How important a log message is, most important first
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[repr(u8)]
+pub enum Level {
+    Error = 1,
+    Warn = 2,
+    Info = 3,
+    Debug = 4,
+    Trace = 5,
+}
+
+impl Level {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        //pad rather than write_str so width and alignment flags line the levels up
+        return f.pad(self.as_str());
+    }
+}
+
+/// The least important level that is let through. *Off* lets nothing through.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[repr(u8)]
+pub enum LevelFilter {
+    Off = 0,
+    Error = 1,
+    Warn = 2,
+    Info = 3,
+    Debug = 4,
+    Trace = 5,
+}
+
+impl LevelFilter {
+    /// Parses a level name such as "debug". Names are not case sensitive.
+    pub fn parse(name: &str) -> Option<LevelFilter> {
+        const NAMES: [(&str, LevelFilter); 6] = [
+            ("off", LevelFilter::Off),
+            ("error", LevelFilter::Error),
+            ("warn", LevelFilter::Warn),
+            ("info", LevelFilter::Info),
+            ("debug", LevelFilter::Debug),
+            ("trace", LevelFilter::Trace),
+        ];
+
+        return NAMES.iter()
+            .find(|(level_name, _)| level_name.eq_ignore_ascii_case(name))
+            .map(|(_, filter)| *filter);
+    }
+
+    pub fn allows(&self, level: Level) -> bool {
+        return level as u8 <= *self as u8;
+    }
+
+    pub fn as_u8(&self) -> u8 {
+        return *self as u8;
+    }
+
+    pub fn from_u8(value: u8) -> LevelFilter {
+        match value {
+            0 => LevelFilter::Off,
+            1 => LevelFilter::Error,
+            2 => LevelFilter::Warn,
+            3 => LevelFilter::Info,
+            4 => LevelFilter::Debug,
+            _ => LevelFilter::Trace,
+        }
+    }
+}
@@ -0,0 +1,14 @@
+#![no_std]
+
+mod filter;
+mod level;
+mod line_buffer;
+mod log_buffer;
+mod logger;
+mod macros;
+
+pub use filter::*;
+pub use level::*;
+pub use line_buffer::*;
+pub use log_buffer::*;
+pub use logger::*;
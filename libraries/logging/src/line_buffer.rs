@@ -0,0 +1,56 @@
+use core::fmt;
+
+/// A fixed size buffer that a log line is formatted into before it is written out, so the
+/// line reaches each destination in one piece. Text that does not fit is dropped.
+pub struct LineBuffer<const N: usize> {
+    buffer: [u8; N],
+    len: usize,
+    truncated: bool,
+}
+
+impl<const N: usize> LineBuffer<N> {
+    pub const fn new() -> LineBuffer<N> {
+        return LineBuffer { buffer: [0; N], len: 0, truncated: false };
+    }
+
+    pub fn as_str(&self) -> &str {
+        //Only whole characters are ever copied in so this is always valid UTF-8
+        return unsafe { core::str::from_utf8_unchecked(&self.buffer[..self.len]) };
+    }
+
+    pub fn len(&self) -> usize {
+        return self.len;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.len == 0;
+    }
+
+    /// True if some of the text written did not fit
+    pub fn is_truncated(&self) -> bool {
+        return self.truncated;
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.truncated = false;
+    }
+}
+
+impl<const N: usize> fmt::Write for LineBuffer<N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let space = N - self.len;
+        let mut count = s.len();
+        if count > space {
+            count = space;
+            while !s.is_char_boundary(count) {
+                count -= 1;
+            }
+            self.truncated = true;
+        }
+
+        self.buffer[self.len..self.len + count].copy_from_slice(&s.as_bytes()[..count]);
+        self.len += count;
+        return Ok(());
+    }
+}
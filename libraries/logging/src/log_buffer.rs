@@ -0,0 +1,77 @@
+/// A ring of log lines that keeps the most recent output. When there is no room for a new
+/// line the oldest whole lines are dropped, so the contents always start at the beginning
+/// of a line. Every stored line ends with a newline.
+pub struct LogBuffer<const N: usize> {
+    buffer: [u8; N],
+    start: usize,
+    len: usize,
+    dropped_lines: usize,
+}
+
+impl<const N: usize> LogBuffer<N> {
+    pub const fn new() -> LogBuffer<N> {
+        return LogBuffer { buffer: [0; N], start: 0, len: 0, dropped_lines: 0 };
+    }
+
+    /// Appends *line*, adding a newline if it does not end with one. A line longer than the
+    /// whole buffer is cut short.
+    pub fn push_line(&mut self, line: &[u8]) {
+        if N == 0 {
+            return;
+        }
+
+        let line = line.strip_suffix(b"\n").unwrap_or(line);
+        let line = &line[..line.len().min(N - 1)];
+        while N - self.len < line.len() + 1 {
+            self.drop_oldest_line();
+        }
+
+        for &byte in line.iter().chain(b"\n") {
+            self.buffer[(self.start + self.len) % N] = byte;
+            self.len += 1;
+        }
+    }
+
+    fn drop_oldest_line(&mut self) {
+        let (first, second) = self.as_slices();
+        let line_length = match first.iter().chain(second.iter()).position(|&byte| byte == b'\n') {
+            Some(position) => position + 1,
+            None => self.len,
+        };
+
+        self.start = (self.start + line_length) % N;
+        self.len -= line_length;
+        self.dropped_lines += 1;
+    }
+
+    /// The contents oldest first. The ring may wrap so it is returned in two parts, the
+    /// second of which is often empty.
+    pub fn as_slices(&self) -> (&[u8], &[u8]) {
+        let end = self.start + self.len;
+        if end <= N {
+            return (&self.buffer[self.start..end], &[]);
+        } else {
+            return (&self.buffer[self.start..], &self.buffer[..end - N]);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        return self.len;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.len == 0;
+    }
+
+    /// The number of lines dropped to make room since the buffer was created
+    pub fn dropped_lines(&self) -> usize {
+        return self.dropped_lines;
+    }
+
+    /// Empties the buffer and resets the count of dropped lines
+    pub fn clear(&mut self) {
+        self.start = 0;
+        self.len = 0;
+        self.dropped_lines = 0;
+    }
+}
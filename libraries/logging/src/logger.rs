@@ -0,0 +1,73 @@
+use core::fmt;
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use spin::{Once, RwLock};
+
+use crate::{Filter, Level, LevelFilter};
+
+/// A single log message
+pub struct Record<'a> {
+    pub level: Level,
+    /// The path of the module the message came from, such as "kernel::pci"
+    pub module: &'a str,
+    pub args: fmt::Arguments<'a>,
+}
+
+/// Where log messages end up. The bootloader and kernel each install their own, which add
+/// timestamps and send the message to their outputs. It is only called for messages that
+/// pass the filter.
+pub trait Logger: Sync {
+    fn log(&self, record: &Record);
+}
+
+static LOGGER: Once<&'static dyn Logger> = Once::new();
+static FILTER: RwLock<Filter> = RwLock::new(Filter::new(LevelFilter::Info));
+/// A copy of *Filter::max_level* so disabled messages can be dropped without taking the lock
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(LevelFilter::Info as u8);
+
+/// Installs the logger. Messages logged before this are dropped. Returns false if a
+/// logger was already installed.
+pub fn set_logger(logger: &'static dyn Logger) -> bool {
+    let mut installed = false;
+    LOGGER.call_once(|| {
+        installed = true;
+        return logger;
+    });
+    return installed;
+}
+
+/// Replaces the filter. This should be done before interrupts are enabled as a message
+/// logged by an interrupt handler on the same processor would wait for it forever.
+pub fn set_filter(filter: Filter) {
+    let mut current = FILTER.write();
+    *current = filter;
+    MAX_LEVEL.store(filter.max_level().as_u8(), Ordering::Relaxed);
+}
+
+pub fn filter() -> Filter {
+    return *FILTER.read();
+}
+
+pub fn max_level() -> LevelFilter {
+    return LevelFilter::from_u8(MAX_LEVEL.load(Ordering::Relaxed));
+}
+
+/// True if a message at *level* from *module* would be logged
+pub fn enabled(level: Level, module: &str) -> bool {
+    if !max_level().allows(level) {
+        return false;
+    }
+    return FILTER.read().enabled(level, module);
+}
+
+#[doc(hidden)]
+pub fn _log(level: Level, module: &'static str, args: fmt::Arguments) {
+    let logger = match LOGGER.get() {
+        Some(logger) => logger,
+        None => { return; },
+    };
+
+    if enabled(level, module) {
+        logger.log(&Record { level: level, module: module, args: args });
+    }
+}
@@ -0,0 +1,70 @@
+#[cfg(test)]
+mod tests {
+    use logging::{Filter, FilterError, Level, LevelFilter};
+
+    #[test]
+    fn test_default_is_info() {
+        let filter = Filter::parse("").unwrap();
+
+        assert_eq!(LevelFilter::Info, filter.default_level());
+        assert_eq!(true, filter.enabled(Level::Info, "kernel::pci"));
+        assert_eq!(false, filter.enabled(Level::Debug, "kernel::pci"));
+    }
+
+    #[test]
+    fn test_bare_level_sets_default() {
+        let filter = Filter::parse("WARN").unwrap();
+
+        assert_eq!(LevelFilter::Warn, filter.default_level());
+        assert_eq!(false, filter.enabled(Level::Info, "kernel"));
+        assert_eq!(true, filter.enabled(Level::Error, "kernel"));
+    }
+
+    #[test]
+    fn test_module_directive_covers_submodules() {
+        let filter = Filter::parse("info, kernel::pci=trace").unwrap();
+
+        assert_eq!(LevelFilter::Trace, filter.level_for("kernel::pci"));
+        assert_eq!(LevelFilter::Trace, filter.level_for("kernel::pci::msi"));
+        assert_eq!(LevelFilter::Info, filter.level_for("kernel::pcie"));
+        assert_eq!(LevelFilter::Info, filter.level_for("kernel::memory"));
+        assert_eq!(LevelFilter::Trace, filter.max_level());
+    }
+
+    #[test]
+    fn test_most_specific_directive_wins() {
+        let filter = Filter::parse("kernel::time::tsc=off,kernel=debug,kernel::time=error").unwrap();
+
+        assert_eq!(LevelFilter::Debug, filter.level_for("kernel::smp"));
+        assert_eq!(LevelFilter::Error, filter.level_for("kernel::time::hpet"));
+        assert_eq!(LevelFilter::Off, filter.level_for("kernel::time::tsc"));
+        assert_eq!(LevelFilter::Info, filter.level_for("bootloader_uefi"));
+    }
+
+    #[test]
+    fn test_later_directive_replaces_earlier() {
+        let filter = Filter::parse("kernel=debug,kernel=error").unwrap();
+
+        assert_eq!(LevelFilter::Error, filter.level_for("kernel"));
+        assert_eq!(LevelFilter::Info, filter.max_level());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(FilterError::UnknownLevel, Filter::parse("loud").err().unwrap());
+        assert_eq!(FilterError::UnknownLevel, Filter::parse("kernel=loud").err().unwrap());
+        assert_eq!(FilterError::EmptyModuleName, Filter::parse("=debug").err().unwrap());
+        assert_eq!(FilterError::ModuleNameTooLong, Filter::parse(&format!("{}=debug", "a".repeat(65))).err().unwrap());
+        assert_eq!(FilterError::TooManyDirectives, Filter::parse("a=info,b=info,c=info,d=info,e=info,f=info,g=info,h=info,i=info").err().unwrap());
+    }
+
+    #[test]
+    fn test_from_command_line() {
+        let filter = Filter::from_command_line("root=/dev/sda log=warn,kernel::smp=trace loglevel=debug quiet").unwrap();
+
+        assert_eq!(LevelFilter::Debug, filter.default_level());
+        assert_eq!(LevelFilter::Trace, filter.level_for("kernel::smp"));
+        assert_eq!(LevelFilter::Info, Filter::from_command_line("").unwrap().default_level());
+        assert_eq!(FilterError::UnknownLevel, Filter::from_command_line("loglevel=7").err().unwrap());
+    }
+}
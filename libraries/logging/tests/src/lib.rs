@@ -0,0 +1,2 @@
+mod filter;
+mod log_buffer;
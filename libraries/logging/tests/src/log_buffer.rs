@@ -0,0 +1,90 @@
+#[cfg(test)]
+mod tests {
+    use core::fmt::Write;
+
+    use logging::{LineBuffer, LogBuffer};
+
+    fn contents<const N: usize>(buffer: &LogBuffer<N>) -> String {
+        let (first, second) = buffer.as_slices();
+        let mut bytes = first.to_vec();
+        bytes.extend_from_slice(second);
+        return String::from_utf8(bytes).unwrap();
+    }
+
+    #[test]
+    fn test_empty_on_init() {
+        let buffer = LogBuffer::<64>::new();
+
+        assert_eq!(true, buffer.is_empty());
+        assert_eq!("", contents(&buffer));
+    }
+
+    #[test]
+    fn test_lines_end_with_newline() {
+        let mut buffer = LogBuffer::<64>::new();
+        buffer.push_line(b"first");
+        buffer.push_line(b"second\n");
+
+        assert_eq!("first\nsecond\n", contents(&buffer));
+        assert_eq!(0, buffer.dropped_lines());
+    }
+
+    #[test]
+    fn test_oldest_lines_dropped_when_full() {
+        let mut buffer = LogBuffer::<16>::new();
+        buffer.push_line(b"aaaa");
+        buffer.push_line(b"bbbb");
+        buffer.push_line(b"cccc");
+        buffer.push_line(b"dddd");
+
+        assert_eq!("bbbb\ncccc\ndddd\n", contents(&buffer));
+        assert_eq!(1, buffer.dropped_lines());
+    }
+
+    #[test]
+    fn test_wraps_around() {
+        let mut buffer = LogBuffer::<16>::new();
+        for line in 0..100 {
+            buffer.push_line(format!("line {}", line).as_bytes());
+        }
+
+        assert_eq!("line 98\nline 99\n", contents(&buffer));
+        assert_eq!(98, buffer.dropped_lines());
+    }
+
+    #[test]
+    fn test_clear_resets_dropped_lines() {
+        let mut buffer = LogBuffer::<16>::new();
+        for line in 0..10 {
+            buffer.push_line(format!("line {}", line).as_bytes());
+        }
+        buffer.clear();
+
+        assert_eq!(true, buffer.is_empty());
+        assert_eq!(0, buffer.dropped_lines());
+        buffer.push_line(b"after");
+        assert_eq!("after\n", contents(&buffer));
+    }
+
+    #[test]
+    fn test_long_line_is_cut_short() {
+        let mut buffer = LogBuffer::<8>::new();
+        buffer.push_line(b"short");
+        buffer.push_line(b"much too long");
+
+        assert_eq!("much to\n", contents(&buffer));
+    }
+
+    #[test]
+    fn test_line_buffer_truncates_at_char_boundary() {
+        let mut line = LineBuffer::<8>::new();
+        write!(line, "{}", "abcdeé").unwrap();
+
+        assert_eq!("abcdeé", line.as_str());
+        assert_eq!(false, line.is_truncated());
+
+        write!(line, "éx").unwrap();
+        assert_eq!("abcdeé", line.as_str());
+        assert_eq!(true, line.is_truncated());
+    }
+}
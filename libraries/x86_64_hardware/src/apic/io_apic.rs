@@ -0,0 +1,243 @@
+use crate::memory::VirtualAddress;
+use spin::Mutex;
+
+const REGISTER_SELECT_OFFSET: u64 = 0x00;
+const REGISTER_WINDOW_OFFSET: u64 = 0x10;
+
+const ID_REGISTER: u32 = 0x00;
+const VERSION_REGISTER: u32 = 0x01;
+const REDIRECTION_TABLE_START: u32 = 0x10;
+
+const MAX_REDIRECTION_ENTRY_SHIFT: u32 = 16;
+
+const ENTRY_VECTOR_MASK: u64 = 0xFF;
+const ENTRY_DELIVERY_MODE_SHIFT: u64 = 8;
+const ENTRY_DELIVERY_MODE_MASK: u64 = 0b111;
+const ENTRY_ACTIVE_LOW: u64 = 1 << 13;
+const ENTRY_LEVEL_TRIGGERED: u64 = 1 << 15;
+const ENTRY_MASKED: u64 = 1 << 16;
+const ENTRY_DESTINATION_SHIFT: u64 = 56;
+
+/// The size of the IO APIC register block
+pub const IO_APIC_MMIO_SIZE: u64 = 0x20;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PinPolarity {
+    ActiveHigh,
+    ActiveLow,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TriggerMode {
+    Edge,
+    Level,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DeliveryMode {
+    Fixed,
+    LowestPriority,
+    Smi,
+    Nmi,
+    Init,
+    ExtInt,
+}
+
+impl DeliveryMode {
+    fn as_u64(&self) -> u64 {
+        match self {
+            DeliveryMode::Fixed => 0b000,
+            DeliveryMode::LowestPriority => 0b001,
+            DeliveryMode::Smi => 0b010,
+            DeliveryMode::Nmi => 0b100,
+            DeliveryMode::Init => 0b101,
+            DeliveryMode::ExtInt => 0b111,
+        }
+    }
+
+    fn from_u64(value: u64) -> DeliveryMode {
+        match value & ENTRY_DELIVERY_MODE_MASK {
+            0b001 => DeliveryMode::LowestPriority,
+            0b010 => DeliveryMode::Smi,
+            0b100 => DeliveryMode::Nmi,
+            0b101 => DeliveryMode::Init,
+            0b111 => DeliveryMode::ExtInt,
+            _ => DeliveryMode::Fixed,
+        }
+    }
+}
+
+/// A redirection table entry. Destinations are always physical APIC IDs so only processors
+/// with an APIC ID below 256 can be targeted.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct RedirectionEntry {
+    pub vector: u8,
+    pub delivery_mode: DeliveryMode,
+    pub polarity: PinPolarity,
+    pub trigger_mode: TriggerMode,
+    pub masked: bool,
+    pub destination: u8,
+}
+
+impl RedirectionEntry {
+    pub fn new(vector: u8, destination: u8, polarity: PinPolarity, trigger_mode: TriggerMode) -> RedirectionEntry {
+        return RedirectionEntry {
+            vector: vector,
+            delivery_mode: DeliveryMode::Fixed,
+            polarity: polarity,
+            trigger_mode: trigger_mode,
+            masked: false,
+            destination: destination,
+        };
+    }
+
+    pub fn as_u64(&self) -> u64 {
+        let mut value = self.vector as u64;
+        value |= self.delivery_mode.as_u64() << ENTRY_DELIVERY_MODE_SHIFT;
+        if self.polarity == PinPolarity::ActiveLow {
+            value |= ENTRY_ACTIVE_LOW;
+        }
+        if self.trigger_mode == TriggerMode::Level {
+            value |= ENTRY_LEVEL_TRIGGERED;
+        }
+        if self.masked {
+            value |= ENTRY_MASKED;
+        }
+        value |= (self.destination as u64) << ENTRY_DESTINATION_SHIFT;
+        return value;
+    }
+
+    pub fn from_u64(value: u64) -> RedirectionEntry {
+        return RedirectionEntry {
+            vector: (value & ENTRY_VECTOR_MASK) as u8,
+            delivery_mode: DeliveryMode::from_u64(value >> ENTRY_DELIVERY_MODE_SHIFT),
+            polarity: if value & ENTRY_ACTIVE_LOW != 0 { PinPolarity::ActiveLow } else { PinPolarity::ActiveHigh },
+            trigger_mode: if value & ENTRY_LEVEL_TRIGGERED != 0 { TriggerMode::Level } else { TriggerMode::Edge },
+            masked: value & ENTRY_MASKED != 0,
+            destination: (value >> ENTRY_DESTINATION_SHIFT) as u8,
+        };
+    }
+}
+
+/// An IO APIC. Each one handles a contiguous range of global system interrupts starting at
+/// *gsi_base*.
+pub struct IoApic {
+    base_address: VirtualAddress,
+    gsi_base: u32,
+    num_entries: u32,
+    //The select and window registers must be used as a pair
+    register_lock: Mutex<()>,
+}
+
+impl IoApic {
+    /// Creates a driver for the IO APIC mapped at *base_address*.
+    /// 
+    /// ## Safety
+    /// 
+    /// This is unsafe as we cannot know the mapping is valid. The caller must ensure the IO
+    /// APIC registers are mapped at *base_address* with caching disabled.
+    pub unsafe fn new(base_address: VirtualAddress, gsi_base: u32) -> IoApic {
+        let mut io_apic = IoApic {
+            base_address: base_address,
+            gsi_base: gsi_base,
+            num_entries: 0,
+            register_lock: Mutex::new(()),
+        };
+        io_apic.num_entries = ((io_apic.read_register(VERSION_REGISTER) >> MAX_REDIRECTION_ENTRY_SHIFT) & 0xFF) + 1;
+        return io_apic;
+    }
+
+    pub fn id(&self) -> u8 {
+        return ((self.read_register(ID_REGISTER) >> 24) & 0xF) as u8;
+    }
+
+    pub fn version(&self) -> u8 {
+        return self.read_register(VERSION_REGISTER) as u8;
+    }
+
+    pub fn gsi_base(&self) -> u32 {
+        return self.gsi_base;
+    }
+
+    pub fn num_entries(&self) -> u32 {
+        return self.num_entries;
+    }
+
+    pub fn handles_gsi(&self, gsi: u32) -> bool {
+        return self.gsi_base <= gsi && gsi < self.gsi_base + self.num_entries;
+    }
+
+    pub fn read_entry(&self, index: u32) -> Option<RedirectionEntry> {
+        if index >= self.num_entries {
+            return None;
+        }
+
+        let _lock_guard = self.register_lock.lock();
+        let low = self.read_register_unlocked(REDIRECTION_TABLE_START + index * 2) as u64;
+        let high = self.read_register_unlocked(REDIRECTION_TABLE_START + index * 2 + 1) as u64;
+        return Some(RedirectionEntry::from_u64(high << 32 | low));
+    }
+
+    pub fn write_entry(&self, index: u32, entry: RedirectionEntry) {
+        if index >= self.num_entries {
+            return;
+        }
+
+        let value = entry.as_u64();
+        let _lock_guard = self.register_lock.lock();
+        //Mask the entry while it is half written so a stale vector or destination cannot fire
+        self.write_register_unlocked(REDIRECTION_TABLE_START + index * 2, ENTRY_MASKED as u32);
+        self.write_register_unlocked(REDIRECTION_TABLE_START + index * 2 + 1, (value >> 32) as u32);
+        self.write_register_unlocked(REDIRECTION_TABLE_START + index * 2, value as u32);
+    }
+
+    /// Programs the entry for a global system interrupt. Returns false if this IO APIC does
+    /// not handle *gsi*.
+    pub fn set_gsi_entry(&self, gsi: u32, entry: RedirectionEntry) -> bool {
+        if !self.handles_gsi(gsi) {
+            return false;
+        }
+        self.write_entry(gsi - self.gsi_base, entry);
+        return true;
+    }
+
+    pub fn set_gsi_masked(&self, gsi: u32, masked: bool) {
+        if !self.handles_gsi(gsi) {
+            return;
+        }
+
+        let index = gsi - self.gsi_base;
+        if let Some(mut entry) = self.read_entry(index) {
+            entry.masked = masked;
+            self.write_entry(index, entry);
+        }
+    }
+
+    pub fn mask_all(&self) {
+        for index in 0..self.num_entries {
+            if let Some(mut entry) = self.read_entry(index) {
+                entry.masked = true;
+                self.write_entry(index, entry);
+            }
+        }
+    }
+
+    fn read_register(&self, register: u32) -> u32 {
+        let _lock_guard = self.register_lock.lock();
+        return self.read_register_unlocked(register);
+    }
+
+    fn read_register_unlocked(&self, register: u32) -> u32 {
+        unsafe {
+            core::ptr::write_volatile((self.base_address.as_u64() + REGISTER_SELECT_OFFSET) as *mut u32, register);
+            return core::ptr::read_volatile((self.base_address.as_u64() + REGISTER_WINDOW_OFFSET) as *const u32);
+        }
+    }
+
+    fn write_register_unlocked(&self, register: u32, value: u32) {
+        unsafe {
+            core::ptr::write_volatile((self.base_address.as_u64() + REGISTER_SELECT_OFFSET) as *mut u32, register);
+            core::ptr::write_volatile((self.base_address.as_u64() + REGISTER_WINDOW_OFFSET) as *mut u32, value);
+        }
+    }
+}
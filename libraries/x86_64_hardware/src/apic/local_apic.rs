@@ -0,0 +1,282 @@
+use crate::cpu;
+use crate::memory::{PhysicalAddress, VirtualAddress};
+
+const IA32_APIC_BASE_MSR: u32 = 0x1B;
+const IA32_TSC_DEADLINE_MSR: u32 = 0x6E0;
+const X2APIC_MSR_BASE: u32 = 0x800;
+
+const APIC_BASE_X2APIC_ENABLE: u64 = 1 << 10;
+const APIC_BASE_GLOBAL_ENABLE: u64 = 1 << 11;
+const APIC_BASE_ADDRESS_MASK: u64 = 0x000F_FFFF_FFFF_F000;
+
+const ID_REGISTER: u32 = 0x020;
+const VERSION_REGISTER: u32 = 0x030;
+const TASK_PRIORITY_REGISTER: u32 = 0x080;
+const EOI_REGISTER: u32 = 0x0B0;
+const SPURIOUS_VECTOR_REGISTER: u32 = 0x0F0;
+const ERROR_STATUS_REGISTER: u32 = 0x280;
+const INTERRUPT_COMMAND_LOW_REGISTER: u32 = 0x300;
+const INTERRUPT_COMMAND_HIGH_REGISTER: u32 = 0x310;
+const LVT_TIMER_REGISTER: u32 = 0x320;
+const LVT_ERROR_REGISTER: u32 = 0x370;
+const TIMER_INITIAL_COUNT_REGISTER: u32 = 0x380;
+const TIMER_CURRENT_COUNT_REGISTER: u32 = 0x390;
+const TIMER_DIVIDE_REGISTER: u32 = 0x3E0;
+
+const SPURIOUS_APIC_ENABLE: u32 = 1 << 8;
+const LVT_MASKED: u32 = 1 << 16;
+const LVT_TIMER_ONE_SHOT: u32 = 0b00 << 17;
+const LVT_TIMER_PERIODIC: u32 = 0b01 << 17;
+const LVT_TIMER_TSC_DEADLINE: u32 = 0b10 << 17;
+
+const ICR_DELIVERY_FIXED: u32 = 0b000 << 8;
+const ICR_DELIVERY_NMI: u32 = 0b100 << 8;
+const ICR_DELIVERY_INIT: u32 = 0b101 << 8;
+const ICR_DELIVERY_STARTUP: u32 = 0b110 << 8;
+const ICR_DELIVERY_PENDING: u32 = 1 << 12;
+const ICR_LEVEL_ASSERT: u32 = 1 << 14;
+const ICR_TRIGGER_LEVEL: u32 = 1 << 15;
+const ICR_SHORTHAND_SELF: u32 = 0b01 << 18;
+const ICR_SHORTHAND_ALL_EXCLUDING_SELF: u32 = 0b11 << 18;
+
+/// The size of the xAPIC register page
+pub const XAPIC_MMIO_SIZE: u64 = 4096;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ApicMode {
+    XApic,
+    X2Apic,
+}
+
+/// The value the timer input clock is divided by before it decrements the count
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TimerDivide {
+    By1,
+    By2,
+    By4,
+    By8,
+    By16,
+    By32,
+    By64,
+    By128,
+}
+
+impl TimerDivide {
+    fn register_value(&self) -> u32 {
+        match self {
+            TimerDivide::By1 => 0b1011,
+            TimerDivide::By2 => 0b0000,
+            TimerDivide::By4 => 0b0001,
+            TimerDivide::By8 => 0b0010,
+            TimerDivide::By16 => 0b0011,
+            TimerDivide::By32 => 0b1000,
+            TimerDivide::By64 => 0b1001,
+            TimerDivide::By128 => 0b1010,
+        }
+    }
+}
+
+/// The best mode the processor supports, or None if there is no local APIC
+pub fn supported_apic_mode() -> Option<ApicMode> {
+    if !cpu::has_apic() || !cpu::has_msr() {
+        return None;
+    } else if cpu::has_x2apic() {
+        return Some(ApicMode::X2Apic);
+    } else {
+        return Some(ApicMode::XApic);
+    }
+}
+
+/// The physical address of the xAPIC register page from the IA32_APIC_BASE MSR
+pub fn apic_base_address() -> PhysicalAddress {
+    return PhysicalAddress::new(unsafe { cpu::read_msr(IA32_APIC_BASE_MSR) } & APIC_BASE_ADDRESS_MASK);
+}
+
+/// The local APIC of the processor executing the code. Every processor sees its own APIC
+/// at the same address so a single instance can be shared between processors.
+pub struct LocalApic {
+    mode: ApicMode,
+    base_address: VirtualAddress,
+}
+
+impl LocalApic {
+    /// Creates a driver for an xAPIC. *base_address* is where the physical page from
+    /// *apic_base_address* has been mapped.
+    /// 
+    /// ## Safety
+    /// 
+    /// This is unsafe as we cannot know the mapping is valid. The caller must ensure the
+    /// register page is mapped at *base_address* with caching disabled.
+    pub const unsafe fn new_xapic(base_address: VirtualAddress) -> LocalApic {
+        return LocalApic { mode: ApicMode::XApic, base_address: base_address };
+    }
+
+    /// Creates a driver for an x2APIC, which is accessed through MSRs rather than MMIO.
+    /// 
+    /// ## Safety
+    /// 
+    /// This is unsafe as the processor must support x2APIC mode.
+    pub const unsafe fn new_x2apic() -> LocalApic {
+        return LocalApic { mode: ApicMode::X2Apic, base_address: VirtualAddress::new(0) };
+    }
+
+    pub fn mode(&self) -> ApicMode {
+        return self.mode;
+    }
+
+    /// Enables the local APIC of the current processor and routes spurious interrupts to
+    /// *spurious_vector*. This must be run on every processor. The spurious vector handler
+    /// must not send an EOI.
+    pub fn enable(&self, spurious_vector: u8) {
+        unsafe {
+            let mut apic_base = cpu::read_msr(IA32_APIC_BASE_MSR) | APIC_BASE_GLOBAL_ENABLE;
+            cpu::write_msr(IA32_APIC_BASE_MSR, apic_base);
+            //x2APIC mode can only be entered once the APIC is globally enabled
+            if self.mode == ApicMode::X2Apic {
+                apic_base |= APIC_BASE_X2APIC_ENABLE;
+                cpu::write_msr(IA32_APIC_BASE_MSR, apic_base);
+            }
+        }
+
+        self.write_register(TASK_PRIORITY_REGISTER, 0);
+        self.write_register(SPURIOUS_VECTOR_REGISTER, SPURIOUS_APIC_ENABLE | spurious_vector as u32);
+    }
+
+    /// Routes APIC internal errors to *vector*
+    pub fn set_error_vector(&self, vector: u8) {
+        self.write_register(LVT_ERROR_REGISTER, vector as u32);
+    }
+
+    /// Reads and clears the error status
+    pub fn error_status(&self) -> u32 {
+        //The register must be written before reading to latch the current errors
+        self.write_register(ERROR_STATUS_REGISTER, 0);
+        return self.read_register(ERROR_STATUS_REGISTER);
+    }
+
+    pub fn id(&self) -> u32 {
+        match self.mode {
+            ApicMode::XApic => self.read_register(ID_REGISTER) >> 24,
+            ApicMode::X2Apic => self.read_register(ID_REGISTER),
+        }
+    }
+
+    pub fn version(&self) -> u8 {
+        return self.read_register(VERSION_REGISTER) as u8;
+    }
+
+    pub fn end_of_interrupt(&self) {
+        self.write_register(EOI_REGISTER, 0);
+    }
+
+    pub fn set_timer_divide(&self, divide: TimerDivide) {
+        self.write_register(TIMER_DIVIDE_REGISTER, divide.register_value());
+    }
+
+    /// Fires *vector* once after *initial_count* timer ticks
+    pub fn start_one_shot_timer(&self, vector: u8, initial_count: u32) {
+        self.write_register(LVT_TIMER_REGISTER, LVT_TIMER_ONE_SHOT | vector as u32);
+        self.write_register(TIMER_INITIAL_COUNT_REGISTER, initial_count);
+    }
+
+    /// Fires *vector* every *initial_count* timer ticks
+    pub fn start_periodic_timer(&self, vector: u8, initial_count: u32) {
+        self.write_register(LVT_TIMER_REGISTER, LVT_TIMER_PERIODIC | vector as u32);
+        self.write_register(TIMER_INITIAL_COUNT_REGISTER, initial_count);
+    }
+
+    /// Fires *vector* once the TSC reaches *deadline*. Returns false if the processor does
+    /// not support TSC-deadline mode.
+    pub fn start_tsc_deadline_timer(&self, vector: u8, deadline: u64) -> bool {
+        if !cpu::has_tsc_deadline() {
+            return false;
+        }
+
+        self.write_register(LVT_TIMER_REGISTER, LVT_TIMER_TSC_DEADLINE | vector as u32);
+        unsafe {
+            //Serialise the LVT write before arming the deadline as the SDM recommends
+            core::arch::asm!("mfence", options(nostack, preserves_flags));
+            cpu::write_msr(IA32_TSC_DEADLINE_MSR, deadline);
+        }
+        return true;
+    }
+
+    pub fn stop_timer(&self) {
+        self.write_register(LVT_TIMER_REGISTER, LVT_MASKED);
+        self.write_register(TIMER_INITIAL_COUNT_REGISTER, 0);
+        if cpu::has_tsc_deadline() {
+            unsafe { cpu::write_msr(IA32_TSC_DEADLINE_MSR, 0) };
+        }
+    }
+
+    pub fn timer_current_count(&self) -> u32 {
+        return self.read_register(TIMER_CURRENT_COUNT_REGISTER);
+    }
+
+    /// Sends a fixed interrupt to the processor with the given APIC ID
+    pub fn send_ipi(&self, destination: u32, vector: u8) {
+        self.send_interrupt_command(destination, ICR_DELIVERY_FIXED | ICR_LEVEL_ASSERT | vector as u32);
+    }
+
+    pub fn send_ipi_to_self(&self, vector: u8) {
+        self.send_interrupt_command(0, ICR_SHORTHAND_SELF | ICR_DELIVERY_FIXED | ICR_LEVEL_ASSERT | vector as u32);
+    }
+
+    pub fn send_ipi_to_all_excluding_self(&self, vector: u8) {
+        self.send_interrupt_command(0, ICR_SHORTHAND_ALL_EXCLUDING_SELF | ICR_DELIVERY_FIXED | ICR_LEVEL_ASSERT | vector as u32);
+    }
+
+    pub fn send_nmi(&self, destination: u32) {
+        self.send_interrupt_command(destination, ICR_DELIVERY_NMI | ICR_LEVEL_ASSERT);
+    }
+
+    /// Sends an INIT IPI, the first step of starting an application processor
+    pub fn send_init(&self, destination: u32) {
+        self.send_interrupt_command(destination, ICR_DELIVERY_INIT | ICR_LEVEL_ASSERT | ICR_TRIGGER_LEVEL);
+    }
+
+    /// Sends a startup IPI. The processor starts in real mode at *start_page* * 4096 so the
+    /// trampoline must be below 1MiB.
+    pub fn send_startup(&self, destination: u32, start_page: u8) {
+        self.send_interrupt_command(destination, ICR_DELIVERY_STARTUP | ICR_LEVEL_ASSERT | start_page as u32);
+    }
+
+    fn send_interrupt_command(&self, destination: u32, command: u32) {
+        match self.mode {
+            ApicMode::XApic => {
+                self.write_register(INTERRUPT_COMMAND_HIGH_REGISTER, destination << 24);
+                self.write_register(INTERRUPT_COMMAND_LOW_REGISTER, command);
+                while self.read_register(INTERRUPT_COMMAND_LOW_REGISTER) & ICR_DELIVERY_PENDING != 0 {
+                    core::hint::spin_loop();
+                }
+            },
+            ApicMode::X2Apic => {
+                //In x2APIC mode the ICR is a single 64 bit MSR and there is no delivery status
+                let value = (destination as u64) << 32 | command as u64;
+                unsafe { cpu::write_msr(X2APIC_MSR_BASE + (INTERRUPT_COMMAND_LOW_REGISTER >> 4), value) };
+            },
+        }
+    }
+
+    fn read_register(&self, offset: u32) -> u32 {
+        match self.mode {
+            ApicMode::XApic => unsafe {
+                core::ptr::read_volatile((self.base_address.as_u64() + offset as u64) as *const u32)
+            },
+            ApicMode::X2Apic => unsafe {
+                cpu::read_msr(X2APIC_MSR_BASE + (offset >> 4)) as u32
+            },
+        }
+    }
+
+    fn write_register(&self, offset: u32, value: u32) {
+        match self.mode {
+            ApicMode::XApic => unsafe {
+                core::ptr::write_volatile((self.base_address.as_u64() + offset as u64) as *mut u32, value);
+            },
+            ApicMode::X2Apic => unsafe {
+                cpu::write_msr(X2APIC_MSR_BASE + (offset >> 4), value as u64);
+            },
+        }
+    }
+}
@@ -0,0 +1,5 @@
+mod io_apic;
+mod local_apic;
+
+pub use io_apic::*;
+pub use local_apic::*;
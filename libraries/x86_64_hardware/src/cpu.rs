@@ -0,0 +1,257 @@
+#[derive(Clone, Copy, Debug)]
+pub struct CpuidResult {
+    pub eax: u32,
+    pub ebx: u32,
+    pub ecx: u32,
+    pub edx: u32,
+}
+
+const CPUID_MAX_LEAF: u32 = 0x0;
+const CPUID_FEATURES_LEAF: u32 = 0x1;
+const CPUID_TSC_CRYSTAL_LEAF: u32 = 0x15;
+const CPUID_PROCESSOR_FREQUENCY_LEAF: u32 = 0x16;
+const CPUID_EXTENDED_MAX_LEAF: u32 = 0x8000_0000;
+const CPUID_EXTENDED_FEATURES_LEAF: u32 = 0x8000_0001;
+const CPUID_ADVANCED_POWER_LEAF: u32 = 0x8000_0007;
+
+const IA32_PAT_MSR: u32 = 0x277;
+const IA32_EFER_MSR: u32 = 0xC000_0080;
+const IA32_GS_BASE_MSR: u32 = 0xC000_0101;
+const IA32_KERNEL_GS_BASE_MSR: u32 = 0xC000_0102;
+
+const FEATURE_ECX_X2APIC: u32 = 1 << 21;
+const FEATURE_ECX_TSC_DEADLINE: u32 = 1 << 24;
+const FEATURE_EDX_TSC: u32 = 1 << 4;
+const FEATURE_EDX_MSR: u32 = 1 << 5;
+const FEATURE_EDX_APIC: u32 = 1 << 9;
+const FEATURE_EDX_PAT: u32 = 1 << 16;
+const EXTENDED_FEATURE_EDX_NO_EXECUTE: u32 = 1 << 20;
+const ADVANCED_POWER_EDX_INVARIANT_TSC: u32 = 1 << 8;
+
+const EFER_NO_EXECUTE_ENABLE: u64 = 1 << 11;
+const CR0_WRITE_PROTECT: u64 = 1 << 16;
+/// The PAT entry used by pages with write-through set and cache disable clear
+const PAT_WRITE_COMBINING_ENTRY: u64 = 1;
+const PAT_WRITE_COMBINING: u64 = 0x01;
+
+pub fn cpuid(leaf: u32, subleaf: u32) -> CpuidResult {
+    let eax: u32;
+    let ebx: u64;
+    let ecx: u32;
+    let edx: u32;
+    unsafe {
+        //rbx is reserved by LLVM so it has to be saved around CPUID
+        core::arch::asm!(
+            "mov {0}, rbx",
+            "cpuid",
+            "xchg {0}, rbx",
+            out(reg) ebx,
+            inout("eax") leaf => eax,
+            inout("ecx") subleaf => ecx,
+            out("edx") edx,
+            options(nomem, nostack, preserves_flags),
+        );
+    }
+
+    return CpuidResult { eax: eax, ebx: ebx as u32, ecx: ecx, edx: edx };
+}
+
+fn features() -> CpuidResult {
+    return cpuid(CPUID_FEATURES_LEAF, 0);
+}
+
+pub fn has_apic() -> bool {
+    return features().edx & FEATURE_EDX_APIC != 0;
+}
+
+pub fn has_x2apic() -> bool {
+    return features().ecx & FEATURE_ECX_X2APIC != 0;
+}
+
+pub fn has_msr() -> bool {
+    return features().edx & FEATURE_EDX_MSR != 0;
+}
+
+pub fn has_tsc() -> bool {
+    return features().edx & FEATURE_EDX_TSC != 0;
+}
+
+pub fn has_tsc_deadline() -> bool {
+    return features().ecx & FEATURE_ECX_TSC_DEADLINE != 0;
+}
+
+/// An invariant TSC runs at a constant rate in all power states
+pub fn has_invariant_tsc() -> bool {
+    if cpuid(CPUID_EXTENDED_MAX_LEAF, 0).eax < CPUID_ADVANCED_POWER_LEAF {
+        return false;
+    }
+    return cpuid(CPUID_ADVANCED_POWER_LEAF, 0).edx & ADVANCED_POWER_EDX_INVARIANT_TSC != 0;
+}
+
+/// True if pages can be marked no execute once *enable_no_execute* has been called
+pub fn has_no_execute() -> bool {
+    if cpuid(CPUID_EXTENDED_MAX_LEAF, 0).eax < CPUID_EXTENDED_FEATURES_LEAF {
+        return false;
+    }
+    return cpuid(CPUID_EXTENDED_FEATURES_LEAF, 0).edx & EXTENDED_FEATURE_EDX_NO_EXECUTE != 0;
+}
+
+/// Sets EFER.NXE so the execute disable bit in page table entries is honoured. Until it is
+/// set that bit is reserved and any entry with it set faults.
+/// 
+/// ## Safety
+/// 
+/// This is unsafe as it raises a general protection fault if *has_no_execute* is false.
+pub unsafe fn enable_no_execute() {
+    write_msr(IA32_EFER_MSR, read_msr(IA32_EFER_MSR) | EFER_NO_EXECUTE_ENABLE);
+}
+
+/// Sets CR0.WP so supervisor code faults on writes to read only pages rather than ignoring
+/// the read/write bit.
+/// 
+/// ## Safety
+/// 
+/// This is unsafe as anything that writes to a page mapped read only, such as its own code,
+/// will fault from now on.
+pub unsafe fn enable_write_protect() {
+    let mut cr0: u64;
+    core::arch::asm!("mov {}, cr0", out(reg) cr0, options(nomem, nostack, preserves_flags));
+    cr0 |= CR0_WRITE_PROTECT;
+    core::arch::asm!("mov cr0, {}", in(reg) cr0, options(nostack, preserves_flags));
+}
+
+pub fn has_pat() -> bool {
+    return cpuid(CPUID_FEATURES_LEAF, 0).edx & FEATURE_EDX_PAT != 0;
+}
+
+/// Changes PAT entry 1 from its default of write-through to write-combining, so pages with
+/// only the write-through bit set are write-combining.
+/// 
+/// ## Safety
+/// 
+/// This is unsafe as it raises a general protection fault if *has_pat* is false. Every CPU
+/// must do this before using such pages so they all agree on the memory type.
+pub unsafe fn enable_write_combining() {
+    let shift = PAT_WRITE_COMBINING_ENTRY * 8;
+    let pat = read_msr(IA32_PAT_MSR) & !(0xFF << shift);
+    write_msr(IA32_PAT_MSR, pat | PAT_WRITE_COMBINING << shift);
+}
+
+/// The address that caused the most recent page fault
+pub fn read_cr2() -> u64 {
+    let cr2: u64;
+    unsafe { core::arch::asm!("mov {}, cr2", out(reg) cr2, options(nomem, nostack, preserves_flags)); }
+    return cr2;
+}
+
+/// The TSC frequency in Hz as reported by the processor. Leaf 0x15 gives the exact ratio to
+/// the crystal clock; leaf 0x16 only gives the nominal base frequency so it is used as a
+/// fallback. Returns None when neither is available and the TSC must be calibrated.
+pub fn tsc_frequency_from_cpuid() -> Option<u64> {
+    let max_leaf = cpuid(CPUID_MAX_LEAF, 0).eax;
+
+    if max_leaf >= CPUID_TSC_CRYSTAL_LEAF {
+        let crystal = cpuid(CPUID_TSC_CRYSTAL_LEAF, 0);
+        //eax is the denominator and ebx the numerator of the TSC/crystal ratio, ecx is the
+        //crystal frequency. Any of them may be 0 if not enumerated.
+        if crystal.eax != 0 && crystal.ebx != 0 && crystal.ecx != 0 {
+            return Some(crystal.ecx as u64 * crystal.ebx as u64 / crystal.eax as u64);
+        }
+    }
+
+    if max_leaf >= CPUID_PROCESSOR_FREQUENCY_LEAF {
+        let base_mhz = cpuid(CPUID_PROCESSOR_FREQUENCY_LEAF, 0).eax & 0xFFFF;
+        if base_mhz != 0 {
+            return Some(base_mhz as u64 * 1_000_000);
+        }
+    }
+
+    return None;
+}
+
+/// The initial APIC ID of the current processor. This is limited to 8 bits; use the local
+/// APIC itself to read a full x2APIC ID.
+pub fn initial_apic_id() -> u8 {
+    return (features().ebx >> 24) as u8;
+}
+
+/// Reads a model specific register.
+/// 
+/// ## Safety
+/// 
+/// This is unsafe as reading an MSR that does not exist raises a general protection fault.
+pub unsafe fn read_msr(msr: u32) -> u64 {
+    let low: u32;
+    let high: u32;
+    core::arch::asm!("rdmsr", in("ecx") msr, out("eax") low, out("edx") high, options(nomem, nostack, preserves_flags));
+    return (high as u64) << 32 | low as u64;
+}
+
+/// Writes a model specific register.
+/// 
+/// ## Safety
+/// 
+/// This is unsafe as MSRs control fundamental processor behaviour and writing one that does
+/// not exist raises a general protection fault.
+pub unsafe fn write_msr(msr: u32, value: u64) {
+    core::arch::asm!("wrmsr", in("ecx") msr, in("eax") value as u32, in("edx") (value >> 32) as u32, options(nostack, preserves_flags));
+}
+
+/// Reads the time stamp counter
+#[inline]
+pub fn read_tsc() -> u64 {
+    let low: u32;
+    let high: u32;
+    unsafe { core::arch::asm!("rdtsc", out("eax") low, out("edx") high, options(nomem, nostack, preserves_flags)); }
+    return (high as u64) << 32 | low as u64;
+}
+
+/// Sets the GS base of the current processor.
+/// 
+/// ## Safety
+/// 
+/// This is unsafe as anything addressed through GS, such as per-CPU data, will be read
+/// from the new base.
+pub unsafe fn write_gs_base(base: u64) {
+    write_msr(IA32_GS_BASE_MSR, base);
+}
+
+pub fn read_gs_base() -> u64 {
+    return unsafe { read_msr(IA32_GS_BASE_MSR) };
+}
+
+/// Sets the GS base that *swapgs* exchanges with the active GS base.
+/// 
+/// ## Safety
+/// 
+/// This is unsafe as the value becomes the active GS base after the next *swapgs*.
+pub unsafe fn write_kernel_gs_base(base: u64) {
+    write_msr(IA32_KERNEL_GS_BASE_MSR, base);
+}
+
+pub fn read_kernel_gs_base() -> u64 {
+    return unsafe { read_msr(IA32_KERNEL_GS_BASE_MSR) };
+}
+
+/// Exchanges the GS base with the kernel GS base.
+/// 
+/// ## Safety
+/// 
+/// This is unsafe as it must be paired on every transition between user and kernel mode.
+/// Swapping twice or not at all leaves the kernel using the user GS base.
+#[inline]
+pub unsafe fn swapgs() {
+    core::arch::asm!("swapgs", options(nomem, nostack, preserves_flags));
+}
+
+/// Reads the 64 bit value at *offset* from the GS base.
+/// 
+/// ## Safety
+/// 
+/// This is unsafe as the GS base must point to memory that is valid at *offset*.
+#[inline]
+pub unsafe fn read_gs_u64(offset: usize) -> u64 {
+    let value: u64;
+    core::arch::asm!("mov {}, gs:[{}]", out(reg) value, in(reg) offset, options(readonly, nostack, preserves_flags));
+    return value;
+}
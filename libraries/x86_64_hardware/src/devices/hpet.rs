@@ -0,0 +1,295 @@
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use crate::memory::VirtualAddress;
+
+const CAPABILITIES_REGISTER: u64 = 0x000;
+const CONFIGURATION_REGISTER: u64 = 0x010;
+const INTERRUPT_STATUS_REGISTER: u64 = 0x020;
+const MAIN_COUNTER_REGISTER: u64 = 0x0F0;
+const TIMER_BLOCK_START: u64 = 0x100;
+const TIMER_BLOCK_SIZE: u64 = 0x20;
+const TIMER_CONFIGURATION_OFFSET: u64 = 0x00;
+const TIMER_COMPARATOR_OFFSET: u64 = 0x08;
+
+const CAPABILITY_NUM_TIMERS_SHIFT: u64 = 8;
+const CAPABILITY_NUM_TIMERS_MASK: u64 = 0x1F;
+const CAPABILITY_COUNTER_64_BIT: u64 = 1 << 13;
+const CAPABILITY_LEGACY_REPLACEMENT: u64 = 1 << 15;
+const CAPABILITY_PERIOD_SHIFT: u64 = 32;
+
+const CONFIGURATION_ENABLE: u64 = 1 << 0;
+const CONFIGURATION_LEGACY_REPLACEMENT: u64 = 1 << 1;
+
+const TIMER_LEVEL_TRIGGERED: u64 = 1 << 1;
+const TIMER_INTERRUPT_ENABLE: u64 = 1 << 2;
+const TIMER_PERIODIC: u64 = 1 << 3;
+const TIMER_PERIODIC_CAPABLE: u64 = 1 << 4;
+const TIMER_64_BIT_CAPABLE: u64 = 1 << 5;
+const TIMER_VALUE_SET: u64 = 1 << 6;
+const TIMER_32_BIT_MODE: u64 = 1 << 8;
+const TIMER_ROUTE_SHIFT: u64 = 9;
+const TIMER_ROUTE_MASK: u64 = 0x1F << TIMER_ROUTE_SHIFT;
+const TIMER_ROUTE_CAPABILITY_SHIFT: u64 = 32;
+
+const FEMTOSECONDS_PER_NANOSECOND: u128 = 1_000_000;
+/// The longest main counter period the HPET specification allows, 100ns
+const MAX_PERIOD_FS: u64 = 0x05F5_E100;
+const MAX_COMPARATORS: u64 = 32;
+
+/// The number of bytes of MMIO space used by the HPET registers, enough for all 32
+/// comparators
+pub const HPET_REGISTER_BLOCK_SIZE: u64 = TIMER_BLOCK_START + MAX_COMPARATORS * TIMER_BLOCK_SIZE;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum HpetError {
+    InvalidComparator,
+    PeriodicNotSupported,
+    RouteNotSupported,
+    PeriodTooShort,
+}
+
+/// A High Precision Event Timer block. The main counter runs at a fixed frequency so it
+/// provides a monotonic clock, and each comparator can raise an interrupt either once or
+/// periodically.
+pub struct Hpet {
+    base_address: VirtualAddress,
+    period_fs: u64,
+    num_comparators: u8,
+    counter_64_bit: bool,
+    minimum_tick: u64,
+    //Used to extend a 32 bit main counter to 64 bits
+    last_counter: AtomicU64,
+}
+
+impl Hpet {
+    /// Creates a new HPET driver. *minimum_tick* is the minimum periodic tick from the ACPI
+    /// HPET table. Returns None if the counter period is 0 or longer than the specification
+    /// allows, as it is on broken firmware.
+    /// 
+    /// ## Safety
+    /// 
+    /// This is unsafe as we cannot know the mapping is valid. The caller must ensure the HPET
+    /// register block is mapped at *base_address* with caching disabled.
+    pub unsafe fn new(base_address: VirtualAddress, minimum_tick: u64) -> Option<Hpet> {
+        let capabilities = core::ptr::read_volatile(base_address.get_mut_ptr::<u64>());
+        let period_fs = capabilities >> CAPABILITY_PERIOD_SHIFT;
+        if period_fs == 0 || period_fs > MAX_PERIOD_FS {
+            return None;
+        }
+
+        return Some(Hpet {
+            base_address: base_address,
+            period_fs: period_fs,
+            num_comparators: ((capabilities >> CAPABILITY_NUM_TIMERS_SHIFT) & CAPABILITY_NUM_TIMERS_MASK) as u8 + 1,
+            counter_64_bit: capabilities & CAPABILITY_COUNTER_64_BIT != 0,
+            minimum_tick: minimum_tick,
+            last_counter: AtomicU64::new(0),
+        });
+    }
+
+    /// The period of the main counter in femtoseconds
+    pub fn period_fs(&self) -> u64 {
+        return self.period_fs;
+    }
+
+    /// The frequency of the main counter in Hz
+    pub fn frequency(&self) -> u64 {
+        return 1_000_000_000_000_000 / self.period_fs;
+    }
+
+    pub fn num_comparators(&self) -> u8 {
+        return self.num_comparators;
+    }
+
+    pub fn counter_is_64_bit(&self) -> bool {
+        return self.counter_64_bit;
+    }
+
+    pub fn legacy_replacement_capable(&self) -> bool {
+        return self.read_register(CAPABILITIES_REGISTER) & CAPABILITY_LEGACY_REPLACEMENT != 0;
+    }
+
+    /// Starts the main counter
+    pub fn enable(&self) {
+        let configuration = self.read_register(CONFIGURATION_REGISTER);
+        self.write_register(CONFIGURATION_REGISTER, configuration | CONFIGURATION_ENABLE);
+    }
+
+    /// Halts the main counter. Comparators do not fire while it is halted
+    pub fn disable(&self) {
+        let configuration = self.read_register(CONFIGURATION_REGISTER);
+        self.write_register(CONFIGURATION_REGISTER, configuration & !CONFIGURATION_ENABLE);
+    }
+
+    /// Routes comparator 0 to IRQ0 and comparator 1 to IRQ8 in place of the PIT and RTC
+    pub fn set_legacy_replacement(&self, enable: bool) {
+        let configuration = self.read_register(CONFIGURATION_REGISTER);
+        if enable {
+            self.write_register(CONFIGURATION_REGISTER, configuration | CONFIGURATION_LEGACY_REPLACEMENT);
+        } else {
+            self.write_register(CONFIGURATION_REGISTER, configuration & !CONFIGURATION_LEGACY_REPLACEMENT);
+        }
+    }
+
+    /// The raw value of the main counter extended to 64 bits. A 32 bit counter is extended in
+    /// software so this must be called at least once per counter wrap (around 5 minutes at
+    /// typical frequencies) to stay monotonic.
+    pub fn counter(&self) -> u64 {
+        let raw_counter = self.read_register(MAIN_COUNTER_REGISTER);
+        if self.counter_64_bit {
+            return raw_counter;
+        }
+
+        let raw_counter = raw_counter & 0xFFFF_FFFF;
+        let mut last_counter = self.last_counter.load(Ordering::Relaxed);
+        loop {
+            let mut new_counter = (last_counter & !0xFFFF_FFFF) | raw_counter;
+            if new_counter < last_counter {
+                new_counter += 1 << 32;
+            }
+
+            match self.last_counter.compare_exchange(last_counter, new_counter, Ordering::Relaxed, Ordering::Relaxed) {
+                Ok(_) => { return new_counter; },
+                Err(current) => {
+                    if current >= new_counter {
+                        return current;
+                    }
+                    last_counter = current;
+                },
+            }
+        }
+    }
+
+    /// Nanoseconds since the main counter was last reset
+    pub fn nanoseconds(&self) -> u64 {
+        return self.ticks_to_nanoseconds(self.counter());
+    }
+
+    pub fn ticks_to_nanoseconds(&self, ticks: u64) -> u64 {
+        return (ticks as u128 * self.period_fs as u128 / FEMTOSECONDS_PER_NANOSECOND) as u64;
+    }
+
+    pub fn nanoseconds_to_ticks(&self, nanoseconds: u64) -> u64 {
+        return (nanoseconds as u128 * FEMTOSECONDS_PER_NANOSECOND / self.period_fs as u128) as u64;
+    }
+
+    /// Busy waits for at least the given number of nanoseconds
+    pub fn busy_wait_ns(&self, nanoseconds: u64) {
+        let target = self.counter() + self.nanoseconds_to_ticks(nanoseconds);
+        while self.counter() < target {
+            core::hint::spin_loop();
+        }
+    }
+
+    pub fn comparator_supports_periodic(&self, comparator: u8) -> bool {
+        if comparator >= self.num_comparators {
+            return false;
+        }
+        return self.read_timer_register(comparator, TIMER_CONFIGURATION_OFFSET) & TIMER_PERIODIC_CAPABLE != 0;
+    }
+
+    /// A bitmask of the IO APIC inputs this comparator can be routed to
+    pub fn comparator_routes(&self, comparator: u8) -> u32 {
+        if comparator >= self.num_comparators {
+            return 0;
+        }
+        return (self.read_timer_register(comparator, TIMER_CONFIGURATION_OFFSET) >> TIMER_ROUTE_CAPABILITY_SHIFT) as u32;
+    }
+
+    /// Arms *comparator* to fire once, *nanoseconds* from now, on IO APIC input *route*.
+    /// In legacy replacement mode the route of comparators 0 and 1 is ignored.
+    pub fn set_one_shot(&self, comparator: u8, nanoseconds: u64, route: u8) -> Result<(), HpetError> {
+        let configuration = self.comparator_configuration(comparator, route)?;
+        self.write_timer_register(comparator, TIMER_CONFIGURATION_OFFSET, configuration);
+
+        let target = self.counter().wrapping_add(self.nanoseconds_to_ticks(nanoseconds));
+        self.write_timer_register(comparator, TIMER_COMPARATOR_OFFSET, target);
+        return Ok(());
+    }
+
+    /// Arms *comparator* to fire every *period_ns* nanoseconds on IO APIC input *route*.
+    /// In legacy replacement mode the route of comparators 0 and 1 is ignored.
+    pub fn set_periodic(&self, comparator: u8, period_ns: u64, route: u8) -> Result<(), HpetError> {
+        if !self.comparator_supports_periodic(comparator) {
+            return Err(HpetError::PeriodicNotSupported);
+        }
+
+        let period_ticks = self.nanoseconds_to_ticks(period_ns);
+        if period_ticks < self.minimum_tick || period_ticks == 0 {
+            return Err(HpetError::PeriodTooShort);
+        }
+
+        let configuration = self.comparator_configuration(comparator, route)? | TIMER_PERIODIC | TIMER_VALUE_SET;
+        self.write_timer_register(comparator, TIMER_CONFIGURATION_OFFSET, configuration);
+
+        //With VALUE_SET the first write sets the comparator and the second sets the accumulator
+        //that is added to it each period
+        self.write_timer_register(comparator, TIMER_COMPARATOR_OFFSET, self.counter().wrapping_add(period_ticks));
+        self.write_timer_register(comparator, TIMER_COMPARATOR_OFFSET, period_ticks);
+        return Ok(());
+    }
+
+    pub fn disable_comparator(&self, comparator: u8) {
+        if comparator >= self.num_comparators {
+            return;
+        }
+
+        let configuration = self.read_timer_register(comparator, TIMER_CONFIGURATION_OFFSET);
+        self.write_timer_register(comparator, TIMER_CONFIGURATION_OFFSET, configuration & !(TIMER_INTERRUPT_ENABLE | TIMER_PERIODIC));
+    }
+
+    /// Clears the interrupt status of a level triggered comparator. Edge triggered comparators
+    /// do not need this.
+    pub fn acknowledge(&self, comparator: u8) {
+        if comparator < self.num_comparators {
+            self.write_register(INTERRUPT_STATUS_REGISTER, 1 << comparator);
+        }
+    }
+
+    /// Builds an edge triggered, interrupt enabled configuration for a comparator
+    fn comparator_configuration(&self, comparator: u8, route: u8) -> Result<u64, HpetError> {
+        if comparator >= self.num_comparators {
+            return Err(HpetError::InvalidComparator);
+        }
+
+        if route >= 32 || self.comparator_routes(comparator) & (1 << route) == 0 {
+            return Err(HpetError::RouteNotSupported);
+        }
+
+        let mut configuration = self.read_timer_register(comparator, TIMER_CONFIGURATION_OFFSET);
+        configuration &= !(TIMER_LEVEL_TRIGGERED | TIMER_PERIODIC | TIMER_VALUE_SET | TIMER_ROUTE_MASK);
+        configuration |= TIMER_INTERRUPT_ENABLE;
+        configuration |= (route as u64) << TIMER_ROUTE_SHIFT;
+
+        //Run the comparator at the same width as the main counter where possible
+        if configuration & TIMER_64_BIT_CAPABLE != 0 && self.counter_64_bit {
+            configuration &= !TIMER_32_BIT_MODE;
+        } else {
+            configuration |= TIMER_32_BIT_MODE;
+        }
+
+        return Ok(configuration);
+    }
+
+    fn read_timer_register(&self, comparator: u8, offset: u64) -> u64 {
+        return self.read_register(TIMER_BLOCK_START + comparator as u64 * TIMER_BLOCK_SIZE + offset);
+    }
+
+    fn write_timer_register(&self, comparator: u8, offset: u64, value: u64) {
+        self.write_register(TIMER_BLOCK_START + comparator as u64 * TIMER_BLOCK_SIZE + offset, value);
+    }
+
+    fn read_register(&self, offset: u64) -> u64 {
+        unsafe {
+            let register = VirtualAddress::new(self.base_address.as_u64() + offset);
+            return core::ptr::read_volatile(register.get_mut_ptr::<u64>());
+        }
+    }
+
+    fn write_register(&self, offset: u64, value: u64) {
+        unsafe {
+            let register = VirtualAddress::new(self.base_address.as_u64() + offset);
+            core::ptr::write_volatile(register.get_mut_ptr::<u64>(), value);
+        }
+    }
+}
@@ -29,4 +29,39 @@ impl Port {
 
         return output;
     }
+
+    pub unsafe fn out_u16(&self, value: u16) {
+        core::arch::asm!("out dx, ax", in("dx") self.port_number, in("ax") value, options(nomem, nostack, preserves_flags));
+    }
+
+    pub unsafe fn in_u16(&self) -> u16 {
+        let output: u16;
+        core::arch::asm!("in ax, dx", out("ax") output, in("dx") self.port_number, options(nomem, nostack, preserves_flags));
+
+        return output;
+    }
+
+    pub unsafe fn out_u32(&self, value: u32) {
+        core::arch::asm!("out dx, eax", in("dx") self.port_number, in("eax") value, options(nomem, nostack, preserves_flags));
+    }
+
+    pub unsafe fn in_u32(&self) -> u32 {
+        let output: u32;
+        core::arch::asm!("in eax, dx", out("eax") output, in("dx") self.port_number, options(nomem, nostack, preserves_flags));
+
+        return output;
+    }
+}
+
+/// An unused port that is safe to write to. Writing to it takes roughly 1-4 microseconds
+const IO_WAIT_PORT: u16 = 0x80;
+
+/// Waits for a short time by writing to an unused port. Old devices such as the 8259 PIC
+/// need time to process a command before the next one arrives.
+/// 
+/// ## Safety
+/// 
+/// This is unsafe as it performs port IO, though port 0x80 is only used for POST codes.
+pub unsafe fn io_wait() {
+    Port::new(IO_WAIT_PORT).out_u8(0);
 }
@@ -1,2 +1,7 @@
+pub mod hpet;
 pub mod ioport;
+pub mod pci;
+pub mod pic_8259;
+pub mod pit_8254;
+pub mod rtc_cmos;
 pub mod uart_16550;
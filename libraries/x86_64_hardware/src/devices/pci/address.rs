@@ -0,0 +1,52 @@
+use core::fmt;
+
+pub const MAX_DEVICE: u8 = 31;
+pub const MAX_FUNCTION: u8 = 7;
+
+/// The location of a single PCI function. The device and function numbers are
+/// masked to their valid ranges on creation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PciAddress {
+    segment: u16,
+    bus: u8,
+    device: u8,
+    function: u8,
+}
+
+impl PciAddress {
+    #[inline]
+    pub const fn new(segment: u16, bus: u8, device: u8, function: u8) -> PciAddress {
+        return PciAddress {
+            segment: segment,
+            bus: bus,
+            device: device & MAX_DEVICE,
+            function: function & MAX_FUNCTION,
+        };
+    }
+
+    #[inline]
+    pub const fn segment(&self) -> u16 {
+        return self.segment;
+    }
+
+    #[inline]
+    pub const fn bus(&self) -> u8 {
+        return self.bus;
+    }
+
+    #[inline]
+    pub const fn device(&self) -> u8 {
+        return self.device;
+    }
+
+    #[inline]
+    pub const fn function(&self) -> u8 {
+        return self.function;
+    }
+}
+
+impl fmt::Display for PciAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04x}:{:02x}:{:02x}.{}", self.segment, self.bus, self.device, self.function)
+    }
+}
@@ -0,0 +1,120 @@
+use crate::devices::pci::*;
+
+const BAR_IO_SPACE: u32 = 1 << 0;
+const BAR_MEMORY_TYPE_MASK: u32 = 0b110;
+const BAR_MEMORY_TYPE_64: u32 = 0b100;
+const BAR_PREFETCHABLE: u32 = 1 << 3;
+const BAR_IO_ADDRESS_MASK: u32 = !0x3;
+const BAR_MEMORY_ADDRESS_MASK: u32 = !0xF;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Bar {
+    None,
+    Memory32 {
+        address: u32,
+        size: u32,
+        prefetchable: bool,
+    },
+    Memory64 {
+        address: u64,
+        size: u64,
+        prefetchable: bool,
+    },
+    Io {
+        port: u16,
+        size: u16,
+    },
+}
+
+impl Bar {
+    pub fn is_none(&self) -> bool {
+        return *self == Bar::None;
+    }
+
+    /// The base address of the BAR. This is a port number for IO BARs
+    pub fn address(&self) -> u64 {
+        match *self {
+            Bar::None => 0,
+            Bar::Memory32 { address, .. } => address as u64,
+            Bar::Memory64 { address, .. } => address,
+            Bar::Io { port, .. } => port as u64,
+        }
+    }
+
+    pub fn size(&self) -> u64 {
+        match *self {
+            Bar::None => 0,
+            Bar::Memory32 { size, .. } => size as u64,
+            Bar::Memory64 { size, .. } => size,
+            Bar::Io { size, .. } => size as u64,
+        }
+    }
+
+    /// Reads and sizes the BAR at *index* of the *num_bars* the header type has. The second
+    /// value returned is the number of BAR slots consumed, which is 2 for a 64 bit memory BAR.
+    /// A 64 bit BAR in the last slot has no upper half so it is returned as Bar::None.
+    /// 
+    /// Sizing requires writing all ones to the BAR so memory and IO decoding is switched off
+    /// for the duration. The command register is restored afterwards.
+    pub fn read(config_space: &impl ConfigSpace, address: PciAddress, index: usize, num_bars: usize) -> (Bar, usize) {
+        if index >= num_bars {
+            return (Bar::None, 1);
+        }
+
+        let bar_offset = BAR0_OFFSET + (index as u16 * 4);
+        let original = config_space.read_u32(address, bar_offset);
+        if original & BAR_IO_SPACE == 0 && original & BAR_MEMORY_TYPE_MASK == BAR_MEMORY_TYPE_64 && index + 1 >= num_bars {
+            return (Bar::None, 1);
+        }
+
+        let command = config_space.read_u16(address, COMMAND_OFFSET);
+        config_space.write_u16(address, COMMAND_OFFSET, command & !(COMMAND_IO_SPACE | COMMAND_MEMORY_SPACE));
+
+        let output = if original & BAR_IO_SPACE != 0 {
+            let mask = Self::size_mask(config_space, address, bar_offset, original);
+            let size = (!(mask & BAR_IO_ADDRESS_MASK)).wrapping_add(1) & 0xFFFF;
+            if mask & BAR_IO_ADDRESS_MASK == 0 {
+                (Bar::None, 1)
+            } else {
+                (Bar::Io { port: (original & BAR_IO_ADDRESS_MASK) as u16, size: size as u16 }, 1)
+            }
+        } else if original & BAR_MEMORY_TYPE_MASK == BAR_MEMORY_TYPE_64 {
+            let upper_offset = bar_offset + 4;
+            let original_upper = config_space.read_u32(address, upper_offset);
+            let mask = Self::size_mask(config_space, address, bar_offset, original);
+            let mask_upper = Self::size_mask(config_space, address, upper_offset, original_upper);
+
+            let full_mask = ((mask_upper as u64) << 32) | (mask & BAR_MEMORY_ADDRESS_MASK) as u64;
+            if full_mask == 0 {
+                (Bar::None, 2)
+            } else {
+                (Bar::Memory64 {
+                    address: ((original_upper as u64) << 32) | (original & BAR_MEMORY_ADDRESS_MASK) as u64,
+                    size: (!full_mask).wrapping_add(1),
+                    prefetchable: original & BAR_PREFETCHABLE != 0,
+                }, 2)
+            }
+        } else {
+            let mask = Self::size_mask(config_space, address, bar_offset, original);
+            if mask & BAR_MEMORY_ADDRESS_MASK == 0 {
+                (Bar::None, 1)
+            } else {
+                (Bar::Memory32 {
+                    address: original & BAR_MEMORY_ADDRESS_MASK,
+                    size: (!(mask & BAR_MEMORY_ADDRESS_MASK)).wrapping_add(1),
+                    prefetchable: original & BAR_PREFETCHABLE != 0,
+                }, 1)
+            }
+        };
+
+        config_space.write_u16(address, COMMAND_OFFSET, command);
+        return output;
+    }
+
+    fn size_mask(config_space: &impl ConfigSpace, address: PciAddress, bar_offset: u16, original: u32) -> u32 {
+        config_space.write_u32(address, bar_offset, 0xFFFF_FFFF);
+        let mask = config_space.read_u32(address, bar_offset);
+        config_space.write_u32(address, bar_offset, original);
+        return mask;
+    }
+}
@@ -0,0 +1,60 @@
+use crate::devices::pci::*;
+
+pub const CAPABILITY_ID_POWER_MANAGEMENT: u8 = 0x01;
+pub const CAPABILITY_ID_MSI: u8 = 0x05;
+pub const CAPABILITY_ID_VENDOR_SPECIFIC: u8 = 0x09;
+pub const CAPABILITY_ID_PCI_EXPRESS: u8 = 0x10;
+pub const CAPABILITY_ID_MSI_X: u8 = 0x11;
+
+//Each capability is at least 4 bytes and sits after the 64 byte header so a well formed
+//list cannot be longer than this. It guards against lists that loop back on themselves.
+const MAX_CAPABILITIES: usize = (LEGACY_CONFIG_SPACE_SIZE as usize - 64) / 4;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Capability {
+    pub id: u8,
+    pub offset: u8,
+}
+
+/// Walks the capability list of a function
+pub struct CapabilityIterator<'a, C: ConfigSpace> {
+    config_space: &'a C,
+    address: PciAddress,
+    next_offset: u8,
+    remaining: usize,
+}
+
+impl<'a, C: ConfigSpace> CapabilityIterator<'a, C> {
+    pub fn new(config_space: &'a C, address: PciAddress) -> CapabilityIterator<'a, C> {
+        let status = config_space.read_u16(address, STATUS_OFFSET);
+        let first_offset = if status & STATUS_CAPABILITIES_LIST != 0 {
+            config_space.read_u8(address, CAPABILITIES_POINTER_OFFSET) & 0xFC
+        } else {
+            0
+        };
+
+        return CapabilityIterator {
+            config_space: config_space,
+            address: address,
+            next_offset: first_offset,
+            remaining: MAX_CAPABILITIES,
+        };
+    }
+}
+
+impl<'a, C: ConfigSpace> Iterator for CapabilityIterator<'a, C> {
+    type Item = Capability;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next_offset == 0 || self.remaining == 0 {
+            return None;
+        }
+
+        let offset = self.next_offset;
+        let id = self.config_space.read_u8(self.address, offset as u16);
+        self.next_offset = self.config_space.read_u8(self.address, offset as u16 + 1) & 0xFC;
+        self.remaining -= 1;
+
+        return Some(Capability { id: id, offset: offset });
+    }
+}
@@ -0,0 +1,29 @@
+use crate::devices::pci::PciAddress;
+
+/// Size of the configuration space reachable through the legacy port mechanism
+pub const LEGACY_CONFIG_SPACE_SIZE: u16 = 256;
+/// Size of the configuration space of a PCI Express function
+pub const EXTENDED_CONFIG_SPACE_SIZE: u16 = 4096;
+
+/// Access to PCI configuration space. Reads of a function that does not exist or an
+/// offset outside the supported configuration space return all ones, matching what
+/// the hardware does on a master abort. Writes to such locations are discarded.
+/// 
+/// Offsets must be naturally aligned for the access size. Unaligned offsets are
+/// rounded down.
+pub trait ConfigSpace {
+    /// The size of the configuration space of each function this accessor can reach
+    fn config_space_size(&self) -> u16;
+
+    fn read_u8(&self, address: PciAddress, offset: u16) -> u8;
+
+    fn read_u16(&self, address: PciAddress, offset: u16) -> u16;
+
+    fn read_u32(&self, address: PciAddress, offset: u16) -> u32;
+
+    fn write_u8(&self, address: PciAddress, offset: u16, value: u8);
+
+    fn write_u16(&self, address: PciAddress, offset: u16, value: u16);
+
+    fn write_u32(&self, address: PciAddress, offset: u16, value: u32);
+}
@@ -0,0 +1,108 @@
+use crate::devices::pci::*;
+
+pub const MAX_BARS: usize = 6;
+
+/// A snapshot of the configuration of a single PCI function taken at enumeration
+#[derive(Clone, Copy, Debug)]
+pub struct PciDevice {
+    pub address: PciAddress,
+    pub vendor_id: u16,
+    pub device_id: u16,
+    pub revision_id: u8,
+    pub class_code: u8,
+    pub subclass: u8,
+    pub prog_if: u8,
+    pub header_type: HeaderType,
+    pub multifunction: bool,
+    pub interrupt_line: u8,
+    pub interrupt_pin: u8,
+    pub bars: [Bar; MAX_BARS],
+    /// Configuration space offset of the MSI capability
+    pub msi_capability: Option<u8>,
+    /// Configuration space offset of the MSI-X capability
+    pub msix_capability: Option<u8>,
+    /// Configuration space offset of the PCI Express capability
+    pub pcie_capability: Option<u8>,
+    /// For PCI to PCI bridges the bus directly behind the bridge
+    pub secondary_bus: Option<u8>,
+    /// For PCI to PCI bridges the highest bus number behind the bridge
+    pub subordinate_bus: Option<u8>,
+}
+
+impl PciDevice {
+    pub const fn empty() -> PciDevice {
+        return PciDevice {
+            address: PciAddress::new(0, 0, 0, 0),
+            vendor_id: INVALID_VENDOR_ID,
+            device_id: 0,
+            revision_id: 0,
+            class_code: 0,
+            subclass: 0,
+            prog_if: 0,
+            header_type: HeaderType::General,
+            multifunction: false,
+            interrupt_line: 0,
+            interrupt_pin: 0,
+            bars: [Bar::None; MAX_BARS],
+            msi_capability: None,
+            msix_capability: None,
+            pcie_capability: None,
+            secondary_bus: None,
+            subordinate_bus: None,
+        };
+    }
+
+    /// Reads the configuration of the function at *address*. Returns None if there is no
+    /// function present.
+    pub fn probe(config_space: &impl ConfigSpace, address: PciAddress) -> Option<PciDevice> {
+        let vendor_id = config_space.read_u16(address, VENDOR_ID_OFFSET);
+        if vendor_id == INVALID_VENDOR_ID {
+            return None;
+        }
+
+        let raw_header_type = config_space.read_u8(address, HEADER_TYPE_OFFSET);
+        let header_type = HeaderType::from_u8(raw_header_type);
+
+        let mut device = PciDevice {
+            address: address,
+            vendor_id: vendor_id,
+            device_id: config_space.read_u16(address, DEVICE_ID_OFFSET),
+            revision_id: config_space.read_u8(address, REVISION_ID_OFFSET),
+            class_code: config_space.read_u8(address, CLASS_CODE_OFFSET),
+            subclass: config_space.read_u8(address, SUBCLASS_OFFSET),
+            prog_if: config_space.read_u8(address, PROG_IF_OFFSET),
+            header_type: header_type,
+            multifunction: raw_header_type & HEADER_TYPE_MULTIFUNCTION != 0,
+            interrupt_line: config_space.read_u8(address, INTERRUPT_LINE_OFFSET),
+            interrupt_pin: config_space.read_u8(address, INTERRUPT_PIN_OFFSET),
+            ..PciDevice::empty()
+        };
+
+        let mut bar_index = 0;
+        while bar_index < header_type.num_bars() {
+            let (bar, slots_used) = Bar::read(config_space, address, bar_index, header_type.num_bars());
+            device.bars[bar_index] = bar;
+            bar_index += slots_used;
+        }
+
+        for capability in CapabilityIterator::new(config_space, address) {
+            match capability.id {
+                CAPABILITY_ID_MSI => { device.msi_capability = Some(capability.offset); },
+                CAPABILITY_ID_MSI_X => { device.msix_capability = Some(capability.offset); },
+                CAPABILITY_ID_PCI_EXPRESS => { device.pcie_capability = Some(capability.offset); },
+                _ => {},
+            }
+        }
+
+        if header_type == HeaderType::PciToPciBridge {
+            device.secondary_bus = Some(config_space.read_u8(address, SECONDARY_BUS_OFFSET));
+            device.subordinate_bus = Some(config_space.read_u8(address, SUBORDINATE_BUS_OFFSET));
+        }
+
+        return Some(device);
+    }
+
+    pub fn is_bridge(&self) -> bool {
+        return self.header_type == HeaderType::PciToPciBridge;
+    }
+}
@@ -0,0 +1,116 @@
+use crate::devices::pci::*;
+use crate::memory::VirtualAddress;
+
+const BUS_SHIFT: u64 = 20;
+const DEVICE_SHIFT: u64 = 15;
+const FUNCTION_SHIFT: u64 = 12;
+
+/// The number of bytes of ECAM space used by a single bus
+pub const ECAM_BUS_SIZE: u64 = 1 << BUS_SHIFT;
+
+/// Memory mapped PCI Express configuration access (ECAM) for a single PCI segment as
+/// described by an MCFG entry.
+pub struct EcamConfigSpace {
+    base_address: VirtualAddress,
+    segment: u16,
+    start_bus: u8,
+    end_bus: u8,
+}
+
+impl EcamConfigSpace {
+    /// Creates a new ECAM accessor. *base_address* is the virtual address of the start of
+    /// the configuration space for *start_bus*.
+    /// 
+    /// ## Safety
+    /// 
+    /// This is unsafe as we cannot know the mapping is valid. The caller must ensure the
+    /// physical ECAM region for buses *start_bus* to *end_bus* inclusive is mapped at
+    /// *base_address* with caching disabled.
+    pub const unsafe fn new(base_address: VirtualAddress, segment: u16, start_bus: u8, end_bus: u8) -> EcamConfigSpace {
+        return EcamConfigSpace {
+            base_address: base_address,
+            segment: segment,
+            start_bus: start_bus,
+            end_bus: end_bus,
+        };
+    }
+
+    /// The number of bytes of ECAM space covered by a segment with the given bus range
+    pub const fn region_size(start_bus: u8, end_bus: u8) -> u64 {
+        return (end_bus as u64 - start_bus as u64 + 1) * ECAM_BUS_SIZE;
+    }
+
+    pub fn segment(&self) -> u16 {
+        return self.segment;
+    }
+
+    pub fn start_bus(&self) -> u8 {
+        return self.start_bus;
+    }
+
+    pub fn end_bus(&self) -> u8 {
+        return self.end_bus;
+    }
+
+    pub fn contains(&self, address: PciAddress) -> bool {
+        return address.segment() == self.segment && address.bus() >= self.start_bus && address.bus() <= self.end_bus;
+    }
+
+    fn register_address(&self, address: PciAddress, offset: u16) -> Option<VirtualAddress> {
+        if !self.contains(address) || offset >= EXTENDED_CONFIG_SPACE_SIZE {
+            return None;
+        }
+
+        let mut register = ((address.bus() - self.start_bus) as u64) << BUS_SHIFT;
+        register |= (address.device() as u64) << DEVICE_SHIFT;
+        register |= (address.function() as u64) << FUNCTION_SHIFT;
+        register |= offset as u64;
+
+        return Some(VirtualAddress::new(self.base_address.as_u64() + register));
+    }
+}
+
+impl ConfigSpace for EcamConfigSpace {
+    fn config_space_size(&self) -> u16 {
+        return EXTENDED_CONFIG_SPACE_SIZE;
+    }
+
+    fn read_u8(&self, address: PciAddress, offset: u16) -> u8 {
+        match self.register_address(address, offset) {
+            Some(register) => unsafe { core::ptr::read_volatile(register.get_mut_ptr::<u8>()) },
+            None => 0xFF,
+        }
+    }
+
+    fn read_u16(&self, address: PciAddress, offset: u16) -> u16 {
+        match self.register_address(address, offset & !0x1) {
+            Some(register) => unsafe { core::ptr::read_volatile(register.get_mut_ptr::<u16>()) },
+            None => 0xFFFF,
+        }
+    }
+
+    fn read_u32(&self, address: PciAddress, offset: u16) -> u32 {
+        match self.register_address(address, offset & !0x3) {
+            Some(register) => unsafe { core::ptr::read_volatile(register.get_mut_ptr::<u32>()) },
+            None => 0xFFFF_FFFF,
+        }
+    }
+
+    fn write_u8(&self, address: PciAddress, offset: u16, value: u8) {
+        if let Some(register) = self.register_address(address, offset) {
+            unsafe { core::ptr::write_volatile(register.get_mut_ptr::<u8>(), value); }
+        }
+    }
+
+    fn write_u16(&self, address: PciAddress, offset: u16, value: u16) {
+        if let Some(register) = self.register_address(address, offset & !0x1) {
+            unsafe { core::ptr::write_volatile(register.get_mut_ptr::<u16>(), value); }
+        }
+    }
+
+    fn write_u32(&self, address: PciAddress, offset: u16, value: u32) {
+        if let Some(register) = self.register_address(address, offset & !0x3) {
+            unsafe { core::ptr::write_volatile(register.get_mut_ptr::<u32>(), value); }
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use crate::devices::pci::*;
+
+pub const MAX_PCI_DEVICES: usize = 256;
+
+/// A fixed capacity list of enumerated PCI functions. This is large so should live in
+/// a static rather than on the stack.
+pub struct PciDeviceList {
+    devices: [PciDevice; MAX_PCI_DEVICES],
+    num_devices: usize,
+}
+
+impl PciDeviceList {
+    pub const fn new() -> PciDeviceList {
+        return PciDeviceList {
+            devices: [PciDevice::empty(); MAX_PCI_DEVICES],
+            num_devices: 0,
+        };
+    }
+
+    pub fn len(&self) -> usize {
+        return self.num_devices;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.num_devices == 0;
+    }
+
+    pub fn is_full(&self) -> bool {
+        return self.num_devices == MAX_PCI_DEVICES;
+    }
+
+    pub fn clear(&mut self) {
+        self.num_devices = 0;
+    }
+
+    /// Adds a device to the list. Returns false if the list is full
+    pub fn push(&mut self, device: PciDevice) -> bool {
+        if self.is_full() {
+            return false;
+        }
+
+        self.devices[self.num_devices] = device;
+        self.num_devices += 1;
+        return true;
+    }
+
+    pub fn get(&self, index: usize) -> Option<&PciDevice> {
+        if index >= self.num_devices {
+            return None;
+        }
+
+        return Some(&self.devices[index]);
+    }
+
+    pub fn find(&self, vendor_id: u16, device_id: u16) -> Option<&PciDevice> {
+        return self.iter().find(|device| device.vendor_id == vendor_id && device.device_id == device_id);
+    }
+
+    pub fn find_by_class(&self, class_code: u8, subclass: u8) -> Option<&PciDevice> {
+        return self.iter().find(|device| device.class_code == class_code && device.subclass == subclass);
+    }
+
+    pub fn iter(&self) -> core::slice::Iter<'_, PciDevice> {
+        return self.devices[..self.num_devices].iter();
+    }
+}
+
+impl Default for PciDeviceList {
+    fn default() -> PciDeviceList {
+        return PciDeviceList::new();
+    }
+}
+
+/// Walks the PCI hierarchy of a single segment from its root bus, following PCI to PCI
+/// bridges to the buses behind them.
+pub struct PciEnumerator<'a, C: ConfigSpace> {
+    config_space: &'a C,
+    segment: u16,
+    start_bus: u8,
+    end_bus: u8,
+    scanned_buses: [u64; 4],
+}
+
+impl<'a, C: ConfigSpace> PciEnumerator<'a, C> {
+    pub fn new(config_space: &'a C, segment: u16, start_bus: u8, end_bus: u8) -> PciEnumerator<'a, C> {
+        return PciEnumerator {
+            config_space: config_space,
+            segment: segment,
+            start_bus: start_bus,
+            end_bus: end_bus,
+            scanned_buses: [0; 4],
+        };
+    }
+
+    /// Adds every function found on the segment to *list*. Returns the number of functions
+    /// added. Enumeration stops early if the list fills.
+    pub fn enumerate(&mut self, list: &mut PciDeviceList) -> usize {
+        let initial_len = list.len();
+        let root = PciAddress::new(self.segment, self.start_bus, 0, 0);
+
+        match PciDevice::probe(self.config_space, root) {
+            Some(host_bridge) if host_bridge.multifunction => {
+                //Multiple host bridges. Each function is responsible for the bus of the same number
+                for function in 0..=MAX_FUNCTION {
+                    let address = PciAddress::new(self.segment, self.start_bus, 0, function);
+                    if self.config_space.read_u16(address, VENDOR_ID_OFFSET) != INVALID_VENDOR_ID {
+                        self.scan_bus(self.start_bus.saturating_add(function), list);
+                    }
+                }
+            },
+            _ => {
+                self.scan_bus(self.start_bus, list);
+            }
+        }
+
+        return list.len() - initial_len;
+    }
+
+    fn scan_bus(&mut self, bus: u8, list: &mut PciDeviceList) {
+        if bus < self.start_bus || bus > self.end_bus || self.bus_scanned(bus) {
+            return;
+        }
+        self.mark_bus_scanned(bus);
+
+        for device in 0..=MAX_DEVICE {
+            self.scan_device(bus, device, list);
+        }
+    }
+
+    fn scan_device(&mut self, bus: u8, device: u8, list: &mut PciDeviceList) {
+        let function0 = match PciDevice::probe(self.config_space, PciAddress::new(self.segment, bus, device, 0)) {
+            Some(function0) => function0,
+            None => { return; }
+        };
+
+        let multifunction = function0.multifunction;
+        self.add_function(function0, list);
+
+        if multifunction {
+            for function in 1..=MAX_FUNCTION {
+                if let Some(pci_device) = PciDevice::probe(self.config_space, PciAddress::new(self.segment, bus, device, function)) {
+                    self.add_function(pci_device, list);
+                }
+            }
+        }
+    }
+
+    fn add_function(&mut self, pci_device: PciDevice, list: &mut PciDeviceList) {
+        if !list.push(pci_device) {
+            return;
+        }
+
+        if let Some(secondary_bus) = pci_device.secondary_bus {
+            self.scan_bus(secondary_bus, list);
+        }
+    }
+
+    fn bus_scanned(&self, bus: u8) -> bool {
+        return self.scanned_buses[bus as usize / 64] & (1 << (bus % 64)) != 0;
+    }
+
+    fn mark_bus_scanned(&mut self, bus: u8) {
+        self.scanned_buses[bus as usize / 64] |= 1 << (bus % 64);
+    }
+}
@@ -0,0 +1,63 @@
+//Offsets and bits of the standard PCI configuration space header
+
+pub const VENDOR_ID_OFFSET: u16 = 0x00;
+pub const DEVICE_ID_OFFSET: u16 = 0x02;
+pub const COMMAND_OFFSET: u16 = 0x04;
+pub const STATUS_OFFSET: u16 = 0x06;
+pub const REVISION_ID_OFFSET: u16 = 0x08;
+pub const PROG_IF_OFFSET: u16 = 0x09;
+pub const SUBCLASS_OFFSET: u16 = 0x0A;
+pub const CLASS_CODE_OFFSET: u16 = 0x0B;
+pub const HEADER_TYPE_OFFSET: u16 = 0x0E;
+pub const BAR0_OFFSET: u16 = 0x10;
+pub const CAPABILITIES_POINTER_OFFSET: u16 = 0x34;
+pub const INTERRUPT_LINE_OFFSET: u16 = 0x3C;
+pub const INTERRUPT_PIN_OFFSET: u16 = 0x3D;
+
+pub const PRIMARY_BUS_OFFSET: u16 = 0x18;
+pub const SECONDARY_BUS_OFFSET: u16 = 0x19;
+pub const SUBORDINATE_BUS_OFFSET: u16 = 0x1A;
+
+pub const COMMAND_IO_SPACE: u16 = 1 << 0;
+pub const COMMAND_MEMORY_SPACE: u16 = 1 << 1;
+pub const COMMAND_BUS_MASTER: u16 = 1 << 2;
+pub const COMMAND_INTERRUPT_DISABLE: u16 = 1 << 10;
+
+pub const STATUS_CAPABILITIES_LIST: u16 = 1 << 4;
+
+pub const HEADER_TYPE_MASK: u8 = 0x7F;
+pub const HEADER_TYPE_MULTIFUNCTION: u8 = 0x80;
+
+pub const INVALID_VENDOR_ID: u16 = 0xFFFF;
+
+pub const CLASS_BRIDGE: u8 = 0x06;
+pub const SUBCLASS_HOST_BRIDGE: u8 = 0x00;
+pub const SUBCLASS_PCI_TO_PCI_BRIDGE: u8 = 0x04;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum HeaderType {
+    General,
+    PciToPciBridge,
+    CardBusBridge,
+    Unknown(u8),
+}
+
+impl HeaderType {
+    pub fn from_u8(header_type: u8) -> HeaderType {
+        match header_type & HEADER_TYPE_MASK {
+            0x00 => HeaderType::General,
+            0x01 => HeaderType::PciToPciBridge,
+            0x02 => HeaderType::CardBusBridge,
+            other => HeaderType::Unknown(other),
+        }
+    }
+
+    /// The number of BARs this header type has
+    pub fn num_bars(&self) -> usize {
+        match self {
+            HeaderType::General => 6,
+            HeaderType::PciToPciBridge => 2,
+            _ => 0,
+        }
+    }
+}
@@ -0,0 +1,19 @@
+mod address;
+mod bar;
+mod capability;
+mod config_space;
+mod device;
+mod ecam;
+mod enumerator;
+mod header;
+mod port_config_space;
+
+pub use address::*;
+pub use bar::*;
+pub use capability::*;
+pub use config_space::*;
+pub use device::*;
+pub use ecam::*;
+pub use enumerator::*;
+pub use header::*;
+pub use port_config_space::*;
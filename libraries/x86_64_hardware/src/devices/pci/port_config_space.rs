@@ -0,0 +1,128 @@
+use spin::Mutex;
+
+use crate::devices::ioport::Port;
+use crate::devices::pci::*;
+
+const CONFIG_ADDRESS_PORT: u16 = 0xCF8;
+const CONFIG_DATA_PORT: u16 = 0xCFC;
+const CONFIG_ENABLE_BIT: u32 = 1 << 31;
+
+/// Legacy PCI configuration access through the 0xCF8/0xCFC port pair. This only
+/// reaches segment 0 and the first 256 bytes of each function's configuration space.
+/// 
+/// Every access is a write to the address port followed by an access to the data port
+/// so the pair is held under a lock for the duration.
+pub struct PortConfigSpace {
+    address_port: Mutex<Port>,
+}
+
+impl PortConfigSpace {
+    /// Creates a new port based accessor.
+    /// 
+    /// ## Safety
+    /// 
+    /// This is unsafe as only one accessor should drive the configuration ports. The caller
+    /// must ensure no other code uses ports 0xCF8-0xCFF.
+    pub const unsafe fn new() -> PortConfigSpace {
+        return PortConfigSpace {
+            address_port: Mutex::new(Port::new(CONFIG_ADDRESS_PORT)),
+        };
+    }
+
+    fn config_address(address: PciAddress, offset: u16) -> Option<u32> {
+        if address.segment() != 0 || offset >= LEGACY_CONFIG_SPACE_SIZE {
+            return None;
+        }
+
+        let mut config_address = CONFIG_ENABLE_BIT;
+        config_address |= (address.bus() as u32) << 16;
+        config_address |= (address.device() as u32) << 11;
+        config_address |= (address.function() as u32) << 8;
+        config_address |= (offset & 0xFC) as u32;
+
+        return Some(config_address);
+    }
+
+    /// The data port for an access is offset by the low bits of the register offset
+    /// so byte and word accesses land on the right part of the dword
+    fn data_port(offset: u16) -> Port {
+        return unsafe { Port::new(CONFIG_DATA_PORT + (offset & 0x3)) };
+    }
+}
+
+impl ConfigSpace for PortConfigSpace {
+    fn config_space_size(&self) -> u16 {
+        return LEGACY_CONFIG_SPACE_SIZE;
+    }
+
+    fn read_u8(&self, address: PciAddress, offset: u16) -> u8 {
+        let config_address = match Self::config_address(address, offset) {
+            Some(config_address) => config_address,
+            None => { return 0xFF; }
+        };
+
+        let address_port = self.address_port.lock();
+        unsafe {
+            address_port.out_u32(config_address);
+            return Self::data_port(offset).in_u8();
+        }
+    }
+
+    fn read_u16(&self, address: PciAddress, offset: u16) -> u16 {
+        let offset = offset & !0x1;
+        let config_address = match Self::config_address(address, offset) {
+            Some(config_address) => config_address,
+            None => { return 0xFFFF; }
+        };
+
+        let address_port = self.address_port.lock();
+        unsafe {
+            address_port.out_u32(config_address);
+            return Self::data_port(offset).in_u16();
+        }
+    }
+
+    fn read_u32(&self, address: PciAddress, offset: u16) -> u32 {
+        let config_address = match Self::config_address(address, offset) {
+            Some(config_address) => config_address,
+            None => { return 0xFFFF_FFFF; }
+        };
+
+        let address_port = self.address_port.lock();
+        unsafe {
+            address_port.out_u32(config_address);
+            return Self::data_port(0).in_u32();
+        }
+    }
+
+    fn write_u8(&self, address: PciAddress, offset: u16, value: u8) {
+        if let Some(config_address) = Self::config_address(address, offset) {
+            let address_port = self.address_port.lock();
+            unsafe {
+                address_port.out_u32(config_address);
+                Self::data_port(offset).out_u8(value);
+            }
+        }
+    }
+
+    fn write_u16(&self, address: PciAddress, offset: u16, value: u16) {
+        let offset = offset & !0x1;
+        if let Some(config_address) = Self::config_address(address, offset) {
+            let address_port = self.address_port.lock();
+            unsafe {
+                address_port.out_u32(config_address);
+                Self::data_port(offset).out_u16(value);
+            }
+        }
+    }
+
+    fn write_u32(&self, address: PciAddress, offset: u16, value: u32) {
+        if let Some(config_address) = Self::config_address(address, offset) {
+            let address_port = self.address_port.lock();
+            unsafe {
+                address_port.out_u32(config_address);
+                Self::data_port(0).out_u32(value);
+            }
+        }
+    }
+}
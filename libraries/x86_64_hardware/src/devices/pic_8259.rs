@@ -0,0 +1,180 @@
+use crate::devices::ioport::{io_wait, Port};
+use spin::Mutex;
+
+const MASTER_COMMAND_PORT: u16 = 0x20;
+const MASTER_DATA_PORT: u16 = 0x21;
+const SLAVE_COMMAND_PORT: u16 = 0xA0;
+const SLAVE_DATA_PORT: u16 = 0xA1;
+
+const ICW1_ICW4_NEEDED: u8 = 0x01;
+const ICW1_INITIALISE: u8 = 0x10;
+const ICW4_8086_MODE: u8 = 0x01;
+const OCW3_READ_ISR: u8 = 0x0B;
+const END_OF_INTERRUPT: u8 = 0x20;
+
+/// The master IRQ line the slave PIC is cascaded through
+const CASCADE_IRQ: u8 = 2;
+const SPURIOUS_IRQ_OFFSET: u8 = 7;
+
+/// The vectors the PICs are remapped to. CPU exceptions use vectors 0-31 so the PICs are
+/// moved directly after them.
+pub const PIC_MASTER_OFFSET: u8 = 32;
+pub const PIC_SLAVE_OFFSET: u8 = PIC_MASTER_OFFSET + 8;
+
+struct Pic {
+    offset: u8,
+    command: Port,
+    data: Port,
+}
+
+impl Pic {
+    fn handles_interrupt(&self, vector: u8) -> bool {
+        return self.offset <= vector && vector < self.offset + 8;
+    }
+
+    unsafe fn end_of_interrupt(&self) {
+        self.command.out_u8(END_OF_INTERRUPT);
+    }
+
+    unsafe fn read_mask(&self) -> u8 {
+        return self.data.in_u8();
+    }
+
+    unsafe fn write_mask(&self, mask: u8) {
+        self.data.out_u8(mask);
+    }
+
+    unsafe fn read_in_service(&self) -> u8 {
+        self.command.out_u8(OCW3_READ_ISR);
+        return self.command.in_u8();
+    }
+}
+
+/// The pair of cascaded 8259 PICs found on every PC. IRQs are numbered 0-15 with 8-15 on
+/// the slave.
+pub struct ChainedPics {
+    master: Pic,
+    slave: Pic,
+}
+
+impl ChainedPics {
+    /// Creates the driver for the PICs. They are not touched until *initialise* is called.
+    /// 
+    /// ## Safety
+    /// 
+    /// This is unsafe as the offsets must not overlap the CPU exception vectors or any other
+    /// interrupt source, and only one instance should exist.
+    pub const unsafe fn new(master_offset: u8, slave_offset: u8) -> ChainedPics {
+        return ChainedPics {
+            master: Pic { offset: master_offset, command: Port::new(MASTER_COMMAND_PORT), data: Port::new(MASTER_DATA_PORT) },
+            slave: Pic { offset: slave_offset, command: Port::new(SLAVE_COMMAND_PORT), data: Port::new(SLAVE_DATA_PORT) },
+        };
+    }
+
+    /// Remaps the PICs to their offsets. Every IRQ is masked afterwards apart from the
+    /// cascade line so IRQs must be unmasked as their handlers are installed.
+    pub fn initialise(&mut self) {
+        unsafe {
+            self.master.command.out_u8(ICW1_INITIALISE | ICW1_ICW4_NEEDED);
+            io_wait();
+            self.slave.command.out_u8(ICW1_INITIALISE | ICW1_ICW4_NEEDED);
+            io_wait();
+
+            self.master.data.out_u8(self.master.offset);
+            io_wait();
+            self.slave.data.out_u8(self.slave.offset);
+            io_wait();
+
+            //Tell the master the slave is on IRQ2 and the slave its cascade identity
+            self.master.data.out_u8(1 << CASCADE_IRQ);
+            io_wait();
+            self.slave.data.out_u8(CASCADE_IRQ);
+            io_wait();
+
+            self.master.data.out_u8(ICW4_8086_MODE);
+            io_wait();
+            self.slave.data.out_u8(ICW4_8086_MODE);
+            io_wait();
+        }
+
+        self.set_masks(!(1 << CASCADE_IRQ));
+    }
+
+    /// Masks every IRQ on both PICs. Used when the APIC takes over interrupt delivery.
+    /// The PICs should have been remapped first so a spurious IRQ cannot be mistaken
+    /// for an exception.
+    pub fn disable(&mut self) {
+        self.set_masks(0xFFFF);
+    }
+
+    /// The mask of both PICs with the slave in the upper byte. A set bit is a masked IRQ
+    pub fn masks(&self) -> u16 {
+        unsafe {
+            return (self.slave.read_mask() as u16) << 8 | self.master.read_mask() as u16;
+        }
+    }
+
+    pub fn set_masks(&mut self, masks: u16) {
+        unsafe {
+            self.master.write_mask(masks as u8);
+            self.slave.write_mask((masks >> 8) as u8);
+        }
+    }
+
+    pub fn mask(&mut self, irq: u8) {
+        let masks = self.masks();
+        self.set_masks(masks | 1 << (irq & 0xF));
+    }
+
+    /// Unmasks an IRQ. Unmasking a slave IRQ also unmasks the cascade line on the master
+    pub fn unmask(&mut self, irq: u8) {
+        let mut masks = self.masks() & !(1 << (irq & 0xF));
+        if irq >= 8 {
+            masks &= !(1 << CASCADE_IRQ);
+        }
+        self.set_masks(masks);
+    }
+
+    pub fn handles_interrupt(&self, vector: u8) -> bool {
+        return self.master.handles_interrupt(vector) || self.slave.handles_interrupt(vector);
+    }
+
+    /// Converts an interrupt vector back to the IRQ that raised it
+    pub fn irq_for_vector(&self, vector: u8) -> Option<u8> {
+        if self.master.handles_interrupt(vector) {
+            return Some(vector - self.master.offset);
+        } else if self.slave.handles_interrupt(vector) {
+            return Some(vector - self.slave.offset + 8);
+        }
+        return None;
+    }
+
+    /// Checks whether an IRQ7 or IRQ15 was spurious. These are raised when an IRQ is withdrawn
+    /// before it is acknowledged and must not be sent an EOI, except that a spurious IRQ15
+    /// still needs an EOI on the master for the cascade line.
+    pub fn is_spurious(&mut self, irq: u8) -> bool {
+        unsafe {
+            if irq == SPURIOUS_IRQ_OFFSET {
+                return self.master.read_in_service() & (1 << SPURIOUS_IRQ_OFFSET) == 0;
+            } else if irq == SPURIOUS_IRQ_OFFSET + 8 {
+                if self.slave.read_in_service() & (1 << SPURIOUS_IRQ_OFFSET) == 0 {
+                    self.master.end_of_interrupt();
+                    return true;
+                }
+            }
+        }
+        return false;
+    }
+
+    /// Signals the end of an interrupt. IRQs on the slave need an EOI on both PICs
+    pub fn end_of_interrupt(&mut self, irq: u8) {
+        unsafe {
+            if irq >= 8 {
+                self.slave.end_of_interrupt();
+            }
+            self.master.end_of_interrupt();
+        }
+    }
+}
+
+pub static PICS: Mutex<ChainedPics> = Mutex::new(unsafe { ChainedPics::new(PIC_MASTER_OFFSET, PIC_SLAVE_OFFSET) });
@@ -0,0 +1,118 @@
+use crate::devices::ioport::Port;
+use spin::Mutex;
+
+const CHANNEL_0_DATA_PORT: u16 = 0x40;
+const COMMAND_PORT: u16 = 0x43;
+
+/// The frequency of the PIT input clock in Hz
+pub const PIT_BASE_FREQUENCY: u32 = 1_193_182;
+/// The IRQ channel 0 is wired to on the master PIC
+pub const PIT_IRQ: u8 = 0;
+
+const SELECT_CHANNEL_0: u8 = 0b00 << 6;
+const ACCESS_LATCH_COUNT: u8 = 0b00 << 4;
+const ACCESS_LOW_HIGH: u8 = 0b11 << 4;
+const MODE_INTERRUPT_ON_TERMINAL_COUNT: u8 = 0b000 << 1;
+const MODE_RATE_GENERATOR: u8 = 0b010 << 1;
+/// Read-back the status of channel 0. The latch bits are active low so bit 5 set skips
+/// latching the count and bit 4 clear latches the status
+const READ_BACK_CHANNEL_0_STATUS: u8 = 0b11 << 6 | 1 << 5 | 1 << 1;
+const STATUS_OUTPUT_HIGH: u8 = 1 << 7;
+
+/// The 8254 Programmable Interval Timer. Only channel 0 is used as channel 1 no longer
+/// exists and channel 2 drives the PC speaker.
+pub struct Pit {
+    channel_0: Port,
+    command: Port,
+}
+
+impl Pit {
+    /// ## Safety
+    /// 
+    /// This is unsafe as only one instance should control the PIT.
+    pub const unsafe fn new() -> Pit {
+        return Pit {
+            channel_0: Port::new(CHANNEL_0_DATA_PORT),
+            command: Port::new(COMMAND_PORT),
+        };
+    }
+
+    /// Converts a frequency in Hz to a reload value. A reload value of 0 is treated by the
+    /// PIT as 65536 so the slowest rate is around 18.2Hz.
+    pub const fn reload_value(frequency: u32) -> u16 {
+        if frequency == 0 {
+            return 0;
+        }
+
+        let divisor = PIT_BASE_FREQUENCY / frequency;
+        if divisor > u16::MAX as u32 {
+            return 0;
+        } else if divisor < 2 {
+            return 2;
+        } else {
+            return divisor as u16;
+        }
+    }
+
+    /// The frequency a reload value actually gives
+    pub const fn frequency_for_reload(reload_value: u16) -> u32 {
+        if reload_value == 0 {
+            return PIT_BASE_FREQUENCY / 65536;
+        }
+        return PIT_BASE_FREQUENCY / reload_value as u32;
+    }
+
+    /// Starts channel 0 firing IRQ0 periodically at roughly *frequency* Hz. Returns the
+    /// frequency actually programmed.
+    pub fn set_periodic(&mut self, frequency: u32) -> u32 {
+        let reload_value = Self::reload_value(frequency);
+        unsafe {
+            self.command.out_u8(SELECT_CHANNEL_0 | ACCESS_LOW_HIGH | MODE_RATE_GENERATOR);
+            self.write_reload(reload_value);
+        }
+        return Self::frequency_for_reload(reload_value);
+    }
+
+    /// Starts channel 0 counting down once from *count*. IRQ0 is raised when it reaches 0 and
+    /// the counter does not restart. At the base frequency one count is about 838ns.
+    pub fn set_one_shot(&mut self, count: u16) {
+        unsafe {
+            self.command.out_u8(SELECT_CHANNEL_0 | ACCESS_LOW_HIGH | MODE_INTERRUPT_ON_TERMINAL_COUNT);
+            self.write_reload(count);
+        }
+    }
+
+    /// Whether a one shot count has reached 0
+    pub fn one_shot_expired(&mut self) -> bool {
+        unsafe {
+            self.command.out_u8(READ_BACK_CHANNEL_0_STATUS);
+            return self.channel_0.in_u8() & STATUS_OUTPUT_HIGH != 0;
+        }
+    }
+
+    /// Busy waits for *count* PIT ticks using one shot mode. This is intended for calibrating
+    /// other timers so it must be used with IRQ0 masked or the periodic tick stopped.
+    pub fn one_shot_wait(&mut self, count: u16) {
+        self.set_one_shot(count);
+        while !self.one_shot_expired() {
+            core::hint::spin_loop();
+        }
+    }
+
+    /// The current value of the channel 0 counter
+    pub fn read_count(&mut self) -> u16 {
+        unsafe {
+            self.command.out_u8(SELECT_CHANNEL_0 | ACCESS_LATCH_COUNT);
+            let low = self.channel_0.in_u8() as u16;
+            let high = self.channel_0.in_u8() as u16;
+            return high << 8 | low;
+        }
+    }
+
+    unsafe fn write_reload(&self, reload_value: u16) {
+        self.channel_0.out_u8(reload_value as u8);
+        self.channel_0.out_u8((reload_value >> 8) as u8);
+    }
+}
+
+pub static PIT: Mutex<Pit> = Mutex::new(unsafe { Pit::new() });
@@ -0,0 +1,244 @@
+use crate::devices::ioport::Port;
+use date_time::{bcd_to_binary, binary_to_bcd, decode_hour, encode_hour, full_year, DateTime};
+use spin::Mutex;
+
+const INDEX_PORT: u16 = 0x70;
+const DATA_PORT: u16 = 0x71;
+
+/// The IRQ the RTC is wired to on the slave PIC
+pub const RTC_IRQ: u8 = 8;
+/// The frequency of the RTC time base in Hz
+pub const RTC_BASE_FREQUENCY: u32 = 32768;
+
+const REGISTER_SECONDS: u8 = 0x00;
+const REGISTER_SECONDS_ALARM: u8 = 0x01;
+const REGISTER_MINUTES: u8 = 0x02;
+const REGISTER_MINUTES_ALARM: u8 = 0x03;
+const REGISTER_HOURS: u8 = 0x04;
+const REGISTER_HOURS_ALARM: u8 = 0x05;
+const REGISTER_DAY_OF_MONTH: u8 = 0x07;
+const REGISTER_MONTH: u8 = 0x08;
+const REGISTER_YEAR: u8 = 0x09;
+const REGISTER_STATUS_A: u8 = 0x0A;
+const REGISTER_STATUS_B: u8 = 0x0B;
+const REGISTER_STATUS_C: u8 = 0x0C;
+
+const STATUS_A_UPDATE_IN_PROGRESS: u8 = 1 << 7;
+const STATUS_A_RATE_MASK: u8 = 0x0F;
+const STATUS_B_24_HOUR: u8 = 1 << 1;
+const STATUS_B_BINARY: u8 = 1 << 2;
+const STATUS_B_UPDATE_INTERRUPT: u8 = 1 << 4;
+const STATUS_B_ALARM_INTERRUPT: u8 = 1 << 5;
+const STATUS_B_PERIODIC_INTERRUPT: u8 = 1 << 6;
+const STATUS_C_UPDATE: u8 = 1 << 4;
+const STATUS_C_ALARM: u8 = 1 << 5;
+const STATUS_C_PERIODIC: u8 = 1 << 6;
+/// An alarm field with the top two bits set matches every value
+const ALARM_DONT_CARE: u8 = 0xC0;
+
+const MAX_READ_ATTEMPTS: usize = 8;
+
+/// A function run from the RTC interrupt handler
+pub type RtcHandler = fn();
+
+/// The interrupt sources reported by a read of status register C
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct RtcInterrupts {
+    pub periodic: bool,
+    pub alarm: bool,
+    pub update: bool,
+}
+
+/// The Motorola MC146818 compatible real-time clock in CMOS RAM.
+pub struct RtcCmos {
+    index: Port,
+    data: Port,
+    century_register: Option<u8>,
+    periodic_handler: Option<RtcHandler>,
+    alarm_handler: Option<RtcHandler>,
+    update_handler: Option<RtcHandler>,
+}
+
+impl RtcCmos {
+    /// ## Safety
+    ///
+    /// This is unsafe as only one instance should control the CMOS index port.
+    pub const unsafe fn new() -> RtcCmos {
+        return RtcCmos {
+            index: Port::new(INDEX_PORT),
+            data: Port::new(DATA_PORT),
+            century_register: None,
+            periodic_handler: None,
+            alarm_handler: None,
+            update_handler: None,
+        };
+    }
+
+    /// Sets the CMOS index of the century register, from the ACPI FADT. Without it the year
+    /// is assumed to be in the 2000s.
+    pub fn set_century_register(&mut self, register: Option<u8>) {
+        self.century_register = register;
+    }
+
+    fn read_register(&self, register: u8) -> u8 {
+        unsafe {
+            //Bit 7 of the index disables NMIs so it is kept clear
+            self.index.out_u8(register & 0x7F);
+            return self.data.in_u8();
+        }
+    }
+
+    fn write_register(&self, register: u8, value: u8) {
+        unsafe {
+            self.index.out_u8(register & 0x7F);
+            self.data.out_u8(value);
+        }
+    }
+
+    fn update_in_progress(&self) -> bool {
+        return self.read_register(REGISTER_STATUS_A) & STATUS_A_UPDATE_IN_PROGRESS != 0;
+    }
+
+    fn read_raw(&self) -> [u8; 7] {
+        while self.update_in_progress() {
+            core::hint::spin_loop();
+        }
+
+        let century = match self.century_register {
+            Some(register) => self.read_register(register),
+            None => 0,
+        };
+        return [
+            self.read_register(REGISTER_SECONDS),
+            self.read_register(REGISTER_MINUTES),
+            self.read_register(REGISTER_HOURS),
+            self.read_register(REGISTER_DAY_OF_MONTH),
+            self.read_register(REGISTER_MONTH),
+            self.read_register(REGISTER_YEAR),
+            century,
+        ];
+    }
+
+    /// Reads the current date and time. The registers are read until two reads in a row agree
+    /// so an update between reads cannot give a torn value.
+    pub fn read(&self) -> DateTime {
+        let mut raw = self.read_raw();
+        for _ in 0..MAX_READ_ATTEMPTS {
+            let next = self.read_raw();
+            if next == raw {
+                break;
+            }
+            raw = next;
+        }
+
+        let status_b = self.read_register(REGISTER_STATUS_B);
+        let binary = status_b & STATUS_B_BINARY != 0;
+        let decode = |value: u8| if binary { value } else { bcd_to_binary(value) };
+
+        let hour = decode_hour(raw[2], binary, status_b & STATUS_B_24_HOUR != 0);
+        let century = self.century_register.map(|_| decode(raw[6]));
+
+        return DateTime {
+            year: full_year(century, decode(raw[5])),
+            month: decode(raw[4]),
+            day: decode(raw[3]),
+            hour: hour,
+            minute: decode(raw[1]),
+            second: decode(raw[0]),
+        };
+    }
+
+    /// Sets the periodic interrupt rate. The frequency is 32768 >> (rate - 1) Hz, so rate 3 is
+    /// 8192Hz and rate 15 is 2Hz. A rate of 0 stops the periodic signal. Returns the frequency
+    /// programmed.
+    pub fn set_periodic_rate(&self, rate: u8) -> u32 {
+        let rate = match rate {
+            0 => 0,
+            1..=3 => 3,
+            _ => core::cmp::min(rate, 15),
+        };
+
+        let status_a = self.read_register(REGISTER_STATUS_A);
+        self.write_register(REGISTER_STATUS_A, (status_a & !STATUS_A_RATE_MASK) | rate);
+        return Self::periodic_frequency(rate);
+    }
+
+    pub const fn periodic_frequency(rate: u8) -> u32 {
+        if rate == 0 {
+            return 0;
+        }
+        return RTC_BASE_FREQUENCY >> (rate - 1);
+    }
+
+    fn set_status_b_bits(&self, bits: u8, enable: bool) {
+        let status_b = self.read_register(REGISTER_STATUS_B);
+        let status_b = if enable { status_b | bits } else { status_b & !bits };
+        self.write_register(REGISTER_STATUS_B, status_b);
+    }
+
+    /// Sets the function run on each periodic interrupt. The interrupt is enabled while there
+    /// is a handler.
+    pub fn set_periodic_handler(&mut self, handler: Option<RtcHandler>) {
+        self.periodic_handler = handler;
+        self.set_status_b_bits(STATUS_B_PERIODIC_INTERRUPT, handler.is_some());
+    }
+
+    /// Sets the function run once a second after the time registers are updated. The
+    /// interrupt is enabled while there is a handler.
+    pub fn set_update_handler(&mut self, handler: Option<RtcHandler>) {
+        self.update_handler = handler;
+        self.set_status_b_bits(STATUS_B_UPDATE_INTERRUPT, handler.is_some());
+    }
+
+    /// Sets the daily alarm. A field of None matches every value so an alarm with only
+    /// *second* set fires once a minute.
+    pub fn set_alarm(&self, hour: Option<u8>, minute: Option<u8>, second: Option<u8>) {
+        let status_b = self.read_register(REGISTER_STATUS_B);
+        let binary = status_b & STATUS_B_BINARY != 0;
+        let twenty_four_hour = status_b & STATUS_B_24_HOUR != 0;
+        let encode = |value: Option<u8>| match value {
+            Some(value) if binary => value,
+            Some(value) => binary_to_bcd(value),
+            None => ALARM_DONT_CARE,
+        };
+        let encoded_hour = match hour {
+            Some(hour) => encode_hour(hour, binary, twenty_four_hour),
+            None => ALARM_DONT_CARE,
+        };
+
+        self.write_register(REGISTER_SECONDS_ALARM, encode(second));
+        self.write_register(REGISTER_MINUTES_ALARM, encode(minute));
+        self.write_register(REGISTER_HOURS_ALARM, encoded_hour);
+    }
+
+    /// Sets the function run when the alarm time is reached. The interrupt is enabled while
+    /// there is a handler.
+    pub fn set_alarm_handler(&mut self, handler: Option<RtcHandler>) {
+        self.alarm_handler = handler;
+        self.set_status_b_bits(STATUS_B_ALARM_INTERRUPT, handler.is_some());
+    }
+
+    /// Reads status register C, which acknowledges the interrupt. The RTC raises no further
+    /// interrupts until this has been read.
+    pub fn acknowledge_interrupt(&self) -> RtcInterrupts {
+        let status_c = self.read_register(REGISTER_STATUS_C);
+        return RtcInterrupts {
+            periodic: status_c & STATUS_C_PERIODIC != 0,
+            alarm: status_c & STATUS_C_ALARM != 0,
+            update: status_c & STATUS_C_UPDATE != 0,
+        };
+    }
+
+    /// Acknowledges the interrupt and returns the handlers of the sources that raised it.
+    /// They are returned rather than run so the caller can release the RTC lock first.
+    pub fn take_interrupt(&self) -> [Option<RtcHandler>; 3] {
+        let interrupts = self.acknowledge_interrupt();
+        return [
+            self.periodic_handler.filter(|_| interrupts.periodic),
+            self.alarm_handler.filter(|_| interrupts.alarm),
+            self.update_handler.filter(|_| interrupts.update),
+        ];
+    }
+}
+
+pub static RTC: Mutex<RtcCmos> = Mutex::new(unsafe { RtcCmos::new() });
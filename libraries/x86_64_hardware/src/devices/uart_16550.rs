@@ -1,18 +1,176 @@
 use crate::devices::ioport::Port;
+use crate::memory::VirtualAddress;
 use core::fmt;
+use core::sync::atomic::{AtomicBool, Ordering};
+use data_structures::ringbuffer::RingBuffer;
 use spin::Mutex;
 
+const DATA_REGISTER: u16 = 0;
+const INTERRUPT_ENABLE_REGISTER: u16 = 1;
+const FIFO_CONTROL_REGISTER: u16 = 2;
+const INTERRUPT_IDENTIFICATION_REGISTER: u16 = 2;
+const LINE_CONTROL_REGISTER: u16 = 3;
+const MODEM_CONTROL_REGISTER: u16 = 4;
+const LINE_STATUS_REGISTER: u16 = 5;
+const MODEM_STATUS_REGISTER: u16 = 6;
+const SCRATCH_REGISTER: u16 = 7;
+
+/// Loopback with RTS, OUT1 and OUT2 set so the transmitter feeds straight back to the receiver
+const MODEM_CONTROL_LOOPBACK: u8 = 0x1E;
+const PROBE_SCRATCH_PATTERNS: [u8; 2] = [0x55, 0xAA];
+const PROBE_LOOPBACK_VALUE: u8 = 0xAE;
+
+const INTERRUPT_RECEIVED_DATA: u8 = 1 << 0;
+const INTERRUPT_TRANSMIT_EMPTY: u8 = 1 << 1;
+const INTERRUPT_LINE_STATUS: u8 = 1 << 2;
+
+const IIR_NO_INTERRUPT_PENDING: u8 = 1 << 0;
+const IIR_ID_MASK: u8 = 0b111 << 1;
+const IIR_MODEM_STATUS: u8 = 0b000 << 1;
+const IIR_TRANSMIT_EMPTY: u8 = 0b001 << 1;
+const IIR_RECEIVED_DATA: u8 = 0b010 << 1;
+const IIR_LINE_STATUS: u8 = 0b011 << 1;
+const IIR_CHARACTER_TIMEOUT: u8 = 0b110 << 1;
+
+const LINE_STATUS_DATA_READY: u8 = 1 << 0;
+const LINE_STATUS_OVERRUN: u8 = 1 << 1;
+const LINE_STATUS_PARITY: u8 = 1 << 2;
+const LINE_STATUS_FRAMING: u8 = 1 << 3;
+const LINE_STATUS_BREAK: u8 = 1 << 4;
+const LINE_STATUS_TRANSMIT_EMPTY: u8 = 1 << 5;
+
+/// The depth of the 16550A transmit FIFO
+const TRANSMIT_FIFO_SIZE: usize = 16;
+pub const RECEIVE_BUFFER_SIZE: usize = 256;
+pub const TRANSMIT_BUFFER_SIZE: usize = 4096;
+
+/// The frequency of the standard PC UART input clock. The UART divides this by 16 so a
+/// divisor of 1 gives 115200 baud.
+pub const DEFAULT_UART_CLOCK_HZ: u32 = 1_843_200;
+pub const DEFAULT_BAUD_RATE: u32 = 115200;
+
+/// Calculates the divisor latch value for *baud_rate* given the UART input clock
+pub const fn baud_divisor(clock_hz: u32, baud_rate: u32) -> u16 {
+    if baud_rate == 0 {
+        return 1;
+    }
+
+    let divisor = clock_hz / (16 * baud_rate);
+    if divisor == 0 {
+        return 1;
+    } else if divisor > u16::MAX as u32 {
+        return u16::MAX;
+    } else {
+        return divisor as u16;
+    }
+}
+
+/// An error reported by the line status register while receiving
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LineError {
+    /// A character arrived while the receive FIFO was full and was lost
+    Overrun,
+    Parity,
+    Framing,
+    /// The line was held low for longer than a character
+    Break,
+    /// The receive buffer was full so received characters were dropped
+    BufferOverflow,
+}
+
+/// The error that goes with the character at the head of the receive FIFO
+fn character_error(line_status: u8) -> Option<LineError> {
+    if line_status & LINE_STATUS_BREAK != 0 {
+        return Some(LineError::Break);
+    } else if line_status & LINE_STATUS_FRAMING != 0 {
+        return Some(LineError::Framing);
+    } else if line_status & LINE_STATUS_PARITY != 0 {
+        return Some(LineError::Parity);
+    }
+    return None;
+}
+
+type ReceivedCharacter = Result<u8, LineError>;
+
+/// The queues used when a port is interrupt driven. They are kept outside of the port so the
+/// interrupt handler can use them without taking the lock around the port.
+pub struct SerialBuffers {
+    receive: RingBuffer<ReceivedCharacter, RECEIVE_BUFFER_SIZE>,
+    transmit: RingBuffer<u8, TRANSMIT_BUFFER_SIZE>,
+    receive_overflowed: AtomicBool,
+}
+
+impl SerialBuffers {
+    pub const fn new() -> SerialBuffers {
+        return SerialBuffers {
+            receive: RingBuffer::new(Ok(0)),
+            transmit: RingBuffer::new(0),
+            receive_overflowed: AtomicBool::new(false),
+        };
+    }
+
+    fn push_received(&self, character: ReceivedCharacter) {
+        if self.receive.write(character).is_none() {
+            self.receive_overflowed.store(true, Ordering::Relaxed);
+        }
+    }
+
+    fn pop_received(&self) -> Option<ReceivedCharacter> {
+        //Report lost characters before the ones after them
+        if self.receive_overflowed.swap(false, Ordering::Relaxed) {
+            return Some(Err(LineError::BufferOverflow));
+        }
+        return self.receive.read();
+    }
+}
+
+#[derive(Clone, Copy)]
+enum RegisterAccess {
+    Io(u16),
+    /// Memory mapped registers. *register_width* is the spacing of the registers in bytes,
+    /// either 1 or 4, and each register is accessed at that width.
+    Mmio { base_address: VirtualAddress, register_width: u8 },
+}
+
+impl RegisterAccess {
+    unsafe fn read(&self, register: u16) -> u8 {
+        match *self {
+            RegisterAccess::Io(base_port) => {
+                return Port::new(base_port + register).in_u8();
+            },
+            RegisterAccess::Mmio { base_address, register_width } => {
+                let address = base_address.as_u64() + register as u64 * register_width as u64;
+                if register_width == 4 {
+                    return core::ptr::read_volatile(address as *const u32) as u8;
+                } else {
+                    return core::ptr::read_volatile(address as *const u8);
+                }
+            },
+        }
+    }
+
+    unsafe fn write(&self, register: u16, value: u8) {
+        match *self {
+            RegisterAccess::Io(base_port) => {
+                Port::new(base_port + register).out_u8(value);
+            },
+            RegisterAccess::Mmio { base_address, register_width } => {
+                let address = base_address.as_u64() + register as u64 * register_width as u64;
+                if register_width == 4 {
+                    core::ptr::write_volatile(address as *mut u32, value as u32);
+                } else {
+                    core::ptr::write_volatile(address as *mut u8, value);
+                }
+            },
+        }
+    }
+}
+
+/// A 16550 UART. Ports start polled; *enable_interrupts* switches them to queue characters
+/// in *SerialBuffers* and move them to and from the FIFOs in the interrupt handler.
 pub struct SerialPort {
-    data_reg : Port,
-    inter_reg : Port,
-    inter_ident_fifo_control_reg : Port,
-    line_control_reg : Port,
-    modem_control_reg : Port,
-    line_status_reg : Port,
-    #[allow(dead_code)]
-    modem_status_reg : Port,
-    #[allow(dead_code)]
-    scratch_reg : Port,
+    registers: RegisterAccess,
+    buffers: Option<&'static SerialBuffers>,
 }
 
 impl SerialPort {
@@ -21,72 +179,306 @@ impl SerialPort {
     ///type is safe.
     pub const unsafe fn new(base_port: u16) -> SerialPort {
         return SerialPort {
-            data_reg : Port::new(base_port),
-            inter_reg : Port::new(base_port + 1),
-            inter_ident_fifo_control_reg : Port::new(base_port + 2),
-            line_control_reg : Port::new(base_port + 3),
-            modem_control_reg : Port::new(base_port + 4),
-            line_status_reg : Port::new(base_port + 5),
-            modem_status_reg : Port::new(base_port + 6),
-            scratch_reg : Port::new(base_port + 7),
+            registers: RegisterAccess::Io(base_port),
+            buffers: None,
         };
     }
 
+    /// Creates a serial port for a memory mapped 16550. *register_width* is the spacing of the
+    /// registers in bytes and must be 1 or 4.
+    /// 
+    /// ## Safety
+    /// 
+    /// This is unsafe as we cannot know the mapping is valid. The caller must ensure the UART
+    /// registers are mapped at *base_address* with caching disabled.
+    pub const unsafe fn new_mmio(base_address: VirtualAddress, register_width: u8) -> SerialPort {
+        let register_width = if register_width == 4 { 4 } else { 1 };
+        return SerialPort {
+            registers: RegisterAccess::Mmio { base_address: base_address, register_width: register_width },
+            buffers: None,
+        };
+    }
+
+    /// Initialises the port at 115200 baud
     pub fn initialise(&self) {
+        self.initialise_with_divisor(baud_divisor(DEFAULT_UART_CLOCK_HZ, DEFAULT_BAUD_RATE));
+    }
+
+    /// Initialises the port at the given baud rate assuming the standard PC UART clock
+    pub fn initialise_with_baud(&self, baud_rate: u32) {
+        self.initialise_with_divisor(baud_divisor(DEFAULT_UART_CLOCK_HZ, baud_rate));
+    }
+
+    pub fn initialise_with_divisor(&self, divisor: u16) {
+        unsafe {
+            self.disable_interrupts();
+            self.set_baud_divisor(divisor);
+            self.configure_line();
+        }
+    }
+
+    /// Initialises the port without touching the divisor. This is used when the firmware has
+    /// already configured the baud rate and asks the OS to keep it.
+    pub fn initialise_preserving_baud(&self) {
+        unsafe {
+            self.disable_interrupts();
+            self.configure_line();
+        }
+    }
+
+    /// Switches the port to interrupt driven IO using *buffers* as the queues. The returned
+    /// handler must be called from the interrupt handler for the UART's IRQ, which the caller
+    /// is responsible for routing.
+    pub fn enable_interrupts(&mut self, buffers: &'static SerialBuffers) -> SerialInterruptHandler {
+        self.buffers = Some(buffers);
+        unsafe {
+            //OUT2 gates the UART interrupt line on PC hardware
+            self.write_register(MODEM_CONTROL_REGISTER, 0x0B);
+            self.write_register(INTERRUPT_ENABLE_REGISTER, INTERRUPT_RECEIVED_DATA | INTERRUPT_LINE_STATUS | INTERRUPT_TRANSMIT_EMPTY);
+        }
+        return SerialInterruptHandler { registers: self.registers, buffers: buffers };
+    }
+
+    /// Checks there is a working UART at this location. The scratch register must hold a value
+    /// and a byte sent in loopback mode must come back. The port must be initialised again
+    /// afterwards as this changes the line and modem settings.
+    pub fn probe(&self) -> bool {
         unsafe {
+            for pattern in PROBE_SCRATCH_PATTERNS {
+                self.write_register(SCRATCH_REGISTER, pattern);
+                if self.read_register(SCRATCH_REGISTER) != pattern {
+                    return false;
+                }
+            }
+
             self.disable_interrupts();
-            self.set_baud_divisor(0x1);
-            self.line_control_reg.out_u8(0x3); // 8 bit mode, no parity, 1 stop bit
-            self.inter_ident_fifo_control_reg.out_u8(0xC7);
-            self.modem_control_reg.out_u8(0x0B);
+            self.set_baud_divisor(baud_divisor(DEFAULT_UART_CLOCK_HZ, DEFAULT_BAUD_RATE));
+            self.configure_line();
+            self.write_register(MODEM_CONTROL_REGISTER, MODEM_CONTROL_LOOPBACK);
+            self.write_register(DATA_REGISTER, PROBE_LOOPBACK_VALUE);
+
+            //The byte takes around 100us to loop back at 115200 baud
+            let mut received = None;
+            for _ in 0..10_000 {
+                if self.read_register(LINE_STATUS_REGISTER) & LINE_STATUS_DATA_READY != 0 {
+                    received = Some(self.read_register(DATA_REGISTER));
+                    break;
+                }
+                core::hint::spin_loop();
+            }
+
+            self.write_register(MODEM_CONTROL_REGISTER, 0x0B);
+            return received == Some(PROBE_LOOPBACK_VALUE);
         }
     }
 
+    /// The base IO port, or None for a memory mapped UART
+    pub fn io_base(&self) -> Option<u16> {
+        match self.registers {
+            RegisterAccess::Io(base_port) => Some(base_port),
+            RegisterAccess::Mmio { .. } => None,
+        }
+    }
+
+    pub fn is_interrupt_driven(&self) -> bool {
+        return self.buffers.is_some();
+    }
+
+    /// Queues *value* for transmission, or sends it directly if the port is polled. When the
+    /// transmit queue is full the oldest queued character is sent by polling to make room.
     pub fn write_byte(&self, value : u8) {
+        match self.buffers {
+            Some(buffers) => {
+                self.queue_byte(buffers, value);
+                self.start_transmitter();
+            },
+            None => unsafe { self.transmit_polled(value) },
+        }
+    }
+
+    fn queue_byte(&self, buffers: &SerialBuffers, value: u8) {
+        while buffers.transmit.write(value).is_none() {
+            if let Some(queued) = buffers.transmit.read() {
+                unsafe { self.transmit_polled(queued) };
+            }
+        }
+    }
+
+    /// Toggling the transmit empty interrupt makes the UART raise it again if the transmitter
+    /// is already idle, so the interrupt handler starts sending the queue.
+    fn start_transmitter(&self) {
+        unsafe {
+            self.write_register(INTERRUPT_ENABLE_REGISTER, INTERRUPT_RECEIVED_DATA | INTERRUPT_LINE_STATUS);
+            self.write_register(INTERRUPT_ENABLE_REGISTER, INTERRUPT_RECEIVED_DATA | INTERRUPT_LINE_STATUS | INTERRUPT_TRANSMIT_EMPTY);
+        }
+    }
+
+    /// Sends everything in the transmit queue by polling. This is for when interrupts cannot
+    /// be relied on, such as before halting.
+    pub fn flush(&self) {
+        if let Some(buffers) = self.buffers {
+            while let Some(value) = buffers.transmit.read() {
+                unsafe { self.transmit_polled(value) };
+            }
+        }
+    }
+
+    unsafe fn transmit_polled(&self, value: u8) {
+        while !self.is_transmit_empty() { } //Wait for transmission to clear
+        self.write_register(DATA_REGISTER, value);
+    }
+
+    /// Returns the next received character without blocking. Errors are reported in order
+    /// with the characters around them; a character with a parity or framing error is
+    /// replaced by the error.
+    pub fn read_byte(&self) -> Result<Option<u8>, LineError> {
+        if let Some(buffers) = self.buffers {
+            return buffers.pop_received().transpose();
+        }
+
         unsafe {
-            while !self.is_transmit_empty() { } //Wait for transmission to clear
-            self.data_reg.out_u8(value);
+            let line_status = self.read_register(LINE_STATUS_REGISTER);
+            //Reading the line status clears the overrun so the data is picked up next call
+            if line_status & LINE_STATUS_OVERRUN != 0 {
+                return Err(LineError::Overrun);
+            }
+            if line_status & LINE_STATUS_DATA_READY == 0 {
+                return Ok(None);
+            }
+
+            let value = self.read_register(DATA_REGISTER);
+            return match character_error(line_status) {
+                Some(error) => Err(error),
+                None => Ok(Some(value)),
+            };
         }
     }
 
+    unsafe fn configure_line(&self) {
+        self.write_register(LINE_CONTROL_REGISTER, 0x3); // 8 bit mode, no parity, 1 stop bit
+        self.write_register(FIFO_CONTROL_REGISTER, 0xC7);
+        self.write_register(MODEM_CONTROL_REGISTER, 0x0B);
+    }
+
     unsafe fn set_baud_divisor(&self, divisor : u16) {
         let upper_divisor : u8 = ((divisor >> 8) & 0xFF) as u8;
         let lower_divisor : u8 = (divisor & 0xFF) as u8;
         self.set_dlab(true);
-        self.data_reg.out_u8(lower_divisor);
-        self.inter_reg.out_u8(upper_divisor);
+        self.write_register(DATA_REGISTER, lower_divisor);
+        self.write_register(INTERRUPT_ENABLE_REGISTER, upper_divisor);
         self.set_dlab(false);
 
     }
 
     unsafe fn disable_interrupts(&self) {
-        self.inter_reg.out_u8(0x00);
+        self.write_register(INTERRUPT_ENABLE_REGISTER, 0x00);
     }
 
     unsafe fn set_dlab(&self, enable : bool) {
-        let current_value = self.line_control_reg.in_u8();
+        let current_value = self.read_register(LINE_CONTROL_REGISTER);
         if enable {
-            self.line_control_reg.out_u8(current_value | 0x80);
+            self.write_register(LINE_CONTROL_REGISTER, current_value | 0x80);
         } else {
-            self.line_control_reg.out_u8(current_value & 0x7F);
+            self.write_register(LINE_CONTROL_REGISTER, current_value & 0x7F);
         }
     }
 
     unsafe fn is_transmit_empty(&self) -> bool {
-        return (self.line_status_reg.in_u8() & 0x20) != 0;
+        return (self.read_register(LINE_STATUS_REGISTER) & LINE_STATUS_TRANSMIT_EMPTY) != 0;
+    }
+
+    unsafe fn read_register(&self, register: u16) -> u8 {
+        return self.registers.read(register);
+    }
+
+    unsafe fn write_register(&self, register: u16, value: u8) {
+        self.registers.write(register, value);
+    }
+}
+
+/// Services the interrupts of an interrupt driven port. It holds its own copy of the register
+/// location so it can run while another processor holds the lock around the port.
+pub struct SerialInterruptHandler {
+    registers: RegisterAccess,
+    buffers: &'static SerialBuffers,
+}
+
+impl SerialInterruptHandler {
+    /// Handles every pending interrupt condition. The caller still sends the EOI.
+    pub fn handle_interrupt(&self) {
+        unsafe {
+            loop {
+                let identification = self.registers.read(INTERRUPT_IDENTIFICATION_REGISTER);
+                if identification & IIR_NO_INTERRUPT_PENDING != 0 {
+                    return;
+                }
+
+                match identification & IIR_ID_MASK {
+                    IIR_LINE_STATUS | IIR_RECEIVED_DATA | IIR_CHARACTER_TIMEOUT => self.receive(),
+                    IIR_TRANSMIT_EMPTY => self.transmit(),
+                    IIR_MODEM_STATUS => { self.registers.read(MODEM_STATUS_REGISTER); },
+                    _ => { return; },
+                }
+            }
+        }
+    }
+
+    unsafe fn receive(&self) {
+        loop {
+            let line_status = self.registers.read(LINE_STATUS_REGISTER);
+            if line_status & LINE_STATUS_OVERRUN != 0 {
+                self.buffers.push_received(Err(LineError::Overrun));
+            }
+            if line_status & LINE_STATUS_DATA_READY == 0 {
+                return;
+            }
+
+            let value = self.registers.read(DATA_REGISTER);
+            match character_error(line_status) {
+                Some(error) => self.buffers.push_received(Err(error)),
+                None => self.buffers.push_received(Ok(value)),
+            }
+        }
+    }
+
+    /// Refills the transmit FIFO. The transmit empty interrupt is only raised when the FIFO
+    /// has completely drained so it can take a full FIFO's worth.
+    unsafe fn transmit(&self) {
+        for _ in 0..TRANSMIT_FIFO_SIZE {
+            match self.buffers.transmit.read() {
+                Some(value) => self.registers.write(DATA_REGISTER, value),
+                None => { return; },
+            }
+        }
     }
 }
 
 impl fmt::Write for SerialPort {
     fn write_str(&mut self, s: &str) -> fmt::Result {
-        for byte in s.bytes() {
-            self.write_byte(byte);
+        match self.buffers {
+            Some(buffers) => {
+                //Queue the whole string so the transmitter is only started once
+                for byte in s.bytes() {
+                    self.queue_byte(buffers, byte);
+                }
+                self.start_transmitter();
+            },
+            None => {
+                for byte in s.bytes() {
+                    self.write_byte(byte);
+                }
+            },
         }
         Ok(())
     }
 }
 
-const COM1_BASE : u16 = 0x3F8;
+pub const COM1_BASE : u16 = 0x3F8;
+pub const COM2_BASE : u16 = 0x2F8;
+pub const COM3_BASE : u16 = 0x3E8;
+pub const COM4_BASE : u16 = 0x2E8;
+/// The ISA IRQs the standard COM ports are wired to. COM3 and COM4 share with COM1 and COM2
+pub const COM_PORT_IRQS: [u8; 4] = [4, 3, 4, 3];
 
 pub fn com1_port() -> SerialPort {
     unsafe {
@@ -97,6 +489,9 @@ pub fn com1_port() -> SerialPort {
 }
 
 pub static COM1: Mutex<SerialPort> = Mutex::new(unsafe {SerialPort::new(COM1_BASE) });
+pub static COM2: Mutex<SerialPort> = Mutex::new(unsafe {SerialPort::new(COM2_BASE) });
+pub static COM3: Mutex<SerialPort> = Mutex::new(unsafe {SerialPort::new(COM3_BASE) });
+pub static COM4: Mutex<SerialPort> = Mutex::new(unsafe {SerialPort::new(COM4_BASE) });
 
 #[macro_export]
 macro_rules! com1_print {
@@ -105,7 +500,7 @@ macro_rules! com1_print {
 
 #[macro_export]
 macro_rules! com1_println {
-    () => ($crate::print!("\n"));
+    () => ($crate::com1_print!("\n"));
     ($($arg:tt)*) => ($crate::com1_print!("{}\n", format_args!($($arg)*)));
 }
 
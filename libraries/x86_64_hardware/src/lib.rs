@@ -1,5 +1,7 @@
 #![no_std]
 
+pub mod apic;
+pub mod cpu;
 pub mod devices;
 pub mod memory;
 pub mod tables;
@@ -69,6 +69,13 @@ impl VirtualAddress {
         return VirtualAddress(addr);
     }
 
+    /// True if *addr* is canonical, with bits 48 to 63 copies of bit 47. Anything else
+    /// faults when used.
+    #[inline]
+    pub const fn is_canonical(addr: u64) -> bool {
+        return VirtualAddress::new(addr).as_u64() == addr;
+    }
+
     #[inline]
     pub fn new_from_page_table_indexes(p4: u64, p3: u64, p2: u64, p1: u64, offset: u64) -> VirtualAddress {
         let mut addr = offset;
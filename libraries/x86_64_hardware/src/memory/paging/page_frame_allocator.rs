@@ -96,6 +96,27 @@ impl PageFrameAllocatorInner {
         return PhysicalAddress::new(0);
     }
 
+    fn request_pages_in_range(&mut self, store: &mut [PhysicalAddress], start: PhysicalAddress, end: PhysicalAddress) -> usize {
+        let first_page = start.as_usize() / PAGE_SIZE as usize;
+        let end_page = core::cmp::min(end.as_usize() / PAGE_SIZE as usize, self.page_bitmap.size() * 8);
+
+        let mut count = 0;
+        for index in first_page..end_page {
+            if count == store.len() {
+                break;
+            }
+
+            if !self.page_bitmap.get(index) {
+                let addr = PhysicalAddress::new(index as u64 * PAGE_SIZE);
+                self.lock_page(addr);
+                store[count] = addr;
+                count += 1;
+            }
+        }
+
+        return count;
+    }
+
     fn free_page(&mut self, address: PhysicalAddress) {
         let page_number = address.as_usize() / PAGE_SIZE as usize;
         if !self.page_bitmap.get(page_number) {
@@ -162,6 +183,12 @@ impl PageFrameAllocator {
         }
     }
 
+    /// Allocates up to *store.len()* free pages within [*start*, *end*) and returns the number
+    /// allocated. Used to take memory from a specific NUMA node.
+    pub fn request_pages_in_range(&self, store: &mut [PhysicalAddress], start: PhysicalAddress, end: PhysicalAddress) -> usize {
+        return self.lockable_allocator.lock().request_pages_in_range(store, start, end);
+    }
+
     pub fn lock_page(&self, address: PhysicalAddress) {
         self.lockable_allocator.lock().lock_page(address);
     }
@@ -5,8 +5,10 @@ pub const PAGE_TABLE_MAX_INDEX: usize = 511;
 const PRESENT_FLAG: u64 = 1 << 0;
 const READ_WRITE_FLAG: u64 = 1 << 1;
 const _USER_SUPERVISOR_FLAG: u64 = 1 << 2;
+const WRITE_THROUGH_FLAG: u64 = 1 << 3;
+const CACHE_DISABLE_FLAG: u64 = 1 << 4;
 const PAGE_SIZE_FLAG: u64 = 1 << 7;
-const _EXECUTE_DISABLE_FLAG: u64 = 1 << 63;
+const EXECUTE_DISABLE_FLAG: u64 = 1 << 63;
 
 
 #[repr(transparent)]
@@ -46,6 +48,26 @@ impl PageTableEntry {
         self.set_flags(READ_WRITE_FLAG, value);
     }
 
+    #[inline]
+    pub fn write_through(&self) -> bool {
+        return self.flags_active(WRITE_THROUGH_FLAG);
+    }
+
+    #[inline]
+    pub fn set_write_through(&mut self, value: bool) {
+        self.set_flags(WRITE_THROUGH_FLAG, value);
+    }
+
+    #[inline]
+    pub fn cache_disable(&self) -> bool {
+        return self.flags_active(CACHE_DISABLE_FLAG);
+    }
+
+    #[inline]
+    pub fn set_cache_disable(&mut self, value: bool) {
+        self.set_flags(CACHE_DISABLE_FLAG, value);
+    }
+
     #[inline]
     pub fn page_size(&self) -> bool {
         return self.flags_active(PAGE_SIZE_FLAG);
@@ -56,6 +78,17 @@ impl PageTableEntry {
         self.set_flags(PAGE_SIZE_FLAG, value);
     }
 
+    /// Set if instructions cannot be fetched from the page. Only valid once EFER.NXE is set.
+    #[inline]
+    pub fn execute_disable(&self) -> bool {
+        return self.flags_active(EXECUTE_DISABLE_FLAG);
+    }
+
+    #[inline]
+    pub fn set_execute_disable(&mut self, value: bool) {
+        self.set_flags(EXECUTE_DISABLE_FLAG, value);
+    }
+
     #[inline]
     pub fn address(&self) -> PhysicalAddress {
         return PhysicalAddress::new(self.entry & PHYSICAL_ADDRESS_MASK);
@@ -86,6 +86,65 @@ impl PageTableManager {
         }
     }
 
+    /// Maps memory with restricted access. Read only pages are only protected from the
+    /// kernel once CR0.WP is set and *executable* is ignored unless EFER.NXE is set, which the
+    /// caller signals with *no_execute_enabled*. A page already mapped to the same frame
+    /// keeps the access it had as well, so a page shared by two ELF segments gets the
+    /// access of both.
+    pub fn map_memory_pages_with_access(&self, virtual_addr : VirtualAddress, physical_addr: PhysicalAddress, num_pages: u64, writable: bool, executable: bool, no_execute_enabled: bool, allocator: &mut impl FrameAllocator) {
+        for page in 0..num_pages {
+            let cur_paddr = physical_addr.increment_page_4kb(page);
+            let cur_vaddr = virtual_addr.increment_page_4kb(page);
+            let (mut writable, mut executable) = (writable, executable);
+            if let Some(page_table_entry) = self.get_page_table_entry(cur_vaddr) {
+                if page_table_entry.present() && page_table_entry.address() == cur_paddr {
+                    writable |= page_table_entry.read_write();
+                    executable |= !page_table_entry.execute_disable();
+                }
+            }
+
+            self.map_memory(cur_vaddr, cur_paddr, allocator);
+            if let Some(page_table_entry) = self.get_page_table_entry(cur_vaddr) {
+                page_table_entry.set_read_write(writable);
+                page_table_entry.set_execute_disable(no_execute_enabled && !executable);
+            }
+            invalidate_page(cur_vaddr);
+        }
+    }
+
+    /// Maps memory mapped IO with caching disabled. Any existing mapping of the virtual
+    /// addresses is replaced and flushed from the TLB.
+    pub fn map_mmio_pages(&self, virtual_addr : VirtualAddress, physical_addr: PhysicalAddress, num_pages: u64, allocator: &impl FrameAllocator) {
+        for page in 0..num_pages {
+            let cur_paddr = physical_addr.increment_page_4kb(page);
+            let cur_vaddr = virtual_addr.increment_page_4kb(page);
+            self.map_memory(cur_vaddr, cur_paddr, allocator);
+
+            if let Some(page_table_entry) = self.get_page_table_entry(cur_vaddr) {
+                page_table_entry.set_cache_disable(true);
+                page_table_entry.set_write_through(true);
+            }
+            invalidate_page(cur_vaddr);
+        }
+    }
+
+    /// Maps pages with write-through set and cache disable clear, which selects PAT entry 1.
+    /// That is write-combining once *cpu::enable_write_combining* has been called and
+    /// write-through before. Any existing mapping is replaced and flushed from the TLB.
+    pub fn map_write_combining_pages(&self, virtual_addr : VirtualAddress, physical_addr: PhysicalAddress, num_pages: u64, allocator: &impl FrameAllocator) {
+        for page in 0..num_pages {
+            let cur_paddr = physical_addr.increment_page_4kb(page);
+            let cur_vaddr = virtual_addr.increment_page_4kb(page);
+            self.map_memory(cur_vaddr, cur_paddr, allocator);
+
+            if let Some(page_table_entry) = self.get_page_table_entry(cur_vaddr) {
+                page_table_entry.set_cache_disable(false);
+                page_table_entry.set_write_through(true);
+            }
+            invalidate_page(cur_vaddr);
+        }
+    }
+
     pub fn map_memory(&self, virtual_addr : VirtualAddress, physical_addr: PhysicalAddress, allocator: &impl FrameAllocator) {
         let p4_ptr = unsafe { self.translate_address(self.p4).get_mut_ptr::<PageTable>() };
 
@@ -208,6 +267,21 @@ impl PageTableManager {
         return Some(page_table_entry.address());
     }
 
+    /// Removes the 4KiB mapping for *virtual_addr* and flushes it from this CPU's TLB, returning
+    /// the frame it mapped. The page tables above it are kept. Pages that are part of a 2MiB or
+    /// 1GiB mapping are left alone and give None.
+    pub fn unmap_page(&self, virtual_addr : VirtualAddress) -> Option<PhysicalAddress> {
+        let page_table_entry = self.get_page_table_entry(virtual_addr)?;
+        if !page_table_entry.present() || page_table_entry.page_size() {
+            return None;
+        }
+
+        let physical_addr = page_table_entry.address();
+        page_table_entry.make_unused();
+        invalidate_page(virtual_addr);
+        return Some(physical_addr);
+    }
+
     pub fn unmap_p4_index(&self, p4_index: usize, allocator: & impl FrameAllocator) {
         //We could error here but honestly doing nothing is just fine. Technically the index is completely unmapped
         if p4_index > PAGE_TABLE_MAX_INDEX {
@@ -268,3 +342,11 @@ impl PageTableManager {
         }
     }
 }
+
+/// Flushes the TLB entry for the page containing *virtual_addr* on the current CPU
+#[inline]
+pub fn invalidate_page(virtual_addr: VirtualAddress) {
+    unsafe {
+        core::arch::asm!("invlpg [{}]", in(reg) virtual_addr.as_u64(), options(nostack, preserves_flags));
+    }
+}
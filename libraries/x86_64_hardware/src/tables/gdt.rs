@@ -1,6 +1,13 @@
 use crate::memory::VirtualAddress;
+use crate::tables::{load_tss, TaskStateSegment};
 use core::mem::size_of;
 
+pub const KERNEL_CODE_SELECTOR: u16 = 0x08;
+pub const KERNEL_DATA_SELECTOR: u16 = 0x10;
+pub const TSS_SELECTOR: u16 = 0x28;
+
+const TSS_ACCESS_BYTE: u64 = 0x89; //Present 64 bit available TSS
+
 #[derive(Clone, Copy)]
 #[repr(C, align(8))]
 pub struct GdtEntry(u64);
@@ -18,6 +25,21 @@ impl GdtEntry {
     pub const fn as_u64(&self) -> u64 {
         return self.0;
     }
+
+    /// Creates the two entries of a TSS descriptor. System descriptors are 16 bytes in long
+    /// mode so the upper half of the base goes in a second entry.
+    pub fn new_tss(tss: &TaskStateSegment) -> [GdtEntry; 2] {
+        let base = tss as *const TaskStateSegment as u64;
+        let limit = (size_of::<TaskStateSegment>() - 1) as u64;
+
+        let mut low = limit & 0xFFFF;
+        low |= (base & 0xFF_FFFF) << 16;
+        low |= TSS_ACCESS_BYTE << 40;
+        low |= ((limit >> 16) & 0xF) << 48;
+        low |= ((base >> 24) & 0xFF) << 56;
+
+        return [GdtEntry(low), GdtEntry(base >> 32)];
+    }
 }
 
 #[repr(C, packed(2))]
@@ -35,7 +57,7 @@ impl GdtPointer {
     }
 }
 
-static DEFAULT_GDT: [GdtEntry;5] = [
+const DEFAULT_GDT_ENTRIES: [GdtEntry;5] = [
     GdtEntry::new(0x00, 0x0), //NULL descriptor
     GdtEntry::new(0x9A, 0xA), //Kernel code
     GdtEntry::new(0x92, 0xA), //Kernel data
@@ -43,6 +65,49 @@ static DEFAULT_GDT: [GdtEntry;5] = [
     GdtEntry::new(0xF2, 0xA), //User data
 ];
 
+static DEFAULT_GDT: [GdtEntry;5] = DEFAULT_GDT_ENTRIES;
+
+/// A GDT with the default segments followed by a TSS descriptor. Every CPU needs its own
+/// as the TSS descriptor is marked busy when it is loaded.
+#[repr(C, align(8))]
+pub struct GdtWithTss {
+    entries: [GdtEntry;7],
+}
+
+impl GdtWithTss {
+    pub const fn new() -> GdtWithTss {
+        let mut entries = [GdtEntry::new(0x00, 0x0);7];
+        let mut index = 0;
+        while index < DEFAULT_GDT_ENTRIES.len() {
+            entries[index] = DEFAULT_GDT_ENTRIES[index];
+            index += 1;
+        }
+        return GdtWithTss { entries: entries };
+    }
+
+    pub fn set_tss(&mut self, tss: &'static TaskStateSegment) {
+        let tss_entries = GdtEntry::new_tss(tss);
+        let tss_index = TSS_SELECTOR as usize / size_of::<GdtEntry>();
+        self.entries[tss_index] = tss_entries[0];
+        self.entries[tss_index + 1] = tss_entries[1];
+    }
+
+    /// Loads this GDT and its TSS on the current CPU
+    pub fn load(&'static self) {
+        let gdt_pointer = GdtPointer::new_from_array(&self.entries);
+        unsafe {
+            load_gdt(&gdt_pointer);
+            load_tss(TSS_SELECTOR);
+        }
+    }
+}
+
+impl Default for GdtWithTss {
+    fn default() -> Self {
+        return GdtWithTss::new();
+    }
+}
+
 extern "C" { pub fn load_gdt(gdt_ptr: *const GdtPointer); }
 
 pub fn init_default_gdt() {
@@ -0,0 +1,178 @@
+use crate::memory::VirtualAddress;
+use crate::tables::KERNEL_CODE_SELECTOR;
+use core::mem::size_of;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use spin::Mutex;
+
+pub const NUM_INTERRUPT_VECTORS: usize = 256;
+/// Vectors below this are reserved for CPU exceptions
+pub const FIRST_EXTERNAL_VECTOR: u8 = 32;
+
+const INTERRUPT_GATE: u8 = 0x8E;
+
+/// The state saved on the stack when an interrupt is taken. The general purpose registers
+/// are pushed by the entry stubs in idt.s and the rest by the CPU.
+#[repr(C)]
+#[derive(Debug)]
+pub struct InterruptFrame {
+    pub r15: u64,
+    pub r14: u64,
+    pub r13: u64,
+    pub r12: u64,
+    pub r11: u64,
+    pub r10: u64,
+    pub r9: u64,
+    pub r8: u64,
+    pub rbp: u64,
+    pub rdi: u64,
+    pub rsi: u64,
+    pub rdx: u64,
+    pub rcx: u64,
+    pub rbx: u64,
+    pub rax: u64,
+    pub vector: u64,
+    /// The error code pushed by the CPU, or 0 for vectors that do not have one
+    pub error_code: u64,
+    pub rip: u64,
+    pub cs: u64,
+    pub rflags: u64,
+    pub rsp: u64,
+    pub ss: u64,
+}
+
+pub type InterruptHandler = fn(frame: &mut InterruptFrame);
+
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct IdtEntry {
+    offset_low: u16,
+    selector: u16,
+    ist: u8,
+    type_attributes: u8,
+    offset_mid: u16,
+    offset_high: u32,
+    reserved: u32,
+}
+
+impl IdtEntry {
+    pub const fn missing() -> IdtEntry {
+        return IdtEntry { offset_low: 0, selector: 0, ist: 0, type_attributes: 0, offset_mid: 0, offset_high: 0, reserved: 0 };
+    }
+
+    /// Creates a present ring 0 interrupt gate. Interrupts are disabled while the handler runs
+    pub const fn new(handler: u64, selector: u16, ist: u8) -> IdtEntry {
+        return IdtEntry {
+            offset_low: handler as u16,
+            selector: selector,
+            ist: ist & 0x7,
+            type_attributes: INTERRUPT_GATE,
+            offset_mid: (handler >> 16) as u16,
+            offset_high: (handler >> 32) as u32,
+            reserved: 0,
+        };
+    }
+}
+
+#[repr(C, packed(2))]
+pub struct IdtPointer {
+    pub size: u16,
+    pub addr: VirtualAddress
+}
+
+impl IdtPointer {
+    pub fn new_from_array(table: &[IdtEntry]) -> IdtPointer {
+        IdtPointer {
+            addr: VirtualAddress::new(table.as_ptr() as u64),
+            size: (size_of::<IdtEntry>() * table.len() - 1) as u16,
+        }
+    }
+}
+
+static IDT: Mutex<[IdtEntry; NUM_INTERRUPT_VECTORS]> = Mutex::new([IdtEntry::missing(); NUM_INTERRUPT_VECTORS]);
+
+const NO_HANDLER: AtomicUsize = AtomicUsize::new(0);
+static INTERRUPT_HANDLERS: [AtomicUsize; NUM_INTERRUPT_VECTORS] = [NO_HANDLER; NUM_INTERRUPT_VECTORS];
+
+extern "C" {
+    static isr_stub_table: [u64; NUM_INTERRUPT_VECTORS];
+    pub fn load_idt(idt_ptr: *const IdtPointer);
+}
+
+/// Points every vector at its entry stub and loads the IDT. Handlers are installed
+/// separately with *register_interrupt_handler*.
+pub fn init_default_idt() {
+    let mut idt = IDT.lock();
+    for (vector, entry) in idt.iter_mut().enumerate() {
+        *entry = IdtEntry::new(unsafe { isr_stub_table[vector] }, KERNEL_CODE_SELECTOR, 0);
+    }
+
+    let idt_pointer = IdtPointer::new_from_array(&*idt);
+    unsafe { load_idt(&idt_pointer); }
+}
+
+/// Loads the already initialised IDT on the current CPU. Used by application processors
+/// which share the IDT set up by the bootstrap processor.
+pub fn load_default_idt() {
+    let idt = IDT.lock();
+    let idt_pointer = IdtPointer::new_from_array(&*idt);
+    unsafe { load_idt(&idt_pointer); }
+}
+
+pub fn register_interrupt_handler(vector: u8, handler: InterruptHandler) {
+    INTERRUPT_HANDLERS[vector as usize].store(handler as usize, Ordering::Release);
+}
+
+pub fn unregister_interrupt_handler(vector: u8) {
+    INTERRUPT_HANDLERS[vector as usize].store(0, Ordering::Release);
+}
+
+#[no_mangle]
+extern "C" fn interrupt_dispatch(frame: *mut InterruptFrame) {
+    let frame = unsafe { &mut *frame };
+    let handler = INTERRUPT_HANDLERS[frame.vector as usize & 0xFF].load(Ordering::Acquire);
+
+    if handler != 0 {
+        let handler: InterruptHandler = unsafe { core::mem::transmute(handler) };
+        handler(frame);
+    } else if frame.vector < FIRST_EXTERNAL_VECTOR as u64 {
+        panic!("Unhandled exception {} at {:#x} error code {:#x}", frame.vector, frame.rip, frame.error_code);
+    }
+}
+
+#[inline]
+pub fn enable_interrupts() {
+    unsafe { core::arch::asm!("sti", options(nomem, nostack)); }
+}
+
+#[inline]
+pub fn disable_interrupts() {
+    unsafe { core::arch::asm!("cli", options(nomem, nostack)); }
+}
+
+pub fn interrupts_enabled() -> bool {
+    let rflags: u64;
+    unsafe { core::arch::asm!("pushfq", "pop {}", out(reg) rflags, options(nomem, preserves_flags)); }
+    return rflags & (1 << 9) != 0;
+}
+
+/// Runs *f* with interrupts disabled, restoring the previous state afterwards. Use this
+/// around locks that are also taken by interrupt handlers.
+pub fn without_interrupts<F: FnOnce() -> R, R>(f: F) -> R {
+    let enabled = interrupts_enabled();
+    if enabled {
+        disable_interrupts();
+    }
+
+    let result = f();
+
+    if enabled {
+        enable_interrupts();
+    }
+    return result;
+}
+
+/// Halts the processor until the next interrupt arrives
+#[inline]
+pub fn wait_for_interrupt() {
+    unsafe { core::arch::asm!("hlt", options(nomem, nostack, preserves_flags)); }
+}
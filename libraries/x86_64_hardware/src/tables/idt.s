@@ -0,0 +1,93 @@
+    .altmacro
+
+# Interrupt entry stubs. Every vector gets a stub that pushes a dummy error code (when
+# the CPU does not push one) and the vector number so interrupt_common always sees the
+# same InterruptFrame layout.
+    .macro isr_stub vector
+isr_stub_\vector:
+    .if (\vector == 8) || (\vector == 10) || (\vector == 11) || (\vector == 12) || (\vector == 13) || (\vector == 14) || (\vector == 17) || (\vector == 21) || (\vector == 29) || (\vector == 30)
+    .else
+    pushq $0
+    .endif
+    pushq $\vector
+    jmp interrupt_common
+    .endm
+
+    .macro isr_stub_address vector
+    .quad isr_stub_\vector
+    .endm
+
+    .section .text
+
+    .set vector, 0
+    .rept 256
+    isr_stub %vector
+    .set vector, vector + 1
+    .endr
+
+# Saves the general purpose registers and calls interrupt_dispatch with a pointer to the
+# InterruptFrame. The stack is 16 byte aligned at the call. When the interrupt arrived from
+# user mode the GS base is swapped so the kernel always sees its per-CPU data through GS.
+interrupt_common:
+    # The saved CS is above the vector number, error code and RIP
+    testb $3, 24(%rsp)
+    jz 1f
+    swapgs
+1:
+    pushq %rax
+    pushq %rbx
+    pushq %rcx
+    pushq %rdx
+    pushq %rsi
+    pushq %rdi
+    pushq %rbp
+    pushq %r8
+    pushq %r9
+    pushq %r10
+    pushq %r11
+    pushq %r12
+    pushq %r13
+    pushq %r14
+    pushq %r15
+    movq %rsp, %rdi
+    cld
+    call interrupt_dispatch
+    popq %r15
+    popq %r14
+    popq %r13
+    popq %r12
+    popq %r11
+    popq %r10
+    popq %r9
+    popq %r8
+    popq %rbp
+    popq %rdi
+    popq %rsi
+    popq %rdx
+    popq %rcx
+    popq %rbx
+    popq %rax
+    testb $3, 24(%rsp)
+    jz 2f
+    swapgs
+2:
+    # Drop the vector number and error code
+    addq $16, %rsp
+    iretq
+
+    .section .rodata
+    .global isr_stub_table
+    .align 8
+isr_stub_table:
+    .set vector, 0
+    .rept 256
+    isr_stub_address %vector
+    .set vector, vector + 1
+    .endr
+
+    .section .text
+    .global load_idt
+
+load_idt:
+    lidt (%rdi)
+    retq
@@ -1,3 +1,7 @@
 mod gdt;
+mod idt;
+mod tss;
 
-pub use gdt::*;
\ No newline at end of file
+pub use gdt::*;
+pub use idt::*;
+pub use tss::*;
\ No newline at end of file
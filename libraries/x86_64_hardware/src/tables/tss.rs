@@ -0,0 +1,48 @@
+use crate::memory::VirtualAddress;
+use core::mem::size_of;
+
+/// The 64 bit Task State Segment. In long mode this only holds the stacks the CPU switches to
+/// on a privilege change or through the Interrupt Stack Table, and the IO permission bitmap.
+#[derive(Clone, Copy)]
+#[repr(C, packed(4))]
+pub struct TaskStateSegment {
+    reserved_1: u32,
+    /// The stacks loaded when entering rings 0-2 from a lower privilege level
+    pub privilege_stack_table: [VirtualAddress; 3],
+    reserved_2: u64,
+    /// Stacks selected by the IST field of an IDT entry. IST index 1 is entry 0
+    pub interrupt_stack_table: [VirtualAddress; 7],
+    reserved_3: u64,
+    reserved_4: u16,
+    pub iomap_base: u16,
+}
+
+impl TaskStateSegment {
+    /// Creates an empty TSS with no IO permission bitmap
+    pub const fn new() -> TaskStateSegment {
+        return TaskStateSegment {
+            reserved_1: 0,
+            privilege_stack_table: [VirtualAddress::new(0); 3],
+            reserved_2: 0,
+            interrupt_stack_table: [VirtualAddress::new(0); 7],
+            reserved_3: 0,
+            reserved_4: 0,
+            iomap_base: size_of::<TaskStateSegment>() as u16,
+        };
+    }
+}
+
+impl Default for TaskStateSegment {
+    fn default() -> Self {
+        return TaskStateSegment::new();
+    }
+}
+
+/// Loads the task register with the TSS descriptor at *selector* in the current GDT
+/// 
+/// ## Safety
+/// 
+/// This is unsafe as the selector must point to a valid, available TSS descriptor.
+pub unsafe fn load_tss(selector: u16) {
+    core::arch::asm!("ltr {0:x}", in(reg) selector, options(nostack, preserves_flags));
+}